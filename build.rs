@@ -0,0 +1,67 @@
+use std::{
+    env, fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chrono::DateTime;
+
+// The dependencies the security of a backup relies on, shown on the about page
+const CRYPTO_CRATES: &[&str] = &[
+    "aes-gcm", "pbkdf2", "sha2", "hmac", "blake3", "sharks", "subtle", "zeroize", "zip",
+];
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    // Reproducible builds pin the date, everything else uses the time of the build
+    let seconds = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs() as i64)
+        });
+    let date = DateTime::from_timestamp(seconds, 0).unwrap_or_default();
+    println!("cargo:rustc-env=BUILD_DATE={}", date.format("%Y-%m-%d"));
+
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").expect("Set by cargo");
+    let lock = fs::read_to_string(Path::new(&manifest_dir).join("Cargo.lock")).unwrap_or_default();
+    let dependencies = CRYPTO_CRATES
+        .iter()
+        .map(|name| {
+            let versions = locked_versions(&lock, name);
+            if versions.is_empty() {
+                format!("{}=unknown", name)
+            } else {
+                format!("{}={}", name, versions.join("/"))
+            }
+        })
+        .collect::<Vec<String>>();
+    println!(
+        "cargo:rustc-env=CRYPTO_DEPENDENCIES={}",
+        dependencies.join(",")
+    );
+}
+
+/// Every version of the package in the lock file, which may hold several of the same crate.
+fn locked_versions<'a>(lock: &'a str, name: &str) -> Vec<&'a str> {
+    let name_line = format!("name = \"{}\"", name);
+    let mut lines = lock.lines();
+    let mut versions = Vec::new();
+    while let Some(line) = lines.next() {
+        if line == name_line {
+            if let Some(version) = lines
+                .next()
+                .and_then(|line| line.strip_prefix("version = \""))
+                .and_then(|line| line.strip_suffix('"'))
+            {
+                versions.push(version);
+            }
+        }
+    }
+    versions
+}
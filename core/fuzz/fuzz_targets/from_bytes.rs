@@ -0,0 +1,18 @@
+//! Feeds arbitrary bytes to `BackupShard::from_bytes`, which parses whatever a QR code, file or
+//! typed text contained. Run with `cargo fuzz run from_bytes` from the core crate.
+#![no_main]
+
+use hyperbacked_core::backup::BackupShard;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Whatever is accepted has to be written and read back the same
+    if let Ok(shard) = BackupShard::from_bytes(data.to_vec()) {
+        let parsed = BackupShard::from_bytes(shard.to_bytes()).expect("Re-encoded shard is valid");
+        assert_eq!(parsed.number, shard.number);
+        assert_eq!(parsed.threshold, shard.threshold);
+        assert_eq!(parsed.backup_id, shard.backup_id);
+        assert_eq!(parsed.data, shard.data);
+        assert_eq!(parsed.metadata, shard.metadata);
+    }
+});
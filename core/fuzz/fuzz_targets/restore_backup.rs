@@ -0,0 +1,11 @@
+//! Restores arbitrary shares, which pass through the parser, combining and decryption. Run with
+//! `cargo fuzz run restore_backup` from the core crate.
+#![no_main]
+
+use hyperbacked_core::backup::restore_backup;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|shares: Vec<Vec<u8>>| {
+    // Only has to return an error instead of panicking, a wrong passphrase being the most likely
+    let _ = restore_backup(shares, "passphrase", None);
+});
@@ -0,0 +1,4431 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    io::{Read, Write},
+    str::FromStr,
+};
+
+use bytes::{Buf, BufMut, BytesMut};
+use chrono::{DateTime, Datelike, Months, NaiveDate, SubsecRound, Utc};
+use hmac::{Hmac, Mac};
+use rand::{random, thread_rng, CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rand_core::CryptoRngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sharks::{Share, Sharks};
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
+
+use crate::{
+    crypto::{
+        decrypt_secret, decrypt_stream, encrypt_secrets_with_rng, encrypt_stream, encrypted_len,
+        key_file_password, max_encrypted_len, max_plaintext_len, should_compress, stored_len,
+        CipherSuite, DecryptedSecret, KdfParams, KeyFileDigest, Secret, SecretEncoding,
+        SecretString, STREAM_KEY_LEN,
+    },
+    errors::{BackupError, ConfigError, CryptoError, RestoreError, Slip39Error},
+    hardware_key::{
+        hardware_key_password, unlock_hardware_key, HardwareKeyChallenge, HardwareKeySecret,
+        MAX_CREDENTIAL_ID_LEN, SALT_LEN as HARDWARE_KEY_SALT_LEN,
+    },
+    mnemonic::{decode_words, encode_words},
+    qrcode::MAX_SHARE_LEN,
+    slip39::{
+        self, format_master_secret, looks_like_mnemonic, parse_master_secret, Slip39Format,
+        Slip39Shamir, Slip39Share,
+    },
+};
+
+// Shamir shares never use x = 0, so this can't be the first byte of a bare share
+pub const SHARD_MAGIC: u8 = 0;
+const SHARD_VERSION: u8 = 12;
+const SHARD_HEADER_LEN: usize = 11;
+// Version 1 shards were written without the trailing CRC32
+const SHARD_CHECKSUM_LEN: usize = 4;
+// Truncated HMAC-SHA256, which replaces the CRC32 from version 5 on
+const SHARD_TAG_LEN: usize = 16;
+// x coordinate and the shortest ciphertext of the first release: salt, one header slot, the
+// nonce and tag of an empty secret and a byte of padding
+const LEGACY_MIN_SHARE_LEN: usize = 1 + 24 + 32 + 12 + 16 + 1;
+// Creation time, total number of shards and label length
+const METADATA_FIXED_LEN: usize = 10;
+// Algorithm and its parameters, stored from version 4 on
+const KDF_PARAMS_LEN: usize = 5;
+// Cipher suite id, stored from version 6 on
+const CIPHER_SUITE_LEN: usize = 1;
+// Bit field, stored from version 7 on
+const SHARD_FLAGS_LEN: usize = 1;
+const FLAG_KEY_FILE: u8 = 0x01;
+const FLAG_COMPRESSED: u8 = 0x02;
+// From version 9 on, followed by the not-before date in big-endian seconds
+const FLAG_NOT_BEFORE: u8 = 0x04;
+const NOT_BEFORE_LEN: usize = 8;
+// From version 10 on, followed by the month to verify the backup by, counted from January 1970
+const FLAG_VERIFY_BY: u8 = 0x08;
+const VERIFY_BY_LEN: usize = 2;
+// From version 11 on, set if the shares were dealt over the AES field by `SssBackend::Gf256`
+const FLAG_AES_FIELD: u8 = 0x10;
+// From version 12 on, followed by the credential ID length, the credential ID and the salt to
+// ask the hardware key with
+const FLAG_HARDWARE_KEY: u8 = 0x20;
+// Entropy, creation time and a truncated SHA-256 checksum
+const RECOVERY_SEED_ENTROPY_LEN: usize = 16;
+const RECOVERY_SEED_CHECKSUM_LEN: usize = 2;
+const RECOVERY_SEED_LEN: usize = RECOVERY_SEED_ENTROPY_LEN + 4 + RECOVERY_SEED_CHECKSUM_LEN;
+// In front of the key that is backed up instead of a streamed secret
+const STREAM_KEY_MAGIC: &[u8] = b"HBSK";
+
+/// A shard as read from a QR code or pasted text, before it has been decoded.
+pub type EncodedShare = Vec<u8>;
+
+/// Built through `BackupConfigBuilder`, which guarantees `1 <= min_shards <= total_shards`.
+pub struct BackupConfig {
+    total_shards: u8,
+    min_shards: u8,
+    label: String,
+    kdf: KdfParams,
+    cipher: CipherSuite,
+    sharing: SssBackend,
+    key_file: Option<KeyFileDigest>,
+    not_before: Option<DateTime<Utc>>,
+    verify_after: Option<u8>,
+    slip39: bool,
+    hardware_key: Option<(HardwareKeyChallenge, HardwareKeySecret)>,
+}
+
+impl BackupConfig {
+    pub fn builder<'a>() -> BackupConfigBuilder<'a> {
+        BackupConfigBuilder::default()
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.min_shards == 0 {
+            return Err(ConfigError::ZeroThreshold);
+        }
+        if self.min_shards > self.total_shards {
+            return Err(ConfigError::ThresholdExceedsTotal {
+                threshold: self.min_shards,
+                total: self.total_shards,
+            });
+        }
+        if self.label.len() > u8::MAX as usize {
+            return Err(ConfigError::LabelTooLong(self.label.len()));
+        }
+        if self.slip39 {
+            // SLIP-0039 shares have no room for any of the metadata
+            let unsupported = [
+                (!self.label.is_empty(), "a label"),
+                (self.key_file.is_some(), "a key file"),
+                (self.not_before.is_some(), "a not-before date"),
+                (self.verify_after.is_some(), "a verify-by reminder"),
+                (self.hardware_key.is_some(), "a hardware key"),
+            ];
+            if let Some((_, what)) = unsupported.into_iter().find(|(set, _)| *set) {
+                return Err(ConfigError::Slip39Unsupported(what));
+            }
+        }
+        if let Some((challenge, _)) = &self.hardware_key {
+            if challenge.credential_id.len() > MAX_CREDENTIAL_ID_LEN {
+                return Err(ConfigError::CredentialIdTooLong(
+                    challenge.credential_id.len(),
+                ));
+            }
+        }
+        self.kdf.validate()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BackupConfigBuilder<'a> {
+    threshold: u8,
+    total: u8,
+    label: &'a str,
+    kdf: KdfParams,
+    cipher: CipherSuite,
+    sharing: SssBackend,
+    key_file: Option<KeyFileDigest>,
+    not_before: Option<DateTime<Utc>>,
+    verify_after: Option<u8>,
+    slip39: bool,
+    hardware_key: Option<(&'a HardwareKeyChallenge, HardwareKeySecret)>,
+}
+
+impl Default for BackupConfigBuilder<'_> {
+    fn default() -> Self {
+        BackupConfigBuilder {
+            threshold: 1,
+            total: 1,
+            label: "",
+            kdf: KdfParams::default(),
+            cipher: CipherSuite::default(),
+            sharing: SssBackend::default(),
+            key_file: None,
+            not_before: None,
+            verify_after: None,
+            slip39: false,
+            hardware_key: None,
+        }
+    }
+}
+
+impl<'a> BackupConfigBuilder<'a> {
+    pub fn with_threshold(mut self, min: u8) -> Self {
+        self.threshold = min;
+        self
+    }
+
+    pub fn with_total(mut self, max: u8) -> Self {
+        self.total = max;
+        self
+    }
+
+    /// Label stored in every shard, readable without the passphrase. Leave it empty to keep it
+    /// off the QR codes.
+    pub fn with_label(mut self, label: &'a str) -> Self {
+        self.label = label;
+        self
+    }
+
+    pub fn with_kdf(mut self, kdf: KdfParams) -> Self {
+        self.kdf = kdf;
+        self
+    }
+
+    /// AEAD that encrypts the secrets. Its id is stored in the shards, so restoring doesn't need
+    /// to be told.
+    pub fn with_cipher(mut self, cipher: CipherSuite) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    /// How the encrypted secrets are split into shares. The shards record it.
+    pub fn with_sharing(mut self, sharing: SssBackend) -> Self {
+        self.sharing = sharing;
+        self
+    }
+
+    /// Makes the key file necessary for restoring, in addition to the passphrase. The shards only
+    /// record that one is needed, not which.
+    pub fn with_key_file(mut self, key_file: KeyFileDigest) -> Self {
+        self.key_file = Some(key_file);
+        self
+    }
+
+    /// Reminder not to restore the backup before this time, shown when it is restored earlier.
+    /// Nothing stops the restore: the shards are not encrypted any stronger. It is authenticated
+    /// like the rest of the metadata, so it can't be removed from the shards without breaking them.
+    pub fn with_not_before(mut self, not_before: DateTime<Utc>) -> Self {
+        self.not_before = Some(not_before.trunc_subsecs(0));
+        self
+    }
+
+    /// Recommends verifying the backup this many years after it was created, as paper fades and
+    /// storage places change. Printed on the shards and shown when restoring once it is due.
+    pub fn with_verify_after(mut self, years: u8) -> Self {
+        self.verify_after = Some(years);
+        self
+    }
+
+    /// Deals SLIP-0039 shares of a single master secret written in hex, which hardware wallets
+    /// and other tools following the standard can restore. The standard encrypts the secret
+    /// with the passphrase itself, so the KDF, cipher and sharing settings don't apply.
+    pub fn with_slip39(mut self, slip39: bool) -> Self {
+        self.slip39 = slip39;
+        self
+    }
+
+    /// Makes the security key that answered `challenge` with `secret` necessary for restoring,
+    /// in addition to the passphrase and any key file. The shards store the challenge to ask the
+    /// key again; see `hardware_key` for what that protects against.
+    pub fn with_hardware_key(
+        mut self,
+        challenge: &'a HardwareKeyChallenge,
+        secret: HardwareKeySecret,
+    ) -> Self {
+        self.hardware_key = Some((challenge, secret));
+        self
+    }
+
+    pub fn build(self) -> Result<BackupConfig, ConfigError> {
+        let config = BackupConfig {
+            total_shards: self.total,
+            min_shards: self.threshold,
+            label: self.label.to_owned(),
+            kdf: self.kdf,
+            cipher: self.cipher,
+            sharing: self.sharing,
+            key_file: self.key_file,
+            not_before: self.not_before,
+            verify_after: self.verify_after,
+            slip39: self.slip39,
+            hardware_key: self
+                .hardware_key
+                .map(|(challenge, secret)| (challenge.clone(), secret)),
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupShard {
+    pub number: usize,
+    pub threshold: u8,
+    pub backup_id: u64,
+    #[serde(with = "base64_data")]
+    pub data: Vec<u8>,
+    /// Printed on the shard and kept in JSON exports, but not part of the QR payload
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<NaiveDate>,
+    /// Missing on shards created before version 3
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ShardMetadata>,
+    /// Set on SLIP-0039 shares, which have no metadata. Their data is the member index, counted
+    /// from 0, followed by the share value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slip39: Option<Slip39Format>,
+}
+
+// JSON exports store the share data as Base64
+mod base64_data {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        base64::decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Details about the backup that every shard carries in the clear. They are authenticated
+/// together with the secrets, so decryption fails if any of them was changed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardMetadata {
+    /// Empty if the label was kept off the QR codes
+    pub label: String,
+    pub created_at: DateTime<Utc>,
+    pub total_shards: u8,
+    /// Missing on version 3 shards, which all used the default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kdf: Option<KdfParams>,
+    /// Missing on shards before version 6, which were all encrypted with AES-256-GCM. Only
+    /// stored together with the KDF parameters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cipher: Option<CipherSuite>,
+    /// Whether the passphrase has to be combined with a key file. Missing on shards before
+    /// version 7, which never needed one. Only stored together with the cipher suite.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_file: Option<bool>,
+    /// Whether the secrets were compressed before encryption. Stored in the same flags as
+    /// `key_file`, so always false before version 7.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub compressed: bool,
+    /// Reminder not to restore the backup before this time. Missing on shards before version 9.
+    #[serde(default, alias = "time_lock", skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<DateTime<Utc>>,
+    /// First day of the month the backup should be verified by. Missing on shards before
+    /// version 10.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verify_by: Option<NaiveDate>,
+    /// How the shares were dealt. Stored in the same flags as `key_file`, so always sharks
+    /// before version 11.
+    #[serde(default, skip_serializing_if = "SssBackend::is_default")]
+    pub sharing: SssBackend,
+    /// Credential and salt to ask the hardware key for its part of the password. Missing on
+    /// shards before version 12.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hardware_key: Option<HardwareKeyChallenge>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+fn known_flags(version: u8) -> u8 {
+    match version {
+        ..=8 => FLAG_KEY_FILE | FLAG_COMPRESSED,
+        9 => FLAG_KEY_FILE | FLAG_COMPRESSED | FLAG_NOT_BEFORE,
+        10 => FLAG_KEY_FILE | FLAG_COMPRESSED | FLAG_NOT_BEFORE | FLAG_VERIFY_BY,
+        11 => FLAG_KEY_FILE | FLAG_COMPRESSED | FLAG_NOT_BEFORE | FLAG_VERIFY_BY | FLAG_AES_FIELD,
+        _ => known_flags(11) | FLAG_HARDWARE_KEY,
+    }
+}
+
+impl ShardMetadata {
+    fn write(&self, buf: &mut BytesMut) {
+        buf.put_i64(self.created_at.timestamp());
+        buf.put_u8(self.total_shards);
+        buf.put_u8(self.label.len() as u8);
+        buf.extend_from_slice(self.label.as_bytes());
+        if let Some(kdf) = self.kdf {
+            let (algorithm, params) = kdf.to_bytes();
+            buf.put_u8(algorithm);
+            buf.extend_from_slice(&params);
+            if let Some(cipher) = self.cipher {
+                buf.put_u8(cipher.id());
+                if let Some(key_file) = self.key_file {
+                    let mut flags = 0;
+                    if key_file {
+                        flags |= FLAG_KEY_FILE;
+                    }
+                    if self.compressed {
+                        flags |= FLAG_COMPRESSED;
+                    }
+                    if self.not_before.is_some() {
+                        flags |= FLAG_NOT_BEFORE;
+                    }
+                    if self.verify_by.is_some() {
+                        flags |= FLAG_VERIFY_BY;
+                    }
+                    if self.sharing == SssBackend::Gf256 {
+                        flags |= FLAG_AES_FIELD;
+                    }
+                    if self.hardware_key.is_some() {
+                        flags |= FLAG_HARDWARE_KEY;
+                    }
+                    buf.put_u8(flags);
+                    if let Some(not_before) = self.not_before {
+                        buf.put_i64(not_before.timestamp());
+                    }
+                    if let Some(verify_by) = self.verify_by {
+                        let months = (verify_by.year() - 1970) * 12 + verify_by.month0() as i32;
+                        buf.put_u16(months.clamp(0, u16::MAX as i32) as u16);
+                    }
+                    if let Some(hardware_key) = &self.hardware_key {
+                        buf.put_u8(hardware_key.credential_id.len() as u8);
+                        buf.extend_from_slice(&hardware_key.credential_id);
+                        buf.extend_from_slice(&hardware_key.salt);
+                    }
+                }
+            }
+        }
+    }
+
+    fn read(buf: &mut &[u8], version: u8) -> anyhow::Result<Self> {
+        if buf.remaining() < METADATA_FIXED_LEN {
+            return Err(BackupError::InvalidShard(String::from("Missing metadata")).into());
+        }
+        let created_at = DateTime::from_timestamp(buf.get_i64(), 0)
+            .ok_or_else(|| BackupError::InvalidShard(String::from("Invalid creation time")))?;
+        let total_shards = buf.get_u8();
+        let label_len = buf.get_u8() as usize;
+        if buf.remaining() < label_len {
+            return Err(BackupError::InvalidShard(String::from("Truncated label")).into());
+        }
+        let label = String::from_utf8(buf[..label_len].to_vec())
+            .map_err(|_| BackupError::InvalidShard(String::from("Invalid label")))?;
+        buf.advance(label_len);
+        let kdf = match version >= 4 {
+            false => None,
+            true if buf.remaining() < KDF_PARAMS_LEN => {
+                return Err(
+                    BackupError::InvalidShard(String::from("Missing KDF parameters")).into(),
+                )
+            }
+            true => {
+                let algorithm = buf.get_u8();
+                let mut params = [0; KDF_PARAMS_LEN - 1];
+                buf.copy_to_slice(&mut params);
+                let kdf = KdfParams::from_bytes(algorithm, params).ok_or_else(|| {
+                    BackupError::InvalidShard(format!("Unknown key derivation {}", algorithm))
+                })?;
+                kdf.validate()
+                    .map_err(|error| BackupError::InvalidShard(error.to_string()))?;
+                Some(kdf)
+            }
+        };
+        let cipher = match version >= 6 {
+            false => None,
+            true if buf.remaining() < CIPHER_SUITE_LEN => {
+                return Err(BackupError::InvalidShard(String::from("Missing cipher suite")).into())
+            }
+            true => Some(CipherSuite::from_id(buf.get_u8())?),
+        };
+        let flags = match version >= 7 {
+            false => None,
+            true if buf.remaining() < SHARD_FLAGS_LEN => {
+                return Err(BackupError::InvalidShard(String::from("Missing flags")).into())
+            }
+            true => match buf.get_u8() {
+                flags if flags & !known_flags(version) != 0 => {
+                    return Err(
+                        BackupError::InvalidShard(format!("Unknown flags {:#04x}", flags)).into(),
+                    )
+                }
+                flags => Some(flags),
+            },
+        };
+        let not_before = match flags.is_some_and(|flags| flags & FLAG_NOT_BEFORE != 0) {
+            false => None,
+            true if buf.remaining() < NOT_BEFORE_LEN => {
+                return Err(BackupError::InvalidShard(String::from("Missing restore date")).into())
+            }
+            true => {
+                Some(DateTime::from_timestamp(buf.get_i64(), 0).ok_or_else(|| {
+                    BackupError::InvalidShard(String::from("Invalid restore date"))
+                })?)
+            }
+        };
+        let verify_by = match flags.is_some_and(|flags| flags & FLAG_VERIFY_BY != 0) {
+            false => None,
+            true if buf.remaining() < VERIFY_BY_LEN => {
+                return Err(
+                    BackupError::InvalidShard(String::from("Missing verify-by date")).into(),
+                )
+            }
+            true => {
+                let months = buf.get_u16() as i32;
+                NaiveDate::from_ymd_opt(1970 + months / 12, months as u32 % 12 + 1, 1)
+            }
+        };
+        let hardware_key = match flags.is_some_and(|flags| flags & FLAG_HARDWARE_KEY != 0) {
+            false => None,
+            true if !buf.has_remaining() => {
+                return Err(BackupError::InvalidShard(String::from(
+                    "Missing hardware key challenge",
+                ))
+                .into())
+            }
+            true => {
+                let credential_len = buf.get_u8() as usize;
+                if credential_len == 0 || buf.remaining() < credential_len + HARDWARE_KEY_SALT_LEN {
+                    return Err(BackupError::InvalidShard(String::from(
+                        "Truncated hardware key challenge",
+                    ))
+                    .into());
+                }
+                let credential_id = buf[..credential_len].to_vec();
+                buf.advance(credential_len);
+                let mut salt = [0; HARDWARE_KEY_SALT_LEN];
+                buf.copy_to_slice(&mut salt);
+                Some(HardwareKeyChallenge {
+                    credential_id,
+                    salt,
+                })
+            }
+        };
+        Ok(ShardMetadata {
+            label,
+            created_at,
+            total_shards,
+            kdf,
+            cipher,
+            key_file: flags.map(|flags| flags & FLAG_KEY_FILE != 0),
+            compressed: flags.is_some_and(|flags| flags & FLAG_COMPRESSED != 0),
+            not_before,
+            verify_by,
+            sharing: match flags.is_some_and(|flags| flags & FLAG_AES_FIELD != 0) {
+                true => SssBackend::Gf256,
+                false => SssBackend::Sharks,
+            },
+            hardware_key,
+        })
+    }
+
+    /// Describes the backup, e.g. "Backup 'Cold wallet' created 2024-03-01, 2 of 3 shares".
+    pub fn summary(&self, threshold: u8) -> String {
+        let name = if self.label.is_empty() {
+            String::from("Backup")
+        } else {
+            format!("Backup '{}'", self.label)
+        };
+        format!(
+            "{} created {}, {} of {} shares",
+            name,
+            self.created_at.format("%Y-%m-%d"),
+            threshold,
+            self.total_shards
+        )
+    }
+
+    /// The month the backup should have been verified by, once it has begun.
+    pub fn verification_due(&self, today: NaiveDate) -> Option<NaiveDate> {
+        self.verify_by.filter(|verify_by| *verify_by <= today)
+    }
+
+    /// The not-before date of the backup, while it hasn't come yet.
+    pub fn restored_early(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.not_before.filter(|not_before| *not_before > now)
+    }
+}
+
+impl BackupShard {
+    pub fn from_bytes(bytes: Vec<u8>) -> anyhow::Result<Self> {
+        decode_share_versioned(bytes)
+    }
+
+    /// The shard written as BIP-39 words, 24 for every 32 bytes of `to_bytes`.
+    pub fn to_bip39_words(&self) -> Vec<String> {
+        encode_words(&self.to_bytes())
+    }
+
+    pub fn from_bip39_words(words: &[String]) -> anyhow::Result<Self> {
+        Self::from_bytes(decode_words(words)?)
+    }
+
+    /// The words to write the shard down with: the mnemonic of a SLIP-0039 share, BIP-39 words
+    /// otherwise.
+    pub fn to_words(&self) -> Vec<String> {
+        match self.to_slip39() {
+            Some(share) => share.words(),
+            None => self.to_bip39_words(),
+        }
+    }
+
+    /// Only shares of a single group fit a shard, as they are combined by member alone.
+    pub fn from_slip39(share: Slip39Share) -> Result<Self, Slip39Error> {
+        if share.group_count != 1 {
+            return Err(Slip39Error::MultipleGroups);
+        }
+        let mut data = vec![share.member_index];
+        data.extend_from_slice(&share.value);
+        Ok(BackupShard {
+            number: share.member_index as usize + 1,
+            threshold: share.member_threshold,
+            backup_id: share.identifier as u64,
+            data,
+            expires_at: None,
+            metadata: None,
+            slip39: Some(share.format),
+        })
+    }
+
+    pub fn to_slip39(&self) -> Option<Slip39Share> {
+        let format = self.slip39?;
+        let (&member_index, value) = self.data.split_first()?;
+        Some(Slip39Share {
+            identifier: self.backup_id as u16,
+            format,
+            group_index: 0,
+            group_threshold: 1,
+            group_count: 1,
+            member_index,
+            member_threshold: self.threshold,
+            value: value.to_vec(),
+        })
+    }
+
+    /// Compact binary form used for QR codes and any other medium: a `PayloadHeader`, the share
+    /// data and a tag over everything before it. Shards from older versions are written in the
+    /// layout they were read in, SLIP-0039 shares as their mnemonic.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        if let Some(share) = self.to_slip39() {
+            return share.to_mnemonic().into_bytes();
+        }
+        if self.is_legacy() {
+            return self.data.clone();
+        }
+        let header = self.header();
+        let mut buf = BytesMut::with_capacity(SHARD_HEADER_LEN + self.data.len() + SHARD_TAG_LEN);
+        header.write(&mut buf);
+        buf.extend_from_slice(&self.data);
+        if header.version >= 5 {
+            let tag = shard_tag(self.backup_id, self.number, &buf);
+            buf.extend_from_slice(&tag);
+        } else {
+            buf.put_u32(crc32fast::hash(&buf));
+        }
+        buf.to_vec()
+    }
+
+    fn header(&self) -> PayloadHeader {
+        let version = match &self.metadata {
+            None => 2,
+            Some(ShardMetadata { kdf: None, .. }) => 3,
+            Some(ShardMetadata { cipher: None, .. }) => 5,
+            Some(ShardMetadata { key_file: None, .. }) => 6,
+            Some(_) => SHARD_VERSION,
+        };
+        PayloadHeader {
+            version,
+            threshold: self.threshold,
+            backup_id: self.backup_id,
+            metadata: self.metadata.clone(),
+        }
+    }
+
+    /// Whether the shard was printed before shards had a header. Those don't know their
+    /// threshold, so it is left at 0, and every shard given is combined.
+    pub fn is_legacy(&self) -> bool {
+        self.threshold == 0
+    }
+
+    pub fn requires_key_file(&self) -> bool {
+        matches!(
+            self.metadata,
+            Some(ShardMetadata {
+                key_file: Some(true),
+                ..
+            })
+        )
+    }
+
+    /// What the hardware key the backup requires has to be asked, if it requires one.
+    pub fn hardware_key(&self) -> Option<&HardwareKeyChallenge> {
+        self.metadata.as_ref()?.hardware_key.as_ref()
+    }
+
+    /// First 32 bits of the BLAKE3 hash of the encoded shard as uppercase hex, so the holders of
+    /// shards can check they have the right one without revealing anything about it.
+    pub fn display_fingerprint(&self) -> String {
+        blake3::hash(&self.to_bytes()).as_bytes()[..4]
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect()
+    }
+
+    /// Authenticated by the encryption of the secrets. Empty for shards without metadata, which
+    /// were encrypted without any.
+    fn associated_data(&self) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        if let Some(metadata) = &self.metadata {
+            buf.put_u8(self.threshold);
+            buf.put_u64(self.backup_id);
+            metadata.write(&mut buf);
+        }
+        buf.to_vec()
+    }
+}
+
+// Shards are identified by their number and share data, the metadata doesn't matter
+impl PartialEq for BackupShard {
+    fn eq(&self, other: &Self) -> bool {
+        self.number == other.number && self.data == other.data
+    }
+}
+
+impl Eq for BackupShard {}
+
+impl PartialOrd for BackupShard {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BackupShard {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // The data only breaks ties between shards of different backups
+        self.number
+            .cmp(&other.number)
+            .then_with(|| self.data.cmp(&other.data))
+    }
+}
+
+/// Everything in front of the share data of an encoded shard. The layout is stable, every
+/// later version only appends to the metadata of the one before:
+///
+/// | Bytes | Field                                                        | Versions |
+/// |-------|--------------------------------------------------------------|----------|
+/// | 1     | Magic byte, always 0                                         | all      |
+/// | 1     | Version                                                      | all      |
+/// | 1     | Threshold                                                    | all      |
+/// | 8     | Backup ID, big-endian                                        | all      |
+/// | 8     | Creation time, big-endian seconds                            | 3+       |
+/// | 1     | Total number of shards                                       | 3+       |
+/// | 1 + n | Label length and UTF-8 label                                 | 3+       |
+/// | 1 + 4 | KDF algorithm and parameters                                 | 4+       |
+/// | 1     | Cipher suite                                                 | 6+       |
+/// | 1     | Flags, see below                                             | 7+       |
+/// | 8     | Not-before date, big-endian seconds, if flag bit 2 is set    | 9+       |
+/// | 2     | Verify-by month since January 1970, if flag bit 3 is set     | 10+      |
+/// | 1 + n | Credential ID length and credential ID, if flag bit 5 is set | 12+      |
+/// | 32    | Hardware key salt, if flag bit 5 is set                      | 12+      |
+///
+/// | Flag bit | Meaning                                                     | Versions |
+/// |----------|-------------------------------------------------------------|----------|
+/// | 0        | The password includes a key file                            | 7+       |
+/// | 1        | The secrets are compressed before they are encrypted        | 7+       |
+/// | 2        | A not-before date follows                                   | 9+       |
+/// | 3        | A verify-by month follows                                   | 10+      |
+/// | 4        | Shares are dealt over the AES field by `SssBackend::Gf256`  | 11+      |
+/// | 5        | The password includes a hardware key, its challenge follows | 12+      |
+///
+/// Flag bits a version doesn't know are rejected. The share data follows, then a CRC32
+/// (versions 2 to 4) or a 16 byte tag (5+) over everything before it. Version 8 kept the
+/// layout, but changed how the secrets are encrypted, which older versions can't decrypt.
+/// Shards of the first release had no header at all and start with the x coordinate of their
+/// share instead of the magic byte. KDF algorithm 1 is PBKDF2 followed by its rounds, 2 is
+/// Argon2id followed by its memory in MiB, its passes and its lanes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PayloadHeader {
+    pub version: u8,
+    pub threshold: u8,
+    pub backup_id: u64,
+    /// Missing before version 3
+    pub metadata: Option<ShardMetadata>,
+}
+
+impl PayloadHeader {
+    /// Reads the header at the front of an encoded shard, returning it along with its length.
+    /// Shards of any older version are accepted, newer ones are rejected with
+    /// `BackupError::NewerVersion`.
+    pub fn parse(bytes: &[u8]) -> anyhow::Result<(Self, usize)> {
+        if bytes.len() < SHARD_HEADER_LEN || bytes[0] != SHARD_MAGIC {
+            return Err(BackupError::InvalidShard(String::from("Missing shard header")).into());
+        }
+        let mut buf = &bytes[1..];
+        let version = buf.get_u8();
+        match version {
+            0 => {
+                return Err(
+                    BackupError::InvalidShard(format!("Unknown shard version {}", version)).into(),
+                )
+            }
+            version if version > SHARD_VERSION => {
+                return Err(BackupError::NewerVersion(version).into())
+            }
+            _ => {}
+        }
+        // A threshold of 0 is left to shards of the first release, which had no header
+        let threshold = match buf.get_u8() {
+            0 => return Err(BackupError::InvalidShard(String::from("Invalid threshold 0")).into()),
+            threshold => threshold,
+        };
+        let backup_id = buf.get_u64();
+        let metadata = match version >= 3 {
+            true => Some(ShardMetadata::read(&mut buf, version)?),
+            false => None,
+        };
+        let header = PayloadHeader {
+            version,
+            threshold,
+            backup_id,
+            metadata,
+        };
+        Ok((header, bytes.len() - buf.len()))
+    }
+
+    fn write(&self, buf: &mut BytesMut) {
+        buf.put_u8(SHARD_MAGIC);
+        buf.put_u8(self.version);
+        buf.put_u8(self.threshold);
+        buf.put_u64(self.backup_id);
+        if let Some(metadata) = &self.metadata {
+            metadata.write(buf);
+        }
+    }
+
+    /// Length of the checksum or tag after the share data.
+    fn trailer_len(&self) -> usize {
+        match self.version {
+            1 => 0,
+            2..=4 => SHARD_CHECKSUM_LEN,
+            _ => SHARD_TAG_LEN,
+        }
+    }
+}
+
+/// Decodes a shard of any version up to the current one, so old printouts stay restorable.
+pub fn decode_share_versioned(bytes: Vec<u8>) -> anyhow::Result<BackupShard> {
+    // Neither the magic byte nor the x coordinate of a legacy share is a letter
+    if let Some(mnemonic) = std::str::from_utf8(&bytes)
+        .ok()
+        .filter(|text| looks_like_mnemonic(text))
+    {
+        let share = Slip39Share::from_mnemonic(mnemonic).map_err(BackupError::from)?;
+        return Ok(BackupShard::from_slip39(share).map_err(BackupError::from)?);
+    }
+    if bytes.first().is_some_and(|&first| first != SHARD_MAGIC) {
+        return decode_legacy_share(bytes);
+    }
+    let (header, header_len) = PayloadHeader::parse(&bytes)?;
+    if bytes.len() < header_len + header.trailer_len() {
+        return Err(BackupError::InvalidShard(String::from("Truncated shard")).into());
+    }
+    let (body, trailer) = bytes.split_at(bytes.len() - header.trailer_len());
+    let data = body[header_len..].to_vec();
+
+    // The first byte of a share is its x coordinate, which is also the shard number
+    let share = Share::try_from(&data[..]).map_err(|e| BackupError::InvalidShard(e.to_owned()))?;
+    let number = match share.x.0 {
+        // The share at 0 would be the secret itself
+        0 => return Err(BackupError::InvalidShard(String::from("Invalid shard number 0")).into()),
+        x => x as usize,
+    };
+    let intact = match header.version {
+        1 => true,
+        2..=4 => (&trailer[..]).get_u32() == crc32fast::hash(body),
+        _ => bool::from(shard_tag(header.backup_id, number, body).ct_eq(trailer)),
+    };
+    if !intact {
+        return Err(BackupError::DamagedShard(number).into());
+    }
+    Ok(BackupShard {
+        number,
+        threshold: header.threshold,
+        backup_id: header.backup_id,
+        data,
+        expires_at: None,
+        metadata: header.metadata,
+        slip39: None,
+    })
+}
+
+/// Shards of the first release were the bare Shamir share, its x coordinate followed by the
+/// ciphertext, without a threshold, backup ID or checksum.
+fn decode_legacy_share(bytes: Vec<u8>) -> anyhow::Result<BackupShard> {
+    if bytes.len() < LEGACY_MIN_SHARE_LEN {
+        return Err(BackupError::InvalidShard(String::from("Missing shard header")).into());
+    }
+    let share = Share::try_from(&bytes[..]).map_err(|e| BackupError::InvalidShard(e.to_owned()))?;
+    Ok(BackupShard {
+        number: share.x.0 as usize,
+        threshold: 0,
+        backup_id: 0,
+        data: bytes,
+        expires_at: None,
+        metadata: None,
+        slip39: None,
+    })
+}
+
+/// Detects shards that were damaged or misread, so they are never combined. Keyed with the
+/// backup ID, which every shard carries in the clear, so this guards against accidents only: the
+/// encryption is what stops deliberate tampering.
+fn shard_tag(backup_id: u64, number: usize, body: &[u8]) -> [u8; SHARD_TAG_LEN] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(&backup_id.to_be_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(&[number as u8]);
+    mac.update(body);
+    let mut tag = [0; SHARD_TAG_LEN];
+    tag.copy_from_slice(&mac.finalize().into_bytes()[..SHARD_TAG_LEN]);
+    tag
+}
+
+/// Length of everything in an encoded shard besides the encrypted secrets.
+fn shard_overhead(label: &str) -> usize {
+    // Shamir shares carry their x coordinate in front of the data
+    SHARD_HEADER_LEN
+        + METADATA_FIXED_LEN
+        + label.len()
+        + KDF_PARAMS_LEN
+        + CIPHER_SUITE_LEN
+        + SHARD_FLAGS_LEN
+        + NOT_BEFORE_LEN
+        + VERIFY_BY_LEN
+        + 1
+        + SHARD_TAG_LEN
+}
+
+/// Upper bound for the length of each encoded shard of a backup of these secrets.
+pub fn max_share_len(secrets: &[Secret], label: &str, cipher: CipherSuite) -> usize {
+    shard_overhead(label) + max_encrypted_len(secrets, cipher)
+}
+
+/// Like `max_share_len`, for secrets whose longest `stored_len` is already known.
+pub fn share_len(longest_body: usize, label: &str, cipher: CipherSuite) -> usize {
+    shard_overhead(label) + encrypted_len(longest_body, cipher)
+}
+
+/// Longest plaintext a secret of a backup with this config may have, limited by both the
+/// encryption format and how many QR codes a shard can be printed as.
+pub fn max_secret_len(config: &BackupConfig) -> usize {
+    let hardware_key = config
+        .hardware_key
+        .as_ref()
+        .map_or(0, |(challenge, _)| challenge.stored_len());
+    let printable = max_plaintext_len(
+        MAX_SHARE_LEN.saturating_sub(shard_overhead(&config.label) + hardware_key),
+        config.cipher,
+    );
+    printable.min(config.cipher.max_secret_len())
+}
+
+/// Lets `create_backup_deterministic` create the same shards again, so a lost one can be
+/// reprinted. Together with the secrets and passphrases it prints valid shards, so it has to be
+/// kept apart from them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecoverySeed {
+    entropy: [u8; RECOVERY_SEED_ENTROPY_LEN],
+    created_at: DateTime<Utc>,
+}
+
+impl RecoverySeed {
+    pub fn generate() -> Self {
+        RecoverySeed {
+            entropy: random(),
+            created_at: Utc::now().trunc_subsecs(0),
+        }
+    }
+
+    fn to_bytes(self) -> BytesMut {
+        let mut buf = BytesMut::with_capacity(RECOVERY_SEED_LEN);
+        buf.extend_from_slice(&self.entropy);
+        buf.put_u32(self.created_at.timestamp() as u32);
+        let checksum = Sha256::digest(&buf);
+        buf.extend_from_slice(&checksum[..RECOVERY_SEED_CHECKSUM_LEN]);
+        buf
+    }
+
+    /// The secrets and config are mixed in, so a seed that is reused for a different backup
+    /// doesn't repeat its nonces.
+    fn rng(&self, secrets: &[Secret], config: &BackupConfig) -> ChaCha20Rng {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.entropy).expect("HMAC accepts keys of any length");
+        mac.update(b"hyperbacked recovery seed");
+        for secret in secrets {
+            for field in [secret.value.as_bytes(), secret.password.as_bytes()] {
+                mac.update(&(field.len() as u32).to_be_bytes());
+                mac.update(field);
+            }
+            mac.update(&[(secret.encoding == SecretEncoding::Base64) as u8]);
+            // Only when there is one, so seeds from before notes still reprint the same shards
+            if !secret.note.is_empty() {
+                mac.update(b"note");
+                mac.update(&(secret.note.len() as u32).to_be_bytes());
+                mac.update(secret.note.as_bytes());
+            }
+        }
+        mac.update(&[config.min_shards, config.total_shards]);
+        mac.update(&(config.label.len() as u32).to_be_bytes());
+        mac.update(config.label.as_bytes());
+        // PBKDF2 only adds its rounds, as it did before there was a choice
+        let (algorithm, params) = config.kdf.to_bytes();
+        if !matches!(config.kdf, KdfParams::Pbkdf2 { .. }) {
+            mac.update(&[algorithm]);
+        }
+        mac.update(&params);
+        if let Some(key_file) = &config.key_file {
+            mac.update(key_file);
+        }
+        if let Some(not_before) = config.not_before {
+            mac.update(&not_before.timestamp().to_be_bytes());
+        }
+        if let Some(years) = config.verify_after {
+            mac.update(&[years]);
+        }
+        if config.slip39 {
+            mac.update(b"slip39");
+        }
+        if let Some((challenge, secret)) = &config.hardware_key {
+            mac.update(b"hardware key");
+            mac.update(&[challenge.credential_id.len() as u8]);
+            mac.update(&challenge.credential_id);
+            mac.update(&challenge.salt);
+            mac.update(secret);
+        }
+        let mut seed = [0; 32];
+        seed.copy_from_slice(&mac.finalize().into_bytes());
+        ChaCha20Rng::from_seed(seed)
+    }
+}
+
+impl fmt::Display for RecoverySeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = self
+            .to_bytes()
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect::<String>();
+        let groups = hex
+            .as_bytes()
+            .chunks(4)
+            .map(|group| String::from_utf8_lossy(group))
+            .collect::<Vec<_>>();
+        write!(f, "{}", groups.join("-"))
+    }
+}
+
+impl FromStr for RecoverySeed {
+    type Err = BackupError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let hex = value
+            .chars()
+            .filter(|c| *c != '-' && !c.is_whitespace())
+            .collect::<String>();
+        if hex.len() != RECOVERY_SEED_LEN * 2 || !hex.is_ascii() {
+            return Err(BackupError::InvalidRecoverySeed);
+        }
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| BackupError::InvalidRecoverySeed)?;
+
+        let mut buf = &bytes[..];
+        let mut entropy = [0; RECOVERY_SEED_ENTROPY_LEN];
+        buf.copy_to_slice(&mut entropy);
+        let created_at = DateTime::from_timestamp(buf.get_u32() as i64, 0)
+            .ok_or(BackupError::InvalidRecoverySeed)?;
+        let seed = RecoverySeed {
+            entropy,
+            created_at,
+        };
+        if seed.to_bytes()[..] != bytes[..] {
+            return Err(BackupError::InvalidRecoverySeed);
+        }
+        Ok(seed)
+    }
+}
+
+pub fn create_backup(
+    secrets: Vec<Secret>,
+    config: BackupConfig,
+) -> Result<Vec<BackupShard>, BackupError> {
+    create_backup_with(
+        secrets,
+        config,
+        Utc::now().trunc_subsecs(0),
+        &mut thread_rng(),
+    )
+}
+
+/// Creates the same shards every time it is given the same secrets, config and seed.
+pub fn create_backup_deterministic(
+    secrets: Vec<Secret>,
+    config: BackupConfig,
+    seed: &RecoverySeed,
+) -> Result<Vec<BackupShard>, BackupError> {
+    let mut rng = seed.rng(&secrets, &config);
+    create_backup_with(secrets, config, seed.created_at, &mut rng)
+}
+
+fn create_backup_with<R: RngCore + CryptoRng>(
+    mut secrets: Vec<Secret>,
+    config: BackupConfig,
+    created_at: DateTime<Utc>,
+    rng: &mut R,
+) -> Result<Vec<BackupShard>, BackupError> {
+    config.validate()?;
+    if config.slip39 {
+        return create_slip39_shards(&secrets, &config, rng);
+    }
+    if let Some(key_file) = &config.key_file {
+        for secret in &mut secrets {
+            secret.password = key_file_password(&secret.password, key_file);
+        }
+    }
+    if let Some((_, hardware_key)) = &config.hardware_key {
+        for secret in &mut secrets {
+            secret.password = hardware_key_password(&secret.password, hardware_key);
+        }
+    }
+    let compressed = should_compress(&secrets);
+    let max = max_secret_len(&config);
+    if let Some(actual) = secrets
+        .iter()
+        .map(|secret| stored_len(secret, compressed))
+        .find(|&len| len > max)
+    {
+        return Err(BackupError::SecretTooLarge { max, actual });
+    }
+    let template = BackupShard {
+        number: 0,
+        threshold: config.min_shards,
+        backup_id: rng.next_u64(),
+        data: Vec::new(),
+        expires_at: None,
+        metadata: Some(ShardMetadata {
+            label: config.label,
+            created_at,
+            total_shards: config.total_shards,
+            kdf: Some(config.kdf),
+            cipher: Some(config.cipher),
+            key_file: Some(config.key_file.is_some()),
+            compressed,
+            not_before: config.not_before,
+            verify_by: config.verify_after.and_then(|years| {
+                created_at
+                    .date_naive()
+                    .with_day(1)?
+                    .checked_add_months(Months::new(12 * years as u32))
+            }),
+            sharing: config.sharing,
+            hardware_key: config.hardware_key.map(|(challenge, _)| challenge),
+        }),
+        slip39: None,
+    };
+    let ciphertext = encrypt_secrets_with_rng(
+        secrets,
+        &template.associated_data(),
+        config.kdf,
+        config.cipher,
+        compressed,
+        rng,
+    )
+    .map_err(|error| match error.downcast::<CryptoError>() {
+        Ok(CryptoError::SecretTooLong(actual)) => BackupError::SecretTooLarge {
+            max: config.cipher.max_secret_len(),
+            actual,
+        },
+        Ok(error) => BackupError::CryptoFailure(error.to_string()),
+        Err(error) => BackupError::CryptoFailure(error.to_string()),
+    })?;
+
+    let shards = config
+        .sharing
+        .split(&ciphertext, config.min_shards, config.total_shards, rng)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, data)| BackupShard {
+            number: index + 1,
+            data,
+            ..template.clone()
+        })
+        .collect::<Vec<BackupShard>>();
+
+    Ok(shards)
+}
+
+/// Deals the only secret as SLIP-0039 shares, encrypted with its passphrase by the standard.
+fn create_slip39_shards(
+    secrets: &[Secret],
+    config: &BackupConfig,
+    rng: &mut dyn CryptoRngCore,
+) -> Result<Vec<BackupShard>, BackupError> {
+    let secret = match secrets {
+        [secret] => secret,
+        [] => return Err(Slip39Error::InvalidSecretLength(0).into()),
+        _ => return Err(ConfigError::Slip39Unsupported("more than one secret").into()),
+    };
+    if !secret.note.is_empty() {
+        return Err(ConfigError::Slip39Unsupported("a note").into());
+    }
+    if secret.encoding != SecretEncoding::Text {
+        return Err(Slip39Error::NotHex.into());
+    }
+    let mut master_secret = parse_master_secret(&secret.value)?;
+    let shares = slip39::split_master_secret(
+        &master_secret,
+        &secret.password,
+        config.min_shards,
+        config.total_shards,
+        rng,
+    );
+    master_secret.zeroize();
+    shares?
+        .into_iter()
+        .map(|share| Ok(BackupShard::from_slip39(share)?))
+        .collect()
+}
+
+/// Backs up data of any size: it is encrypted from `reader` to `writer` under a random key, and
+/// only that key is split into the shards. The encrypted file has to be kept as well, but can be
+/// stored anywhere, as it is bound to the backup ID. `progress` is called with the number of
+/// bytes encrypted so far.
+pub fn create_backup_stream<R: Read, W: Write>(
+    reader: R,
+    writer: W,
+    config: BackupConfig,
+    passphrase: &str,
+    progress: impl FnMut(u64),
+) -> anyhow::Result<Vec<BackupShard>> {
+    let mut secret = STREAM_KEY_MAGIC.to_vec();
+    secret.extend(random::<[u8; STREAM_KEY_LEN]>());
+    let secrets = vec![Secret {
+        value: base64::encode(&secret).into(),
+        password: passphrase.into(),
+        encoding: SecretEncoding::Base64,
+        note: Default::default(),
+    }];
+    let result = create_backup(secrets, config)
+        .map_err(anyhow::Error::from)
+        .and_then(|shards| {
+            let backup_id = shards[0].backup_id.to_be_bytes();
+            encrypt_stream(
+                reader,
+                writer,
+                &secret[STREAM_KEY_MAGIC.len()..],
+                &backup_id,
+                progress,
+            )?;
+            Ok(shards)
+        });
+    secret.zeroize();
+    result
+}
+
+/// Restores the key from the shares of a backup made by `create_backup_stream`, and decrypts
+/// the file from `reader` to `writer` with it. Returns the number of bytes restored.
+pub fn restore_backup_stream<R: Read, W: Write>(
+    shares: Vec<EncodedShare>,
+    passphrase: &str,
+    key_file: Option<&KeyFileDigest>,
+    reader: R,
+    writer: W,
+    progress: impl FnMut(u64),
+) -> anyhow::Result<u64> {
+    let backup_id = match shares.first() {
+        Some(share) => BackupShard::from_bytes(share.clone())?.backup_id,
+        None => {
+            return Err(RestoreError::NotEnoughShares {
+                provided: 0,
+                required: 1,
+            }
+            .into())
+        }
+    };
+    let restored = restore_backup(shares, passphrase, key_file)?;
+    decrypt_backup_stream(&restored.secret, backup_id, reader, writer, progress)
+}
+
+/// Whether a restored secret is the key of a file encrypted by `create_backup_stream`.
+pub fn is_stream_key(secret: &DecryptedSecret) -> bool {
+    secret.encoding == SecretEncoding::Base64
+        && secret.to_bytes().is_ok_and(|mut bytes| {
+            let found = bytes.len() == STREAM_KEY_MAGIC.len() + STREAM_KEY_LEN
+                && bytes.starts_with(STREAM_KEY_MAGIC);
+            bytes.zeroize();
+            found
+        })
+}
+
+/// Decrypts a file encrypted by `create_backup_stream` with the key restored from its shards.
+pub fn decrypt_backup_stream<R: Read, W: Write>(
+    secret: &DecryptedSecret,
+    backup_id: u64,
+    reader: R,
+    writer: W,
+    progress: impl FnMut(u64),
+) -> anyhow::Result<u64> {
+    if !is_stream_key(secret) {
+        return Err(BackupError::NotStreamed.into());
+    }
+    let mut key = secret.to_bytes()?;
+    let result = decrypt_stream(
+        reader,
+        writer,
+        &key[STREAM_KEY_MAGIC.len()..],
+        &backup_id.to_be_bytes(),
+        progress,
+    );
+    key.zeroize();
+    result
+}
+
+pub fn restore_shards(shards: &[BackupShard], password: &str) -> anyhow::Result<DecryptedSecret> {
+    if shards.iter().any(|shard| shard.slip39.is_some()) {
+        return Ok(decrypt_slip39(shards, password)?);
+    }
+    let ciphertext = combine_shards(shards)?;
+    let associated_data = shards
+        .first()
+        .map(BackupShard::associated_data)
+        .unwrap_or_default();
+    let conflicting = shards
+        .iter()
+        .filter(|shard| shard.associated_data() != associated_data)
+        .map(|shard| shard.number)
+        .collect::<Vec<usize>>();
+    if !conflicting.is_empty() {
+        return Err(BackupError::InconsistentMetadata(conflicting).into());
+    }
+    let metadata = shards.first().and_then(|shard| shard.metadata.as_ref());
+    let kdf = metadata
+        .and_then(|metadata| metadata.kdf)
+        .unwrap_or_default();
+    let cipher = metadata
+        .and_then(|metadata| metadata.cipher)
+        .unwrap_or_default();
+    let compressed = metadata.is_some_and(|metadata| metadata.compressed);
+    decrypt_secret(
+        &ciphertext,
+        password,
+        &associated_data,
+        kdf,
+        cipher,
+        compressed,
+    )
+}
+
+/// Restores the master secret of SLIP-0039 shares, in hex. Nothing checks the passphrase: a wrong
+/// one restores a different master secret.
+fn decrypt_slip39(shards: &[BackupShard], password: &str) -> Result<DecryptedSecret, BackupError> {
+    let shares = shards
+        .iter()
+        .map(|shard| shard.to_slip39().ok_or(Slip39Error::MismatchedShares))
+        .collect::<Result<Vec<Slip39Share>, Slip39Error>>()?;
+    let mut master_secret =
+        slip39::combine_shares(&shares, password).map_err(|error| match error {
+            Slip39Error::NotEnoughShares { provided, required } => {
+                BackupError::NotEnoughShards { provided, required }
+            }
+            error => BackupError::Slip39(error),
+        })?;
+    let value = SecretString::from(format_master_secret(&master_secret));
+    master_secret.zeroize();
+    Ok(DecryptedSecret {
+        value,
+        encoding: SecretEncoding::Text,
+        note: SecretString::default(),
+    })
+}
+
+#[derive(Clone, Debug)]
+pub struct RestoredSecret {
+    pub secret: DecryptedSecret,
+    /// Numbers of the shards that were combined, in ascending order
+    pub shards_used: Vec<usize>,
+    pub threshold: u8,
+    pub metadata: Option<ShardMetadata>,
+}
+
+/// The password the secrets of these shards were encrypted with, which includes the key file and
+/// the secret of the hardware key if the backup requires them.
+pub fn unlock_password(
+    shards: &[BackupShard],
+    passphrase: &str,
+    key_file: Option<&KeyFileDigest>,
+    hardware_key: Option<&HardwareKeySecret>,
+) -> Result<SecretString, BackupError> {
+    let required = shards.iter().any(BackupShard::requires_key_file);
+    let password = match (required, key_file) {
+        (true, Some(key_file)) => key_file_password(passphrase, key_file),
+        (true, None) => return Err(BackupError::KeyFileRequired),
+        (false, _) => passphrase.into(),
+    };
+    match (
+        shards.iter().any(|shard| shard.hardware_key().is_some()),
+        hardware_key,
+    ) {
+        (true, Some(hardware_key)) => Ok(hardware_key_password(&password, hardware_key)),
+        (true, None) => Err(BackupError::HardwareKeyRequired),
+        (false, _) => Ok(password),
+    }
+}
+
+/// Decodes the shares, combines them and decrypts the secret matching the passphrase, and the key
+/// file if the backup requires one. Backups that require a hardware key ask the connected one,
+/// which waits for it to be touched.
+pub fn restore_backup(
+    shares: Vec<EncodedShare>,
+    passphrase: &str,
+    key_file: Option<&KeyFileDigest>,
+) -> Result<RestoredSecret, RestoreError> {
+    if shares.is_empty() {
+        return Err(RestoreError::NotEnoughShares {
+            provided: 0,
+            required: 1,
+        });
+    }
+
+    let shards = shares
+        .into_iter()
+        .enumerate()
+        .map(|(index, data)| {
+            BackupShard::from_bytes(data).map_err(|error| match error.downcast_ref() {
+                Some(BackupError::NewerVersion(_)) => RestoreError::Other(error.to_string()),
+                Some(BackupError::DamagedShard(number)) => RestoreError::ShareVerificationError {
+                    index,
+                    number: *number,
+                },
+                _ => RestoreError::CorruptShare(index),
+            })
+        })
+        .collect::<Result<Vec<BackupShard>, RestoreError>>()?;
+
+    if key_file.is_none() && shards.iter().any(BackupShard::requires_key_file) {
+        return Err(RestoreError::KeyFileRequired);
+    }
+    let hardware_key = shards
+        .iter()
+        .find_map(BackupShard::hardware_key)
+        .map(unlock_hardware_key)
+        .transpose()
+        .map_err(RestoreError::HardwareKey)?;
+    let password = unlock_password(&shards, passphrase, key_file, hardware_key.as_ref())
+        .map_err(|error| restore_error(error.into()))?;
+    let secret = restore_shards(&shards, &password).map_err(restore_error)?;
+    let mut shards_used = shards
+        .iter()
+        .map(|shard| shard.number)
+        .collect::<Vec<usize>>();
+    shards_used.sort_unstable();
+    shards_used.dedup();
+    let first = &shards[0];
+    Ok(RestoredSecret {
+        secret,
+        shards_used,
+        threshold: first.threshold,
+        metadata: first.metadata.clone(),
+    })
+}
+
+fn restore_error(error: anyhow::Error) -> RestoreError {
+    match error.downcast_ref() {
+        Some(CryptoError::WrongPassphrase) => return RestoreError::WrongPassphrase,
+        Some(CryptoError::MetadataMismatch) => return RestoreError::MetadataMismatch,
+        // All shares of a backup are the same length, so the first is as short as any
+        Some(CryptoError::TruncatedBlob) => return RestoreError::CorruptShare(0),
+        _ => {}
+    }
+    match error.downcast::<BackupError>() {
+        Ok(BackupError::NotEnoughShards { provided, required }) => {
+            RestoreError::NotEnoughShares { provided, required }
+        }
+        Ok(BackupError::MismatchedShards {
+            expected,
+            conflicting,
+        }) => RestoreError::MismatchedBackups {
+            expected,
+            conflicting,
+        },
+        Ok(BackupError::KeyFileRequired) => RestoreError::KeyFileRequired,
+        Ok(BackupError::HardwareKeyRequired) => RestoreError::HardwareKeyRequired,
+        Ok(error) => RestoreError::Other(error.to_string()),
+        Err(error) => RestoreError::Other(error.to_string()),
+    }
+}
+
+/// Restores the backup from the minimum number of shards and checks it against the original secret.
+pub fn verify_backup(
+    shards: &[BackupShard],
+    password: &str,
+    expected_secret: &str,
+) -> anyhow::Result<bool> {
+    let threshold = shards.first().map_or(0, |shard| shard.threshold as usize);
+
+    // Round trip through the serialized form so encoding bugs are caught as well
+    let parsed = shards
+        .iter()
+        .take(threshold)
+        .map(|shard| BackupShard::from_bytes(shard.to_bytes()))
+        .collect::<anyhow::Result<Vec<BackupShard>>>()?;
+
+    let restored = restore_shards(&parsed, password)?;
+    // A master secret is restored the way it is written in SLIP-0039 test vectors
+    let expected = match parsed.iter().any(|shard| shard.slip39.is_some()) {
+        true => SecretString::from(format_master_secret(&parse_master_secret(expected_secret)?)),
+        false => SecretString::from(expected_secret),
+    };
+    Ok(restored.value.as_bytes().ct_eq(expected.as_bytes()).into())
+}
+
+/// Largest number of shards the diagnostic pass will search through, as the number of
+/// combinations grows quickly.
+pub const MAX_DIAGNOSE_SHARDS: usize = 8;
+
+#[derive(Clone, Debug)]
+pub struct Diagnosis {
+    pub secret: DecryptedSecret,
+    pub used: Vec<usize>,
+    pub damaged: Vec<usize>,
+}
+
+/// Tries threshold-sized subsets of the shards until one decrypts, then checks which of the
+/// remaining shards break decryption when swapped in.
+pub fn diagnose_shards(shards: &[BackupShard], password: &str) -> Option<Diagnosis> {
+    let threshold = shards.iter().map(|shard| shard.threshold).max()? as usize;
+    if shards.len() <= threshold || shards.len() > MAX_DIAGNOSE_SHARDS {
+        return None;
+    }
+
+    for subset in combinations(shards.len(), threshold) {
+        let candidate = subset
+            .iter()
+            .map(|&index| shards[index].clone())
+            .collect::<Vec<BackupShard>>();
+        let secret = match restore_shards(&candidate, password) {
+            Ok(secret) => secret,
+            Err(_) => continue,
+        };
+
+        let damaged = (0..shards.len())
+            .filter(|index| !subset.contains(index))
+            .filter(|&index| {
+                let mut swapped = candidate.clone();
+                swapped[0] = shards[index].clone();
+                restore_shards(&swapped, password).is_err()
+            })
+            .map(|index| shards[index].number)
+            .collect();
+
+        return Some(Diagnosis {
+            secret,
+            used: candidate.iter().map(|shard| shard.number).collect(),
+            damaged,
+        });
+    }
+
+    None
+}
+
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 {
+        return vec![Vec::new()];
+    }
+    (k - 1..n)
+        .flat_map(|last| {
+            combinations(last, k - 1)
+                .into_iter()
+                .map(move |mut subset| {
+                    subset.push(last);
+                    subset
+                })
+        })
+        .collect()
+}
+
+/// Number of shards a backup is printed as. Shards added later are numbered after the original
+/// total, so the highest number counts them too.
+pub fn shard_total(shards: &[BackupShard]) -> usize {
+    shards.iter().map(|shard| shard.number).max().unwrap_or(0)
+}
+
+/// Shards may be given in any order, since each share carries its own x coordinate.
+pub fn combine_shards(shards: &[BackupShard]) -> anyhow::Result<Vec<u8>> {
+    if let Some(first) = shards.first() {
+        let conflicting = shards
+            .iter()
+            .filter(|shard| shard.backup_id != first.backup_id)
+            .map(|shard| shard.number)
+            .collect::<Vec<usize>>();
+        if !conflicting.is_empty() {
+            return Err(BackupError::MismatchedShards {
+                expected: first.number,
+                conflicting,
+            }
+            .into());
+        }
+    }
+
+    let threshold = shards
+        .iter()
+        .map(|shard| shard.threshold)
+        .max()
+        .unwrap_or(0);
+    let shares = shards
+        .iter()
+        .map(|shard| &shard.data[..])
+        .collect::<Vec<&[u8]>>();
+    Ok(sharing_of(shards).combine(&shares, threshold)?)
+}
+
+/// The scheme the shards were dealt with, which all of them record alike.
+fn sharing_of(shards: &[BackupShard]) -> &'static dyn SharingScheme {
+    if shards.iter().any(|shard| shard.slip39.is_some()) {
+        return &Slip39Shamir;
+    }
+    shards
+        .iter()
+        .find_map(|shard| shard.metadata.as_ref())
+        .map_or(SssBackend::Sharks, |metadata| metadata.sharing)
+        .scheme()
+}
+
+/// What is known of an existing backup that shards are added to.
+pub enum RestoredContext {
+    /// At least a threshold of its shards, which fix the polynomial they were dealt from, and
+    /// the password of `unlock_password` to check they belong together
+    Shards {
+        shards: Vec<BackupShard>,
+        password: SecretString,
+    },
+    /// The secrets and config it was created with, and its recovery seed if it has one
+    Secrets {
+        secrets: Vec<Secret>,
+        config: BackupConfig,
+        seed: Option<RecoverySeed>,
+    },
+}
+
+/// Deals `additional` shards for an existing backup, numbered after the highest one known. They
+/// lie on the same polynomial as the shards printed before, so any threshold of old and new
+/// shards restores the backup, and they carry the same metadata, including its total.
+///
+/// Without a recovery seed, the random coefficients of a backup are only kept in its shards, so
+/// its secrets alone are refused.
+pub fn extend_backup(
+    existing: RestoredContext,
+    additional: u8,
+) -> anyhow::Result<Vec<BackupShard>> {
+    extend_backup_from(existing, None, additional)
+}
+
+/// Like `extend_backup`, numbering the added shards from `first_number` instead. Shards added
+/// before that weren't loaded aren't known, so their numbers have to be skipped this way.
+pub fn extend_backup_from(
+    existing: RestoredContext,
+    first_number: Option<usize>,
+    additional: u8,
+) -> anyhow::Result<Vec<BackupShard>> {
+    let shards = match existing {
+        RestoredContext::Shards { shards, password } => {
+            // Shards that don't decrypt would only pass their damage on to the new ones
+            restore_shards(&shards, &password)?;
+            shards
+        }
+        RestoredContext::Secrets { seed: None, .. } => {
+            return Err(BackupError::NotExtendable.into())
+        }
+        RestoredContext::Secrets {
+            secrets,
+            config,
+            seed: Some(seed),
+        } => create_backup_deterministic(secrets, config, &seed)?,
+    };
+
+    let first = shards.first().ok_or(BackupError::NotEnoughShards {
+        provided: 0,
+        required: 1,
+    })?;
+    if first.is_legacy() {
+        return Err(BackupError::InvalidShard(String::from(
+            "Shards of the first release don't record their threshold, so none can be added",
+        ))
+        .into());
+    }
+    if first.slip39.is_some() {
+        return Err(BackupError::from(Slip39Error::NotExtendable).into());
+    }
+    let total = first
+        .metadata
+        .as_ref()
+        .map_or(0, |metadata| metadata.total_shards as usize);
+    let known = shards
+        .iter()
+        .map(|shard| shard.number)
+        .fold(total, usize::max);
+    let next = match first_number {
+        Some(number) if number <= known => return Err(BackupError::ShardNumberTaken(number).into()),
+        Some(number) => number,
+        None => known + 1,
+    };
+    let last = next + additional as usize - 1;
+    if last > u8::MAX as usize {
+        return Err(BackupError::TooManyShards(last).into());
+    }
+
+    let existing = shards
+        .iter()
+        .map(|shard| &shard.data[..])
+        .collect::<Vec<&[u8]>>();
+    (next..=last)
+        .map(|number| {
+            Ok(BackupShard {
+                number,
+                threshold: first.threshold,
+                backup_id: first.backup_id,
+                data: sharing_of(&shards).extend(&existing, first.threshold, number as u8)?,
+                expires_at: first.expires_at,
+                metadata: first.metadata.clone(),
+                slip39: None,
+            })
+        })
+        .collect()
+}
+
+/// Splits the encrypted secrets into shares and combines them again. Shards record which
+/// `SssBackend` dealt them, another scheme needs a flag of its own to stay restorable. Schemes
+/// are picked at runtime as `&dyn SharingScheme`, like `Slip39Shamir` for SLIP-0039 shares.
+pub trait SharingScheme {
+    /// Deals `count` shares, any `threshold` of which recover `secret`.
+    fn split(
+        &self,
+        secret: &[u8],
+        threshold: u8,
+        count: u8,
+        rng: &mut dyn CryptoRngCore,
+    ) -> Result<Vec<Vec<u8>>, BackupError>;
+
+    /// Recovers the secret from at least `threshold` distinct shares, in any order.
+    fn combine(&self, shares: &[&[u8]], threshold: u8) -> Result<Vec<u8>, BackupError>;
+
+    /// Deals the share at x coordinate `x` from at least `threshold` of the others.
+    fn extend(&self, shares: &[&[u8]], threshold: u8, x: u8) -> Result<Vec<u8>, BackupError> {
+        // Addition is XOR in GF(2^8), so moving every share by x makes combine interpolate the
+        // polynomial at x rather than at 0
+        let mut moved = Vec::with_capacity(shares.len());
+        for &share in shares {
+            match share.first() {
+                Some(&share_x) if share_x == x => {
+                    return Err(BackupError::InvalidShard(format!(
+                        "Shard {} exists already",
+                        x
+                    )))
+                }
+                Some(&share_x) => {
+                    let mut share = share.to_vec();
+                    share[0] = share_x ^ x;
+                    moved.push(share);
+                }
+                None => return Err(BackupError::InvalidShard(String::from("Empty share"))),
+            }
+        }
+        let moved_shares = moved.iter().map(Vec::as_slice).collect::<Vec<&[u8]>>();
+        let mut share = vec![x];
+        share.extend(self.combine(&moved_shares, threshold)?);
+        Ok(share)
+    }
+}
+
+/// Shamir's secret sharing as done by sharks, byte by byte over GF(2^8) with the polynomial
+/// x^8 + x^4 + x^3 + x^2 + 1. Printed shards depend on this field, unlike the AES field of
+/// `Gf256Shamir`. The first byte of a share is its x coordinate.
+pub struct Shamir;
+
+impl SharingScheme for Shamir {
+    fn split(
+        &self,
+        secret: &[u8],
+        threshold: u8,
+        count: u8,
+        mut rng: &mut dyn CryptoRngCore,
+    ) -> Result<Vec<Vec<u8>>, BackupError> {
+        let shares = Sharks(threshold)
+            .dealer_rng(secret, &mut rng)
+            .take(count as usize)
+            .map(|share| Vec::from(&share))
+            .collect::<Vec<Vec<u8>>>();
+        if shares.len() != count as usize {
+            return Err(BackupError::SharingFailure(format!(
+                "{} of {} shards were dealt",
+                shares.len(),
+                count
+            )));
+        }
+        Ok(shares)
+    }
+
+    fn combine(&self, shares: &[&[u8]], threshold: u8) -> Result<Vec<u8>, BackupError> {
+        let mut decoded = Vec::<Share>::new();
+        for &data in shares {
+            let share =
+                Share::try_from(data).map_err(|e| BackupError::InvalidShard(e.to_owned()))?;
+
+            // Interpolating the same point twice would corrupt the result
+            if decoded.iter().all(|s| s.x.0 != share.x.0) {
+                decoded.push(share);
+            }
+        }
+
+        if decoded.len() < threshold as usize {
+            return Err(BackupError::NotEnoughShards {
+                provided: decoded.len(),
+                required: threshold as usize,
+            });
+        }
+
+        Sharks(decoded.len() as u8)
+            .recover(&decoded[..])
+            .map_err(|e| BackupError::SharksError(e.to_owned()))
+    }
+}
+
+/// Shamir's secret sharing as done by vsss-rs, byte by byte in constant time over GF(2^8) with the
+/// AES polynomial x^8 + x^4 + x^3 + x + 1, the field of SLIP-0039 and Vault. The first byte of a
+/// share is its x coordinate, like with `Shamir`.
+pub struct Gf256Shamir;
+
+impl SharingScheme for Gf256Shamir {
+    fn split(
+        &self,
+        secret: &[u8],
+        threshold: u8,
+        count: u8,
+        rng: &mut dyn CryptoRngCore,
+    ) -> Result<Vec<Vec<u8>>, BackupError> {
+        if threshold == 0 || threshold > count {
+            return Err(BackupError::SharingFailure(format!(
+                "Can't deal {} of {} shards",
+                threshold, count
+            )));
+        }
+        // vsss-rs wants a polynomial of degree 1 at least, the shares of a constant one are the
+        // secret itself
+        if threshold == 1 {
+            return Ok((1..=count)
+                .map(|x| {
+                    let mut share = vec![x];
+                    share.extend_from_slice(secret);
+                    share
+                })
+                .collect());
+        }
+        vsss_rs::Gf256::split_array(threshold as usize, count as usize, secret, rng)
+            .map_err(|e| BackupError::SharingFailure(e.to_string()))
+    }
+
+    fn combine(&self, shares: &[&[u8]], threshold: u8) -> Result<Vec<u8>, BackupError> {
+        let mut decoded = Vec::<Vec<u8>>::new();
+        for &data in shares {
+            if data.len() < 2 || data[0] == 0 {
+                return Err(BackupError::InvalidShard(String::from("Invalid share")));
+            }
+
+            // Interpolating the same point twice would corrupt the result
+            if decoded.iter().all(|s| s[0] != data[0]) {
+                decoded.push(data.to_vec());
+            }
+        }
+
+        if decoded.is_empty() || decoded.len() < threshold as usize {
+            return Err(BackupError::NotEnoughShards {
+                provided: decoded.len(),
+                required: threshold.max(1) as usize,
+            });
+        }
+        if decoded.len() == 1 {
+            return Ok(decoded.swap_remove(0).split_off(1));
+        }
+
+        vsss_rs::Gf256::combine_array(&decoded)
+            .map_err(|e| BackupError::InvalidShard(e.to_string()))
+    }
+}
+
+/// Secret sharing the shards are dealt with, recorded in the shards. Both backends share each byte
+/// on its own over GF(2^8), so a share is one byte longer than the encrypted secrets either way,
+/// the longest secret is the same and there are at most 255 shards. They only differ in the
+/// field, so shares of one can't be combined by the other.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SssBackend {
+    /// sharks over x^8 + x^4 + x^3 + x^2 + 1, see `Shamir`.
+    #[default]
+    Sharks,
+    /// vsss-rs over the AES field, see `Gf256Shamir`.
+    Gf256,
+}
+
+impl SssBackend {
+    pub const ALL: [SssBackend; 2] = [SssBackend::Sharks, SssBackend::Gf256];
+
+    fn is_default(&self) -> bool {
+        *self == SssBackend::default()
+    }
+
+    pub fn scheme(self) -> &'static dyn SharingScheme {
+        match self {
+            SssBackend::Sharks => &Shamir,
+            SssBackend::Gf256 => &Gf256Shamir,
+        }
+    }
+}
+
+impl fmt::Display for SssBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SssBackend::Sharks => write!(f, "sharks"),
+            SssBackend::Gf256 => write!(f, "gf256"),
+        }
+    }
+}
+
+impl FromStr for SssBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SssBackend::ALL
+            .into_iter()
+            .find(|backend| backend.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("'{}' is not sharks or gf256", s))
+    }
+}
+
+impl SharingScheme for SssBackend {
+    fn split(
+        &self,
+        secret: &[u8],
+        threshold: u8,
+        count: u8,
+        rng: &mut dyn CryptoRngCore,
+    ) -> Result<Vec<Vec<u8>>, BackupError> {
+        self.scheme().split(secret, threshold, count, rng)
+    }
+
+    fn combine(&self, shares: &[&[u8]], threshold: u8) -> Result<Vec<u8>, BackupError> {
+        self.scheme().combine(shares, threshold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, SubsecRound, Utc};
+    use proptest::prelude::*;
+    use rand::{seq::SliceRandom, thread_rng, Rng};
+    use sharks::Sharks;
+
+    use crate::{
+        crypto::{
+            key_file_digest, stored_len, CipherSuite, KdfParams, Secret, SecretEncoding,
+            MAX_ARGON2_ITERATIONS, MAX_ARGON2_MEMORY_MIB, MAX_ARGON2_PARALLELISM, MAX_KDF_ROUNDS,
+            MIN_ARGON2_MEMORY_MIB, MIN_KDF_ROUNDS,
+        },
+        passphrase::gen_passphrase,
+        qrcode::qrcode_count,
+    };
+
+    use super::{
+        combinations, create_backup, create_backup_deterministic, create_backup_stream,
+        diagnose_shards, extend_backup, extend_backup_from, max_secret_len, max_share_len,
+        restore_backup, restore_backup_stream, restore_error, restore_shards, shard_tag, share_len,
+        unlock_password, verify_backup, BackupConfig, BackupShard, Gf256Shamir, PayloadHeader,
+        RecoverySeed, RestoredContext, Shamir, ShardMetadata, SharingScheme, SssBackend,
+        SHARD_HEADER_LEN, SHARD_MAGIC, SHARD_TAG_LEN, SHARD_VERSION, VERIFY_BY_LEN,
+    };
+    use crate::errors::{BackupError, ConfigError, CryptoError, RestoreError, Slip39Error};
+    use crate::hardware_key::{HardwareKeyChallenge, MAX_CREDENTIAL_ID_LEN};
+    use crate::qrcode::{parse_share_payload, share_payload};
+    use crate::slip39::{combine_mnemonics, format_master_secret, Slip39Shamir};
+
+    // After the header, the creation time and the total number of shards
+    const LABEL_LEN_OFFSET: usize = SHARD_HEADER_LEN + 8 + 1;
+
+    #[test]
+    fn config_builder_bounds() {
+        let build = |min, max| {
+            BackupConfig::builder()
+                .with_threshold(min)
+                .with_total(max)
+                .build()
+        };
+
+        let config = BackupConfig::builder()
+            .build()
+            .expect("Default config is valid");
+        assert_eq!((config.min_shards, config.total_shards), (1, 1));
+        assert!(build(1, 1).is_ok());
+        assert!(build(1, 255).is_ok());
+        assert!(build(255, 255).is_ok());
+        assert!(build(3, 5).is_ok());
+        assert_eq!(build(0, 5).err(), Some(ConfigError::ZeroThreshold));
+        assert_eq!(build(0, 0).err(), Some(ConfigError::ZeroThreshold));
+        assert_eq!(
+            build(6, 5).err(),
+            Some(ConfigError::ThresholdExceedsTotal {
+                threshold: 6,
+                total: 5
+            })
+        );
+        assert_eq!(
+            build(255, 254).err(),
+            Some(ConfigError::ThresholdExceedsTotal {
+                threshold: 255,
+                total: 254
+            })
+        );
+    }
+
+    #[test]
+    fn create_backup_errors() {
+        let secret = |value: String, password: &str| Secret {
+            value: value.into(),
+            password: password.into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        };
+        let config = || {
+            BackupConfig::builder()
+                .with_kdf(KdfParams::Pbkdf2 { rounds: 1_000 })
+                .build()
+                .unwrap()
+        };
+
+        let max = max_secret_len(&config());
+        let random = |len| {
+            let mut data = vec![0u8; len];
+            thread_rng().fill(&mut data[..]);
+            base64::encode(data)
+        };
+        let too_large = Secret {
+            encoding: SecretEncoding::Base64,
+            ..secret(random(max), "passphrase")
+        };
+        assert!(matches!(
+            create_backup(vec![too_large], config()),
+            Err(BackupError::SecretTooLarge { max: m, actual }) if m == max && actual == max + 1
+        ));
+
+        let invalid = BackupConfig {
+            min_shards: 3,
+            total_shards: 2,
+            ..config()
+        };
+        assert!(matches!(
+            create_backup(vec![secret("secret".into(), "passphrase")], invalid),
+            Err(BackupError::InvalidConfig {
+                reason: ConfigError::ThresholdExceedsTotal {
+                    threshold: 3,
+                    total: 2
+                }
+            })
+        ));
+
+        assert!(matches!(
+            create_backup(vec![secret("secret".into(), " ")], config()),
+            Err(BackupError::CryptoFailure(_))
+        ));
+        assert!(matches!(
+            create_backup(vec![secret("secret".into(), "passphrase"); 5], config()),
+            Err(BackupError::CryptoFailure(_))
+        ));
+    }
+
+    // Shards printed by version 1, before the checksum was added. They must keep restoring.
+    const V1_FIXTURE_PASSPHRASE: &str = "correct horse battery staple";
+    const V1_FIXTURE_SECRET: &str = "Hyperbacked v1 fixture";
+    const V1_FIXTURES: [&str; 2] = [
+        "009E_gmD!>r?rRyK*Om(w`4&%<x$g^ZC(oV5#k~>L!W$ON_@hJSnqY4VAe)TJvr~{?CNgT(L|VYUllo*hl%Bd0cC_ZW6*2*qwryh9VQ)x?778|_@;&UA-LUq#}i_K?dEWo_l`Y6_G>~-LWcDl1s2tiG~D<Tq}%}",
+        "009E_gmD!>r?rRzQ9*I*)I*^vhNvxrJp04^(4dsK6?JZfpP=lJ-kzy{-qeTjYG^+I7sNnslL$w;6dO#VqShm}-8)#s%UE?`P+8&;LC>e=NH|9y2Xe7}vX8mc74adH_z(hojLqjkyICmsEbKLIXy~V14R1zQ{5P8",
+    ];
+
+    // Shards of the first release (58f1139), the bare share without any header. Created with
+    // a decoy, like the fixtures above.
+    const LEGACY_FIXTURE_SECRET: &str = "This is my secret";
+    const LEGACY_FIXTURE_DECOY: &str = "This is my decoy";
+    const LEGACY_FIXTURES: [&str; 3] = [
+        "0Y6vSM@uNDML#G5s0CO*d`6QuS(L6{>jGAN*YY3_8yf*A+}KFTeV4b*{A7@7pK$e7@mO*5qqM9}aA<`qiV-a3)J2*E9f)EWUdQ{*N)hu!>gXdaqmQ>i6Bo!>uIF{~HPzF_LdTlV7>ANW#0OSsTR)4#@m&@p=-d%38$x3op<Ba%?j{Y96w!VYPd=<b1k4E)&2abk*9PIK;+x&3+o(u+&{)2I@9Q1uT_2&fW0tT+*n<",
+        "0&WKlJwHBEP;s5jdLj+KCA1wc4?%@qV^WxoZO_pFS{1s+1sAtk%h~nQIxLuPx{k(2NDTqelz=_s@umk+{A>KNE5LK{Cfa{8B()TEvwoZdd%bq_5xL>@4cp?u;B(~xF4=kZ@<Bdc3r;-G6=#Mn7!5VkPMz;aKaOInWFS0dB>)VVVE6#LGj7b2FiJnVJi#KM7Kh`}(_Xt?tUto>z)LBt60G3i!3#}L>zR>6^|7v?QAh",
+        "1FAPc;1kF6s7nQWG(SEx5)o%7J^7rf8)7Nn@Go@*W#rSGxl-aQQsc|ncob`FQZLs?)IQqJa%Z`7ju&~1CALR)en{V<#2<9u`J1{O<XgEu|BgjSf2-4(2cHkN+VquBC*2?CX~?PC@d>R!T1Pz-(&%3((A~J2a!a_@y7dXWS;9$>8nW<KF7Tol?q*Kx-!VHlUm@=}B%O`;mZ&^8$>IGJrwbuq+n{~Sau9*whpeOt-TD",
+    ];
+
+    #[test]
+    fn round_trip_test() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig {
+            total_shards: 5,
+            min_shards: 3,
+            label: String::new(),
+            kdf: KdfParams::default(),
+            cipher: CipherSuite::default(),
+            sharing: SssBackend::default(),
+            key_file: None,
+            not_before: None,
+            verify_after: None,
+            slip39: false,
+            hardware_key: None,
+        };
+
+        let shards = create_backup(secrets, config).expect("Failed to create backup");
+        assert_eq!(shards.len(), 5);
+
+        let restored =
+            restore_shards(&shards[1..4], &passphrase).expect("Failed to restore backup");
+        assert_eq!(&*restored.value, "This is my secret");
+    }
+
+    #[test]
+    fn xchacha_round_trip_test() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig::builder()
+            .with_threshold(2)
+            .with_total(3)
+            .with_kdf(KdfParams::Pbkdf2 { rounds: 1_000 })
+            .with_cipher(CipherSuite::XChaCha20Poly1305)
+            .build()
+            .unwrap();
+
+        let shards = create_backup(secrets, config)
+            .expect("Failed to create backup")
+            .iter()
+            .map(|shard| BackupShard::from_bytes(shard.to_bytes()).unwrap())
+            .collect::<Vec<BackupShard>>();
+        assert!(shards.iter().all(|shard| {
+            shard.metadata.as_ref().unwrap().cipher == Some(CipherSuite::XChaCha20Poly1305)
+        }));
+        let restored = restore_shards(&shards[1..], &passphrase).expect("Failed to restore");
+        assert_eq!(&*restored.value, "This is my secret");
+
+        let mut tampered = shards.clone();
+        tampered[1].data[5] ^= 1;
+        assert!(restore_shards(&tampered[1..], &passphrase).is_err());
+    }
+
+    #[test]
+    fn shard_number_from_bytes() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig {
+            total_shards: 3,
+            min_shards: 2,
+            label: String::new(),
+            kdf: KdfParams::default(),
+            cipher: CipherSuite::default(),
+            sharing: SssBackend::default(),
+            key_file: None,
+            not_before: None,
+            verify_after: None,
+            slip39: false,
+            hardware_key: None,
+        };
+
+        let shards = create_backup(secrets, config).expect("Failed to create backup");
+        for shard in shards {
+            let parsed = BackupShard::from_bytes(shard.to_bytes()).expect("Failed to parse shard");
+            assert_eq!(parsed.number, shard.number);
+            assert_eq!(parsed.threshold, 2);
+            assert_eq!(parsed.backup_id, shard.backup_id);
+            assert_eq!(parsed.data, shard.data);
+        }
+    }
+
+    #[test]
+    fn display_fingerprint() {
+        let shard = BackupShard {
+            number: 1,
+            threshold: 1,
+            backup_id: 0x0123456789abcdef,
+            data: vec![1, 2, 3, 4, 5],
+            expires_at: None,
+            metadata: None,
+            slip39: None,
+        };
+        // Pinned, as users compare it against what was printed
+        assert_eq!(shard.display_fingerprint(), "E3557D2D");
+
+        // Shamir shares start with their x coordinate, which is the shard number
+        let other = BackupShard {
+            number: 2,
+            data: vec![2, 2, 3, 4, 5],
+            ..shard.clone()
+        };
+        assert_ne!(shard.display_fingerprint(), other.display_fingerprint());
+    }
+
+    #[test]
+    fn shard_survives_bip39_words() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let secrets = vec![Secret {
+            value: "Written down as words".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig::builder()
+            .with_threshold(2)
+            .with_total(3)
+            .build()
+            .expect("Invalid config");
+        let shards = create_backup(secrets, config).expect("Failed to create backup");
+
+        let words = shards
+            .iter()
+            .map(BackupShard::to_bip39_words)
+            .collect::<Vec<Vec<String>>>();
+        assert!(words.iter().all(|words| words.len() % 24 == 0));
+        let parsed = BackupShard::from_bip39_words(&words[2]).expect("Failed to parse words");
+        assert_eq!(parsed.to_bytes(), shards[2].to_bytes());
+
+        let restored = restore_backup(
+            [&words[0], &words[2]]
+                .into_iter()
+                .map(|words| BackupShard::from_bip39_words(words).unwrap().to_bytes())
+                .collect(),
+            &passphrase,
+            None,
+        )
+        .expect("Failed to restore");
+        assert_eq!(&*restored.secret.value, "Written down as words");
+    }
+
+    /// Shards of every version, with every combination of the optional metadata fields. Labels
+    /// go up to the 255 bytes their length prefix allows, including multi-byte characters.
+    fn arb_shard() -> impl Strategy<Value = BackupShard> {
+        let label = prop_oneof![
+            "[ -~]{0,255}",
+            prop::collection::vec(any::<char>(), 0..=63).prop_map(String::from_iter),
+        ];
+        let metadata = (
+            label,
+            0..i32::MAX as i64,
+            prop::option::of(prop_oneof![
+                (MIN_KDF_ROUNDS..=MAX_KDF_ROUNDS).prop_map(|rounds| KdfParams::Pbkdf2 { rounds }),
+                (
+                    MIN_ARGON2_MEMORY_MIB..=MAX_ARGON2_MEMORY_MIB,
+                    1..=MAX_ARGON2_ITERATIONS,
+                    1..=MAX_ARGON2_PARALLELISM,
+                )
+                    .prop_map(|(memory_mib, iterations, parallelism)| {
+                        KdfParams::Argon2id {
+                            memory_mib,
+                            iterations,
+                            parallelism,
+                        }
+                    }),
+            ]),
+            prop::option::of(prop::sample::select(CipherSuite::ALL.to_vec())),
+            any::<(bool, bool, bool)>(),
+            prop::option::of(0..i32::MAX as i64),
+            prop::option::of(any::<u16>()),
+            prop::option::of((
+                prop::collection::vec(any::<u8>(), 1..=MAX_CREDENTIAL_ID_LEN),
+                any::<[u8; 32]>(),
+            )),
+        );
+        (1..=255u8)
+            .prop_flat_map(move |number| {
+                (
+                    Just(number),
+                    1..=number,
+                    any::<u64>(),
+                    prop::collection::vec(any::<u8>(), 1..300),
+                    prop::option::of(metadata.clone()),
+                )
+            })
+            .prop_map(|(number, threshold, backup_id, share, metadata)| {
+                let metadata = metadata.map(
+                    |(
+                        label,
+                        created_at,
+                        kdf,
+                        cipher,
+                        flags,
+                        not_before,
+                        verify_by,
+                        hardware_key,
+                    )| {
+                        // Each field is only written if all the ones before it are
+                        let (has_flags, compressed, aes_field) = flags;
+                        let cipher = kdf.and(cipher);
+                        let key_file = cipher.and(has_flags.then_some(compressed));
+                        ShardMetadata {
+                            label,
+                            created_at: DateTime::from_timestamp(created_at, 0).unwrap(),
+                            total_shards: number,
+                            kdf,
+                            cipher,
+                            key_file,
+                            compressed: key_file.is_some() && compressed,
+                            not_before: key_file
+                                .and(not_before)
+                                .map(|not_before| DateTime::from_timestamp(not_before, 0).unwrap()),
+                            verify_by: key_file.and(verify_by).map(|months| {
+                                let months = months as i32;
+                                NaiveDate::from_ymd_opt(
+                                    1970 + months / 12,
+                                    months as u32 % 12 + 1,
+                                    1,
+                                )
+                                .unwrap()
+                            }),
+                            sharing: match key_file.is_some() && aes_field {
+                                true => SssBackend::Gf256,
+                                false => SssBackend::Sharks,
+                            },
+                            hardware_key: key_file.and(hardware_key).map(
+                                |(credential_id, salt)| HardwareKeyChallenge {
+                                    credential_id,
+                                    salt,
+                                },
+                            ),
+                        }
+                    },
+                );
+                let mut data = vec![number];
+                data.extend(share);
+                BackupShard {
+                    number: number as usize,
+                    threshold,
+                    backup_id,
+                    data,
+                    expires_at: None,
+                    metadata,
+                    slip39: None,
+                }
+            })
+    }
+
+    #[test]
+    fn zero_threshold_and_number_are_rejected() {
+        // Found by fuzzing, a threshold of 0 passed the shard off as one of the first release
+        let mut bytes = vec![SHARD_MAGIC, 1, 0];
+        bytes.extend([0; 140]);
+        assert!(BackupShard::from_bytes(bytes.clone()).is_err());
+        bytes[2] = 1;
+        assert!(BackupShard::from_bytes(bytes).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn shards_serialization_round_trip(shard in arb_shard()) {
+            let parsed = BackupShard::from_bytes(shard.to_bytes()).expect("Failed to parse shard");
+            prop_assert_eq!(parsed.number, shard.number);
+            prop_assert_eq!(parsed.threshold, shard.threshold);
+            prop_assert_eq!(parsed.backup_id, shard.backup_id);
+            prop_assert_eq!(&parsed.data, &shard.data);
+            prop_assert_eq!(&parsed.metadata, &shard.metadata);
+        }
+
+        #[test]
+        fn truncated_encodings_are_rejected(
+            shard in arb_shard(),
+            cut in any::<prop::sample::Index>(),
+        ) {
+            let bytes = shard.to_bytes();
+            let truncated = bytes[..cut.index(bytes.len())].to_vec();
+            prop_assert!(BackupShard::from_bytes(truncated).is_err());
+        }
+
+        #[test]
+        fn flipped_bits_are_rejected(
+            shard in arb_shard(),
+            index in any::<prop::sample::Index>(),
+            bit in 0..8u8,
+        ) {
+            // A flipped magic byte makes a headerless shard of the first release
+            let mut bytes = shard.to_bytes();
+            let index = 1 + index.index(bytes.len() - 1);
+            bytes[index] ^= 1 << bit;
+            prop_assert!(BackupShard::from_bytes(bytes).is_err());
+        }
+
+        #[test]
+        fn label_length_prefix_is_checked(shard in arb_shard(), len in any::<u8>()) {
+            // With the tag recomputed, only the length prefix tells where the label ends
+            let mut bytes = shard.to_bytes();
+            // Shards without a KDF are checked with a CRC instead
+            let label_len = match &shard.metadata {
+                Some(metadata) if metadata.kdf.is_some() => metadata.label.len(),
+                _ => return Ok(()),
+            };
+            if len as usize == label_len {
+                return Ok(());
+            }
+            bytes[LABEL_LEN_OFFSET] = len;
+            bytes.truncate(bytes.len() - SHARD_TAG_LEN);
+            let tag = shard_tag(shard.backup_id, shard.number, &bytes);
+            bytes.extend_from_slice(&tag);
+            if let Ok(parsed) = BackupShard::from_bytes(bytes) {
+                prop_assert_ne!(
+                    parsed.metadata.map(|metadata| metadata.label.len()),
+                    Some(label_len)
+                );
+            }
+        }
+
+        #[test]
+        fn arbitrary_bytes_are_parsed_without_panicking(
+            version in 0..=SHARD_VERSION + 1,
+            rest in prop::collection::vec(any::<u8>(), 0..400),
+        ) {
+            let mut bytes = vec![SHARD_MAGIC, version];
+            bytes.extend(&rest);
+            let _ = BackupShard::from_bytes(bytes);
+            let _ = BackupShard::from_bytes(rest);
+        }
+    }
+
+    #[test]
+    fn duplicate_shards_are_ignored() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig {
+            total_shards: 3,
+            min_shards: 2,
+            label: String::new(),
+            kdf: KdfParams::default(),
+            cipher: CipherSuite::default(),
+            sharing: SssBackend::default(),
+            key_file: None,
+            not_before: None,
+            verify_after: None,
+            slip39: false,
+            hardware_key: None,
+        };
+
+        let shards = create_backup(secrets, config).expect("Failed to create backup");
+        let duplicated = vec![shards[0].clone(), shards[0].clone(), shards[2].clone()];
+
+        let restored = restore_shards(&duplicated, &passphrase).expect("Failed to restore backup");
+        assert_eq!(&*restored.value, "This is my secret");
+    }
+
+    #[test]
+    fn not_enough_shards() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig {
+            total_shards: 5,
+            min_shards: 3,
+            label: String::new(),
+            kdf: KdfParams::default(),
+            cipher: CipherSuite::default(),
+            sharing: SssBackend::default(),
+            key_file: None,
+            not_before: None,
+            verify_after: None,
+            slip39: false,
+            hardware_key: None,
+        };
+
+        let shards = create_backup(secrets, config).expect("Failed to create backup");
+        let error = restore_shards(&shards[..2], &passphrase).expect_err("Restored too few shards");
+        assert!(matches!(
+            error.downcast_ref::<BackupError>(),
+            Some(BackupError::NotEnoughShards {
+                provided: 2,
+                required: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn mismatched_backups_are_rejected() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let config = || BackupConfig {
+            total_shards: 3,
+            min_shards: 2,
+            label: String::new(),
+            kdf: KdfParams::default(),
+            cipher: CipherSuite::default(),
+            sharing: SssBackend::default(),
+            key_file: None,
+            not_before: None,
+            verify_after: None,
+            slip39: false,
+            hardware_key: None,
+        };
+        let secret = || {
+            vec![Secret {
+                value: "This is my secret".into(),
+                password: passphrase.as_str().into(),
+                encoding: SecretEncoding::Text,
+                note: Default::default(),
+            }]
+        };
+
+        let first = create_backup(secret(), config()).expect("Failed to create backup");
+        let second = create_backup(secret(), config()).expect("Failed to create backup");
+        let mixed = vec![first[0].clone(), second[1].clone(), second[2].clone()];
+
+        let error = restore_shards(&mixed, &passphrase).expect_err("Restored mixed backups");
+        match error.downcast_ref::<BackupError>() {
+            Some(BackupError::MismatchedShards {
+                expected,
+                conflicting,
+            }) => {
+                assert_eq!(*expected, 1);
+                assert_eq!(conflicting, &vec![2, 3]);
+            }
+            _ => panic!("Unexpected error: {}", error),
+        }
+
+        let restored = restore_shards(&second, &passphrase).expect("Failed to restore backup");
+        assert_eq!(&*restored.value, "This is my secret");
+    }
+
+    #[test]
+    fn metadata_is_authenticated() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig::builder()
+            .with_threshold(2)
+            .with_total(3)
+            .with_label("Cold wallet #2")
+            .build()
+            .unwrap();
+
+        let shares = create_backup(secrets, config)
+            .expect("Failed to create backup")
+            .iter()
+            .map(BackupShard::to_bytes)
+            .collect::<Vec<Vec<u8>>>();
+        let restored =
+            restore_backup(shares[..2].to_vec(), &passphrase, None).expect("Failed to restore");
+        let metadata = restored.metadata.expect("Missing metadata");
+        assert_eq!(metadata.label, "Cold wallet #2");
+        assert_eq!(
+            metadata.summary(restored.threshold),
+            format!(
+                "Backup 'Cold wallet #2' created {}, 2 of 3 shares",
+                metadata.created_at.format("%Y-%m-%d")
+            )
+        );
+
+        // Rewrite the label consistently in both shards, including their checksums
+        let tampered = shares[..2]
+            .iter()
+            .map(|bytes| {
+                let mut shard = BackupShard::from_bytes(bytes.clone()).unwrap();
+                if let Some(metadata) = &mut shard.metadata {
+                    metadata.label = String::from("Cold wallet #3");
+                }
+                shard.to_bytes()
+            })
+            .collect();
+        assert!(restore_backup(tampered, &passphrase, None).is_err());
+
+        let mut relabelled = BackupShard::from_bytes(shares[1].clone()).unwrap();
+        relabelled.metadata = None;
+        let first = BackupShard::from_bytes(shares[0].clone()).unwrap();
+        let error = restore_shards(&[first, relabelled], &passphrase).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<BackupError>(),
+            Some(BackupError::InconsistentMetadata(numbers)) if numbers == &vec![2]
+        ));
+
+        let long_label = "x".repeat(256);
+        assert_eq!(
+            BackupConfig::builder()
+                .with_label(&long_label)
+                .build()
+                .err(),
+            Some(ConfigError::LabelTooLong(256))
+        );
+    }
+
+    #[test]
+    fn kdf_params_are_stored() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let argon2 = KdfParams::Argon2id {
+            memory_mib: 8,
+            iterations: 2,
+            parallelism: 2,
+        };
+        for kdf in [KdfParams::Pbkdf2 { rounds: 1_234 }, argon2] {
+            let config = BackupConfig::builder().with_kdf(kdf).build().unwrap();
+            let shares = create_backup(secrets.clone(), config)
+                .expect("Failed to create backup")
+                .iter()
+                .map(BackupShard::to_bytes)
+                .collect::<Vec<Vec<u8>>>();
+            assert_eq!(shares[0][1], SHARD_VERSION);
+            let restored = restore_backup(shares, &passphrase, None).expect("Failed to restore");
+            assert_eq!(&*restored.secret.value, "This is my secret");
+            assert_eq!(
+                restored.metadata.and_then(|metadata| metadata.kdf),
+                Some(kdf)
+            );
+        }
+
+        assert_eq!(
+            BackupConfig::builder()
+                .with_kdf(KdfParams::Pbkdf2 { rounds: 10 })
+                .build()
+                .err(),
+            Some(ConfigError::KdfRoundsOutOfRange(10))
+        );
+        assert_eq!(
+            BackupConfig::builder()
+                .with_kdf(KdfParams::Argon2id {
+                    memory_mib: 8,
+                    iterations: 0,
+                    parallelism: 1,
+                })
+                .build()
+                .err(),
+            Some(ConfigError::Argon2ParamsOutOfRange)
+        );
+    }
+
+    #[test]
+    fn kdf_params_out_of_range_are_rejected() {
+        let too_costly = [
+            KdfParams::Pbkdf2 { rounds: 10 },
+            KdfParams::Pbkdf2 {
+                rounds: MAX_KDF_ROUNDS + 1,
+            },
+            KdfParams::Argon2id {
+                memory_mib: MAX_ARGON2_MEMORY_MIB + 1,
+                iterations: 1,
+                parallelism: 1,
+            },
+            KdfParams::Argon2id {
+                memory_mib: MIN_ARGON2_MEMORY_MIB,
+                iterations: MAX_ARGON2_ITERATIONS + 1,
+                parallelism: 1,
+            },
+        ];
+        for kdf in too_costly {
+            // Written as is, like a forged shard would be
+            let shard = BackupShard {
+                number: 1,
+                threshold: 1,
+                backup_id: 7,
+                data: vec![1u8, 2, 3],
+                expires_at: None,
+                metadata: Some(ShardMetadata {
+                    label: String::new(),
+                    created_at: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+                    total_shards: 1,
+                    kdf: Some(kdf),
+                    cipher: Some(CipherSuite::default()),
+                    key_file: Some(false),
+                    compressed: false,
+                    not_before: None,
+                    verify_by: None,
+                    sharing: SssBackend::Sharks,
+                    hardware_key: None,
+                }),
+                slip39: None,
+            };
+            let error = BackupShard::from_bytes(shard.to_bytes()).expect_err("Accepted the KDF");
+            assert!(
+                matches!(
+                    error.downcast_ref::<BackupError>(),
+                    Some(BackupError::InvalidShard(message))
+                        if *message == kdf.validate().unwrap_err().to_string()
+                ),
+                "{}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn damaged_shard_is_detected() {
+        let shard = BackupShard {
+            number: 3,
+            threshold: 2,
+            backup_id: 42,
+            data: vec![3u8, 1, 4, 1, 5, 9],
+            expires_at: None,
+            metadata: None,
+            slip39: None,
+        };
+
+        let mut bytes = shard.to_bytes();
+        let last_data_byte = bytes.len() - 5;
+        bytes[last_data_byte] ^= 0x10;
+
+        let error = BackupShard::from_bytes(bytes).expect_err("Accepted damaged shard");
+        assert!(matches!(
+            error.downcast_ref::<BackupError>(),
+            Some(BackupError::DamagedShard(3))
+        ));
+    }
+
+    #[test]
+    fn shard_tag_covers_every_byte() {
+        let shard = BackupShard {
+            number: 3,
+            threshold: 2,
+            backup_id: 42,
+            data: vec![3u8, 1, 4, 1, 5, 9],
+            expires_at: None,
+            metadata: Some(ShardMetadata {
+                label: String::from("Tagged"),
+                created_at: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+                total_shards: 3,
+                kdf: Some(KdfParams::default()),
+                cipher: Some(CipherSuite::default()),
+                key_file: Some(true),
+                compressed: false,
+                not_before: None,
+                verify_by: None,
+                sharing: SssBackend::Sharks,
+                hardware_key: None,
+            }),
+            slip39: None,
+        };
+
+        let bytes = shard.to_bytes();
+        assert_eq!(bytes[1], SHARD_VERSION);
+        let decoded = BackupShard::from_bytes(bytes.clone()).expect("Failed to decode");
+        assert_eq!(decoded.metadata, shard.metadata);
+
+        for index in 0..bytes.len() {
+            let mut damaged = bytes.clone();
+            damaged[index] ^= 0x01;
+            assert!(
+                BackupShard::from_bytes(damaged).is_err(),
+                "Damage to byte {} went unnoticed",
+                index
+            );
+        }
+
+        let mut damaged = bytes;
+        let last_data_byte = damaged.len() - 17;
+        damaged[last_data_byte] ^= 0x10;
+        let error = BackupShard::from_bytes(damaged).expect_err("Accepted damaged shard");
+        assert!(matches!(
+            error.downcast_ref::<BackupError>(),
+            Some(BackupError::DamagedShard(3))
+        ));
+    }
+
+    #[test]
+    fn unknown_cipher_suite_is_rejected() {
+        let shard = BackupShard {
+            number: 1,
+            threshold: 1,
+            backup_id: 7,
+            data: vec![1u8, 2, 3],
+            expires_at: None,
+            metadata: Some(ShardMetadata {
+                label: String::new(),
+                created_at: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+                total_shards: 1,
+                kdf: Some(KdfParams::default()),
+                cipher: Some(CipherSuite::default()),
+                key_file: None,
+                compressed: false,
+                not_before: None,
+                verify_by: None,
+                sharing: SssBackend::Sharks,
+                hardware_key: None,
+            }),
+            slip39: None,
+        };
+
+        // In version 6 the cipher suite id is the last byte of the metadata, before the data
+        let mut bytes = shard.to_bytes();
+        bytes.truncate(bytes.len() - SHARD_TAG_LEN);
+        let cipher_id = bytes.len() - shard.data.len() - 1;
+        assert_eq!(bytes[cipher_id], CipherSuite::default().id());
+        bytes[cipher_id] = 0xEE;
+        let tag = shard_tag(shard.backup_id, shard.number, &bytes);
+        bytes.extend_from_slice(&tag);
+
+        let error = BackupShard::from_bytes(bytes).expect_err("Accepted unknown cipher suite");
+        assert!(matches!(
+            error.downcast_ref::<CryptoError>(),
+            Some(CryptoError::UnknownCipherSuite(0xEE))
+        ));
+        assert!(error.to_string().contains("Unknown cipher suite 238"));
+    }
+
+    #[test]
+    fn v1_fixtures_restore() {
+        let shards = V1_FIXTURES
+            .iter()
+            .map(|payload| {
+                let data = parse_share_payload(payload).expect("Invalid fixture payload");
+                assert_eq!(data[1], 1);
+                BackupShard::from_bytes(data).expect("Failed to decode v1 shard")
+            })
+            .collect::<Vec<BackupShard>>();
+
+        assert_eq!(shards[0].number, 1);
+        assert_eq!(shards[1].number, 2);
+        assert_eq!(shards[0].threshold, 2);
+
+        let secret = restore_shards(&shards, V1_FIXTURE_PASSPHRASE).expect("Failed to restore");
+        assert_eq!(&*secret.value, V1_FIXTURE_SECRET);
+    }
+
+    #[test]
+    fn legacy_fixtures_restore() {
+        let shares = LEGACY_FIXTURES
+            .iter()
+            .map(|payload| parse_share_payload(payload).expect("Invalid fixture payload"))
+            .collect::<Vec<Vec<u8>>>();
+        let shards = shares
+            .iter()
+            .map(|data| BackupShard::from_bytes(data.clone()).expect("Failed to decode shard"))
+            .collect::<Vec<BackupShard>>();
+        assert_eq!(
+            shards.iter().map(|shard| shard.number).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        assert!(shards.iter().all(BackupShard::is_legacy));
+        assert_eq!(shards[2].to_bytes(), shares[2]);
+
+        let secret =
+            restore_shards(&shards[1..], V1_FIXTURE_PASSPHRASE).expect("Failed to restore");
+        assert_eq!(&*secret.value, LEGACY_FIXTURE_SECRET);
+        let restored = restore_backup(
+            vec![shares[2].clone(), shares[0].clone()],
+            "wrong horse battery staple",
+            None,
+        )
+        .expect("Failed to restore");
+        assert_eq!(&*restored.secret.value, LEGACY_FIXTURE_DECOY);
+        assert_eq!(restored.shards_used, [1, 3]);
+
+        // Nothing says how many shards are needed, so a single one just doesn't decrypt
+        assert!(restore_shards(&shards[..1], V1_FIXTURE_PASSPHRASE).is_err());
+        assert!(BackupShard::from_bytes(shares[0][..40].to_vec()).is_err());
+    }
+
+    /// One shard of every version that is still written, as the older ones are kept in the
+    /// layout they were read in.
+    fn shards_of_every_version() -> Vec<BackupShard> {
+        let metadata = ShardMetadata {
+            label: String::from("Header"),
+            created_at: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            total_shards: 5,
+            kdf: Some(KdfParams::Pbkdf2 { rounds: 1_234 }),
+            cipher: Some(CipherSuite::default()),
+            key_file: Some(true),
+            compressed: false,
+            not_before: None,
+            verify_by: None,
+            sharing: SssBackend::Sharks,
+            hardware_key: None,
+        };
+        [
+            None,
+            Some(ShardMetadata {
+                kdf: None,
+                cipher: None,
+                key_file: None,
+                compressed: false,
+                ..metadata.clone()
+            }),
+            Some(ShardMetadata {
+                cipher: None,
+                key_file: None,
+                compressed: false,
+                ..metadata.clone()
+            }),
+            Some(ShardMetadata {
+                key_file: None,
+                compressed: false,
+                ..metadata.clone()
+            }),
+            Some(metadata),
+        ]
+        .into_iter()
+        .map(|metadata| BackupShard {
+            number: 3,
+            threshold: 2,
+            backup_id: 0x0123_4567_89ab_cdef,
+            data: vec![3u8, 1, 4, 1, 5, 9],
+            expires_at: None,
+            metadata,
+            slip39: None,
+        })
+        .collect()
+    }
+
+    #[test]
+    fn payload_header_round_trip() {
+        let shards = shards_of_every_version();
+        let versions = shards
+            .iter()
+            .map(|shard| shard.to_bytes()[1])
+            .collect::<Vec<u8>>();
+        assert_eq!(versions, vec![2, 3, 5, 6, SHARD_VERSION]);
+
+        for shard in shards {
+            let bytes = shard.to_bytes();
+            let (header, len) = PayloadHeader::parse(&bytes).expect("Failed to parse header");
+            assert_eq!(header, shard.header());
+            assert_eq!(&bytes[len..len + shard.data.len()], &shard.data[..]);
+
+            let decoded = BackupShard::from_bytes(bytes).expect("Failed to decode");
+            assert_eq!(decoded.metadata, shard.metadata);
+            assert_eq!(decoded.backup_id, shard.backup_id);
+        }
+    }
+
+    #[test]
+    fn truncated_shards_are_rejected() {
+        for shard in shards_of_every_version() {
+            let bytes = shard.to_bytes();
+            let (_, header_len) = PayloadHeader::parse(&bytes).unwrap();
+            for len in 0..header_len {
+                assert!(
+                    PayloadHeader::parse(&bytes[..len]).is_err(),
+                    "Parsed a version {} header cut off after {} bytes",
+                    bytes[1],
+                    len
+                );
+            }
+            for len in 0..bytes.len() {
+                assert!(
+                    BackupShard::from_bytes(bytes[..len].to_vec()).is_err(),
+                    "Decoded a version {} shard cut off after {} bytes",
+                    bytes[1],
+                    len
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let shard = BackupShard {
+            number: 3,
+            threshold: 2,
+            backup_id: 42,
+            data: vec![3u8, 1, 4, 1, 5, 9],
+            expires_at: None,
+            metadata: None,
+            slip39: None,
+        };
+
+        let mut bytes = shard.to_bytes();
+        bytes[1] = SHARD_VERSION + 1;
+
+        let error = BackupShard::from_bytes(bytes).expect_err("Accepted newer shard");
+        assert!(matches!(
+            error.downcast_ref::<BackupError>(),
+            Some(BackupError::NewerVersion(version)) if *version == SHARD_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn verify_backup_test() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig {
+            total_shards: 5,
+            min_shards: 3,
+            label: String::new(),
+            kdf: KdfParams::default(),
+            cipher: CipherSuite::default(),
+            sharing: SssBackend::default(),
+            key_file: None,
+            not_before: None,
+            verify_after: None,
+            slip39: false,
+            hardware_key: None,
+        };
+
+        let shards = create_backup(secrets, config).expect("Failed to create backup");
+        assert!(verify_backup(&shards, &passphrase, "This is my secret").unwrap());
+        assert!(!verify_backup(&shards, &passphrase, "This is not my secret").unwrap());
+        assert!(verify_backup(&shards, "wrong passphrase", "This is my secret").is_err());
+    }
+
+    fn seeded_config() -> (Vec<Secret>, BackupConfig) {
+        let secrets = vec![
+            Secret {
+                value: "This is my secret".into(),
+                password: "correct horse battery staple".into(),
+                encoding: SecretEncoding::Text,
+                note: Default::default(),
+            },
+            Secret {
+                value: "This is my decoy".into(),
+                password: "wrong horse battery staple".into(),
+                encoding: SecretEncoding::Text,
+                note: Default::default(),
+            },
+        ];
+        let config = BackupConfig::builder()
+            .with_threshold(2)
+            .with_total(4)
+            .with_label("Seeded")
+            .with_kdf(KdfParams::Pbkdf2 { rounds: 1_000 })
+            .build()
+            .unwrap();
+        (secrets, config)
+    }
+
+    fn seeded_backup(seed: &RecoverySeed) -> Vec<Vec<u8>> {
+        let (secrets, config) = seeded_config();
+        create_backup_deterministic(secrets, config, seed)
+            .expect("Failed to create backup")
+            .iter()
+            .map(BackupShard::to_bytes)
+            .collect()
+    }
+
+    #[test]
+    fn regenerated_shards_are_identical() {
+        let seed = RecoverySeed::generate();
+        let shares = seeded_backup(&seed);
+        assert_eq!(shares, seeded_backup(&seed));
+
+        // A reprinted shard combines with the ones that were kept
+        let reprinted = seeded_backup(&seed.to_string().parse().unwrap());
+        let restored = restore_backup(
+            vec![shares[0].clone(), reprinted[3].clone()],
+            "correct horse battery staple",
+            None,
+        )
+        .expect("Failed to restore backup");
+        assert_eq!(&*restored.secret.value, "This is my secret");
+    }
+
+    #[test]
+    fn extended_shards_combine_with_old_ones() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig {
+            total_shards: 3,
+            min_shards: 2,
+            label: String::from("Extended"),
+            kdf: KdfParams::Pbkdf2 { rounds: 1_000 },
+            cipher: CipherSuite::default(),
+            sharing: SssBackend::default(),
+            key_file: None,
+            not_before: None,
+            verify_after: None,
+            slip39: false,
+            hardware_key: None,
+        };
+        let shards = create_backup(secrets, config).expect("Failed to create backup");
+        let extend = |shards: &[BackupShard], password: &str, additional| {
+            extend_backup(
+                RestoredContext::Shards {
+                    shards: shards.to_vec(),
+                    password: password.into(),
+                },
+                additional,
+            )
+        };
+
+        let added = extend(&shards[..2], &passphrase, 2).expect("Failed to extend backup");
+        assert_eq!(
+            added.iter().map(|shard| shard.number).collect::<Vec<_>>(),
+            [4, 5]
+        );
+        assert_eq!(added[0].metadata, shards[0].metadata);
+        assert_eq!(added[0].backup_id, shards[0].backup_id);
+        // Any other threshold of shards fixes the same polynomial
+        let from_others = extend(&[shards[2].clone(), shards[0].clone()], &passphrase, 2).unwrap();
+        assert_eq!(from_others[1].data, added[1].data);
+
+        let restored = restore_shards(&[shards[1].clone(), added[0].clone()], &passphrase)
+            .expect("Failed to restore backup");
+        assert_eq!(&*restored.value, "This is my secret");
+        let restored = restore_backup(
+            vec![added[1].to_bytes(), added[0].to_bytes()],
+            &passphrase,
+            None,
+        )
+        .expect("Failed to restore backup");
+        assert_eq!(&*restored.secret.value, "This is my secret");
+
+        // The next ones go after the added shards
+        let more = extend(&[shards[0].clone(), added[1].clone()], &passphrase, 1).unwrap();
+        assert_eq!(more[0].number, 6);
+        // Unless those weren't loaded, then they have to be skipped
+        let existing = || RestoredContext::Shards {
+            shards: shards[..2].to_vec(),
+            password: passphrase.as_str().into(),
+        };
+        let skipped = extend_backup_from(existing(), Some(6), 1).unwrap();
+        assert_eq!(skipped[0].to_bytes(), more[0].to_bytes());
+        let error = extend_backup_from(existing(), Some(3), 1).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<BackupError>(),
+            Some(BackupError::ShardNumberTaken(3))
+        ));
+
+        assert!(extend(&shards[..2], "wrong passphrase", 1).is_err());
+        assert!(extend(&shards[..1], &passphrase, 1).is_err());
+        let error = extend(&shards[..2], &passphrase, 253).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<BackupError>(),
+            Some(BackupError::TooManyShards(256))
+        ));
+        assert_eq!(extend(&shards[..2], &passphrase, 252).unwrap().len(), 252);
+    }
+
+    #[test]
+    fn seeded_backups_are_extended_from_secrets() {
+        let seed = RecoverySeed::generate();
+        let shares = seeded_backup(&seed);
+        let (secrets, config) = seeded_config();
+        let added = extend_backup(
+            RestoredContext::Secrets {
+                secrets,
+                config,
+                seed: Some(seed),
+            },
+            1,
+        )
+        .expect("Failed to extend backup");
+        assert_eq!(added[0].number, 5);
+
+        let shards = shares[..2]
+            .iter()
+            .map(|bytes| BackupShard::from_bytes(bytes.clone()).unwrap())
+            .collect::<Vec<BackupShard>>();
+        let from_shards = extend_backup(
+            RestoredContext::Shards {
+                shards,
+                password: "correct horse battery staple".into(),
+            },
+            1,
+        )
+        .unwrap();
+        assert_eq!(from_shards[0].to_bytes(), added[0].to_bytes());
+
+        let restored = restore_backup(
+            vec![shares[3].clone(), added[0].to_bytes()],
+            "wrong horse battery staple",
+            None,
+        )
+        .expect("Failed to restore backup");
+        assert_eq!(&*restored.secret.value, "This is my decoy");
+
+        // Without a seed the coefficients are gone
+        let (secrets, config) = seeded_config();
+        let error = extend_backup(
+            RestoredContext::Secrets {
+                secrets,
+                config,
+                seed: None,
+            },
+            1,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<BackupError>(),
+            Some(BackupError::NotExtendable)
+        ));
+    }
+
+    #[test]
+    fn different_seeds_give_unrelated_shards() {
+        let shares = seeded_backup(&RecoverySeed::generate());
+        let other = seeded_backup(&RecoverySeed::generate());
+        let first = BackupShard::from_bytes(shares[0].clone()).unwrap();
+        let second = BackupShard::from_bytes(other[0].clone()).unwrap();
+        assert_ne!(first.backup_id, second.backup_id);
+        let equal_bytes = first
+            .data
+            .iter()
+            .zip(&second.data)
+            .filter(|(a, b)| a == b)
+            .count();
+        // About one byte in 256 matches by chance
+        assert!(equal_bytes < first.data.len() / 32);
+
+        assert!(matches!(
+            restore_backup(
+                vec![shares[0].clone(), other[1].clone()],
+                "correct horse battery staple",
+                None
+            ),
+            Err(RestoreError::MismatchedBackups { .. })
+        ));
+    }
+
+    #[test]
+    fn recovery_seed_round_trip() {
+        let seed = RecoverySeed {
+            entropy: *b"0123456789abcdef",
+            created_at: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+        };
+        let text = seed.to_string();
+        assert!(text.starts_with("3031-3233-"));
+        assert_eq!(text.len(), 54);
+        assert_eq!(text.parse::<RecoverySeed>().unwrap(), seed);
+        assert_eq!(
+            text.to_lowercase()
+                .replace('-', " ")
+                .parse::<RecoverySeed>()
+                .unwrap(),
+            seed
+        );
+
+        let mut typo = text.into_bytes();
+        typo[0] = if typo[0] == b'0' { b'1' } else { b'0' };
+        assert!(matches!(
+            String::from_utf8(typo).unwrap().parse::<RecoverySeed>(),
+            Err(BackupError::InvalidRecoverySeed)
+        ));
+        assert!(matches!(
+            "0123-4567".parse::<RecoverySeed>(),
+            Err(BackupError::InvalidRecoverySeed)
+        ));
+    }
+
+    #[test]
+    fn changed_metadata_fails_authentication() {
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: "correct horse battery staple".into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig::builder()
+            .with_threshold(2)
+            .with_total(3)
+            .with_label("Bound")
+            .with_kdf(KdfParams::Pbkdf2 { rounds: 1_000 })
+            .build()
+            .unwrap();
+        let shares = create_backup(secrets, config)
+            .expect("Failed to create backup")
+            .iter()
+            .map(BackupShard::to_bytes)
+            .collect::<Vec<Vec<u8>>>();
+        let tamper = |change: fn(&mut BackupShard)| {
+            let tampered = shares[..2]
+                .iter()
+                .map(|bytes| {
+                    let mut shard = BackupShard::from_bytes(bytes.clone()).unwrap();
+                    change(&mut shard);
+                    shard.to_bytes()
+                })
+                .collect();
+            restore_backup(tampered, "correct horse battery staple", None)
+        };
+        assert!(tamper(|_| {}).is_ok());
+
+        let changes: [fn(&mut BackupShard); 6] = [
+            |shard| shard.threshold = 1,
+            |shard| shard.backup_id ^= 1,
+            |shard| shard.metadata.as_mut().unwrap().total_shards = 2,
+            |shard| shard.metadata.as_mut().unwrap().label.push('!'),
+            |shard| shard.metadata.as_mut().unwrap().created_at += Duration::seconds(1),
+            |shard| shard.metadata.as_mut().unwrap().compressed ^= true,
+        ];
+        for (index, change) in changes.into_iter().enumerate() {
+            assert!(
+                matches!(tamper(change), Err(RestoreError::MetadataMismatch)),
+                "change {}",
+                index
+            );
+        }
+
+        // The shard number is the x coordinate of its share, so relabelling a shard garbles the
+        // whole ciphertext instead
+        let mut relabelled = BackupShard::from_bytes(shares[0].clone()).unwrap();
+        relabelled.number = 3;
+        relabelled.data[0] = 3;
+        assert!(matches!(
+            restore_backup(
+                vec![relabelled.to_bytes(), shares[1].clone()],
+                "correct horse battery staple",
+                None
+            ),
+            Err(RestoreError::WrongPassphrase)
+        ));
+    }
+
+    #[test]
+    fn not_before_is_only_a_reminder() {
+        let secrets = || {
+            vec![Secret {
+                value: "This is my secret".into(),
+                password: "correct horse battery staple".into(),
+                encoding: SecretEncoding::Text,
+                note: Default::default(),
+            }]
+        };
+        let create = |not_before| {
+            let config = BackupConfig::builder()
+                .with_kdf(KdfParams::Pbkdf2 { rounds: 1_000 })
+                .with_not_before(not_before)
+                .build()
+                .unwrap();
+            create_backup(secrets(), config).expect("Failed to create backup")
+        };
+
+        let until = Utc::now().trunc_subsecs(0) + Duration::days(365);
+        let shards = create(until);
+        let bytes = shards[0].to_bytes();
+        assert_eq!(bytes[1], SHARD_VERSION);
+        let decoded = BackupShard::from_bytes(bytes.clone()).expect("Failed to decode");
+        assert_eq!(decoded.metadata.as_ref().unwrap().not_before, Some(until));
+        let restored = restore_backup(vec![bytes], "correct horse battery staple", None)
+            .expect("Failed to restore");
+        assert_eq!(&*restored.secret.value, "This is my secret");
+        let metadata = restored.metadata.unwrap();
+        assert_eq!(metadata.restored_early(Utc::now()), Some(until));
+        assert_eq!(metadata.restored_early(until + Duration::seconds(1)), None);
+
+        // Taking the reminder off breaks the authentication of the metadata
+        let mut stripped = decoded;
+        stripped.metadata.as_mut().unwrap().not_before = None;
+        assert!(matches!(
+            restore_backup(
+                vec![stripped.to_bytes()],
+                "correct horse battery staple",
+                None
+            ),
+            Err(RestoreError::MetadataMismatch)
+        ));
+    }
+
+    #[test]
+    fn verify_by_is_stored_when_set() {
+        let create = |verify_after: Option<u8>| {
+            let mut builder = BackupConfig::builder().with_kdf(KdfParams::Pbkdf2 { rounds: 1_000 });
+            if let Some(years) = verify_after {
+                builder = builder.with_verify_after(years);
+            }
+            let secrets = vec![Secret {
+                value: "This is my secret".into(),
+                password: "correct horse battery staple".into(),
+                encoding: SecretEncoding::Text,
+                note: Default::default(),
+            }];
+            create_backup(secrets, builder.build().unwrap()).expect("Failed to create backup")
+        };
+
+        let plain = create(None).remove(0);
+        let reminded = create(Some(3)).remove(0);
+        let metadata = reminded.metadata.as_ref().unwrap();
+        let created = metadata.created_at.date_naive();
+        let verify_by = metadata.verify_by.expect("Missing verify-by date");
+        assert_eq!(verify_by.day(), 1);
+        assert_eq!(verify_by.month(), created.month());
+        assert_eq!(verify_by.year(), created.year() + 3);
+        assert_eq!(metadata.verification_due(created), None);
+        assert_eq!(metadata.verification_due(verify_by), Some(verify_by));
+        assert_eq!(plain.metadata.as_ref().unwrap().verify_by, None);
+        assert_eq!(
+            plain.metadata.as_ref().unwrap().verification_due(verify_by),
+            None
+        );
+
+        // The month takes two bytes of the header, and only when it is set
+        let plain_bytes = plain.to_bytes();
+        let reminded_bytes = reminded.to_bytes();
+        assert_eq!(
+            reminded_bytes.len() - reminded.data.len(),
+            plain_bytes.len() - plain.data.len() + VERIFY_BY_LEN
+        );
+        for (bytes, verify_by) in [(plain_bytes, None), (reminded_bytes, Some(verify_by))] {
+            let decoded = BackupShard::from_bytes(bytes.clone()).expect("Failed to decode");
+            assert_eq!(decoded.metadata.as_ref().unwrap().verify_by, verify_by);
+            let restored = restore_backup(vec![bytes], "correct horse battery staple", None)
+                .expect("Failed to restore");
+            assert_eq!(restored.metadata.unwrap().verify_by, verify_by);
+        }
+
+        // Moving the date breaks the authentication of the metadata
+        let mut postponed = reminded;
+        postponed.metadata.as_mut().unwrap().verify_by =
+            verify_by.checked_add_months(Months::new(12));
+        assert!(matches!(
+            restore_backup(
+                vec![postponed.to_bytes()],
+                "correct horse battery staple",
+                None
+            ),
+            Err(RestoreError::MetadataMismatch)
+        ));
+    }
+
+    #[test]
+    fn streamed_backup_round_trip() {
+        let mut data = vec![0u8; 1_500_000];
+        thread_rng().fill(&mut data[..]);
+        let config = || {
+            BackupConfig::builder()
+                .with_threshold(2)
+                .with_total(3)
+                .with_kdf(KdfParams::Pbkdf2 { rounds: 1_000 })
+                .build()
+                .unwrap()
+        };
+        let mut encrypted = Vec::new();
+        let shares = create_backup_stream(
+            &data[..],
+            &mut encrypted,
+            config(),
+            "correct horse battery staple",
+            |_| {},
+        )
+        .expect("Failed to create backup")
+        .iter()
+        .map(BackupShard::to_bytes)
+        .collect::<Vec<Vec<u8>>>();
+        // Only the key is split, so the shards stay printable
+        assert!(shares.iter().all(|share| share.len() < 1_000));
+
+        let mut restored = Vec::new();
+        let total = restore_backup_stream(
+            shares[1..].to_vec(),
+            "correct horse battery staple",
+            None,
+            &encrypted[..],
+            &mut restored,
+            |_| {},
+        )
+        .expect("Failed to restore backup");
+        assert_eq!(total, data.len() as u64);
+        assert!(restored == data);
+
+        // The file of another backup can't be decrypted with these shards
+        let mut other = Vec::new();
+        create_backup_stream(
+            &data[..],
+            &mut other,
+            config(),
+            "correct horse battery staple",
+            |_| {},
+        )
+        .unwrap();
+        assert!(restore_backup_stream(
+            shares[1..].to_vec(),
+            "correct horse battery staple",
+            None,
+            &other[..],
+            Vec::new(),
+            |_| {},
+        )
+        .is_err());
+
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: "correct horse battery staple".into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let plain = create_backup(secrets, config())
+            .unwrap()
+            .iter()
+            .map(BackupShard::to_bytes)
+            .collect();
+        let error = restore_backup_stream(
+            plain,
+            "correct horse battery staple",
+            None,
+            &encrypted[..],
+            Vec::new(),
+            |_| {},
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<BackupError>(),
+            Some(BackupError::NotStreamed)
+        ));
+    }
+
+    #[test]
+    fn key_file_is_required() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let key_file = key_file_digest(b"contents of the key file");
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig::builder()
+            .with_threshold(2)
+            .with_total(3)
+            .with_key_file(key_file)
+            .build()
+            .unwrap();
+
+        let shards = create_backup(secrets, config).expect("Failed to create backup");
+        assert!(shards.iter().all(BackupShard::requires_key_file));
+        let shares = shards
+            .iter()
+            .map(BackupShard::to_bytes)
+            .collect::<Vec<Vec<u8>>>();
+        assert!(BackupShard::from_bytes(shares[0].clone())
+            .unwrap()
+            .requires_key_file());
+
+        let restored = restore_backup(shares.clone(), &passphrase, Some(&key_file))
+            .expect("Failed to restore with the key file");
+        assert_eq!(&*restored.secret.value, "This is my secret");
+
+        let other_file = key_file_digest(b"contents of the key file\n");
+        assert!(matches!(
+            restore_backup(shares.clone(), &passphrase, Some(&other_file)),
+            Err(RestoreError::WrongPassphrase)
+        ));
+        assert!(matches!(
+            restore_backup(shares, &passphrase, None),
+            Err(RestoreError::KeyFileRequired)
+        ));
+    }
+
+    #[test]
+    fn hardware_key_is_required() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let key_file = key_file_digest(b"contents of the key file");
+        // What a security key would have answered
+        let challenge = HardwareKeyChallenge {
+            credential_id: vec![0x42; 64],
+            salt: [7; 32],
+        };
+        let hardware_key = [9; 32];
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig::builder()
+            .with_threshold(2)
+            .with_total(3)
+            .with_key_file(key_file)
+            .with_hardware_key(&challenge, hardware_key)
+            .build()
+            .unwrap();
+
+        let shards = create_backup(secrets, config).expect("Failed to create backup");
+        let parsed = BackupShard::from_bytes(shards[1].to_bytes()).unwrap();
+        assert_eq!(parsed.hardware_key(), Some(&challenge));
+        assert_eq!(parsed.to_bytes(), shards[1].to_bytes());
+
+        let password = unlock_password(&shards, &passphrase, Some(&key_file), Some(&hardware_key))
+            .expect("Failed to unlock");
+        let restored = restore_shards(&shards[..2], &password).expect("Failed to restore");
+        assert_eq!(&*restored.value, "This is my secret");
+
+        // Neither the key file nor the hardware key stands in for the other
+        let password = unlock_password(&shards, &passphrase, Some(&key_file), Some(&[8; 32]))
+            .expect("Failed to unlock");
+        assert!(restore_shards(&shards[..2], &password).is_err());
+        assert!(matches!(
+            unlock_password(&shards, &passphrase, Some(&key_file), None),
+            Err(BackupError::HardwareKeyRequired)
+        ));
+        assert!(matches!(
+            unlock_password(&shards, &passphrase, None, Some(&hardware_key)),
+            Err(BackupError::KeyFileRequired)
+        ));
+    }
+
+    #[test]
+    fn restore_backup_asks_hardware_key() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let key_file = key_file_digest(b"contents of the key file");
+        let challenge = HardwareKeyChallenge {
+            credential_id: vec![0x42; 64],
+            salt: [7; 32],
+        };
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig::builder()
+            .with_threshold(2)
+            .with_total(3)
+            .with_key_file(key_file)
+            .with_hardware_key(&challenge, [9; 32])
+            .build()
+            .unwrap();
+        let shares = create_backup(secrets, config)
+            .expect("Failed to create backup")
+            .iter()
+            .map(BackupShard::to_bytes)
+            .collect::<Vec<Vec<u8>>>();
+
+        // The key file is asked for before the security key has to be touched
+        assert!(matches!(
+            restore_backup(shares.clone(), &passphrase, None),
+            Err(RestoreError::KeyFileRequired)
+        ));
+        #[cfg(not(feature = "hardware-key"))]
+        assert!(matches!(
+            restore_backup(shares, &passphrase, Some(&key_file)),
+            Err(RestoreError::HardwareKey(
+                crate::errors::HardwareKeyError::Unsupported
+            ))
+        ));
+
+        // A missing factor is reported as the one that is missing
+        assert!(matches!(
+            restore_error(BackupError::HardwareKeyRequired.into()),
+            RestoreError::HardwareKeyRequired
+        ));
+        assert!(matches!(
+            restore_error(BackupError::KeyFileRequired.into()),
+            RestoreError::KeyFileRequired
+        ));
+    }
+
+    #[test]
+    fn hardware_key_config_is_checked() {
+        let challenge = HardwareKeyChallenge {
+            credential_id: vec![0x42; MAX_CREDENTIAL_ID_LEN + 1],
+            salt: [7; 32],
+        };
+        assert_eq!(
+            BackupConfig::builder()
+                .with_hardware_key(&challenge, [9; 32])
+                .build()
+                .err(),
+            Some(ConfigError::CredentialIdTooLong(MAX_CREDENTIAL_ID_LEN + 1))
+        );
+
+        let challenge = HardwareKeyChallenge {
+            credential_id: vec![0x42; MAX_CREDENTIAL_ID_LEN],
+            salt: [7; 32],
+        };
+        assert!(BackupConfig::builder()
+            .with_hardware_key(&challenge, [9; 32])
+            .build()
+            .is_ok());
+        assert_eq!(
+            BackupConfig::builder()
+                .with_slip39(true)
+                .with_hardware_key(&challenge, [9; 32])
+                .build()
+                .err(),
+            Some(ConfigError::Slip39Unsupported("a hardware key"))
+        );
+    }
+
+    #[test]
+    fn compression_is_flagged() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let backup = |value: String, encoding| {
+            let secrets = vec![Secret {
+                value: value.into(),
+                password: passphrase.as_str().into(),
+                encoding,
+                note: Default::default(),
+            }];
+            let config = BackupConfig::builder().build().unwrap();
+            create_backup(secrets, config).expect("Failed to create backup")
+        };
+
+        let notes = "Seed words are in the safe, the passphrase is with the lawyer.\n".repeat(20);
+        let shards = backup(notes.clone(), SecretEncoding::Text);
+        let bytes = shards[0].to_bytes();
+        assert!(bytes.len() < notes.len());
+        let decoded = BackupShard::from_bytes(bytes.clone()).unwrap();
+        assert!(decoded.metadata.unwrap().compressed);
+        let restored = restore_backup(vec![bytes], &passphrase, None).expect("Failed to restore");
+        assert_eq!(&*restored.secret.value, notes);
+
+        let mut data = vec![0u8; 500];
+        thread_rng().fill(&mut data[..]);
+        let shards = backup(base64::encode(&data), SecretEncoding::Base64);
+        assert!(!shards[0].metadata.as_ref().unwrap().compressed);
+        let restored = restore_backup(vec![shards[0].to_bytes()], &passphrase, None)
+            .expect("Failed to restore");
+        assert_eq!(restored.secret.to_bytes().unwrap(), data);
+    }
+
+    #[test]
+    fn key_file_is_ignored_without_flag() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig::builder().build().unwrap();
+
+        let shards = create_backup(secrets, config).expect("Failed to create backup");
+        assert!(!shards[0].requires_key_file());
+        let key_file = key_file_digest(b"unrelated file");
+        let restored = restore_backup(vec![shards[0].to_bytes()], &passphrase, Some(&key_file))
+            .expect("Failed to restore");
+        assert_eq!(&*restored.secret.value, "This is my secret");
+    }
+
+    /// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1, written out bit by bit so it
+    /// doesn't share anything with the lookup tables of sharks.
+    fn gf256_mul(a: u8, b: u8) -> u8 {
+        field_mul(a, b, 0x1d)
+    }
+
+    /// The same modulo the AES polynomial x^8 + x^4 + x^3 + x + 1.
+    fn aes_mul(a: u8, b: u8) -> u8 {
+        field_mul(a, b, 0x1b)
+    }
+
+    fn field_mul(mut a: u8, mut b: u8, reduction: u8) -> u8 {
+        let mut product = 0;
+        while b != 0 {
+            if b & 1 != 0 {
+                product ^= a;
+            }
+            let carry = a & 0x80 != 0;
+            a <<= 1;
+            if carry {
+                a ^= reduction;
+            }
+            b >>= 1;
+        }
+        product
+    }
+
+    fn gf256_inv(a: u8) -> u8 {
+        field_inv(a, gf256_mul)
+    }
+
+    fn aes_inv(a: u8) -> u8 {
+        field_inv(a, aes_mul)
+    }
+
+    fn field_inv(a: u8, mul: fn(u8, u8) -> u8) -> u8 {
+        // a^254 = a^-1, as the multiplicative group has 255 elements
+        (0..254).fold(1, |inverse, _| mul(inverse, a))
+    }
+
+    /// Lagrange interpolation at x = 0, where addition and subtraction are both XOR.
+    fn interpolate(points: &[&Vec<u8>], mul: fn(u8, u8) -> u8, inv: fn(u8) -> u8) -> Vec<u8> {
+        (1..points[0].len())
+            .map(|byte| {
+                points.iter().fold(0, |sum, point| {
+                    let basis = points
+                        .iter()
+                        .filter(|other| other[0] != point[0])
+                        .fold(1, |basis, other| {
+                            mul(basis, mul(other[0], inv(other[0] ^ point[0])))
+                        });
+                    sum ^ mul(point[byte], basis)
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn shamir_scheme_round_trip() {
+        let secret = b"split and combined again";
+        let shares = Shamir
+            .split(secret, 3, 5, &mut thread_rng())
+            .expect("Failed to split");
+        assert_eq!(shares.len(), 5);
+
+        let subset = [
+            &shares[4][..],
+            &shares[1][..],
+            &shares[1][..],
+            &shares[2][..],
+        ];
+        let combined = Shamir.combine(&subset, 3).expect("Failed to combine");
+        assert_eq!(combined, secret);
+
+        let error = Shamir.combine(&subset[..3], 3).unwrap_err();
+        assert!(matches!(
+            error,
+            BackupError::NotEnoughShards {
+                provided: 2,
+                required: 3
+            }
+        ));
+    }
+
+    #[test]
+    fn shamir_extend_deals_missing_shares() {
+        let shares = Shamir
+            .split(b"extended", 3, 5, &mut thread_rng())
+            .expect("Failed to split");
+        let subset = [&shares[2][..], &shares[0][..], &shares[4][..]];
+        for (index, share) in shares.iter().enumerate() {
+            if index % 2 == 1 {
+                let dealt = Shamir.extend(&subset, 3, index as u8 + 1).unwrap();
+                assert_eq!(&dealt, share);
+            }
+        }
+        assert!(Shamir.extend(&subset, 3, 3).is_err());
+        assert!(Shamir.extend(&subset[..2], 3, 2).is_err());
+    }
+
+    #[test]
+    fn shares_interpolate_over_gf256() {
+        assert_eq!(gf256_mul(0x80, 0x02), 0x1d);
+        assert_eq!(gf256_mul(0x53, gf256_inv(0x53)), 1);
+
+        let secret = b"Shamir over GF(2^8)";
+        let shares = Sharks(3)
+            .dealer(secret)
+            .take(5)
+            .map(|share| Vec::from(&share))
+            .collect::<Vec<Vec<u8>>>();
+
+        for subset in [[0, 1, 2], [1, 3, 4], [4, 2, 0]] {
+            let points = subset.map(|index| &shares[index]);
+            let recovered = interpolate(&points, gf256_mul, gf256_inv);
+            assert_eq!(&recovered[..], secret);
+        }
+    }
+
+    #[test]
+    fn aes_field_shares_interpolate() {
+        // FIPS-197 section 4.2 and the S-box derivation in 5.1.1
+        assert_eq!(aes_mul(0x57, 0x83), 0xc1);
+        assert_eq!(aes_mul(0x57, 0x13), 0xfe);
+        assert_eq!(aes_inv(0x53), 0xca);
+
+        // f(x) = 0x12 + 0x57x through (1, 0x45) and (0x83, 0xd3), as any GF(2^8) Shamir over the
+        // AES field such as Vault's or SLIP-0039's deals them
+        let shares = [[0x01, 0x12 ^ 0x57], [0x83, 0x12 ^ 0xc1]];
+        let subset = shares
+            .iter()
+            .map(|share| &share[..])
+            .collect::<Vec<&[u8]>>();
+        assert_eq!(Gf256Shamir.combine(&subset, 2).unwrap(), [0x12]);
+        assert_eq!(
+            Gf256Shamir.extend(&subset, 2, 0x13).unwrap(),
+            [0x13, 0x12 ^ 0xfe]
+        );
+
+        let secret = b"Shamir over the AES field";
+        let shares = Gf256Shamir
+            .split(secret, 3, 5, &mut thread_rng())
+            .expect("Failed to split");
+        for subset in [[0, 1, 2], [1, 3, 4], [4, 2, 0]] {
+            let points = subset.map(|index| &shares[index]);
+            assert_eq!(&interpolate(&points, aes_mul, aes_inv)[..], secret);
+        }
+    }
+
+    #[test]
+    fn gf256_scheme_round_trip() {
+        let secret = b"split and combined again";
+        for threshold in 1..=3 {
+            let shares = Gf256Shamir
+                .split(secret, threshold, 5, &mut thread_rng())
+                .expect("Failed to split");
+            assert_eq!(shares.len(), 5);
+            assert!(shares.iter().all(|share| share.len() == secret.len() + 1));
+
+            let subset = [
+                &shares[4][..],
+                &shares[1][..],
+                &shares[2][..],
+                &shares[1][..],
+            ];
+            let combined = Gf256Shamir
+                .combine(&subset, threshold)
+                .expect("Failed to combine");
+            assert_eq!(combined, secret);
+            let combined = Gf256Shamir
+                .combine(&subset[..threshold as usize], threshold)
+                .expect("Failed to combine");
+            assert_eq!(combined, secret);
+
+            for index in [0, 3] {
+                let dealt = Gf256Shamir
+                    .extend(&subset, threshold, index as u8 + 1)
+                    .unwrap();
+                assert_eq!(dealt, shares[index]);
+            }
+        }
+
+        let shares = Gf256Shamir.split(secret, 3, 5, &mut thread_rng()).unwrap();
+        let subset = [&shares[0][..], &shares[0][..], &shares[3][..]];
+        assert!(matches!(
+            Gf256Shamir.combine(&subset, 3),
+            Err(BackupError::NotEnoughShards {
+                provided: 2,
+                required: 3
+            })
+        ));
+        assert!(Gf256Shamir.split(secret, 4, 3, &mut thread_rng()).is_err());
+    }
+
+    #[test]
+    fn sharing_backend_is_stored() {
+        for sharing in SssBackend::ALL {
+            assert_eq!(sharing.to_string().parse::<SssBackend>(), Ok(sharing));
+            let config = BackupConfig::builder()
+                .with_threshold(3)
+                .with_total(5)
+                .with_kdf(KdfParams::Pbkdf2 { rounds: 1_000 })
+                .with_sharing(sharing)
+                .build()
+                .unwrap();
+            let secrets = vec![Secret {
+                value: "secret".into(),
+                password: "passphrase".into(),
+                encoding: SecretEncoding::Text,
+                note: Default::default(),
+            }];
+            let shards = create_backup(secrets, config).expect("Failed to create backup");
+
+            // Without dates the flags are the last byte of the header
+            let bytes = shards[0].to_bytes();
+            let flags = bytes[bytes.len() - SHARD_TAG_LEN - shards[0].data.len() - 1];
+            assert_eq!(flags & 0x10 != 0, sharing == SssBackend::Gf256);
+
+            let bytes = shards[2..]
+                .iter()
+                .map(BackupShard::to_bytes)
+                .collect::<Vec<_>>();
+            let decoded = BackupShard::from_bytes(bytes[0].clone()).expect("Failed to decode");
+            assert_eq!(decoded.metadata.unwrap().sharing, sharing);
+            let restored = restore_backup(bytes, "passphrase", None).expect("Failed to restore");
+            assert_eq!(&*restored.secret.value, "secret");
+        }
+        assert!("gf257".parse::<SssBackend>().is_err());
+    }
+
+    #[test]
+    fn schemes_are_picked_at_runtime() {
+        let secret = b"any encrypted secret of some length";
+        let schemes: [&dyn SharingScheme; 3] = [&Shamir, &Gf256Shamir, &Slip39Shamir];
+        for scheme in schemes {
+            let shares = scheme.split(secret, 3, 5, &mut thread_rng()).unwrap();
+            let subset = [&shares[4][..], &shares[0][..], &shares[2][..]];
+            assert_eq!(scheme.combine(&subset, 3).unwrap(), secret);
+            assert_eq!(scheme.extend(&subset, 3, shares[1][0]).unwrap(), shares[1]);
+        }
+    }
+
+    #[test]
+    fn slip39_backup_round_trip() {
+        let master_secret = "BB54 AAC4 B89D C868 BA37 D9CC 21B2 CECE";
+        let secrets = vec![Secret {
+            value: master_secret.into(),
+            password: "TREZOR".into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = || {
+            BackupConfig::builder()
+                .with_threshold(2)
+                .with_total(3)
+                .with_slip39(true)
+        };
+        let shards = create_backup(secrets.clone(), config().build().unwrap()).unwrap();
+        assert_eq!(shards.len(), 3);
+        assert!(shards.iter().all(|shard| shard.metadata.is_none()));
+        assert_eq!(shards[0].to_words().len(), 20);
+        assert!(verify_backup(&shards, "TREZOR", master_secret).unwrap());
+
+        // The QR payload is the mnemonic, which other tools combine as well
+        let payloads = shards
+            .iter()
+            .map(|shard| share_payload(&shard.to_bytes()))
+            .collect::<Vec<String>>();
+        let mnemonics = [&payloads[2][..], &payloads[0][..]];
+        assert_eq!(
+            format_master_secret(&combine_mnemonics(&mnemonics, "TREZOR").unwrap()),
+            "bb54aac4b89dc868ba37d9cc21b2cece"
+        );
+        let restored = restore_backup(
+            payloads[1..]
+                .iter()
+                .map(|p| p.as_bytes().to_vec())
+                .collect(),
+            "TREZOR",
+            None,
+        )
+        .unwrap();
+        assert_eq!(&*restored.secret.value, "bb54aac4b89dc868ba37d9cc21b2cece");
+        assert_eq!(restored.shards_used, [2, 3]);
+        assert!(matches!(
+            restore_backup(vec![payloads[0].clone().into_bytes()], "TREZOR", None),
+            Err(RestoreError::NotEnoughShares {
+                provided: 1,
+                required: 2
+            })
+        ));
+
+        assert!(matches!(
+            extend_backup(
+                RestoredContext::Shards {
+                    shards: shards.clone(),
+                    password: "TREZOR".into(),
+                },
+                1,
+            )
+            .unwrap_err()
+            .downcast::<BackupError>(),
+            Ok(BackupError::Slip39(Slip39Error::NotExtendable))
+        ));
+        assert_eq!(
+            config().with_label("Wallet").build().err(),
+            Some(ConfigError::Slip39Unsupported("a label"))
+        );
+        assert_eq!(
+            config().with_verify_after(5).build().err(),
+            Some(ConfigError::Slip39Unsupported("a verify-by reminder"))
+        );
+        let mut secrets = secrets;
+        secrets[0].value = "not hex".into();
+        assert!(matches!(
+            create_backup(secrets, config().build().unwrap()),
+            Err(BackupError::Slip39(Slip39Error::NotHex))
+        ));
+    }
+
+    #[test]
+    fn reversed_shards() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig {
+            total_shards: 5,
+            min_shards: 3,
+            label: String::new(),
+            kdf: KdfParams::default(),
+            cipher: CipherSuite::default(),
+            sharing: SssBackend::default(),
+            key_file: None,
+            not_before: None,
+            verify_after: None,
+            slip39: false,
+            hardware_key: None,
+        };
+
+        let mut shards = create_backup(secrets, config).expect("Failed to create backup");
+        shards.reverse();
+
+        let restored = restore_shards(&shards, &passphrase).expect("Failed to restore backup");
+        assert_eq!(&*restored.value, "This is my secret");
+    }
+
+    #[test]
+    fn shuffled_shards_with_duplicates() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig {
+            total_shards: 5,
+            min_shards: 3,
+            label: String::new(),
+            kdf: KdfParams::default(),
+            cipher: CipherSuite::default(),
+            sharing: SssBackend::default(),
+            key_file: None,
+            not_before: None,
+            verify_after: None,
+            slip39: false,
+            hardware_key: None,
+        };
+
+        let shards = create_backup(secrets, config).expect("Failed to create backup");
+        let mut shuffled = vec![
+            shards[4].clone(),
+            shards[1].clone(),
+            shards[4].clone(),
+            shards[2].clone(),
+            shards[1].clone(),
+        ];
+        shuffled.shuffle(&mut thread_rng());
+
+        let restored = restore_shards(&shuffled, &passphrase).expect("Failed to restore backup");
+        assert_eq!(&*restored.value, "This is my secret");
+    }
+
+    #[test]
+    fn sorted_shards() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig {
+            total_shards: 5,
+            min_shards: 3,
+            label: String::new(),
+            kdf: KdfParams::default(),
+            cipher: CipherSuite::default(),
+            sharing: SssBackend::default(),
+            key_file: None,
+            not_before: None,
+            verify_after: None,
+            slip39: false,
+            hardware_key: None,
+        };
+
+        let shards = create_backup(secrets, config).expect("Failed to create backup");
+        let mut sorted = shards.clone();
+        sorted.shuffle(&mut thread_rng());
+        sorted.sort();
+        assert_eq!(sorted, shards);
+        assert_eq!(
+            sorted
+                .iter()
+                .map(|shard| shard.number)
+                .collect::<Vec<usize>>(),
+            vec![1, 2, 3, 4, 5]
+        );
+
+        let restored = restore_shards(&sorted[2..], &passphrase).expect("Failed to restore backup");
+        assert_eq!(&*restored.value, "This is my secret");
+    }
+
+    #[test]
+    fn random_configurations_round_trip() {
+        let mut rng = thread_rng();
+        for _ in 0..6 {
+            let total_shards = rng.gen_range(1..=10u8);
+            let min_shards = rng.gen_range(1..=total_shards);
+            let passphrase = gen_passphrase(6, Some(' '));
+            let mut value = vec![0u8; rng.gen_range(1..=200)];
+            rng.fill(&mut value[..]);
+            let value = base64::encode(value);
+
+            let secrets = vec![Secret {
+                value: value.as_str().into(),
+                password: passphrase.as_str().into(),
+                encoding: SecretEncoding::Base64,
+                note: Default::default(),
+            }];
+            let config = BackupConfig {
+                total_shards,
+                min_shards,
+                label: String::new(),
+                kdf: KdfParams::default(),
+                cipher: CipherSuite::default(),
+                sharing: SssBackend::default(),
+                key_file: None,
+                not_before: None,
+                verify_after: None,
+                slip39: false,
+                hardware_key: None,
+            };
+            let mut shards = create_backup(secrets, config).expect("Failed to create backup");
+            shards.shuffle(&mut rng);
+
+            let shares = shards[..min_shards as usize]
+                .iter()
+                .map(BackupShard::to_bytes)
+                .collect();
+            let restored = restore_backup(shares, &passphrase, None).unwrap_or_else(|error| {
+                panic!("{} of {} shards: {}", min_shards, total_shards, error)
+            });
+            assert_eq!(&*restored.secret.value, value);
+            assert_eq!(restored.shards_used.len(), min_shards as usize);
+        }
+    }
+
+    #[test]
+    fn restore_errors() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let create = || {
+            let secrets = vec![Secret {
+                value: "This is my secret".into(),
+                password: passphrase.as_str().into(),
+                encoding: SecretEncoding::Text,
+                note: Default::default(),
+            }];
+            let config = BackupConfig {
+                total_shards: 3,
+                min_shards: 2,
+                label: String::new(),
+                kdf: KdfParams::default(),
+                cipher: CipherSuite::default(),
+                sharing: SssBackend::default(),
+                key_file: None,
+                not_before: None,
+                verify_after: None,
+                slip39: false,
+                hardware_key: None,
+            };
+            create_backup(secrets, config)
+                .expect("Failed to create backup")
+                .iter()
+                .map(BackupShard::to_bytes)
+                .collect::<Vec<Vec<u8>>>()
+        };
+        let shares = create();
+        let other = create();
+
+        let error = restore_backup(shares[..1].to_vec(), &passphrase, None).unwrap_err();
+        assert!(matches!(
+            error,
+            RestoreError::NotEnoughShares {
+                provided: 1,
+                required: 2
+            }
+        ));
+
+        let error = restore_backup(vec![shares[0].clone(), other[1].clone()], &passphrase, None)
+            .unwrap_err();
+        assert!(matches!(error, RestoreError::MismatchedBackups { .. }));
+
+        let mut damaged = shares.clone();
+        let last_data_byte = damaged[1].len() - 17;
+        damaged[1][last_data_byte] ^= 0x01;
+        let error = restore_backup(damaged, &passphrase, None).unwrap_err();
+        assert!(matches!(
+            error,
+            RestoreError::ShareVerificationError {
+                index: 1,
+                number: 2
+            }
+        ));
+
+        let mut garbled = shares.clone();
+        garbled[1].truncate(8);
+        let error = restore_backup(garbled, &passphrase, None).unwrap_err();
+        assert!(matches!(error, RestoreError::CorruptShare(1)));
+
+        let error = restore_backup(shares, "wrong passphrase", None).unwrap_err();
+        assert!(matches!(error, RestoreError::WrongPassphrase));
+
+        // Valid shard framing around too little ciphertext to hold a salt and a header
+        let short = BackupShard {
+            number: 1,
+            threshold: 1,
+            backup_id: 7,
+            data: vec![1, 0x41],
+            expires_at: None,
+            metadata: None,
+            slip39: None,
+        };
+        let error = restore_backup(vec![short.to_bytes()], &passphrase, None).unwrap_err();
+        assert!(matches!(error, RestoreError::CorruptShare(0)));
+    }
+
+    #[test]
+    fn binary_secret_round_trip() {
+        // Every byte value, including sequences that aren't valid UTF-8
+        let data = (0..=255u8).cycle().take(3000).collect::<Vec<u8>>();
+        let passphrase = gen_passphrase(6, Some(' '));
+        let secrets = vec![Secret {
+            value: base64::encode(&data).into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Base64,
+            note: Default::default(),
+        }];
+        let config = BackupConfig::builder()
+            .with_threshold(2)
+            .with_total(3)
+            .build()
+            .unwrap();
+
+        let shares = create_backup(secrets, config)
+            .expect("Failed to create backup")
+            .iter()
+            .map(BackupShard::to_bytes)
+            .collect::<Vec<Vec<u8>>>();
+        let restored =
+            restore_backup(shares[1..].to_vec(), &passphrase, None).expect("Failed to restore");
+        assert_eq!(restored.secret.encoding, SecretEncoding::Base64);
+        assert_eq!(restored.secret.to_bytes().unwrap(), data);
+    }
+
+    // Pinned so that changes to the shard or encryption format are noticed
+    #[test]
+    fn max_secret_len_fits() {
+        let config = BackupConfig::builder().build().unwrap();
+        assert_eq!(max_secret_len(&config), 16317);
+        let label = "x".repeat(255);
+        let config = BackupConfig::builder().with_label(&label).build().unwrap();
+        assert_eq!(max_secret_len(&config), 16317);
+
+        // Random data doesn't compress, so this is the longest share possible
+        let mut data = vec![0u8; max_secret_len(&config) - 1];
+        thread_rng().fill(&mut data[..]);
+        let secrets = vec![Secret {
+            value: base64::encode(data).into(),
+            password: gen_passphrase(6, Some(' ')).into(),
+            encoding: SecretEncoding::Base64,
+            note: Default::default(),
+        }];
+        let cipher = CipherSuite::default();
+        assert_eq!(max_share_len(&secrets, &label, cipher), 65853);
+        assert_eq!(
+            share_len(stored_len(&secrets[0], false), &label, cipher),
+            65853
+        );
+        assert_eq!(qrcode_count(max_share_len(&secrets, &label, cipher)), 36);
+        let shards = create_backup(secrets.clone(), config).unwrap();
+        assert!(shards[0].to_bytes().len() <= max_share_len(&secrets, &label, cipher));
+    }
+
+    #[test]
+    fn multiple_secrets() {
+        let real_passphrase = gen_passphrase(6, Some(' '));
+        let decoy_passphrase = gen_passphrase(6, Some(' '));
+        let secrets = vec![
+            Secret {
+                value: "This is my real secret".into(),
+                password: real_passphrase.as_str().into(),
+                encoding: SecretEncoding::Text,
+                note: Default::default(),
+            },
+            Secret {
+                value: "This is a decoy".into(),
+                password: decoy_passphrase.as_str().into(),
+                encoding: SecretEncoding::Text,
+                note: Default::default(),
+            },
+        ];
+        let config = BackupConfig {
+            total_shards: 3,
+            min_shards: 2,
+            label: String::new(),
+            kdf: KdfParams::default(),
+            cipher: CipherSuite::default(),
+            sharing: SssBackend::default(),
+            key_file: None,
+            not_before: None,
+            verify_after: None,
+            slip39: false,
+            hardware_key: None,
+        };
+
+        let shards = create_backup(secrets, config).expect("Failed to create backup");
+
+        let real = restore_shards(&shards[..2], &real_passphrase).expect("Failed to restore");
+        assert_eq!(&*real.value, "This is my real secret");
+
+        let decoy = restore_shards(&shards[1..], &decoy_passphrase).expect("Failed to restore");
+        assert_eq!(&*decoy.value, "This is a decoy");
+
+        let error = restore_shards(&shards, "wrong passphrase")
+            .expect_err("Restored with wrong passphrase");
+        assert!(matches!(
+            error.downcast_ref::<CryptoError>(),
+            Some(CryptoError::WrongPassphrase)
+        ));
+    }
+
+    #[test]
+    fn combinations_test() {
+        assert_eq!(combinations(3, 2), vec![vec![0, 1], vec![0, 2], vec![1, 2]]);
+        assert_eq!(combinations(5, 3).len(), 10);
+    }
+
+    #[test]
+    fn diagnose_damaged_shard() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig {
+            total_shards: 4,
+            min_shards: 2,
+            label: String::new(),
+            kdf: KdfParams::default(),
+            cipher: CipherSuite::default(),
+            sharing: SssBackend::default(),
+            key_file: None,
+            not_before: None,
+            verify_after: None,
+            slip39: false,
+            hardware_key: None,
+        };
+
+        let mut shards = create_backup(secrets, config).expect("Failed to create backup");
+        // Byte 0 is the x coordinate, byte 5 falls into the salt
+        shards[0].data[5] ^= 0x55;
+        assert!(restore_shards(&shards, &passphrase).is_err());
+
+        let diagnosis = diagnose_shards(&shards, &passphrase).expect("Diagnosis failed");
+        assert_eq!(&*diagnosis.secret.value, "This is my secret");
+        assert_eq!(diagnosis.damaged, vec![1]);
+        assert!(!diagnosis.used.contains(&1));
+
+        assert!(diagnose_shards(&shards, "wrong passphrase").is_none());
+    }
+}
@@ -0,0 +1,2223 @@
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+    io::{Read, Write},
+    ops::Deref,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use aes_gcm::{
+    aead::{generic_array::GenericArray, Aead, Payload},
+    Aes256Gcm, KeyInit,
+};
+use argon2::{Algorithm, Argon2, Version};
+use bytes::{Buf, BufMut, BytesMut};
+use chacha20poly1305::XChaCha20Poly1305;
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use pbkdf2::{
+    password_hash::{PasswordHasher, Salt},
+    Params, Pbkdf2,
+};
+use rand::{seq::SliceRandom, thread_rng, CryptoRng, Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtle::{Choice, ConditionallySelectable};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::{
+    errors::{ConfigError, CryptoError},
+    mnemonic::entropy_to_words,
+};
+
+const SALT_LEN: usize = 24;
+// Nonce of AES-256-GCM, which also encrypts streams and blobs
+const NONCE_LEN: usize = 12;
+const XNONCE_LEN: usize = 24;
+const TAG_LEN: usize = 16;
+// Of the keys the passphrases are derived into, as long as PBKDF2-HMAC-SHA256 output
+const KEY_LEN: usize = 32;
+// A header slot holds the encrypted position and length of a body, each a u16
+const HEADER_PLAINTEXT_LEN: usize = 4;
+const MAX_PADDING_LEN: usize = 11;
+// Streams are encrypted in chunks of this much plaintext, each with its own tag
+const STREAM_CHUNK_LEN: usize = 64 * 1024;
+// Random part of the chunk nonces, followed by a 4 byte counter and a flag for the last chunk
+const STREAM_NONCE_PREFIX_LEN: usize = 7;
+
+/// Length of the keys `encrypt_stream` takes.
+pub const STREAM_KEY_LEN: usize = 32;
+
+/// Every backup has room for this many secrets, the real one and its decoys. Unused slots are
+/// filled with random data, so the backup doesn't reveal how many secrets it holds.
+pub const MAX_SECRETS: usize = 4;
+
+/// Longest plaintext a secret may have with any cipher suite. Headers store positions as u16, so
+/// all slots together have to fit into 64 KiB.
+pub const MAX_SECRET_LEN: usize = CipherSuite::Aes256Gcm.max_secret_len();
+
+/// Bounds for a chosen work factor. Shards with other values are rejected too, so a forged one
+/// can't make restoring take forever.
+pub const MIN_KDF_ROUNDS: u32 = 1_000;
+pub const MAX_KDF_ROUNDS: u32 = 100_000_000;
+pub const MIN_ARGON2_MEMORY_MIB: u16 = 8;
+pub const MAX_ARGON2_MEMORY_MIB: u16 = 4_096;
+pub const MAX_ARGON2_ITERATIONS: u8 = 64;
+pub const MAX_ARGON2_PARALLELISM: u8 = 16;
+
+const KDF_PBKDF2_SHA256: u8 = 1;
+const KDF_ARGON2ID: u8 = 2;
+
+// Never part of valid UTF-8, so it can't be confused with a text secret
+const BINARY_MARKER: u8 = 0xFF;
+// Also invalid UTF-8, followed by the length of the note, the note and the secret
+const NOTE_MARKER: u8 = 0xFE;
+const NOTE_HEADER_LEN: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecretEncoding {
+    Text,
+    Base64,
+}
+
+/// A string that is overwritten with zeros when it is dropped. With the `mlock` feature its
+/// buffer is also locked into memory, so it can't end up in swap. The string can't be modified
+/// in place, which would move it to a new buffer and leave the old one behind.
+#[derive(Default, PartialEq, Eq)]
+pub struct SecretString(String);
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        lock_memory(value.as_ptr(), value.capacity());
+        SecretString(value)
+    }
+}
+
+impl Clone for SecretString {
+    fn clone(&self) -> Self {
+        SecretString::from(self.0.clone())
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        SecretString::from(value.to_owned())
+    }
+}
+
+impl Deref for SecretString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+// Keeps secrets out of logs and panic messages
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretString(..)")
+    }
+}
+
+// Wipes the whole buffer but keeps it allocated
+impl Zeroize for SecretString {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+        unlock_memory(self.0.as_ptr(), self.0.capacity());
+    }
+}
+
+impl ZeroizeOnDrop for SecretString {}
+
+// Locks aren't counted, so unlocking a buffer also unlocks other buffers sharing its pages
+#[cfg(all(unix, feature = "mlock"))]
+fn lock_memory(ptr: *const u8, len: usize) {
+    if len > 0 {
+        // Best effort, this fails once RLIMIT_MEMLOCK is reached
+        unsafe { libc::mlock(ptr.cast(), len) };
+    }
+}
+
+#[cfg(all(unix, feature = "mlock"))]
+fn unlock_memory(ptr: *const u8, len: usize) {
+    if len > 0 {
+        unsafe { libc::munlock(ptr.cast(), len) };
+    }
+}
+
+#[cfg(not(all(unix, feature = "mlock")))]
+fn lock_memory(_ptr: *const u8, _len: usize) {}
+
+#[cfg(not(all(unix, feature = "mlock")))]
+fn unlock_memory(_ptr: *const u8, _len: usize) {}
+
+#[derive(Clone, Debug)]
+pub struct Secret {
+    pub value: SecretString,
+    pub password: SecretString,
+    pub encoding: SecretEncoding,
+    /// Encrypted along with the value and shown apart from it after restoring. Empty if there
+    /// is none.
+    pub note: SecretString,
+}
+
+impl Zeroize for Secret {
+    fn zeroize(&mut self) {
+        self.value.zeroize();
+        self.password.zeroize();
+        self.note.zeroize();
+    }
+}
+
+// All strings zeroize themselves when dropped
+impl ZeroizeOnDrop for Secret {}
+
+/// Key derivation and its work factor. They are stored in the shards, so restoring doesn't need
+/// to know which were chosen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KdfParams {
+    /// PBKDF2-HMAC-SHA256
+    Pbkdf2 { rounds: u32 },
+    /// Memory-hard, so guessing passphrases on GPUs and ASICs costs far more than with PBKDF2
+    Argon2id {
+        memory_mib: u16,
+        iterations: u8,
+        parallelism: u8,
+    },
+}
+
+// What every backup used before the work factor could be chosen
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams::Pbkdf2 { rounds: 10_000 }
+    }
+}
+
+impl KdfParams {
+    pub fn algorithm(&self) -> KdfAlgorithm {
+        match self {
+            KdfParams::Pbkdf2 { .. } => KdfAlgorithm::Pbkdf2,
+            KdfParams::Argon2id { .. } => KdfAlgorithm::Argon2id,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        match *self {
+            KdfParams::Pbkdf2 { rounds }
+                if !(MIN_KDF_ROUNDS..=MAX_KDF_ROUNDS).contains(&rounds) =>
+            {
+                Err(ConfigError::KdfRoundsOutOfRange(rounds))
+            }
+            KdfParams::Argon2id {
+                memory_mib,
+                iterations,
+                parallelism,
+            } if !(MIN_ARGON2_MEMORY_MIB..=MAX_ARGON2_MEMORY_MIB).contains(&memory_mib)
+                || !(1..=MAX_ARGON2_ITERATIONS).contains(&iterations)
+                || !(1..=MAX_ARGON2_PARALLELISM).contains(&parallelism) =>
+            {
+                Err(ConfigError::Argon2ParamsOutOfRange)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Id of the algorithm and its parameters, as stored in the shards.
+    pub fn to_bytes(&self) -> (u8, [u8; 4]) {
+        match *self {
+            KdfParams::Pbkdf2 { rounds } => (KDF_PBKDF2_SHA256, rounds.to_be_bytes()),
+            KdfParams::Argon2id {
+                memory_mib,
+                iterations,
+                parallelism,
+            } => {
+                let [high, low] = memory_mib.to_be_bytes();
+                (KDF_ARGON2ID, [high, low, iterations, parallelism])
+            }
+        }
+    }
+
+    /// The inverse of `to_bytes`, or None for an unknown algorithm.
+    pub fn from_bytes(id: u8, bytes: [u8; 4]) -> Option<Self> {
+        match id {
+            KDF_PBKDF2_SHA256 => Some(KdfParams::Pbkdf2 {
+                rounds: u32::from_be_bytes(bytes),
+            }),
+            KDF_ARGON2ID => Some(KdfParams::Argon2id {
+                memory_mib: u16::from_be_bytes([bytes[0], bytes[1]]),
+                iterations: bytes[2],
+                parallelism: bytes[3],
+            }),
+            _ => None,
+        }
+    }
+
+    /// Estimates how long deriving one key takes on this machine by timing a short derivation.
+    pub fn estimate_duration(&self) -> Duration {
+        let (sample, scale) = match *self {
+            KdfParams::Pbkdf2 { rounds } => {
+                const SAMPLE_ROUNDS: u32 = 2_000;
+                let sample = KdfParams::Pbkdf2 {
+                    rounds: SAMPLE_ROUNDS,
+                };
+                (sample, rounds as f64 / SAMPLE_ROUNDS as f64)
+            }
+            KdfParams::Argon2id {
+                memory_mib,
+                iterations,
+                parallelism,
+            } => {
+                // Time grows with the memory times the passes over it
+                let sample_mib = memory_mib.min(MIN_ARGON2_MEMORY_MIB * 2);
+                let sample = KdfParams::Argon2id {
+                    memory_mib: sample_mib,
+                    iterations: 1,
+                    parallelism,
+                };
+                (
+                    sample,
+                    memory_mib as f64 / sample_mib as f64 * iterations as f64,
+                )
+            }
+        };
+        let start = Instant::now();
+        let _ = derive_key("estimate", &[0u8; SALT_LEN], sample);
+        start.elapsed().mul_f64(scale)
+    }
+}
+
+impl Display for KdfParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KdfParams::Pbkdf2 { rounds } => write!(f, "PBKDF2, {} rounds", rounds),
+            KdfParams::Argon2id {
+                memory_mib,
+                iterations,
+                parallelism,
+            } => write!(
+                f,
+                "Argon2id, {} MiB, {} iterations, {} lanes",
+                memory_mib, iterations, parallelism
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KdfAlgorithm {
+    #[default]
+    Pbkdf2,
+    Argon2id,
+}
+
+impl KdfAlgorithm {
+    pub const ALL: [KdfAlgorithm; 2] = [KdfAlgorithm::Pbkdf2, KdfAlgorithm::Argon2id];
+}
+
+impl Display for KdfAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KdfAlgorithm::Pbkdf2 => write!(f, "PBKDF2"),
+            KdfAlgorithm::Argon2id => write!(f, "Argon2id"),
+        }
+    }
+}
+
+impl FromStr for KdfAlgorithm {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        KdfAlgorithm::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.to_string().eq_ignore_ascii_case(value))
+            .ok_or_else(|| format!("'{}' is not pbkdf2 or argon2id", value))
+    }
+}
+
+/// The AEAD protecting the secrets. Its id is stored in the shards, so restoring picks the right
+/// one without asking.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CipherSuite {
+    #[default]
+    Aes256Gcm,
+    /// Its 24 byte nonces can be drawn at random without any worry about collisions, and it
+    /// doesn't rely on AES hardware to run in constant time.
+    XChaCha20Poly1305,
+}
+
+impl CipherSuite {
+    pub const ALL: [CipherSuite; 2] = [CipherSuite::Aes256Gcm, CipherSuite::XChaCha20Poly1305];
+
+    pub fn id(&self) -> u8 {
+        match self {
+            CipherSuite::Aes256Gcm => 1,
+            CipherSuite::XChaCha20Poly1305 => 2,
+        }
+    }
+
+    pub const fn nonce_len(&self) -> usize {
+        match self {
+            CipherSuite::Aes256Gcm => NONCE_LEN,
+            CipherSuite::XChaCha20Poly1305 => XNONCE_LEN,
+        }
+    }
+
+    const fn header_len(&self) -> usize {
+        self.nonce_len() + HEADER_PLAINTEXT_LEN + TAG_LEN
+    }
+
+    /// Longest plaintext a secret encrypted with this suite may have, as the longer nonces
+    /// leave less room in the 64 KiB the headers can point into.
+    pub const fn max_secret_len(&self) -> usize {
+        (u16::MAX as usize - SALT_LEN - MAX_SECRETS * self.header_len()) / MAX_SECRETS
+            - self.nonce_len()
+            - TAG_LEN
+    }
+
+    pub fn from_id(id: u8) -> Result<Self, CryptoError> {
+        CipherSuite::ALL
+            .into_iter()
+            .find(|suite| suite.id() == id)
+            .ok_or(CryptoError::UnknownCipherSuite(id))
+    }
+
+    fn encrypt(&self, key: &[u8], nonce: &[u8], payload: Payload) -> anyhow::Result<Vec<u8>> {
+        match self {
+            CipherSuite::Aes256Gcm => Ok(Aes256Gcm::new(GenericArray::from_slice(key))
+                .encrypt(GenericArray::from_slice(nonce), payload)?),
+            CipherSuite::XChaCha20Poly1305 => {
+                Ok(XChaCha20Poly1305::new(GenericArray::from_slice(key))
+                    .encrypt(GenericArray::from_slice(nonce), payload)?)
+            }
+        }
+    }
+
+    fn decrypt(&self, key: &[u8], nonce: &[u8], payload: Payload) -> anyhow::Result<Vec<u8>> {
+        match self {
+            CipherSuite::Aes256Gcm => Ok(Aes256Gcm::new(GenericArray::from_slice(key))
+                .decrypt(GenericArray::from_slice(nonce), payload)?),
+            CipherSuite::XChaCha20Poly1305 => {
+                Ok(XChaCha20Poly1305::new(GenericArray::from_slice(key))
+                    .decrypt(GenericArray::from_slice(nonce), payload)?)
+            }
+        }
+    }
+}
+
+impl Display for CipherSuite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CipherSuite::Aes256Gcm => write!(f, "AES-256-GCM"),
+            CipherSuite::XChaCha20Poly1305 => write!(f, "XChaCha20-Poly1305"),
+        }
+    }
+}
+
+impl FromStr for CipherSuite {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        CipherSuite::ALL
+            .into_iter()
+            .find(|suite| suite.to_string().eq_ignore_ascii_case(value))
+            .ok_or_else(|| format!("'{}' is not AES-256-GCM or XChaCha20-Poly1305", value))
+    }
+}
+
+/// Presets for the work factor, trading the time a backup takes to create and restore against
+/// the cost of guessing its passphrase. `Advanced` leaves the number of rounds to the user.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SecurityLevel {
+    Fast,
+    #[default]
+    Balanced,
+    Paranoid,
+    Advanced,
+}
+
+impl SecurityLevel {
+    pub const ALL: [SecurityLevel; 4] = [
+        SecurityLevel::Fast,
+        SecurityLevel::Balanced,
+        SecurityLevel::Paranoid,
+        SecurityLevel::Advanced,
+    ];
+
+    pub fn kdf_params(&self) -> Option<KdfParams> {
+        let rounds = match self {
+            SecurityLevel::Fast => 10_000,
+            SecurityLevel::Balanced => 200_000,
+            SecurityLevel::Paranoid => 2_000_000,
+            SecurityLevel::Advanced => return None,
+        };
+        Some(KdfParams::Pbkdf2 { rounds })
+    }
+}
+
+impl Display for SecurityLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecurityLevel::Fast => write!(f, "Fast"),
+            SecurityLevel::Balanced => write!(f, "Balanced"),
+            SecurityLevel::Paranoid => write!(f, "Paranoid"),
+            SecurityLevel::Advanced => write!(f, "Advanced"),
+        }
+    }
+}
+
+impl FromStr for SecurityLevel {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        SecurityLevel::ALL
+            .into_iter()
+            .find(|level| level.to_string().eq_ignore_ascii_case(value))
+            .ok_or_else(|| format!("'{}' is not fast, balanced, paranoid or advanced", value))
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct DecryptedSecret {
+    pub value: SecretString,
+    pub encoding: SecretEncoding,
+    /// Empty if none was stored
+    pub note: SecretString,
+}
+
+// Keeps the restored secret out of logs and panic messages
+impl fmt::Debug for DecryptedSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DecryptedSecret")
+            .field("encoding", &self.encoding)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Zeroize for DecryptedSecret {
+    fn zeroize(&mut self) {
+        self.value.zeroize();
+        self.note.zeroize();
+    }
+}
+
+// Both strings zeroize themselves when dropped
+impl ZeroizeOnDrop for DecryptedSecret {}
+
+impl DecryptedSecret {
+    /// The raw bytes of the secret, undoing the Base64 encoding of binary secrets.
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        match self.encoding {
+            SecretEncoding::Text => Ok(self.value.as_bytes().to_vec()),
+            SecretEncoding::Base64 => Ok(base64::decode(self.value.as_bytes())?),
+        }
+    }
+}
+
+struct Header {
+    position: usize,
+    length: usize,
+}
+
+impl Header {
+    fn from_bytes(bytes: &mut BytesMut) -> Self {
+        let position = bytes.get_u16() as usize;
+        let length = bytes.get_u16() as usize;
+        Self { position, length }
+    }
+
+    fn to_bytes(&self) -> BytesMut {
+        let mut output = BytesMut::with_capacity(4);
+        output.put_u16(self.position as u16);
+        output.put_u16(self.length as u16);
+        return output;
+    }
+}
+
+fn random_bytes<R: RngCore>(rng: &mut R, length: usize) -> Vec<u8> {
+    let mut data = vec![0u8; length];
+    rng.fill_bytes(&mut data);
+    return data;
+}
+
+fn derive_key(password: &str, salt: &[u8], kdf: KdfParams) -> anyhow::Result<Vec<u8>> {
+    let rounds = match kdf {
+        KdfParams::Pbkdf2 { rounds } => rounds,
+        KdfParams::Argon2id {
+            memory_mib,
+            iterations,
+            parallelism,
+        } => {
+            let params = argon2::Params::new(
+                memory_mib as u32 * 1024,
+                iterations as u32,
+                parallelism as u32,
+                Some(KEY_LEN),
+            )
+            .map_err(|error| anyhow::anyhow!("Invalid Argon2 parameters: {}", error))?;
+            let mut key = vec![0; KEY_LEN];
+            Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                .hash_password_into(password.as_bytes(), salt, &mut key)
+                .map_err(|error| anyhow::anyhow!("Argon2 failed: {}", error))?;
+            return Ok(key);
+        }
+    };
+    let salt_str = base64::encode(salt);
+    let password_bytes = password.as_bytes();
+    let params = Params {
+        rounds,
+        ..Params::default()
+    };
+    let hash = Pbkdf2
+        .hash_password_customized(password_bytes, None, None, params, Salt::new(&salt_str)?)?
+        .hash;
+    let hash_bytes = hash.expect("Password hasher failed").as_bytes().to_owned();
+    return Ok(hash_bytes);
+}
+
+/// The bytes that are encrypted for a secret, before any compression.
+fn plaintext(secret: &Secret) -> anyhow::Result<Vec<u8>> {
+    let mut plaintext = Vec::new();
+    if !secret.note.is_empty() {
+        plaintext.push(NOTE_MARKER);
+        plaintext.extend((secret.note.len() as u16).to_be_bytes());
+        plaintext.extend(secret.note.as_bytes());
+    }
+    match secret.encoding {
+        SecretEncoding::Text => plaintext.extend(secret.value.as_bytes()),
+        SecretEncoding::Base64 => {
+            plaintext.push(BINARY_MARKER);
+            let mut decoded = base64::decode(secret.value.as_bytes())?;
+            plaintext.extend(&decoded);
+            decoded.zeroize();
+        }
+    }
+    Ok(plaintext)
+}
+
+/// Splits the note off the front of a decrypted plaintext, if it has one.
+fn split_note(mut plaintext: Vec<u8>) -> Result<(SecretString, Vec<u8>), CryptoError> {
+    if plaintext.first() != Some(&NOTE_MARKER) {
+        return Ok((SecretString::default(), plaintext));
+    }
+    if plaintext.len() < NOTE_HEADER_LEN {
+        return Err(CryptoError::InvalidNote);
+    }
+    let note_len = u16::from_be_bytes([plaintext[1], plaintext[2]]) as usize;
+    if plaintext.len() < NOTE_HEADER_LEN + note_len {
+        return Err(CryptoError::InvalidNote);
+    }
+    let rest = plaintext.split_off(NOTE_HEADER_LEN + note_len);
+    let note = match String::from_utf8(plaintext.split_off(NOTE_HEADER_LEN)) {
+        Ok(note) => SecretString::from(note),
+        Err(error) => {
+            error.into_bytes().zeroize();
+            return Err(CryptoError::InvalidNote);
+        }
+    };
+    Ok((note, rest))
+}
+
+fn compress(plaintext: &[u8]) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder
+        .write_all(plaintext)
+        .expect("Writing to a Vec can't fail");
+    encoder.finish().expect("Writing to a Vec can't fail")
+}
+
+fn decompress(compressed: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    // Bounded, so a forged payload can't inflate to an arbitrary size
+    let mut plaintext = Vec::new();
+    DeflateDecoder::new(compressed)
+        .take(MAX_SECRET_LEN as u64 + 1)
+        .read_to_end(&mut plaintext)
+        .map_err(|_| CryptoError::InvalidCompression)?;
+    if plaintext.len() > MAX_SECRET_LEN {
+        return Err(CryptoError::InvalidCompression);
+    }
+    Ok(plaintext)
+}
+
+/// Whether `encrypt_secrets` should compress the secrets. It is all of them or none, and only
+/// if that makes the longest one shorter, as every slot has its length. The choice is stored in
+/// the clear, so it reveals a single bit about the secrets.
+pub fn should_compress(secrets: &[Secret]) -> bool {
+    let longest = |compressed: bool| {
+        secrets
+            .iter()
+            .map(|secret| stored_len(secret, compressed))
+            .max()
+            .unwrap_or(0)
+    };
+    longest(true) < longest(false)
+}
+
+/// Length of the plaintext that is encrypted for a secret, after the compression
+/// `should_compress` decided on.
+pub fn stored_len(secret: &Secret, compressed: bool) -> usize {
+    match plaintext(secret) {
+        Ok(mut plaintext) if compressed => {
+            let mut compressed = compress(&plaintext);
+            let len = compressed.len();
+            plaintext.zeroize();
+            compressed.zeroize();
+            len
+        }
+        _ => plaintext_len(&secret.value, secret.encoding) + note_len(secret),
+    }
+}
+
+/// Encrypts the secrets, compressing them first if `compressed` is set. `associated_data`
+/// isn't stored, but has to be passed unchanged to `decrypt_secret`, which authenticates it
+/// along with the secrets.
+pub fn encrypt_secrets(
+    secrets: Vec<Secret>,
+    associated_data: &[u8],
+    kdf: KdfParams,
+    cipher: CipherSuite,
+    compressed: bool,
+) -> anyhow::Result<Vec<u8>> {
+    encrypt_secrets_with_rng(
+        secrets,
+        associated_data,
+        kdf,
+        cipher,
+        compressed,
+        &mut thread_rng(),
+    )
+}
+
+/// Like `encrypt_secrets`, but draws the salt, nonces, slots and padding from `rng`. Nonces
+/// repeat if the same seed is used for different secrets, so it has to be derived from them.
+pub fn encrypt_secrets_with_rng<R: RngCore + CryptoRng>(
+    secrets: Vec<Secret>,
+    associated_data: &[u8],
+    kdf: KdfParams,
+    cipher: CipherSuite,
+    compressed: bool,
+    rng: &mut R,
+) -> anyhow::Result<Vec<u8>> {
+    let mut ciphertext =
+        encrypt_slots(secrets, associated_data, kdf, cipher, compressed, rng)?.ciphertext;
+
+    let padding_size = rng.gen_range(1..=MAX_PADDING_LEN);
+    let padding = random_bytes(rng, padding_size);
+    ciphertext.extend_from_slice(&padding);
+
+    Ok(ciphertext)
+}
+
+/// The secrets encrypted into their slots, before the padding is added.
+pub struct EncryptedSlots {
+    /// Salt, headers and bodies, with the unused slots left random
+    pub ciphertext: Vec<u8>,
+    /// One for each secret, in the order they were given
+    pub slots: Vec<EncryptedSlot>,
+}
+
+/// Where a secret went and the nonces it was encrypted with.
+pub struct EncryptedSlot {
+    pub index: usize,
+    pub header_nonce: Vec<u8>,
+    pub body_nonce: Vec<u8>,
+}
+
+/// The first part of `encrypt_secrets_with_rng`, exposing the nonces so they can be checked.
+/// Secrets with the same passphrase share a key, so no nonce is ever used twice in a backup:
+/// an `rng` that repeats itself fails with `CryptoError::NonceReuse` instead.
+pub fn encrypt_slots<R: RngCore + CryptoRng>(
+    secrets: Vec<Secret>,
+    associated_data: &[u8],
+    kdf: KdfParams,
+    cipher: CipherSuite,
+    compressed: bool,
+    rng: &mut R,
+) -> anyhow::Result<EncryptedSlots> {
+    if secrets.len() > MAX_SECRETS {
+        return Err(CryptoError::TooManySecrets(secrets.len()).into());
+    }
+
+    let mut plaintexts = Vec::with_capacity(secrets.len());
+    let result = collect_plaintexts(&secrets, compressed, cipher, &mut plaintexts)
+        .and_then(|()| seal_slots(&secrets, &plaintexts, associated_data, kdf, cipher, rng));
+    // Every plaintext is wiped, whether or not the encryption got to it
+    plaintexts.zeroize();
+    result
+}
+
+/// The plaintexts of the secrets, compressed if `compressed` is set. They are pushed one by one,
+/// so the caller can wipe them even if a later secret is rejected.
+fn collect_plaintexts(
+    secrets: &[Secret],
+    compressed: bool,
+    cipher: CipherSuite,
+    plaintexts: &mut Vec<Vec<u8>>,
+) -> anyhow::Result<()> {
+    for secret in secrets {
+        if secret.password.trim().is_empty() {
+            return Err(CryptoError::EmptyPassphrase.into());
+        }
+        if secret.value.is_empty() {
+            return Err(CryptoError::EmptySecret.into());
+        }
+        plaintexts.push(plaintext(secret)?);
+    }
+    if let Some(len) = plaintexts
+        .iter()
+        .map(Vec::len)
+        .find(|&len| len > cipher.max_secret_len())
+    {
+        return Err(CryptoError::SecretTooLong(len).into());
+    }
+    if compressed {
+        for plaintext in plaintexts.iter_mut() {
+            let mut uncompressed = std::mem::replace(plaintext, compress(plaintext));
+            uncompressed.zeroize();
+        }
+    }
+    Ok(())
+}
+
+fn seal_slots<R: RngCore + CryptoRng>(
+    secrets: &[Secret],
+    plaintexts: &[Vec<u8>],
+    associated_data: &[u8],
+    kdf: KdfParams,
+    cipher: CipherSuite,
+    rng: &mut R,
+) -> anyhow::Result<EncryptedSlots> {
+    // Every slot is as long as the longest body, so decoys can't be told apart by their length
+    let nonce_len = cipher.nonce_len();
+    let header_len = cipher.header_len();
+    let slot_len = nonce_len + TAG_LEN + plaintexts.iter().map(Vec::len).max().unwrap_or(0);
+    let body_offset = SALT_LEN + MAX_SECRETS * header_len;
+
+    // Unused slots stay random, which is indistinguishable from an encrypted header or body
+    let mut ciphertext = random_bytes(rng, body_offset + MAX_SECRETS * slot_len);
+    let salt = ciphertext[..SALT_LEN].to_vec();
+    let mut slots = (0..MAX_SECRETS).collect::<Vec<usize>>();
+    slots.shuffle(rng);
+
+    let mut used_nonces = HashSet::new();
+    let mut encrypted = Vec::with_capacity(secrets.len());
+    for ((secret, plaintext), slot) in secrets.iter().zip(plaintexts).zip(slots) {
+        let cipher_key = derive_key(&secret.password, &salt, kdf)?;
+
+        let body_nonce = random_bytes(rng, nonce_len);
+        let header_nonce = random_bytes(rng, nonce_len);
+        if !used_nonces.insert(body_nonce.clone()) || !used_nonces.insert(header_nonce.clone()) {
+            return Err(CryptoError::NonceReuse.into());
+        }
+
+        let body_ciphertext = cipher.encrypt(
+            &cipher_key,
+            &body_nonce,
+            Payload {
+                msg: plaintext,
+                aad: associated_data,
+            },
+        )?;
+        let header = Header {
+            length: body_ciphertext.len() + body_nonce.len(),
+            position: body_offset + slot * slot_len,
+        };
+        let body = &mut ciphertext[header.position..header.position + header.length];
+        body[..nonce_len].copy_from_slice(&body_nonce);
+        body[nonce_len..].copy_from_slice(&body_ciphertext);
+
+        // Only the body authenticates the associated data, so a header that decrypts tells
+        // changed metadata apart from a wrong passphrase
+        let header_plaintext = header.to_bytes();
+        let header_ciphertext =
+            cipher.encrypt(&cipher_key, &header_nonce, header_plaintext.as_ref().into())?;
+        let header_position = SALT_LEN + slot * header_len;
+        let header_slot = &mut ciphertext[header_position..header_position + header_len];
+        header_slot[..nonce_len].copy_from_slice(&header_nonce);
+        header_slot[nonce_len..].copy_from_slice(&header_ciphertext);
+        encrypted.push(EncryptedSlot {
+            index: slot,
+            header_nonce,
+            body_nonce,
+        });
+    }
+
+    Ok(EncryptedSlots {
+        ciphertext,
+        slots: encrypted,
+    })
+}
+
+/// Length of the plaintext `encrypt_secrets` produces for a secret, which for binary secrets is
+/// the decoded data and a marker byte.
+pub fn plaintext_len(value: &str, encoding: SecretEncoding) -> usize {
+    match encoding {
+        SecretEncoding::Text => value.len(),
+        SecretEncoding::Base64 => {
+            let padding = value.bytes().rev().take_while(|&b| b == b'=').count();
+            (value.len() / 4 * 3).saturating_sub(padding) + 1
+        }
+    }
+}
+
+/// Length the note adds to the plaintext of a secret.
+fn note_len(secret: &Secret) -> usize {
+    match secret.note.len() {
+        0 => 0,
+        len => NOTE_HEADER_LEN + len,
+    }
+}
+
+/// Upper bound for the length of `encrypt_secrets` output, which varies with the random padding.
+pub fn max_encrypted_len(secrets: &[Secret], cipher: CipherSuite) -> usize {
+    let compressed = should_compress(secrets);
+    let longest_body = secrets
+        .iter()
+        .map(|secret| stored_len(secret, compressed))
+        .max()
+        .unwrap_or(0);
+    encrypted_len(longest_body, cipher)
+}
+
+/// Upper bound for the length of `encrypt_secrets` output if the longest `stored_len` of the
+/// secrets is already known.
+pub fn encrypted_len(longest_body: usize, cipher: CipherSuite) -> usize {
+    let slot_len = cipher.header_len() + cipher.nonce_len() + TAG_LEN + longest_body;
+    SALT_LEN + MAX_SECRETS * slot_len + MAX_PADDING_LEN
+}
+
+/// Longest plaintext whose `encrypt_secrets` output is guaranteed to fit into `encrypted_len`
+/// bytes, the inverse of `max_encrypted_len`.
+pub fn max_plaintext_len(encrypted_len: usize, cipher: CipherSuite) -> usize {
+    (encrypted_len.saturating_sub(SALT_LEN + MAX_PADDING_LEN) / MAX_SECRETS)
+        .saturating_sub(cipher.header_len() + cipher.nonce_len() + TAG_LEN)
+}
+
+pub fn decrypt_secret(
+    ciphertext: &[u8],
+    password: &str,
+    associated_data: &[u8],
+    kdf: KdfParams,
+    cipher: CipherSuite,
+    compressed: bool,
+) -> anyhow::Result<DecryptedSecret> {
+    if ciphertext.len() < SALT_LEN + cipher.header_len() {
+        return Err(CryptoError::TruncatedBlob.into());
+    }
+
+    let (salt, rest) = ciphertext.split_at(SALT_LEN);
+    let cipher_key = derive_key(password, salt, kdf)?;
+
+    // The bodies are tried as headers too, as nothing tells where the headers end
+    let slots = rest
+        .chunks(cipher.header_len())
+        .take(rest.len().saturating_sub(1) / cipher.header_len())
+        .collect::<Vec<&[u8]>>();
+    let (header, _) = open_any_slot(&slots, &cipher_key, associated_data, cipher);
+    let header = header.ok_or(CryptoError::WrongPassphrase)?;
+
+    // A header can only point past the end if the shards were made that way with the passphrase
+    let body = header
+        .position
+        .checked_add(header.length)
+        .and_then(|end| ciphertext.get(header.position..end))
+        .filter(|body| body.len() >= cipher.nonce_len())
+        .ok_or(CryptoError::TruncatedBlob)?;
+    let mut body_ciphertext = BytesMut::from(body);
+
+    let nonce = body_ciphertext.split_to(cipher.nonce_len());
+    let mut decrypted = cipher
+        .decrypt(
+            &cipher_key,
+            &nonce,
+            Payload {
+                msg: &body_ciphertext,
+                aad: associated_data,
+            },
+        )
+        .map_err(|_| CryptoError::MetadataMismatch)?;
+    if compressed {
+        let decompressed = decompress(&decrypted);
+        decrypted.zeroize();
+        decrypted = decompressed?;
+    }
+
+    let (note, mut decrypted) = split_note(decrypted)?;
+    if let Some((&BINARY_MARKER, data)) = decrypted.split_first() {
+        let value = SecretString::from(base64::encode(data));
+        decrypted.zeroize();
+        return Ok(DecryptedSecret {
+            value,
+            encoding: SecretEncoding::Base64,
+            note,
+        });
+    }
+    match String::from_utf8(decrypted) {
+        Ok(value) => Ok(DecryptedSecret {
+            value: value.into(),
+            encoding: SecretEncoding::Text,
+            note,
+        }),
+        // The error holds on to the plaintext
+        Err(error) => {
+            let utf8_error = error.utf8_error();
+            error.into_bytes().zeroize();
+            Err(utf8_error.into())
+        }
+    }
+}
+
+/// Finds the first header slot the key opens. Every slot is tried the same way, with and without
+/// the associated data, and the match is picked without branching on it, so neither the timing
+/// nor the error reveals which slot matched or how many other secrets the backup holds. A body
+/// with a four byte plaintext passes as a header too, but it always comes after the header
+/// pointing to it. Also returns the number of decryptions, which only depends on the slot count.
+fn open_any_slot(
+    slots: &[&[u8]],
+    cipher_key: &[u8],
+    associated_data: &[u8],
+    cipher: CipherSuite,
+) -> (Option<Header>, usize) {
+    let mut found = Choice::from(0);
+    let mut selected = 0u32;
+    let mut decryptions = 0;
+
+    for slot in slots {
+        let (nonce, header_ciphertext) = slot.split_at(cipher.nonce_len());
+        let open = |payload: Payload| {
+            let plaintext = cipher.decrypt(cipher_key, nonce, payload).ok();
+            let bytes = plaintext.and_then(|plaintext| <[u8; 4]>::try_from(plaintext).ok());
+            (
+                Choice::from(bytes.is_some() as u8),
+                u32::from_be_bytes(bytes.unwrap_or_default()),
+            )
+        };
+
+        // Headers authenticated the associated data too before shard version 8
+        let (opened_with_data, with_data) = open(Payload {
+            msg: header_ciphertext,
+            aad: associated_data,
+        });
+        let (opened_without_data, without_data) = open(header_ciphertext.into());
+        decryptions += 2;
+
+        let candidate = u32::conditional_select(&without_data, &with_data, opened_with_data);
+        let opened = opened_with_data | opened_without_data;
+        selected.conditional_assign(&candidate, opened & !found);
+        found |= opened;
+    }
+
+    let header = bool::from(found)
+        .then(|| Header::from_bytes(&mut BytesMut::from(&selected.to_be_bytes()[..])));
+    (header, decryptions)
+}
+
+/// Line breaks and tabs are fine in multi-line secrets, other control characters are likely
+/// accidental and don't survive being typed back in.
+pub fn has_control_chars(value: &str) -> bool {
+    value
+        .chars()
+        .any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
+}
+
+/// SHA-256 of the data as hex, so a binary secret can be compared without showing it.
+pub fn fingerprint(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// How a secret generated from fresh randomness is written down.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SecretFormat {
+    #[default]
+    Hex,
+    Base58,
+    Mnemonic,
+}
+
+impl SecretFormat {
+    pub const ALL: [SecretFormat; 3] = [
+        SecretFormat::Hex,
+        SecretFormat::Base58,
+        SecretFormat::Mnemonic,
+    ];
+
+    /// Bytes of entropy offered for the format. BIP-39 mnemonics of 16 and 32 bytes are the
+    /// usual 12 and 24 words.
+    pub fn strengths(&self) -> &'static [usize] {
+        match self {
+            SecretFormat::Hex | SecretFormat::Base58 => &[16, 32, 64],
+            SecretFormat::Mnemonic => &[16, 32],
+        }
+    }
+}
+
+impl Display for SecretFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretFormat::Hex => write!(f, "Hex"),
+            SecretFormat::Base58 => write!(f, "Base58"),
+            SecretFormat::Mnemonic => write!(f, "BIP-39 words"),
+        }
+    }
+}
+
+/// A new random secret of `strength` bytes of entropy, for when there is none to back up yet.
+pub fn gen_secret(format: SecretFormat, strength: usize) -> Result<String, CryptoError> {
+    if !format.strengths().contains(&strength) {
+        return Err(CryptoError::InvalidSecretStrength(strength));
+    }
+    let mut entropy = vec![0u8; strength];
+    thread_rng().fill_bytes(&mut entropy);
+    let secret = match format {
+        SecretFormat::Hex => entropy.iter().map(|byte| format!("{:02x}", byte)).collect(),
+        SecretFormat::Base58 => base58(&entropy),
+        SecretFormat::Mnemonic => {
+            let mut words = entropy_to_words(&entropy);
+            let secret = words.join(" ");
+            words.zeroize();
+            secret
+        }
+    };
+    entropy.zeroize();
+    Ok(secret)
+}
+
+/// Base58 in the Bitcoin alphabet, leaving out characters that are easily confused.
+fn base58(data: &[u8]) -> String {
+    // Digits of the number in base 58, least significant first
+    let mut digits: Vec<u8> = Vec::with_capacity(data.len() * 138 / 100 + 1);
+    for &byte in data {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    // Every leading zero byte is written as a leading '1'
+    let zeros = data.iter().take_while(|&&byte| byte == 0).count();
+    let encoded = std::iter::repeat_n(BASE58_ALPHABET[0], zeros)
+        .chain(
+            digits
+                .iter()
+                .rev()
+                .map(|&digit| BASE58_ALPHABET[digit as usize]),
+        )
+        .map(char::from)
+        .collect();
+    digits.zeroize();
+    encoded
+}
+
+/// Hash of a key file, which is all a backup needs of it.
+pub type KeyFileDigest = [u8; 32];
+
+// Keeps the hash of a key file apart from any other SHA-256 of the same data
+const KEY_FILE_DOMAIN: &[u8] = b"hyperbacked key file v1\0";
+
+pub fn key_file_digest(data: &[u8]) -> KeyFileDigest {
+    Sha256::new()
+        .chain_update(KEY_FILE_DOMAIN)
+        .chain_update(data)
+        .finalize()
+        .into()
+}
+
+/// The password a backup that requires a key file is encrypted with: the passphrase, a NUL that
+/// typed passphrases can't contain, and the hex digest of the key file.
+pub fn key_file_password(passphrase: &str, key_file: &KeyFileDigest) -> SecretString {
+    let mut password = String::with_capacity(passphrase.len() + 1 + 2 * key_file.len());
+    password.push_str(passphrase);
+    password.push('\0');
+    for byte in key_file {
+        password.push_str(&format!("{:02x}", byte));
+    }
+    password.into()
+}
+
+/// Encrypts a standalone blob as salt, nonce and ciphertext.
+pub fn encrypt_blob(data: &[u8], password: &str) -> anyhow::Result<Vec<u8>> {
+    let mut rng = thread_rng();
+    let salt = random_bytes(&mut rng, SALT_LEN);
+    let nonce = random_bytes(&mut rng, NONCE_LEN);
+    let cipher_key = derive_key(password, &salt, KdfParams::default())?;
+    let ciphertext = CipherSuite::default().encrypt(&cipher_key, &nonce, data.into())?;
+
+    let mut output = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+    output.extend_from_slice(&salt);
+    output.extend_from_slice(&nonce);
+    output.extend_from_slice(&ciphertext);
+    Ok(output)
+}
+
+pub fn decrypt_blob(data: &[u8], password: &str) -> anyhow::Result<Vec<u8>> {
+    if data.len() < SALT_LEN + NONCE_LEN {
+        return Err(CryptoError::TruncatedBlob.into());
+    }
+
+    let (salt, rest) = data.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let cipher_key = derive_key(password, salt, KdfParams::default())?;
+    CipherSuite::default().decrypt(&cipher_key, nonce, ciphertext.into())
+}
+
+fn stream_nonce(prefix: &[u8], counter: u32, last: bool) -> [u8; NONCE_LEN] {
+    let mut nonce = [0; NONCE_LEN];
+    nonce[..STREAM_NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[STREAM_NONCE_PREFIX_LEN..NONCE_LEN - 1].copy_from_slice(&counter.to_be_bytes());
+    nonce[NONCE_LEN - 1] = last as u8;
+    nonce
+}
+
+/// Fills the buffer as far as the reader allows, returning how much was read.
+fn read_chunk<R: Read>(reader: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(read) => len += read,
+            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(len)
+}
+
+/// Encrypts everything the reader yields in chunks, so its length isn't bounded by memory. The
+/// nonce of every chunk holds its position and whether it is the last one, so chunks can't be
+/// reordered, dropped or cut off without `decrypt_stream` noticing. `progress` is called with the
+/// number of bytes read so far. Returns the total.
+pub fn encrypt_stream<R: Read, W: Write>(
+    mut reader: R,
+    mut writer: W,
+    key: &[u8],
+    associated_data: &[u8],
+    mut progress: impl FnMut(u64),
+) -> anyhow::Result<u64> {
+    let cipher = CipherSuite::default();
+    let prefix = random_bytes(&mut thread_rng(), STREAM_NONCE_PREFIX_LEN);
+    writer.write_all(&prefix)?;
+
+    let mut chunk = vec![0; STREAM_CHUNK_LEN];
+    let mut next = vec![0; STREAM_CHUNK_LEN];
+    let mut len = read_chunk(&mut reader, &mut chunk)?;
+    let mut total = 0u64;
+    let mut counter = 0u32;
+    loop {
+        // Reading ahead is the only way to know that a full chunk is the last one
+        let next_len = match len {
+            STREAM_CHUNK_LEN => read_chunk(&mut reader, &mut next)?,
+            _ => 0,
+        };
+        let last = next_len == 0;
+        let ciphertext = cipher.encrypt(
+            key,
+            &stream_nonce(&prefix, counter, last),
+            Payload {
+                msg: &chunk[..len],
+                aad: associated_data,
+            },
+        )?;
+        writer.write_all(&ciphertext)?;
+        total += len as u64;
+        progress(total);
+        if last {
+            break;
+        }
+        std::mem::swap(&mut chunk, &mut next);
+        len = next_len;
+        counter = counter.checked_add(1).ok_or(CryptoError::StreamTooLong)?;
+    }
+    chunk.zeroize();
+    next.zeroize();
+    writer.flush()?;
+    Ok(total)
+}
+
+/// Reverses `encrypt_stream`. Plaintext is written as soon as its chunk is authenticated, so
+/// the output has to be discarded if this fails.
+pub fn decrypt_stream<R: Read, W: Write>(
+    mut reader: R,
+    mut writer: W,
+    key: &[u8],
+    associated_data: &[u8],
+    mut progress: impl FnMut(u64),
+) -> anyhow::Result<u64> {
+    let cipher = CipherSuite::default();
+    let mut prefix = [0; STREAM_NONCE_PREFIX_LEN];
+    if read_chunk(&mut reader, &mut prefix)? < STREAM_NONCE_PREFIX_LEN {
+        return Err(CryptoError::DamagedStream(0).into());
+    }
+
+    let mut chunk = vec![0; STREAM_CHUNK_LEN + TAG_LEN];
+    let mut next = vec![0; STREAM_CHUNK_LEN + TAG_LEN];
+    let mut len = read_chunk(&mut reader, &mut chunk)?;
+    let mut total = 0u64;
+    let mut counter = 0u32;
+    loop {
+        let next_len = match len {
+            len if len == chunk.len() => read_chunk(&mut reader, &mut next)?,
+            _ => 0,
+        };
+        let last = next_len == 0;
+        let mut plaintext = cipher
+            .decrypt(
+                key,
+                &stream_nonce(&prefix, counter, last),
+                Payload {
+                    msg: &chunk[..len],
+                    aad: associated_data,
+                },
+            )
+            .map_err(|_| CryptoError::DamagedStream(total))?;
+        writer.write_all(&plaintext)?;
+        total += plaintext.len() as u64;
+        plaintext.zeroize();
+        progress(total);
+        if last {
+            break;
+        }
+        std::mem::swap(&mut chunk, &mut next);
+        len = next_len;
+        counter = counter.checked_add(1).ok_or(CryptoError::StreamTooLong)?;
+    }
+    writer.flush()?;
+    Ok(total)
+}
+
+impl Display for SecretEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecretEncoding::Text => write!(f, "Text"),
+            SecretEncoding::Base64 => write!(f, "Base64"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{crypto::decrypt_secret, errors::CryptoError, passphrase::gen_passphrase};
+
+    use std::slice;
+
+    use rand::{thread_rng, CryptoRng, RngCore};
+    use zeroize::{Zeroize, ZeroizeOnDrop};
+
+    use super::{
+        base58, compress, decompress, decrypt_stream, derive_key, encrypt_secrets, encrypt_slots,
+        encrypt_stream, fingerprint, gen_secret, has_control_chars, open_any_slot, plaintext_len,
+        should_compress, split_note, stored_len, CipherSuite, DecryptedSecret, KdfParams, Secret,
+        SecretEncoding, SecretFormat, SecretString, SecurityLevel, MAX_PADDING_LEN, MAX_SECRETS,
+        MAX_SECRET_LEN, NONCE_LEN, NOTE_MARKER, SALT_LEN, STREAM_CHUNK_LEN, STREAM_KEY_LEN,
+        STREAM_NONCE_PREFIX_LEN, TAG_LEN,
+    };
+
+    #[test]
+    fn round_trip_test() {
+        let pass1 = gen_passphrase(8, Some(' '));
+        let pass2 = gen_passphrase(8, Some(' '));
+        let pass3 = gen_passphrase(8, Some(' '));
+
+        const VALUE1: &'static str = "This is my real secret";
+        const VALUE2: &'static str = "This is a fake secret";
+        const VALUE3: &'static str = "This is another fake secret";
+
+        let secrets = vec![
+            Secret {
+                value: VALUE1.into(),
+                password: pass1.as_str().into(),
+                encoding: SecretEncoding::Text,
+                note: Default::default(),
+            },
+            Secret {
+                value: VALUE2.into(),
+                password: pass2.as_str().into(),
+                encoding: SecretEncoding::Text,
+                note: Default::default(),
+            },
+            Secret {
+                value: VALUE3.into(),
+                password: pass3.as_str().into(),
+                encoding: SecretEncoding::Text,
+                note: Default::default(),
+            },
+        ];
+
+        let ciphertext = encrypt_secrets(
+            secrets,
+            &[],
+            KdfParams::default(),
+            CipherSuite::default(),
+            false,
+        )
+        .expect("Failed to encrypt");
+
+        let decrypted1 = decrypt_secret(
+            &ciphertext,
+            &pass1,
+            &[],
+            KdfParams::default(),
+            CipherSuite::default(),
+            false,
+        )
+        .expect("Failed to decrypt first secret");
+        let decrypted2 = decrypt_secret(
+            &ciphertext,
+            &pass2,
+            &[],
+            KdfParams::default(),
+            CipherSuite::default(),
+            false,
+        )
+        .expect("Failed to decrypt second secret");
+        let decrypted3 = decrypt_secret(
+            &ciphertext,
+            &pass3,
+            &[],
+            KdfParams::default(),
+            CipherSuite::default(),
+            false,
+        )
+        .expect("Failed to decrypt third secret");
+
+        assert_eq!(&*decrypted1.value, VALUE1);
+        assert_eq!(&*decrypted2.value, VALUE2);
+        assert_eq!(&*decrypted3.value, VALUE3);
+    }
+
+    #[test]
+    fn binary_round_trip_test() {
+        let pass = gen_passphrase(8, Some(' '));
+        let value = base64::encode([0u8, 159, 146, 150, 255]);
+
+        let secrets = vec![Secret {
+            value: value.as_str().into(),
+            password: pass.as_str().into(),
+            encoding: SecretEncoding::Base64,
+            note: Default::default(),
+        }];
+
+        let ciphertext = encrypt_secrets(
+            secrets,
+            &[],
+            KdfParams::default(),
+            CipherSuite::default(),
+            false,
+        )
+        .expect("Failed to encrypt");
+        let decrypted = decrypt_secret(
+            &ciphertext,
+            &pass,
+            &[],
+            KdfParams::default(),
+            CipherSuite::default(),
+            false,
+        )
+        .expect("Failed to decrypt");
+
+        assert_eq!(&*decrypted.value, value);
+        assert_eq!(decrypted.encoding, SecretEncoding::Base64);
+    }
+
+    #[test]
+    fn random_binary_round_trip_test() {
+        let pass = gen_passphrase(8, Some(' '));
+        let mut rng = thread_rng();
+
+        for length in [1, 31, 32, 33, 257] {
+            let mut data = vec![0u8; length];
+            rng.fill_bytes(&mut data);
+            let value = base64::encode(&data);
+
+            let secrets = vec![Secret {
+                value: value.as_str().into(),
+                password: pass.as_str().into(),
+                encoding: SecretEncoding::Base64,
+                note: Default::default(),
+            }];
+            let ciphertext = encrypt_secrets(
+                secrets,
+                &[],
+                KdfParams::default(),
+                CipherSuite::default(),
+                false,
+            )
+            .expect("Failed to encrypt");
+            let decrypted = decrypt_secret(
+                &ciphertext,
+                &pass,
+                &[],
+                KdfParams::default(),
+                CipherSuite::default(),
+                false,
+            )
+            .expect("Failed to decrypt");
+
+            assert_eq!(decrypted.encoding, SecretEncoding::Base64);
+            assert_eq!(decrypted.to_bytes().expect("Invalid Base64"), data);
+        }
+    }
+
+    #[test]
+    fn fingerprint_test() {
+        assert_eq!(
+            fingerprint(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn base58_test() {
+        assert_eq!(base58(b""), "");
+        assert_eq!(base58(b"Hello World!"), "2NEpo7TZRRrLZSi2U");
+        assert_eq!(base58(&[0, 0, 0x28, 0x7f, 0xb4, 0xcd]), "11233QC4");
+    }
+
+    #[test]
+    fn gen_secret_test() {
+        let hex = gen_secret(SecretFormat::Hex, 32).unwrap();
+        assert_eq!(hex.len(), 64);
+        assert!(hex.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(hex, gen_secret(SecretFormat::Hex, 32).unwrap());
+
+        let base58 = gen_secret(SecretFormat::Base58, 16).unwrap();
+        assert!((16..=22).contains(&base58.len()));
+        assert!(!base58.contains(['0', 'O', 'I', 'l']));
+
+        for (strength, words) in [(16, 12), (32, 24)] {
+            let mnemonic = gen_secret(SecretFormat::Mnemonic, strength).unwrap();
+            assert_eq!(mnemonic.split(' ').count(), words);
+        }
+
+        assert!(matches!(
+            gen_secret(SecretFormat::Mnemonic, 64),
+            Err(CryptoError::InvalidSecretStrength(64))
+        ));
+        assert!(matches!(
+            gen_secret(SecretFormat::Hex, 0),
+            Err(CryptoError::InvalidSecretStrength(0))
+        ));
+    }
+
+    /// Returns the same bytes over and over, like a broken or badly seeded generator.
+    struct RepeatingRng;
+
+    impl RngCore for RepeatingRng {
+        fn next_u32(&mut self) -> u32 {
+            7
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            7
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            dest.fill(7);
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            dest.fill(7);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for RepeatingRng {}
+
+    #[test]
+    fn nonces_are_never_reused_test() {
+        // The last two share a passphrase and with it a key
+        let secrets = || {
+            ["one", "two", "three", "three"]
+                .into_iter()
+                .map(|password| Secret {
+                    value: "same value".into(),
+                    password: password.into(),
+                    encoding: SecretEncoding::Text,
+                    note: Default::default(),
+                })
+                .collect::<Vec<Secret>>()
+        };
+        let kdf = KdfParams::Pbkdf2 { rounds: 1_000 };
+        let cipher = CipherSuite::default();
+
+        let first = encrypt_slots(secrets(), &[], kdf, cipher, false, &mut thread_rng())
+            .expect("Failed to encrypt");
+        let second = encrypt_slots(secrets(), &[], kdf, cipher, false, &mut thread_rng())
+            .expect("Failed to encrypt");
+        assert_ne!(first.ciphertext[..SALT_LEN], second.ciphertext[..SALT_LEN]);
+        let mut nonces = first
+            .slots
+            .iter()
+            .chain(&second.slots)
+            .flat_map(|slot| [&slot.header_nonce, &slot.body_nonce])
+            .collect::<Vec<&Vec<u8>>>();
+        nonces.sort();
+        nonces.dedup();
+        assert_eq!(nonces.len(), 4 * MAX_SECRETS);
+        let mut indices = first
+            .slots
+            .iter()
+            .map(|slot| slot.index)
+            .collect::<Vec<usize>>();
+        indices.sort();
+        assert_eq!(indices, (0..MAX_SECRETS).collect::<Vec<usize>>());
+
+        let error = encrypt_slots(secrets(), &[], kdf, cipher, false, &mut RepeatingRng)
+            .err()
+            .and_then(|error| error.downcast::<CryptoError>().ok());
+        assert!(matches!(error, Some(CryptoError::NonceReuse)));
+    }
+
+    #[test]
+    fn slot_search_does_not_depend_on_match_test() {
+        let values = ["decoy one", "real one", "decoy two"];
+        let passphrases = ["one", "two", "three"];
+        let secrets = values
+            .iter()
+            .zip(passphrases)
+            .map(|(value, password)| Secret {
+                value: (*value).into(),
+                password: password.into(),
+                encoding: SecretEncoding::Text,
+                note: Default::default(),
+            })
+            .collect();
+        let kdf = KdfParams::Pbkdf2 { rounds: 1_000 };
+        let ciphertext = encrypt_secrets(secrets, &[], kdf, CipherSuite::default(), false)
+            .expect("Failed to encrypt");
+
+        let (salt, rest) = ciphertext.split_at(SALT_LEN);
+        let header_len = CipherSuite::default().header_len();
+        let slots = rest.chunks_exact(header_len).collect::<Vec<&[u8]>>();
+        let mut positions = Vec::new();
+        for password in passphrases.iter().chain(&["wrong"]) {
+            let key = derive_key(password, salt, kdf).unwrap();
+            let (header, decryptions) = open_any_slot(&slots, &key, &[], CipherSuite::default());
+            assert_eq!(decryptions, 2 * slots.len());
+            positions.push(header.map(|header| header.position));
+        }
+
+        assert!(positions[..3].iter().all(Option::is_some));
+        assert_eq!(positions[3], None);
+        positions.dedup();
+        assert_eq!(positions.len(), 4);
+    }
+
+    #[test]
+    fn length_hides_number_of_secrets_test() {
+        let values = ["decoy one", "decoy two", "real one!", "decoy six"];
+        let passphrases = ["one", "two", "three", "four"];
+        let header_len = CipherSuite::default().header_len();
+        let base = SALT_LEN + MAX_SECRETS * (header_len + NONCE_LEN + TAG_LEN + values[0].len());
+
+        for count in 1..=MAX_SECRETS {
+            let secrets = (0..count)
+                .map(|index| Secret {
+                    value: values[index].into(),
+                    password: passphrases[index].into(),
+                    encoding: SecretEncoding::Text,
+                    note: Default::default(),
+                })
+                .collect();
+            let ciphertext = encrypt_secrets(
+                secrets,
+                &[],
+                KdfParams::default(),
+                CipherSuite::default(),
+                false,
+            )
+            .expect("Failed to encrypt");
+            // Only the random padding varies
+            assert!((base + 1..=base + MAX_PADDING_LEN).contains(&ciphertext.len()));
+
+            for index in 0..count {
+                let decrypted = decrypt_secret(
+                    &ciphertext,
+                    passphrases[index],
+                    &[],
+                    KdfParams::default(),
+                    CipherSuite::default(),
+                    false,
+                )
+                .unwrap();
+                assert_eq!(&*decrypted.value, values[index]);
+            }
+            assert!(decrypt_secret(
+                &ciphertext,
+                "wrong",
+                &[],
+                KdfParams::default(),
+                CipherSuite::default(),
+                false
+            )
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn too_many_secrets_test() {
+        let secrets = vec![
+            Secret {
+                value: "secret".into(),
+                password: "passphrase".into(),
+                encoding: SecretEncoding::Text,
+                note: Default::default(),
+            };
+            MAX_SECRETS + 1
+        ];
+        assert!(encrypt_secrets(
+            secrets,
+            &[],
+            KdfParams::default(),
+            CipherSuite::default(),
+            false
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn header_sized_body_test() {
+        // A four byte body encrypts to exactly the size of a header
+        let secrets = vec![Secret {
+            value: "abcd".into(),
+            password: "passphrase".into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let ciphertext = encrypt_secrets(
+            secrets,
+            b"metadata",
+            KdfParams::default(),
+            CipherSuite::default(),
+            false,
+        )
+        .expect("Failed to encrypt");
+        let decrypted = decrypt_secret(
+            &ciphertext,
+            "passphrase",
+            b"metadata",
+            KdfParams::default(),
+            CipherSuite::default(),
+            false,
+        )
+        .expect("Failed to decrypt");
+        assert_eq!(&*decrypted.value, "abcd");
+        assert!(decrypt_secret(
+            &ciphertext,
+            "passphrase",
+            b"tampered",
+            KdfParams::default(),
+            CipherSuite::default(),
+            false
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn empty_inputs_test() {
+        let secret = |value: &str, password: &str| Secret {
+            value: value.into(),
+            password: password.into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        };
+        let error = encrypt_secrets(
+            vec![secret("secret", "   ")],
+            &[],
+            KdfParams::default(),
+            CipherSuite::default(),
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "The passphrase must not be empty");
+        let error = encrypt_secrets(
+            vec![secret("", "passphrase")],
+            &[],
+            KdfParams::default(),
+            CipherSuite::default(),
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "The secret must not be empty");
+    }
+
+    #[test]
+    fn secrets_are_zeroized() {
+        fn assert_zeroized_on_drop<T: Zeroize + ZeroizeOnDrop>() {}
+        assert_zeroized_on_drop::<SecretString>();
+        assert_zeroized_on_drop::<Secret>();
+
+        let mut secret = Secret {
+            value: "correct horse".into(),
+            password: "battery staple".into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        };
+        secret.zeroize();
+        assert!(secret.value.is_empty());
+        assert!(secret.password.is_empty());
+        assert_eq!(
+            format!("{:?}", SecretString::from("hunter2")),
+            "SecretString(..)"
+        );
+
+        let mut decrypted = DecryptedSecret {
+            value: "correct horse".into(),
+            encoding: SecretEncoding::Text,
+            note: "battery staple".into(),
+        };
+        assert_eq!(
+            format!("{:?}", decrypted),
+            "DecryptedSecret { encoding: Text, .. }"
+        );
+        decrypted.zeroize();
+        assert!(decrypted.value.is_empty());
+        assert!(decrypted.note.is_empty());
+    }
+
+    #[test]
+    fn secret_string_wipes_its_buffer() {
+        let mut secret = SecretString::from("correct horse battery staple");
+        let (ptr, len) = (secret.as_ptr(), secret.len());
+        // Drop runs the same wipe and then frees the buffer, which can't be read afterwards.
+        // Zeroizing keeps the buffer allocated, so it can still be inspected here.
+        secret.zeroize();
+        let buffer = unsafe { std::slice::from_raw_parts(ptr, len) };
+        assert!(buffer.iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn control_chars_test() {
+        assert!(!has_control_chars("correct horse battery staple"));
+        assert!(!has_control_chars("-----BEGIN KEY-----\r\n\tabc\n"));
+        assert!(has_control_chars("null\0byte"));
+        assert!(has_control_chars("escape\u{1b}[0m"));
+    }
+
+    #[test]
+    fn security_level_parses_case_insensitively() {
+        assert_eq!("paranoid".parse(), Ok(SecurityLevel::Paranoid));
+        assert_eq!("Fast".parse(), Ok(SecurityLevel::Fast));
+        assert!("medium".parse::<SecurityLevel>().is_err());
+        assert_eq!(SecurityLevel::Advanced.kdf_params(), None);
+    }
+
+    #[test]
+    fn every_cipher_suite_round_trips() {
+        for cipher in CipherSuite::ALL {
+            let secrets = vec![Secret {
+                value: "secret".into(),
+                password: "passphrase".into(),
+                encoding: SecretEncoding::Text,
+                note: Default::default(),
+            }];
+            let ciphertext =
+                encrypt_secrets(secrets, b"metadata", KdfParams::default(), cipher, false)
+                    .expect("Failed to encrypt");
+            let decrypted = decrypt_secret(
+                &ciphertext,
+                "passphrase",
+                b"metadata",
+                KdfParams::default(),
+                cipher,
+                false,
+            )
+            .expect("Failed to decrypt");
+            assert_eq!(&*decrypted.value, "secret");
+            assert_eq!(CipherSuite::from_id(cipher.id()).ok(), Some(cipher));
+            assert_eq!(cipher.to_string().parse(), Ok(cipher));
+        }
+        assert_eq!(
+            "xchacha20-poly1305".parse(),
+            Ok(CipherSuite::XChaCha20Poly1305)
+        );
+        assert!("chacha20".parse::<CipherSuite>().is_err());
+    }
+
+    #[test]
+    fn argon2id_derives_distinct_keys() {
+        let salt = [7u8; SALT_LEN];
+        let kdf = KdfParams::Argon2id {
+            memory_mib: 8,
+            iterations: 1,
+            parallelism: 1,
+        };
+        let key = derive_key("passphrase", &salt, kdf).unwrap();
+        assert_eq!(key.len(), 32);
+        assert_eq!(key, derive_key("passphrase", &salt, kdf).unwrap());
+        assert_ne!(
+            key,
+            derive_key("passphrase", &[8u8; SALT_LEN], kdf).unwrap()
+        );
+        assert_ne!(key, derive_key("passphrasf", &salt, kdf).unwrap());
+        for other in [
+            KdfParams::Argon2id {
+                memory_mib: 9,
+                iterations: 1,
+                parallelism: 1,
+            },
+            KdfParams::Argon2id {
+                memory_mib: 8,
+                iterations: 2,
+                parallelism: 1,
+            },
+            KdfParams::Argon2id {
+                memory_mib: 8,
+                iterations: 1,
+                parallelism: 2,
+            },
+            KdfParams::Pbkdf2 { rounds: 1_000 },
+        ] {
+            assert_ne!(key, derive_key("passphrase", &salt, other).unwrap());
+        }
+
+        let (id, bytes) = kdf.to_bytes();
+        assert_eq!(KdfParams::from_bytes(id, bytes), Some(kdf));
+        let default = KdfParams::default();
+        let (id, bytes) = default.to_bytes();
+        assert_eq!((id, bytes), (1, 10_000u32.to_be_bytes()));
+        assert_eq!(KdfParams::from_bytes(id, bytes), Some(default));
+        assert_eq!(KdfParams::from_bytes(3, bytes), None);
+    }
+
+    #[test]
+    fn every_cipher_suite_detects_tampering() {
+        let kdf = KdfParams::Pbkdf2 { rounds: 1_000 };
+        for cipher in CipherSuite::ALL {
+            let secrets = vec![Secret {
+                value: "secret".into(),
+                password: "passphrase".into(),
+                encoding: SecretEncoding::Text,
+                note: Default::default(),
+            }];
+            let encrypted =
+                encrypt_slots(secrets, b"metadata", kdf, cipher, false, &mut thread_rng())
+                    .expect("Failed to encrypt");
+            let slot = encrypted.slots[0].index;
+            let header = SALT_LEN + slot * cipher.header_len();
+            let slot_len = cipher.nonce_len() + TAG_LEN + "secret".len();
+            let body = SALT_LEN + MAX_SECRETS * cipher.header_len() + slot * slot_len;
+            let decrypt = |ciphertext: &[u8], associated_data: &[u8], cipher| {
+                decrypt_secret(
+                    ciphertext,
+                    "passphrase",
+                    associated_data,
+                    kdf,
+                    cipher,
+                    false,
+                )
+            };
+            // A damaged header looks like a wrong passphrase, a damaged body like changed metadata
+            let header_failed = |error: anyhow::Error| match error.downcast_ref::<CryptoError>() {
+                Some(CryptoError::WrongPassphrase) => true,
+                Some(CryptoError::MetadataMismatch) => false,
+                _ => panic!("Unexpected error: {}", error),
+            };
+            assert_eq!(
+                &*decrypt(&encrypted.ciphertext, b"metadata", cipher)
+                    .unwrap()
+                    .value,
+                "secret"
+            );
+
+            for (position, in_header) in [
+                (header, true),
+                (header + cipher.nonce_len(), true),
+                (body, false),
+                (body + slot_len - 1, false),
+            ] {
+                let mut tampered = encrypted.ciphertext.clone();
+                tampered[position] ^= 1;
+                let error = decrypt(&tampered, b"metadata", cipher).expect_err("Accepted a flip");
+                assert_eq!(
+                    header_failed(error),
+                    in_header,
+                    "{} at {}",
+                    cipher,
+                    position
+                );
+            }
+            let error = decrypt(&encrypted.ciphertext, b"changed", cipher).unwrap_err();
+            assert!(!header_failed(error));
+            let other = CipherSuite::ALL.into_iter().find(|&other| other != cipher);
+            assert!(decrypt(&encrypted.ciphertext, b"metadata", other.unwrap()).is_err());
+        }
+    }
+
+    #[test]
+    fn secret_length_is_capped() {
+        let secret = |len| {
+            vec![Secret {
+                value: "x".repeat(len).into(),
+                password: "passphrase".into(),
+                encoding: SecretEncoding::Text,
+                note: Default::default(),
+            }]
+        };
+        assert_eq!(CipherSuite::default().max_secret_len(), MAX_SECRET_LEN);
+        for cipher in CipherSuite::ALL {
+            let max = cipher.max_secret_len();
+            assert!(max <= MAX_SECRET_LEN);
+            let kdf = KdfParams::default();
+            let ciphertext =
+                encrypt_secrets(secret(max), &[], kdf, cipher, false).expect("Failed to encrypt");
+            let decrypted = decrypt_secret(&ciphertext, "passphrase", &[], kdf, cipher, false)
+                .expect("Failed to decrypt");
+            assert_eq!(decrypted.value.len(), max);
+
+            let error = encrypt_secrets(secret(max + 1), &[], kdf, cipher, false).unwrap_err();
+            assert!(matches!(
+                error.downcast_ref::<CryptoError>(),
+                Some(CryptoError::SecretTooLong(len)) if *len == max + 1
+            ));
+        }
+    }
+
+    fn compression_test_secrets() -> (Secret, Secret) {
+        let descriptor = "wsh(sortedmulti(2,[d34db33f/48h/0h/0h/2h]xpub6DkFAXWQ2dHxq2vatrt9qyA3bXYU4ToWQwCHbf5XB2mSTexcHZCeKS1VZYcPoBd5X8yVcbXFHJR9R8UCVpt82VX1VhR28mCyxUFL4r6KFrf/<0;1>/*))#abcdefgh\n".repeat(8);
+        let text = Secret {
+            value: descriptor.into(),
+            password: "passphrase".into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        };
+        let mut data = vec![0u8; 2000];
+        thread_rng().fill_bytes(&mut data);
+        let random = Secret {
+            value: base64::encode(data).into(),
+            password: "other passphrase".into(),
+            encoding: SecretEncoding::Base64,
+            note: Default::default(),
+        };
+        (text, random)
+    }
+
+    #[test]
+    fn compressible_secrets_are_compressed() {
+        let (text, random) = compression_test_secrets();
+        assert!(should_compress(slice::from_ref(&text)));
+        assert!(stored_len(&text, true) < plaintext_len(&text.value, text.encoding));
+        assert!(!should_compress(slice::from_ref(&random)));
+        // The random secret is the longest, and compressing it only makes it longer
+        assert!(!should_compress(&[text.clone(), random]));
+
+        let value = text.value.to_string();
+        let ciphertext = encrypt_secrets(
+            vec![text],
+            &[],
+            KdfParams::default(),
+            CipherSuite::default(),
+            true,
+        )
+        .expect("Failed to encrypt");
+        let decrypted = decrypt_secret(
+            &ciphertext,
+            "passphrase",
+            &[],
+            KdfParams::default(),
+            CipherSuite::default(),
+            true,
+        )
+        .expect("Failed to decrypt");
+        assert_eq!(&*decrypted.value, value);
+        assert!(decrypt_secret(
+            &ciphertext,
+            "passphrase",
+            &[],
+            KdfParams::default(),
+            CipherSuite::default(),
+            false,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn compressed_binary_round_trip() {
+        let data = [0u8, 1, 2, 3].repeat(100);
+        let secret = Secret {
+            value: base64::encode(&data).into(),
+            password: "passphrase".into(),
+            encoding: SecretEncoding::Base64,
+            note: Default::default(),
+        };
+        assert!(should_compress(slice::from_ref(&secret)));
+        let ciphertext = encrypt_secrets(
+            vec![secret],
+            &[],
+            KdfParams::default(),
+            CipherSuite::default(),
+            true,
+        )
+        .expect("Failed to encrypt");
+        let decrypted = decrypt_secret(
+            &ciphertext,
+            "passphrase",
+            &[],
+            KdfParams::default(),
+            CipherSuite::default(),
+            true,
+        )
+        .expect("Failed to decrypt");
+        assert_eq!(decrypted.encoding, SecretEncoding::Base64);
+        assert_eq!(decrypted.to_bytes().unwrap(), data);
+    }
+
+    #[test]
+    fn notes_are_kept_apart_from_the_secret() {
+        let secrets = vec![
+            Secret {
+                value: "This is my secret".into(),
+                password: "first passphrase".into(),
+                encoding: SecretEncoding::Text,
+                note: "Wallet fingerprint abc123".into(),
+            },
+            Secret {
+                value: base64::encode([0u8, 159, 146, 150, 255]).into(),
+                password: "second passphrase".into(),
+                encoding: SecretEncoding::Base64,
+                note: "Binary, with a note".into(),
+            },
+            Secret {
+                value: "No note".into(),
+                password: "third passphrase".into(),
+                encoding: SecretEncoding::Text,
+                note: Default::default(),
+            },
+        ];
+        assert_eq!(
+            stored_len(&secrets[0], false),
+            plaintext_len(&secrets[0].value, SecretEncoding::Text) + 3 + 25
+        );
+
+        for compressed in [false, true] {
+            let ciphertext = encrypt_secrets(
+                secrets.clone(),
+                &[],
+                KdfParams::default(),
+                CipherSuite::default(),
+                compressed,
+            )
+            .expect("Failed to encrypt");
+            let decrypt = |password: &str| {
+                decrypt_secret(
+                    &ciphertext,
+                    password,
+                    &[],
+                    KdfParams::default(),
+                    CipherSuite::default(),
+                    compressed,
+                )
+                .expect("Failed to decrypt")
+            };
+
+            let text = decrypt("first passphrase");
+            assert_eq!(&*text.value, "This is my secret");
+            assert_eq!(&*text.note, "Wallet fingerprint abc123");
+            let binary = decrypt("second passphrase");
+            assert_eq!(binary.encoding, SecretEncoding::Base64);
+            assert_eq!(binary.to_bytes().unwrap(), [0u8, 159, 146, 150, 255]);
+            assert_eq!(&*binary.note, "Binary, with a note");
+            let plain = decrypt("third passphrase");
+            assert_eq!(&*plain.value, "No note");
+            assert!(plain.note.is_empty());
+        }
+
+        assert!(matches!(
+            split_note(vec![NOTE_MARKER, 0, 5, b'x']),
+            Err(CryptoError::InvalidNote)
+        ));
+    }
+
+    #[test]
+    fn decompression_is_bounded() {
+        let bomb = compress(&vec![b'x'; MAX_SECRET_LEN + 1]);
+        assert!(bomb.len() < 100);
+        assert!(matches!(
+            decompress(&bomb),
+            Err(CryptoError::InvalidCompression)
+        ));
+        let inflated = decompress(&compress(&vec![b'x'; MAX_SECRET_LEN])).unwrap();
+        assert_eq!(inflated.len(), MAX_SECRET_LEN);
+    }
+
+    #[test]
+    fn stream_round_trip() {
+        let key = [7u8; STREAM_KEY_LEN];
+        for len in [
+            0,
+            1,
+            STREAM_CHUNK_LEN - 1,
+            STREAM_CHUNK_LEN,
+            STREAM_CHUNK_LEN + 1,
+            3 * STREAM_CHUNK_LEN,
+        ] {
+            let mut data = vec![0u8; len];
+            thread_rng().fill_bytes(&mut data);
+            let mut encrypted = Vec::new();
+            let mut progress = Vec::new();
+            let total = encrypt_stream(&data[..], &mut encrypted, &key, b"backup", |done| {
+                progress.push(done)
+            })
+            .expect("Failed to encrypt");
+            assert_eq!(total, len as u64);
+            assert_eq!(progress.last(), Some(&(len as u64)));
+            assert!(progress.windows(2).all(|pair| pair[0] < pair[1]));
+
+            let mut decrypted = Vec::new();
+            decrypt_stream(&encrypted[..], &mut decrypted, &key, b"backup", |_| {})
+                .expect("Failed to decrypt");
+            assert_eq!(decrypted, data, "length {}", len);
+        }
+    }
+
+    #[test]
+    fn damaged_streams_are_rejected() {
+        let key = [7u8; STREAM_KEY_LEN];
+        let data = vec![1u8; 2 * STREAM_CHUNK_LEN + 100];
+        let mut encrypted = Vec::new();
+        encrypt_stream(&data[..], &mut encrypted, &key, b"backup", |_| {}).unwrap();
+        let decrypt = |encrypted: &[u8], key: &[u8], associated_data: &[u8]| {
+            decrypt_stream(encrypted, Vec::new(), key, associated_data, |_| {})
+                .map_err(|error| error.downcast::<CryptoError>().unwrap())
+        };
+        assert!(decrypt(&encrypted, &key, b"backup").is_ok());
+
+        let mut flipped = encrypted.clone();
+        flipped[STREAM_NONCE_PREFIX_LEN + STREAM_CHUNK_LEN + TAG_LEN + 5] ^= 1;
+        assert!(matches!(
+            decrypt(&flipped, &key, b"backup"),
+            Err(CryptoError::DamagedStream(offset)) if offset == STREAM_CHUNK_LEN as u64
+        ));
+
+        // Cut off after a whole chunk, which only the last chunk flag reveals
+        let truncated = &encrypted[..STREAM_NONCE_PREFIX_LEN + 2 * (STREAM_CHUNK_LEN + TAG_LEN)];
+        assert!(matches!(
+            decrypt(truncated, &key, b"backup"),
+            Err(CryptoError::DamagedStream(_))
+        ));
+        assert!(matches!(
+            decrypt(&encrypted, &key, b"other backup"),
+            Err(CryptoError::DamagedStream(0))
+        ));
+        assert!(matches!(
+            decrypt(&encrypted, &[8u8; STREAM_KEY_LEN], b"backup"),
+            Err(CryptoError::DamagedStream(0))
+        ));
+    }
+}
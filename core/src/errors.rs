@@ -0,0 +1,671 @@
+use std::{error, fmt};
+
+use crate::{
+    crypto::{
+        MAX_ARGON2_ITERATIONS, MAX_ARGON2_MEMORY_MIB, MAX_ARGON2_PARALLELISM, MAX_KDF_ROUNDS,
+        MAX_SECRETS, MAX_SECRET_LEN, MIN_ARGON2_MEMORY_MIB, MIN_KDF_ROUNDS,
+    },
+    hardware_key::MAX_CREDENTIAL_ID_LEN,
+    slip39::{
+        MAX_SHARES as MAX_SLIP39_SHARES, MIN_SECRET_LEN as MIN_SLIP39_SECRET_LEN,
+        MIN_WORDS as MIN_SLIP39_WORDS,
+    },
+};
+
+#[derive(Clone, Debug)]
+pub enum BackupError {
+    SharksError(String),
+    InvalidShard(String),
+    NewerVersion(u8),
+    DamagedShard(usize),
+    NotEnoughShards {
+        provided: usize,
+        required: usize,
+    },
+    MismatchedShards {
+        expected: usize,
+        conflicting: Vec<usize>,
+    },
+    InconsistentMetadata(Vec<usize>),
+    KeyFileRequired,
+    /// The shards need the secret of a hardware key, but none was given
+    HardwareKeyRequired,
+    InvalidRecoverySeed,
+    NotStreamed,
+    SecretTooLarge {
+        max: usize,
+        actual: usize,
+    },
+    InvalidConfig {
+        reason: ConfigError,
+    },
+    CryptoFailure(String),
+    SharingFailure(String),
+    /// The file of a streamed backup couldn't be read or written
+    StreamFailure(String),
+    /// Shards can only be added to a backup without a recovery seed from its shards
+    NotExtendable,
+    /// Number the last added shard would have
+    TooManyShards(usize),
+    /// Number asked for the first added shard, which a known shard already has
+    ShardNumberTaken(usize),
+    Slip39(Slip39Error),
+}
+
+impl fmt::Display for BackupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self {
+            BackupError::SharksError(message) => write!(f, "SharksError: {}", message),
+            BackupError::InvalidShard(message) => write!(f, "Invalid backup shard: {}", message),
+            BackupError::NewerVersion(version) => write!(
+                f,
+                "This backup was created by a newer version of Hyperbacked (shard version {})",
+                version
+            ),
+            BackupError::DamagedShard(number) => {
+                write!(
+                    f,
+                    "Backup shard #{} is damaged: integrity check failed",
+                    number
+                )
+            }
+            BackupError::NotEnoughShards { provided, required } => write!(
+                f,
+                "Not enough backup shards: {} of {} required",
+                provided, required
+            ),
+            BackupError::MismatchedShards {
+                expected,
+                conflicting,
+            } => write!(
+                f,
+                "Backup shard(s) {} belong to a different backup than shard #{}",
+                conflicting
+                    .iter()
+                    .map(|number| format!("#{}", number))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                expected
+            ),
+            BackupError::InconsistentMetadata(numbers) => write!(
+                f,
+                "The backup details stored in shard(s) {} don't match the others",
+                numbers
+                    .iter()
+                    .map(|number| format!("#{}", number))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            BackupError::KeyFileRequired => {
+                write!(f, "This backup can only be restored with its key file")
+            }
+            BackupError::HardwareKeyRequired => write!(
+                f,
+                "This backup can only be restored with the security key it was created with"
+            ),
+            BackupError::InvalidRecoverySeed => {
+                write!(f, "The recovery seed is mistyped or incomplete")
+            }
+            BackupError::NotStreamed => write!(
+                f,
+                "This backup holds a secret, not the key of an encrypted file"
+            ),
+            BackupError::SecretTooLarge { max, actual } => write!(
+                f,
+                "The secret is too long ({} bytes, at most {} fit in a backup)",
+                actual, max
+            ),
+            BackupError::InvalidConfig { reason } => {
+                write!(f, "Invalid backup settings: {}", reason)
+            }
+            BackupError::CryptoFailure(message) => write!(f, "Encryption failed: {}", message),
+            BackupError::SharingFailure(message) => {
+                write!(f, "Splitting the backup into shards failed: {}", message)
+            }
+            BackupError::StreamFailure(message) => {
+                write!(f, "Encrypting the file failed: {}", message)
+            }
+            BackupError::NotExtendable => write!(
+                f,
+                "This backup has no recovery seed, so the random part of its shards can't be \
+                 recreated from the secret. Add shards from a threshold of the existing ones instead"
+            ),
+            BackupError::TooManyShards(last) => write!(
+                f,
+                "A backup can have at most {} shards, the new ones would go up to #{}",
+                u8::MAX,
+                last
+            ),
+            BackupError::ShardNumberTaken(number) => write!(
+                f,
+                "Shard #{} is already part of the backup, start after the shards known so far",
+                number
+            ),
+            BackupError::Slip39(error) => error.fmt(f),
+        }
+    }
+}
+
+impl error::Error for BackupError {}
+
+impl From<ConfigError> for BackupError {
+    fn from(reason: ConfigError) -> Self {
+        BackupError::InvalidConfig { reason }
+    }
+}
+
+impl From<Slip39Error> for BackupError {
+    fn from(error: Slip39Error) -> Self {
+        BackupError::Slip39(error)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigError {
+    ZeroThreshold,
+    ThresholdExceedsTotal {
+        threshold: u8,
+        total: u8,
+    },
+    LabelTooLong(usize),
+    KdfRoundsOutOfRange(u32),
+    Argon2ParamsOutOfRange,
+    /// Names what the SLIP-0039 shares can't hold
+    Slip39Unsupported(&'static str),
+    CredentialIdTooLong(usize),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self {
+            ConfigError::ZeroThreshold => write!(f, "At least one shard must be required"),
+            ConfigError::ThresholdExceedsTotal { threshold, total } => write!(
+                f,
+                "Cannot require {} shards when only {} are created",
+                threshold, total
+            ),
+            ConfigError::LabelTooLong(len) => write!(
+                f,
+                "The label is {} bytes long, at most {} fit on a shard",
+                len,
+                u8::MAX
+            ),
+            ConfigError::KdfRoundsOutOfRange(rounds) => write!(
+                f,
+                "{} key derivation rounds are not supported, choose between {} and {}",
+                rounds, MIN_KDF_ROUNDS, MAX_KDF_ROUNDS
+            ),
+            ConfigError::Argon2ParamsOutOfRange => write!(
+                f,
+                "Argon2id needs {} to {} MiB of memory, 1 to {} iterations and 1 to {} lanes",
+                MIN_ARGON2_MEMORY_MIB,
+                MAX_ARGON2_MEMORY_MIB,
+                MAX_ARGON2_ITERATIONS,
+                MAX_ARGON2_PARALLELISM
+            ),
+            ConfigError::Slip39Unsupported(what) => {
+                write!(f, "SLIP-0039 shares can't have {}", what)
+            }
+            ConfigError::CredentialIdTooLong(len) => write!(
+                f,
+                "The security key returned a credential ID of {} bytes, at most {} fit on a shard",
+                len, MAX_CREDENTIAL_ID_LEN
+            ),
+        }
+    }
+}
+
+impl error::Error for ConfigError {}
+
+#[derive(Clone, Debug)]
+pub enum RestoreError {
+    NotEnoughShares {
+        provided: usize,
+        required: usize,
+    },
+    MismatchedBackups {
+        expected: usize,
+        conflicting: Vec<usize>,
+    },
+    /// Index into the shares that were passed in, starting at 0
+    CorruptShare(usize),
+    /// The share decoded, but failed its integrity check
+    ShareVerificationError {
+        index: usize,
+        number: usize,
+    },
+    WrongPassphrase,
+    /// The shards say a key file is needed, but none was given
+    KeyFileRequired,
+    /// The shards say a hardware key is needed, but its secret wasn't given
+    HardwareKeyRequired,
+    /// The shards need a hardware key, which couldn't be asked for its secret
+    HardwareKey(HardwareKeyError),
+    /// The passphrase is right, but the threshold, backup ID or other metadata of the shards
+    /// were changed after they were created
+    MetadataMismatch,
+    Other(String),
+}
+
+impl fmt::Display for RestoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self {
+            RestoreError::NotEnoughShares { provided, required } => write!(
+                f,
+                "Not enough backup shards: {} of {} required",
+                provided, required
+            ),
+            RestoreError::MismatchedBackups {
+                expected,
+                conflicting,
+            } => write!(
+                f,
+                "Backup shard(s) {} belong to a different backup than shard #{}",
+                conflicting
+                    .iter()
+                    .map(|number| format!("#{}", number))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                expected
+            ),
+            RestoreError::CorruptShare(index) => write!(
+                f,
+                "Share {} is damaged or not a valid backup shard",
+                index + 1
+            ),
+            RestoreError::ShareVerificationError { number, .. } => write!(
+                f,
+                "Backup shard #{} is damaged, rescan it or leave it out",
+                number
+            ),
+            RestoreError::WrongPassphrase => write!(f, "Wrong passphrase"),
+            RestoreError::KeyFileRequired => write!(
+                f,
+                "This backup also needs the key file that was chosen when it was created"
+            ),
+            RestoreError::HardwareKeyRequired => write!(
+                f,
+                "This backup also needs the security key that was chosen when it was created"
+            ),
+            RestoreError::HardwareKey(error) => error.fmt(f),
+            RestoreError::MetadataMismatch => write!(
+                f,
+                "Share metadata mismatch: the shards were changed after the backup was created"
+            ),
+            RestoreError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl error::Error for RestoreError {}
+
+#[derive(Debug)]
+pub enum CryptoError {
+    WrongPassphrase,
+    TruncatedBlob,
+    EmptyPassphrase,
+    EmptySecret,
+    TooManySecrets(usize),
+    SecretTooLong(usize),
+    UnknownCipherSuite(u8),
+    InvalidCompression,
+    InvalidNote,
+    /// The random number generator returned a nonce that was already used
+    NonceReuse,
+    /// The passphrase is right, but the associated data differs from what was encrypted
+    MetadataMismatch,
+    /// Offset into the plaintext where authentication failed
+    DamagedStream(u64),
+    StreamTooLong,
+    /// Bytes of entropy a generated secret can't be made of in its format
+    InvalidSecretStrength(usize),
+}
+
+impl fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self {
+            CryptoError::WrongPassphrase => write!(f, "Wrong passphrase"),
+            CryptoError::TruncatedBlob => write!(f, "Encrypted data is too short"),
+            CryptoError::EmptyPassphrase => write!(f, "The passphrase must not be empty"),
+            CryptoError::EmptySecret => write!(f, "The secret must not be empty"),
+            CryptoError::TooManySecrets(count) => write!(
+                f,
+                "A backup can hold at most {} secrets, got {}",
+                MAX_SECRETS, count
+            ),
+            CryptoError::SecretTooLong(len) => write!(
+                f,
+                "A secret can be at most {} bytes long, got {}",
+                MAX_SECRET_LEN, len
+            ),
+            CryptoError::UnknownCipherSuite(id) => write!(
+                f,
+                "Unknown cipher suite {}, the backup may have been created by a newer version of \
+                 Hyperbacked",
+                id
+            ),
+            CryptoError::InvalidCompression => {
+                write!(f, "The decrypted secret could not be decompressed")
+            }
+            CryptoError::InvalidNote => write!(f, "The note stored with the secret is damaged"),
+            CryptoError::NonceReuse => write!(
+                f,
+                "The random number generator repeated itself, so encrypting was stopped"
+            ),
+            CryptoError::MetadataMismatch => write!(
+                f,
+                "Share metadata mismatch: the backup details don't match what was encrypted"
+            ),
+            CryptoError::DamagedStream(offset) => write!(
+                f,
+                "The encrypted file is damaged or incomplete after {} bytes",
+                offset
+            ),
+            CryptoError::StreamTooLong => write!(f, "The file is too large to encrypt"),
+            CryptoError::InvalidSecretStrength(strength) => write!(
+                f,
+                "A secret of {} bytes can't be generated in this format",
+                strength
+            ),
+        }
+    }
+}
+
+impl error::Error for CryptoError {}
+
+/// What a scanned or pasted payload turned out to be, see `qrcode::classify_payload`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PayloadKind {
+    Share,
+    NewerShare,
+    /// Valid Base85, but without the shard header
+    NotAShare,
+    Url,
+    WalletAddress,
+    Bip39Phrase,
+    /// Words of a SLIP-0039 share that don't check out, or of a backup of several groups
+    Slip39Share,
+    /// Not recognizable as anything, most likely a mistyped share
+    Malformed,
+}
+
+impl fmt::Display for PayloadKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PayloadKind::Share => write!(f, "This is a Hyperbacked share"),
+            PayloadKind::NewerShare => write!(
+                f,
+                "This share was created by a newer version of Hyperbacked"
+            ),
+            PayloadKind::NotAShare | PayloadKind::Malformed => {
+                write!(f, "This is not a Hyperbacked share")
+            }
+            PayloadKind::Url => write!(f, "This is a web address, not a Hyperbacked share"),
+            PayloadKind::WalletAddress => write!(
+                f,
+                "This is a cryptocurrency address, not a Hyperbacked share"
+            ),
+            PayloadKind::Bip39Phrase => write!(
+                f,
+                "This looks like a BIP-39 recovery phrase, not a Hyperbacked share"
+            ),
+            PayloadKind::Slip39Share => write!(
+                f,
+                "This looks like a SLIP-0039 share, but it is mistyped or one of several groups"
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum QrCodeError {
+    NoCodeFound,
+    ScanCancelled,
+    Unrecognized(PayloadKind),
+}
+
+impl fmt::Display for QrCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self {
+            QrCodeError::NoCodeFound => write!(f, "No QR code could be found in the image"),
+            QrCodeError::ScanCancelled => write!(f, "Scanning was cancelled"),
+            QrCodeError::Unrecognized(kind) => write!(f, "{}", kind),
+        }
+    }
+}
+
+impl error::Error for QrCodeError {}
+
+#[derive(Debug)]
+pub enum PayloadError {
+    Empty,
+    InvalidCharacter { character: char, position: usize },
+    InvalidLength(usize),
+    InvalidEncoding,
+}
+
+impl fmt::Display for PayloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self {
+            PayloadError::Empty => write!(f, "The shard text is empty"),
+            PayloadError::InvalidCharacter {
+                character,
+                position,
+            } => write!(
+                f,
+                "Invalid character '{}' at position {}",
+                character.escape_default(),
+                position
+            ),
+            PayloadError::InvalidLength(length) => write!(
+                f,
+                "The shard text is {} characters long, which can't be complete - a character might be missing",
+                length
+            ),
+            PayloadError::InvalidEncoding => write!(f, "The shard text is not valid Base85"),
+        }
+    }
+}
+
+impl error::Error for PayloadError {}
+
+#[derive(Debug, PartialEq)]
+pub enum MnemonicError {
+    Empty,
+    InvalidWordCount(usize),
+    UnknownWord { word: String, position: usize },
+    ChecksumMismatch { first: usize, last: usize },
+    InvalidPadding,
+}
+
+impl fmt::Display for MnemonicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self {
+            MnemonicError::Empty => write!(f, "No words were entered"),
+            MnemonicError::InvalidWordCount(count) => write!(
+                f,
+                "{} words can't be a complete shard, which has a multiple of 24 - a word might be missing",
+                count
+            ),
+            MnemonicError::UnknownWord { word, position } => {
+                write!(f, "Word {} '{}' is not a BIP-39 word", position, word)
+            }
+            MnemonicError::ChecksumMismatch { first, last } => write!(
+                f,
+                "Words {} to {} don't match their checksum - one of them is wrong or out of order",
+                first, last
+            ),
+            MnemonicError::InvalidPadding => {
+                write!(f, "The words are valid but don't end a shard")
+            }
+        }
+    }
+}
+
+impl error::Error for MnemonicError {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Slip39Error {
+    TooShort(usize),
+    UnknownWord {
+        word: String,
+        position: usize,
+    },
+    ChecksumMismatch,
+    InvalidPadding,
+    InvalidGroups,
+    MismatchedShares,
+    /// Member index, counted from 0 like in the share
+    DuplicateShare(u8),
+    NotEnoughShares {
+        provided: usize,
+        required: usize,
+    },
+    NotEnoughGroups {
+        provided: usize,
+        required: usize,
+    },
+    InvalidDigest,
+    InvalidShareCount {
+        threshold: u8,
+        count: u8,
+    },
+    InvalidSecretLength(usize),
+    NotHex,
+    InvalidPassphrase,
+    MultipleGroups,
+    NotExtendable,
+}
+
+impl fmt::Display for Slip39Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self {
+            Slip39Error::TooShort(count) => write!(
+                f,
+                "A SLIP-0039 share has at least {} words, this one has {}",
+                MIN_SLIP39_WORDS, count
+            ),
+            Slip39Error::UnknownWord { word, position } => {
+                write!(f, "Word {} '{}' is not a SLIP-0039 word", position, word)
+            }
+            Slip39Error::ChecksumMismatch => write!(
+                f,
+                "The words of the SLIP-0039 share don't match their checksum - one of them is wrong or out of order"
+            ),
+            Slip39Error::InvalidPadding => {
+                write!(f, "The SLIP-0039 share has a word too many or too few")
+            }
+            Slip39Error::InvalidGroups => write!(
+                f,
+                "The SLIP-0039 share requires more groups than its backup has"
+            ),
+            Slip39Error::MismatchedShares => {
+                write!(f, "The SLIP-0039 shares belong to different backups")
+            }
+            Slip39Error::DuplicateShare(index) => write!(
+                f,
+                "Two different SLIP-0039 shares both claim to be share {}",
+                *index as usize + 1
+            ),
+            Slip39Error::NotEnoughShares { provided, required } => write!(
+                f,
+                "Not enough SLIP-0039 shares: {} of {} required",
+                provided, required
+            ),
+            Slip39Error::NotEnoughGroups { provided, required } => write!(
+                f,
+                "Not enough SLIP-0039 groups: {} of {} required",
+                provided, required
+            ),
+            Slip39Error::InvalidDigest => write!(
+                f,
+                "The SLIP-0039 shares don't add up to their secret - one of them is damaged or from another backup"
+            ),
+            Slip39Error::InvalidShareCount { threshold, count } => write!(
+                f,
+                "SLIP-0039 can't deal {} shares requiring {}: there are at most {}, and requiring 1 of several isn't allowed",
+                count, threshold, MAX_SLIP39_SHARES
+            ),
+            Slip39Error::InvalidSecretLength(len) => write!(
+                f,
+                "SLIP-0039 shares secrets of at least {} bytes in steps of 2, not {} bytes",
+                MIN_SLIP39_SECRET_LEN, len
+            ),
+            Slip39Error::NotHex => write!(
+                f,
+                "For SLIP-0039 the secret has to be a master secret written in hex"
+            ),
+            Slip39Error::InvalidPassphrase => write!(
+                f,
+                "SLIP-0039 only allows passphrases of printable ASCII characters"
+            ),
+            Slip39Error::MultipleGroups => {
+                write!(f, "Only SLIP-0039 backups of a single group can be restored here")
+            }
+            Slip39Error::NotExtendable => write!(f, "Shards can't be added to SLIP-0039 shares"),
+        }
+    }
+}
+
+impl error::Error for Slip39Error {}
+
+#[derive(Debug, PartialEq)]
+pub enum FragmentError {
+    MissingPart { part: u8, total: u8 },
+    DuplicatePart { part: u8, total: u8 },
+    Inconsistent,
+}
+
+impl fmt::Display for FragmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self {
+            FragmentError::MissingPart { part, total } => {
+                write!(f, "Part {} of {} of a backup shard is missing", part, total)
+            }
+            FragmentError::DuplicatePart { part, total } => write!(
+                f,
+                "Part {} of {} of a backup shard was found more than once",
+                part, total
+            ),
+            FragmentError::Inconsistent => {
+                write!(f, "The QR codes of a backup shard don't fit together")
+            }
+        }
+    }
+}
+
+impl error::Error for FragmentError {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum HardwareKeyError {
+    /// Built without the `hardware-key` feature
+    Unsupported,
+    /// No key, several keys, or the key refused to answer
+    Device(String),
+    /// The key doesn't support the hmac-secret extension, or left its output out
+    NoHmacSecret,
+}
+
+impl fmt::Display for HardwareKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self {
+            HardwareKeyError::Unsupported => write!(
+                f,
+                "This backup also needs the security key it was created with, which this build of \
+                 Hyperbacked can't use. Restore it with one built with the hardware-key feature"
+            ),
+            HardwareKeyError::Device(message) => write!(
+                f,
+                "The security key couldn't be used: {}. Connect exactly one key and touch it \
+                 when it blinks",
+                message
+            ),
+            HardwareKeyError::NoHmacSecret => write!(
+                f,
+                "The security key doesn't support the FIDO2 hmac-secret extension"
+            ),
+        }
+    }
+}
+
+impl error::Error for HardwareKeyError {}
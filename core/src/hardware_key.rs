@@ -0,0 +1,185 @@
+//! FIDO2 security keys as a second factor of a backup, through their hmac-secret extension.
+//!
+//! When a backup is created, a credential is registered on the key and asked for the HMAC of a
+//! random salt. That output is mixed into the password of every secret, next to the passphrase
+//! and any key file, so restoring needs a threshold of shards, the passphrase and the same key.
+//! The shards only store the credential ID and the salt, which are useless without the key: it
+//! computes the HMAC with a secret that never leaves it.
+//!
+//! What this does and doesn't protect against:
+//! - Someone who collects enough shards and learns or guesses the passphrase still can't decrypt
+//!   the secrets without holding the key.
+//! - Losing or resetting the key makes the backup unrecoverable, as no shard holds a copy of what
+//!   it adds. A second key can't be enrolled later, only with a new backup.
+//! - Keys answer without a PIN or fingerprint, as those would change the output of hmac-secret.
+//!   Whoever holds the key and the rest can restore, so keep it apart from the shards.
+//! - The key only adds to the passphrase: anyone who can run code on the computer restoring the
+//!   backup sees the secrets anyway.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    crypto::{key_file_password, KeyFileDigest, SecretString},
+    errors::HardwareKeyError,
+};
+
+/// Longest credential ID the shards have room for. Keys usually return between 16 and 128 bytes.
+pub const MAX_CREDENTIAL_ID_LEN: usize = u8::MAX as usize;
+pub const SALT_LEN: usize = 32;
+
+// Keeps the hmac-secret output apart from the digest of a key file with the same bytes
+const HARDWARE_KEY_DOMAIN: &[u8] = b"hyperbacked hardware key v1\0";
+
+/// What the shards store to ask the key for its part of the password again.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HardwareKeyChallenge {
+    pub credential_id: Vec<u8>,
+    pub salt: [u8; SALT_LEN],
+}
+
+impl HardwareKeyChallenge {
+    /// Length of the challenge in the shard metadata: length byte, credential ID and salt.
+    pub fn stored_len(&self) -> usize {
+        1 + self.credential_id.len() + SALT_LEN
+    }
+}
+
+/// Output of hmac-secret for the salt of a challenge, which the key computes again every time.
+pub type HardwareKeySecret = [u8; 32];
+
+/// The password of a backup that requires a hardware key, built on top of `password` like the
+/// key file is.
+pub fn hardware_key_password(password: &str, secret: &HardwareKeySecret) -> SecretString {
+    let digest: KeyFileDigest = Sha256::new()
+        .chain_update(HARDWARE_KEY_DOMAIN)
+        .chain_update(secret)
+        .finalize()
+        .into();
+    key_file_password(password, &digest)
+}
+
+#[cfg(feature = "hardware-key")]
+mod device {
+    use ctap_hid_fido2::{
+        fidokey::{
+            AssertionExtension, CredentialExtension, GetAssertionArgsBuilder,
+            MakeCredentialArgsBuilder,
+        },
+        verifier, Cfg, FidoKeyHid, FidoKeyHidFactory,
+    };
+
+    use super::{HardwareKeyChallenge, HardwareKeyError, HardwareKeySecret};
+
+    const RP_ID: &str = "hyperbacked";
+
+    fn open() -> Result<FidoKeyHid, HardwareKeyError> {
+        FidoKeyHidFactory::create(&Cfg::init())
+            .map_err(|error| HardwareKeyError::Device(error.to_string()))
+    }
+
+    /// Registers a new credential on the only connected key, then asks it for the secret of a
+    /// fresh salt. Both need a touch.
+    pub fn register_hardware_key(
+    ) -> Result<(HardwareKeyChallenge, HardwareKeySecret), HardwareKeyError> {
+        let device = open()?;
+        let challenge = verifier::create_challenge();
+        let args = MakeCredentialArgsBuilder::new(RP_ID, &challenge)
+            .without_pin_and_uv()
+            .extensions(&[CredentialExtension::HmacSecret(Some(true))])
+            .build();
+        let attestation = device
+            .make_credential_with_args(&args)
+            .map_err(|error| HardwareKeyError::Device(error.to_string()))?;
+        if !attestation
+            .extensions
+            .iter()
+            .any(|extension| matches!(extension, CredentialExtension::HmacSecret(Some(true))))
+        {
+            return Err(HardwareKeyError::NoHmacSecret);
+        }
+        let verified = verifier::verify_attestation(RP_ID, &challenge, &attestation);
+        if !verified.is_success {
+            return Err(HardwareKeyError::Device(String::from(
+                "The key's answer didn't verify",
+            )));
+        }
+        let challenge = HardwareKeyChallenge {
+            credential_id: verified.credential_id,
+            salt: rand::random(),
+        };
+        let secret = unlock_with(&device, &challenge)?;
+        Ok((challenge, secret))
+    }
+
+    /// Asks the only connected key for the secret of a backup, which needs a touch.
+    pub fn unlock_hardware_key(
+        challenge: &HardwareKeyChallenge,
+    ) -> Result<HardwareKeySecret, HardwareKeyError> {
+        unlock_with(&open()?, challenge)
+    }
+
+    fn unlock_with(
+        device: &FidoKeyHid,
+        challenge: &HardwareKeyChallenge,
+    ) -> Result<HardwareKeySecret, HardwareKeyError> {
+        let client_data_hash = verifier::create_challenge();
+        let args = GetAssertionArgsBuilder::new(RP_ID, &client_data_hash)
+            .without_pin_and_uv()
+            .credential_id(&challenge.credential_id)
+            .extensions(&[AssertionExtension::HmacSecret(Some(challenge.salt))])
+            .build();
+        let assertions = device
+            .get_assertion_with_args(&args)
+            .map_err(|error| HardwareKeyError::Device(error.to_string()))?;
+        assertions
+            .iter()
+            .flat_map(|assertion| &assertion.extensions)
+            .find_map(|extension| match extension {
+                AssertionExtension::HmacSecret(Some(secret)) => Some(*secret),
+                _ => None,
+            })
+            .ok_or(HardwareKeyError::NoHmacSecret)
+    }
+}
+
+#[cfg(feature = "hardware-key")]
+pub use device::{register_hardware_key, unlock_hardware_key};
+
+/// Without the `hardware-key` feature there is no way to talk to the key, so backups that need
+/// one can't be restored.
+#[cfg(not(feature = "hardware-key"))]
+pub fn unlock_hardware_key(
+    _challenge: &HardwareKeyChallenge,
+) -> Result<HardwareKeySecret, HardwareKeyError> {
+    Err(HardwareKeyError::Unsupported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hardware_key_password, HardwareKeyChallenge};
+    use crate::crypto::{key_file_digest, key_file_password};
+
+    #[test]
+    fn password_depends_on_secret() {
+        let password = hardware_key_password("passphrase", &[7; 32]);
+        assert!(password.starts_with("passphrase\0"));
+        assert_eq!(password.len(), "passphrase".len() + 1 + 64);
+        assert_ne!(password, hardware_key_password("passphrase", &[8; 32]));
+        assert_ne!(password, hardware_key_password("passphrasf", &[7; 32]));
+        // A key file with the same contents as the hmac-secret output doesn't stand in for it
+        assert_ne!(
+            password,
+            key_file_password("passphrase", &key_file_digest(&[7; 32]))
+        );
+    }
+
+    #[test]
+    fn stored_len_counts_length_byte() {
+        let challenge = HardwareKeyChallenge {
+            credential_id: vec![1; 64],
+            salt: [2; 32],
+        };
+        assert_eq!(challenge.stored_len(), 97);
+    }
+}
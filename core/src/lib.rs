@@ -0,0 +1,11 @@
+//! Creating, printing and restoring Hyperbacked backups, without any user interface.
+
+pub mod backup;
+pub mod crypto;
+pub mod errors;
+pub mod hardware_key;
+pub mod mnemonic;
+pub mod passphrase;
+pub mod printer;
+pub mod qrcode;
+pub mod slip39;
@@ -0,0 +1,238 @@
+use lazy_static::lazy_static;
+use sha2::{Digest, Sha256};
+
+use crate::errors::MnemonicError;
+
+const WORD_LIST: &str = include_str!("../res/wordlist_bip39.txt");
+
+lazy_static! {
+    static ref WORDS: Vec<&'static str> = WORD_LIST.lines().collect();
+}
+
+// Data is written in blocks of 32 bytes, each a standard 24 word BIP-39 mnemonic
+const BLOCK_LEN: usize = 32;
+pub const WORDS_PER_BLOCK: usize = 24;
+
+/// Writes data as BIP-39 words. It is padded to whole blocks, every block ending with the
+/// number of padding bytes, so a single checksum covers each group of 24 words.
+pub fn encode_words(data: &[u8]) -> Vec<String> {
+    let padding = BLOCK_LEN - data.len() % BLOCK_LEN;
+    let mut padded = data.to_vec();
+    padded.resize(data.len() + padding, padding as u8);
+
+    padded
+        .chunks_exact(BLOCK_LEN)
+        .flat_map(entropy_to_words)
+        .collect()
+}
+
+/// Standard BIP-39 mnemonic of 16 to 32 bytes of entropy in steps of 4, 12 to 24 words.
+pub fn entropy_to_words(entropy: &[u8]) -> Vec<String> {
+    debug_assert!((16..=BLOCK_LEN).contains(&entropy.len()) && entropy.len().is_multiple_of(4));
+    // One bit of checksum for every 32 bits of entropy
+    let checksum_len = entropy.len() / 4;
+    let checksum = Sha256::digest(entropy)[0] >> (8 - checksum_len);
+    let mut words = Vec::with_capacity((entropy.len() * 8 + checksum_len) / 11);
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+    for (value, len) in entropy
+        .iter()
+        .map(|&byte| (byte, 8))
+        .chain([(checksum, checksum_len)])
+    {
+        bits = (bits << len) | value as u32;
+        bit_count += len;
+        while bit_count >= 11 {
+            bit_count -= 11;
+            words.push(WORDS[(bits >> bit_count) as usize & 0x7FF].to_owned());
+        }
+        bits &= (1 << bit_count) - 1;
+    }
+    words
+}
+
+/// Reads data written by `encode_words`. Words are matched case-insensitively, and like most
+/// BIP-39 tools their first four letters are enough.
+pub fn decode_words(words: &[String]) -> Result<Vec<u8>, MnemonicError> {
+    if words.is_empty() {
+        return Err(MnemonicError::Empty);
+    }
+    if !words.len().is_multiple_of(WORDS_PER_BLOCK) {
+        return Err(MnemonicError::InvalidWordCount(words.len()));
+    }
+
+    let mut data = Vec::with_capacity(words.len() / WORDS_PER_BLOCK * BLOCK_LEN);
+    for (block, block_words) in words.chunks_exact(WORDS_PER_BLOCK).enumerate() {
+        let mut decoded = Vec::with_capacity(BLOCK_LEN + 1);
+        let mut bits = 0u32;
+        let mut bit_count = 0;
+        for (index, word) in block_words.iter().enumerate() {
+            let position = block * WORDS_PER_BLOCK + index + 1;
+            let value = word_index(word).ok_or_else(|| MnemonicError::UnknownWord {
+                word: word.clone(),
+                position,
+            })?;
+            bits = (bits << 11) | value as u32;
+            bit_count += 11;
+            while bit_count >= 8 {
+                bit_count -= 8;
+                decoded.push((bits >> bit_count) as u8);
+            }
+            bits &= (1 << bit_count) - 1;
+        }
+
+        let checksum = decoded.pop();
+        if checksum != Some(Sha256::digest(&decoded)[0]) {
+            return Err(MnemonicError::ChecksumMismatch {
+                first: block * WORDS_PER_BLOCK + 1,
+                last: (block + 1) * WORDS_PER_BLOCK,
+            });
+        }
+        data.extend(decoded);
+    }
+
+    let padding = data.last().copied().unwrap_or(0) as usize;
+    if !(1..=BLOCK_LEN).contains(&padding)
+        || data[data.len() - padding..]
+            .iter()
+            .any(|&byte| byte as usize != padding)
+    {
+        return Err(MnemonicError::InvalidPadding);
+    }
+    data.truncate(data.len() - padding);
+    Ok(data)
+}
+
+/// Whether pasted text is meant as words rather than Base85, which always mixes in other
+/// characters over this many groups.
+pub fn looks_like_words(text: &str) -> bool {
+    text.split_whitespace().count() >= WORDS_PER_BLOCK
+        && text
+            .split_whitespace()
+            .all(|word| word.chars().all(|c| c.is_ascii_alphabetic()))
+}
+
+fn word_index(word: &str) -> Option<usize> {
+    let word = word.to_ascii_lowercase();
+    // The first four letters of every word are unique
+    WORDS.binary_search(&word.as_str()).ok().or_else(|| {
+        WORDS
+            .iter()
+            .position(|candidate| word.len() >= 4 && candidate.starts_with(&word))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use sha2::{Digest, Sha256};
+
+    use super::{
+        decode_words, encode_words, entropy_to_words, looks_like_words, WORDS, WORDS_PER_BLOCK,
+    };
+    use crate::errors::MnemonicError;
+
+    /// Test vectors of the BIP-39 reference implementation, as entropy and mnemonic.
+    const VECTORS: [(&str, &str); 4] = [
+        (
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art",
+        ),
+        (
+            "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+            "legal winner thank year wave sausage worth useful legal winner thank year wave sausage worth useful legal winner thank year wave sausage worth title",
+        ),
+        (
+            "8080808080808080808080808080808080808080808080808080808080808080",
+            "letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic bless",
+        ),
+        (
+            "f585c11aec520db57dd353c69554b21a89b20fb0650966fa0a9d6f74fd989d8f",
+            "void come effort suffer camp survey warrior heavy shoot primary clutch crush open amazing screen patrol group space point ten exist slush involve unfold",
+        ),
+    ];
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn word_list_is_complete() {
+        assert_eq!(WORDS.len(), 2048);
+        assert!(WORDS.windows(2).all(|pair| pair[0] < pair[1]));
+        // SHA-256 of the official english.txt
+        let digest = Sha256::digest(WORDS.join("\n") + "\n");
+        assert_eq!(
+            format!("{:x}", digest),
+            "2f5eed53a4727b4bf8880d8f3f199efc90e58503646d9ff8eff3a2ed3b24dbda"
+        );
+    }
+
+    #[test]
+    fn blocks_match_bip39_vectors() {
+        for (entropy, mnemonic) in VECTORS {
+            // A full block gets a block of padding, which doesn't change the words before it
+            let words = encode_words(&from_hex(entropy));
+            assert_eq!(words.len(), 2 * WORDS_PER_BLOCK);
+            assert_eq!(words[..WORDS_PER_BLOCK].join(" "), mnemonic);
+        }
+
+        // 12 words from 16 bytes, with a checksum of only 4 bits
+        for (entropy, mnemonic) in [
+            ("00000000000000000000000000000000", "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"),
+            ("7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f", "legal winner thank year wave sausage worth useful legal winner thank yellow"),
+            ("ffffffffffffffffffffffffffffffff", "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong"),
+        ] {
+            assert_eq!(entropy_to_words(&from_hex(entropy)).join(" "), mnemonic);
+        }
+    }
+
+    #[test]
+    fn words_round_trip() {
+        for len in [0, 1, 31, 32, 33, 300] {
+            let data = (0..len).map(|i| (i * 7) as u8).collect::<Vec<u8>>();
+            let words = encode_words(&data);
+            assert_eq!(words.len() % WORDS_PER_BLOCK, 0);
+            assert!(looks_like_words(&words.join(" ")));
+            assert_eq!(decode_words(&words).unwrap(), data);
+        }
+
+        // Upper case and the first four letters are accepted
+        let mut words = encode_words(b"short");
+        words[0] = words[0].to_uppercase();
+        words[1] = words[1].chars().take(4).collect();
+        assert_eq!(decode_words(&words).unwrap(), b"short");
+    }
+
+    #[test]
+    fn damaged_words_are_rejected() {
+        let mut words = encode_words(&[5u8; 40]);
+        assert_eq!(
+            decode_words(&words[..30]),
+            Err(MnemonicError::InvalidWordCount(30))
+        );
+
+        words[30] = String::from("bitcoin");
+        assert_eq!(
+            decode_words(&words),
+            Err(MnemonicError::UnknownWord {
+                word: String::from("bitcoin"),
+                position: 31
+            })
+        );
+
+        words[30] = String::from("zoo");
+        assert_eq!(
+            decode_words(&words),
+            Err(MnemonicError::ChecksumMismatch {
+                first: 25,
+                last: 48
+            })
+        );
+
+        assert!(!looks_like_words("abandon ability"));
+        assert!(!looks_like_words(&"0x5f ".repeat(WORDS_PER_BLOCK)));
+    }
+}
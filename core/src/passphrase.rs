@@ -0,0 +1,111 @@
+use lazy_static::lazy_static;
+use rand::prelude::*;
+
+const WORD_LIST: &'static str = include_str!("../res/wordlist_eff.txt");
+
+lazy_static! {
+    static ref WORDS: Vec<&'static str> = WORD_LIST.split("\n").collect();
+}
+
+pub const MIN_WORDS: u8 = 4;
+pub const MAX_WORDS: u8 = 12;
+
+/// Lowest zxcvbn score (out of 4) the command line accepts for a backup passphrase
+pub const MIN_SCORE: u8 = 2;
+
+/// zxcvbn's estimate of how hard a passphrase is to guess.
+#[derive(Clone, Debug)]
+pub struct PassphraseStrength {
+    /// From 0 to 4
+    pub score: u8,
+    pub crack_time: String,
+    /// zxcvbn's warning and suggestions, usually empty for strong passphrases
+    pub feedback: Vec<String>,
+}
+
+/// Rates a passphrase, penalizing any of `user_inputs` it contains, like the secret or label of
+/// the backup. Returns `None` for an empty passphrase.
+pub fn passphrase_strength(passphrase: &str, user_inputs: &[&str]) -> Option<PassphraseStrength> {
+    let estimate = zxcvbn::zxcvbn(passphrase, user_inputs).ok()?;
+    let mut feedback = Vec::new();
+    if let Some(estimate_feedback) = estimate.feedback() {
+        feedback.extend(
+            estimate_feedback
+                .warning()
+                .map(|warning| warning.to_string()),
+        );
+        feedback.extend(
+            estimate_feedback
+                .suggestions()
+                .iter()
+                .map(|suggestion| suggestion.to_string()),
+        );
+    }
+    Some(PassphraseStrength {
+        score: estimate.score(),
+        crack_time: estimate
+            .crack_times()
+            .offline_slow_hashing_1e4_per_second()
+            .to_string(),
+        feedback,
+    })
+}
+
+pub fn passphrase_score(passphrase: &str, user_inputs: &[&str]) -> u8 {
+    passphrase_strength(passphrase, user_inputs).map_or(0, |strength| strength.score)
+}
+
+/// Generates a passphrase of random words, joined by `separator` or run together if it's `None`.
+pub fn gen_passphrase(words: u8, separator: Option<char>) -> String {
+    let mut rng = thread_rng();
+    return WORDS
+        .choose_multiple(&mut rng, words as usize)
+        .map(|&word| word)
+        .collect::<Vec<&str>>()
+        .join(&separator.map(String::from).unwrap_or_default());
+}
+
+#[cfg(test)]
+mod test {
+    use super::{gen_passphrase, passphrase_score, passphrase_strength, MIN_SCORE, MIN_WORDS};
+
+    #[test]
+    fn test_scores() {
+        assert!(passphrase_score(&gen_passphrase(MIN_WORDS, Some(' ')), &[]) >= MIN_SCORE);
+        assert!(passphrase_score("password", &[]) < MIN_SCORE);
+        assert_eq!(passphrase_score("", &[]), 0);
+    }
+
+    #[test]
+    fn test_user_inputs() {
+        let label = "Grandmas Jewelry Vault 1987";
+        assert!(passphrase_score(label, &[label]) < passphrase_score(label, &[]));
+
+        let strength = passphrase_strength("password", &[]).unwrap();
+        assert!(!strength.feedback.is_empty());
+        assert!(passphrase_strength("", &[]).is_none());
+    }
+
+    #[test]
+    fn test_word_count() {
+        let passphrase = gen_passphrase(4, Some(' '));
+        assert_eq!(4, passphrase.split(" ").count());
+    }
+
+    #[test]
+    fn test_different_results() {
+        let passphrase1 = gen_passphrase(4, Some(' '));
+        let passphrase2 = gen_passphrase(4, Some(' '));
+        assert_ne!(passphrase1, passphrase2);
+    }
+
+    #[test]
+    fn test_separator() {
+        let passphrase = gen_passphrase(5, Some('-'));
+        assert_eq!(5, passphrase.split('-').count());
+        assert!(!passphrase.contains(' '));
+
+        let passphrase = gen_passphrase(5, None);
+        assert!(!passphrase.contains(' '));
+    }
+}
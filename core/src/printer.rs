@@ -0,0 +1,501 @@
+use std::path::PathBuf;
+
+use genpdf::{
+    fonts::FontFamily,
+    style::{Color, Style},
+    Alignment, Element, Margins, Scale,
+};
+use image::{DynamicImage, GrayImage};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backup::BackupShard,
+    qrcode::{qrcode_count, qrcode_encode, split_share},
+};
+
+const QRCODE_SIZE: u32 = 1024;
+// About 40 mm wide, images are placed at 300 DPI
+const LOGO_MAX_WIDTH_PX: u32 = 472;
+
+/// Customizations of the printed shards. The default prints them exactly as they always were.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdfTheme {
+    pub logo_path: Option<PathBuf>,
+    /// Colour of the text on the sheet
+    pub primary_color: [u8; 3],
+    /// Heading printed above the shard number
+    pub title: String,
+}
+
+impl PdfTheme {
+    fn text_style(&self) -> Style {
+        match self.primary_color {
+            // Plain black is what a paragraph gets without any colour set
+            [0, 0, 0] => Style::new(),
+            [r, g, b] => Style::new().with_color(Color::Rgb(r, g, b)),
+        }
+    }
+}
+
+/// Parses a colour written as `#rrggbb` or `rrggbb`.
+pub fn parse_hex_color(input: &str) -> anyhow::Result<[u8; 3]> {
+    let hex = input.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        anyhow::bail!("'{}' is not a colour in the form #rrggbb", input);
+    }
+    let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16);
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+pub fn format_hex_color([r, g, b]: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+pub fn print_pdf(
+    shard: &BackupShard,
+    backup_name: &str,
+    total_shards: usize,
+    theme: &PdfTheme,
+) -> anyhow::Result<genpdf::Document> {
+    let mut doc = document(backup_name)?;
+    doc.push(shard_sheet(shard, backup_name, total_shards, theme)?);
+    Ok(doc)
+}
+
+/// Prints every shard of a backup into one document, for carrying them all to a key ceremony:
+/// a cover page that lists them, then each shard on a page of its own.
+pub fn print_merged_pdf(
+    shards: &[BackupShard],
+    backup_name: &str,
+    total_shards: usize,
+    theme: &PdfTheme,
+) -> anyhow::Result<genpdf::Document> {
+    let mut shards = shards.iter().collect::<Vec<&BackupShard>>();
+    shards.sort_by_key(|shard| shard.number);
+    let threshold = match shards.first() {
+        Some(shard) => shard.threshold,
+        None => anyhow::bail!("There are no shards to print"),
+    };
+
+    let mut doc = document(backup_name)?;
+    let mut cover = genpdf::elements::LinearLayout::vertical();
+    let style = theme.text_style();
+    push_heading(&mut cover, theme)?;
+    cover.push(
+        genpdf::elements::Paragraph::new(if backup_name.is_empty() {
+            "Backup shards"
+        } else {
+            backup_name
+        })
+        .aligned(Alignment::Center)
+        .styled(style.bold().with_font_size(16))
+        .padded(genpdf::Margins::vh(1, 0)),
+    );
+    cover.push(
+        genpdf::elements::Paragraph::new(format!(
+            "Any {} of the {} shards restore the backup. This document holds {} of them.",
+            threshold,
+            total_shards,
+            shards.len()
+        ))
+        .aligned(Alignment::Center)
+        .styled(style),
+    );
+    cover.push(
+        genpdf::elements::Paragraph::new(
+            "Keep it sealed until the shards are handed out or combined: with the passphrase, \
+             enough of them restore the backup on their own.",
+        )
+        .aligned(Alignment::Center)
+        .styled(style)
+        .padded(genpdf::Margins::vh(1, 0)),
+    );
+
+    // The codes themselves are on the following pages, so a scan of the document finds only shards
+    let mut index = genpdf::elements::TableLayout::new(vec![1, 1, 2]);
+    index.set_cell_decorator(genpdf::elements::FrameCellDecorator::new(true, true, false));
+    let cell = |content: String, style: Style| -> Box<dyn Element> {
+        Box::new(
+            genpdf::elements::Paragraph::new(content)
+                .styled(style)
+                .padded(Margins::all(1)),
+        )
+    };
+    index.push_row(vec![
+        cell(String::from("Shard"), style.bold()),
+        cell(String::from("QR codes"), style.bold()),
+        cell(String::from("Fingerprint"), style.bold()),
+    ])?;
+    for shard in &shards {
+        index.push_row(vec![
+            cell(format!("#{}", shard.number), style),
+            cell(qrcode_count(shard.to_bytes().len()).to_string(), style),
+            cell(shard.display_fingerprint(), style),
+        ])?;
+    }
+    cover.push(index);
+    doc.push(cover.padded(Margins::all(2)));
+
+    for shard in &shards {
+        doc.push(genpdf::elements::PageBreak::new());
+        doc.push(shard_sheet(shard, backup_name, total_shards, theme)?);
+    }
+    Ok(doc)
+}
+
+fn document(title: &str) -> anyhow::Result<genpdf::Document> {
+    let font = genpdf::fonts::FontData::new(include_bytes!("../res/OpenSans.ttf").to_vec(), None)?;
+    let font_family = FontFamily {
+        regular: font.clone(),
+        bold: font.clone(),
+        italic: font.clone(),
+        bold_italic: font,
+    };
+    let mut doc = genpdf::Document::new(font_family);
+    doc.set_title(title);
+
+    let mut decorator = genpdf::SimplePageDecorator::new();
+    decorator.set_margins(10);
+    doc.set_page_decorator(decorator);
+    Ok(doc)
+}
+
+/// The logo and title of the theme, if it has them.
+fn push_heading(
+    layout: &mut genpdf::elements::LinearLayout,
+    theme: &PdfTheme,
+) -> anyhow::Result<()> {
+    if let Some(logo_path) = &theme.logo_path {
+        layout.push(logo_image(logo_path)?.padded(Margins::all(1)));
+    }
+    if !theme.title.is_empty() {
+        layout.push(
+            genpdf::elements::Paragraph::new(&theme.title)
+                .aligned(Alignment::Center)
+                .styled(theme.text_style().bold().with_font_size(16)),
+        );
+    }
+    Ok(())
+}
+
+/// The framed sheet of a single shard.
+fn shard_sheet(
+    shard: &BackupShard,
+    backup_name: &str,
+    total_shards: usize,
+    theme: &PdfTheme,
+) -> anyhow::Result<impl Element> {
+    let mut layout = genpdf::elements::LinearLayout::vertical();
+    let style = theme.text_style();
+    push_heading(&mut layout, theme)?;
+
+    layout.push(
+        genpdf::elements::Paragraph::new(format!("{}/{}", shard.number, total_shards))
+            .aligned(Alignment::Center)
+            .styled(style)
+            .padded(genpdf::Margins::vh(1, 0)),
+    );
+
+    let parts = split_share(&shard.to_bytes())?;
+    if let [part] = &parts[..] {
+        layout.push(qrcode_image(part)?.padded(Margins::all(1)));
+    } else {
+        // Two codes side by side, each labelled so missing parts can be named on restore
+        let mut table = genpdf::elements::TableLayout::new(vec![1, 1]);
+        for (row, pair) in parts.chunks(2).enumerate() {
+            let mut cells = Vec::<Box<dyn Element>>::new();
+            for (column, part) in pair.iter().enumerate() {
+                let mut cell = genpdf::elements::LinearLayout::vertical();
+                cell.push(qrcode_image(part)?);
+                cell.push(
+                    genpdf::elements::Paragraph::new(format!(
+                        "Part {} of {}",
+                        row * 2 + column + 1,
+                        parts.len()
+                    ))
+                    .aligned(Alignment::Center)
+                    .styled(style),
+                );
+                cells.push(Box::new(cell.padded(Margins::all(1))));
+            }
+            if cells.len() < 2 {
+                cells.push(Box::new(genpdf::elements::Paragraph::new("")));
+            }
+            table.push_row(cells)?;
+        }
+        layout.push(table);
+    }
+
+    // Hardware wallets are restored by typing in the words of SLIP-0039 shares
+    if shard.slip39.is_some() {
+        layout.push(word_table(&shard.to_words(), style)?.padded(Margins::vh(1, 4)));
+    }
+
+    layout.push(
+        genpdf::elements::Paragraph::new(backup_name)
+            .aligned(Alignment::Center)
+            .styled(style)
+            .padded(genpdf::Margins::vh(1, 0)),
+    );
+
+    layout.push(
+        genpdf::elements::Paragraph::new(format!("Fingerprint {}", shard.display_fingerprint()))
+            .aligned(Alignment::Center)
+            .styled(style),
+    );
+
+    if let Some(expires_at) = shard.expires_at {
+        layout.push(
+            genpdf::elements::Paragraph::new(format!("Renew by {}", expires_at))
+                .aligned(Alignment::Center)
+                .styled(style),
+        );
+    }
+
+    if let Some(verify_by) = shard
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.verify_by)
+    {
+        layout.push(
+            genpdf::elements::Paragraph::new(format!("Verify by: {}", verify_by.format("%Y-%m")))
+                .aligned(Alignment::Center)
+                .styled(style),
+        );
+    }
+
+    Ok(layout.padded(Margins::all(2)).framed())
+}
+
+/// The words numbered in rows of four, to be read left to right.
+fn word_table(words: &[String], style: Style) -> anyhow::Result<impl Element> {
+    let mut table = genpdf::elements::TableLayout::new(vec![1; 4]);
+    for (row, chunk) in words.chunks(4).enumerate() {
+        let mut cells = chunk
+            .iter()
+            .enumerate()
+            .map(|(column, word)| {
+                Box::new(
+                    genpdf::elements::Paragraph::new(format!("{}. {}", row * 4 + column + 1, word))
+                        .styled(style),
+                ) as Box<dyn Element>
+            })
+            .collect::<Vec<Box<dyn Element>>>();
+        while cells.len() < 4 {
+            cells.push(Box::new(genpdf::elements::Paragraph::new("")));
+        }
+        table.push_row(cells)?;
+    }
+    Ok(table)
+}
+
+/// Reads back the shards of a PDF written by `print_pdf` or `print_merged_pdf`. Each one has
+/// passed its integrity check, which catches damage but not deliberate changes: only
+/// restoring the backup proves the shards are genuine.
+#[cfg(feature = "pdf")]
+pub fn parse_shares_from_pdf(path: &std::path::Path) -> anyhow::Result<Vec<BackupShard>> {
+    let codes = crate::qrcode::qrcode_read_pdf(path)?;
+    if codes.is_empty() {
+        anyhow::bail!("No backup shard found in {}", path.display());
+    }
+    codes.into_iter().map(BackupShard::from_bytes).collect()
+}
+
+fn logo_image(path: &std::path::Path) -> anyhow::Result<genpdf::elements::Image> {
+    let logo = image::open(path)
+        .map_err(|error| anyhow::anyhow!("Failed to read logo {}: {}", path.display(), error))?;
+    // PDF images can't have an alpha channel
+    let logo = logo.to_rgb8();
+    let scale = (LOGO_MAX_WIDTH_PX as f64 / logo.width() as f64).min(1.0);
+    Ok(
+        genpdf::elements::Image::from_dynamic_image(DynamicImage::ImageRgb8(logo))?
+            .with_alignment(Alignment::Center)
+            .with_scale(Scale::new(scale, scale)),
+    )
+}
+
+fn qrcode_image(data: &[u8]) -> anyhow::Result<genpdf::elements::Image> {
+    let qrcode_data = qrcode_encode(data, QRCODE_SIZE as usize)?;
+    let qrcode_image = GrayImage::from_raw(QRCODE_SIZE, QRCODE_SIZE, qrcode_data)
+        .expect("QR Code generator created invalid image");
+    Ok(
+        genpdf::elements::Image::from_dynamic_image(DynamicImage::ImageLuma8(qrcode_image))?
+            .with_alignment(Alignment::Center),
+    )
+}
+
+/// Sends the document to the default printer without writing it anywhere the user can see.
+#[cfg(unix)]
+pub fn print_to_system_printer(document: genpdf::Document) -> anyhow::Result<()> {
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    let mut pdf_data = Vec::new();
+    document.render(&mut pdf_data)?;
+
+    // Both Linux and macOS print through CUPS, which reads the job from stdin
+    let mut lp = Command::new("lp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    lp.stdin
+        .take()
+        .expect("Stdin of lp is piped")
+        .write_all(&pdf_data)?;
+
+    let output = lp.wait_with_output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Printing failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Sends the document to the default printer through the shell's print verb.
+#[cfg(windows)]
+pub fn print_to_system_printer(document: genpdf::Document) -> anyhow::Result<()> {
+    use std::process::Command;
+
+    let file = std::env::temp_dir().join(format!("hyperbacked-{}.pdf", rand::random::<u64>()));
+    document.render_to_file(&file)?;
+
+    let status = Command::new("powershell")
+        .args(["-NoProfile", "-Command"])
+        .arg(format!(
+            "Start-Process -Wait -Verb Print -FilePath '{}'",
+            file.display()
+        ))
+        .status();
+    std::fs::remove_file(&file)?;
+
+    if !status?.success() {
+        anyhow::bail!("Printing failed, is a PDF viewer installed?");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use image::RgbaImage;
+
+    #[cfg(feature = "pdf")]
+    use super::parse_shares_from_pdf;
+    use super::{format_hex_color, parse_hex_color, print_merged_pdf, print_pdf, PdfTheme};
+    use crate::backup::BackupShard;
+    #[cfg(feature = "pdf")]
+    use crate::slip39::split_master_secret;
+
+    #[test]
+    fn hex_colors() {
+        assert_eq!(parse_hex_color("#1a2B3c").unwrap(), [0x1a, 0x2b, 0x3c]);
+        assert_eq!(parse_hex_color(" ff0000 ").unwrap(), [255, 0, 0]);
+        assert!(parse_hex_color("#fff").is_err());
+        assert!(parse_hex_color("#gg0000").is_err());
+        assert_eq!(format_hex_color([0x1a, 0x2b, 0x3c]), "#1a2b3c");
+    }
+
+    #[cfg(feature = "pdf")]
+    #[test]
+    fn themed_pdf_stays_readable() {
+        let logo_path = std::env::temp_dir().join("hyperbacked_themed_pdf_logo.png");
+        RgbaImage::from_pixel(800, 200, image::Rgba([200, 30, 30, 128]))
+            .save(&logo_path)
+            .expect("Failed to save logo");
+        let shard = BackupShard {
+            number: 1,
+            threshold: 1,
+            backup_id: 42,
+            data: vec![1u8, 2, 3, 4, 5, 6, 7, 8],
+            expires_at: None,
+            metadata: None,
+            slip39: None,
+        };
+        let theme = PdfTheme {
+            logo_path: Some(logo_path.clone()),
+            primary_color: [0, 90, 200],
+            title: String::from("ACME Corp"),
+        };
+
+        let mut pdf = Vec::new();
+        let rendered =
+            print_pdf(&shard, "Test", 1, &theme).and_then(|doc| Ok(doc.render(&mut pdf)?));
+        std::fs::remove_file(&logo_path).ok();
+        rendered.expect("Failed to print");
+
+        let codes = crate::qrcode::qrcode_read_pdf_data(&pdf).expect("Failed to read PDF");
+        assert_eq!(codes, vec![shard.to_bytes()]);
+    }
+
+    #[cfg(feature = "pdf")]
+    #[test]
+    fn merged_pdf_holds_every_shard() {
+        let shards = [3, 1, 2]
+            .map(|number| BackupShard {
+                number,
+                threshold: 2,
+                backup_id: 42,
+                data: vec![number as u8; 16],
+                expires_at: None,
+                metadata: None,
+                slip39: None,
+            })
+            .to_vec();
+
+        let mut pdf = Vec::new();
+        print_merged_pdf(&shards, "Test", 3, &PdfTheme::default())
+            .expect("Failed to print")
+            .render(&mut pdf)
+            .expect("Failed to render");
+
+        let codes = crate::qrcode::qrcode_read_pdf_data(&pdf).expect("Failed to read PDF");
+        assert_eq!(codes.len(), 3);
+        for shard in &shards {
+            assert!(codes.contains(&shard.to_bytes()));
+        }
+        assert!(print_merged_pdf(&[], "Test", 3, &PdfTheme::default()).is_err());
+    }
+
+    #[cfg(feature = "pdf")]
+    #[test]
+    fn printed_shard_parses_back() {
+        let path = std::env::temp_dir().join("hyperbacked_printed_shard_parses_back.pdf");
+        let shard = BackupShard {
+            number: 2,
+            threshold: 2,
+            backup_id: 42,
+            data: vec![2u8; 32],
+            expires_at: None,
+            metadata: None,
+            slip39: None,
+        };
+        let rendered = print_pdf(&shard, "Test", 3, &PdfTheme::default())
+            .and_then(|doc| Ok(doc.render_to_file(&path)?));
+        let parsed = rendered.and_then(|()| parse_shares_from_pdf(&path));
+        std::fs::remove_file(&path).ok();
+
+        let parsed = parsed.expect("Failed to parse");
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].to_bytes(), shard.to_bytes());
+    }
+
+    #[cfg(feature = "pdf")]
+    #[test]
+    fn printed_slip39_share_parses_back() {
+        let path = std::env::temp_dir().join("hyperbacked_printed_slip39_share_parses_back.pdf");
+        let shares = split_master_secret(&[9; 16], "", 2, 2, &mut rand::thread_rng()).unwrap();
+        let shard = BackupShard::from_slip39(shares[1].clone()).unwrap();
+        let rendered = print_pdf(&shard, "Test", 2, &PdfTheme::default())
+            .and_then(|doc| Ok(doc.render_to_file(&path)?));
+        let parsed = rendered.and_then(|()| parse_shares_from_pdf(&path));
+        std::fs::remove_file(&path).ok();
+
+        let parsed = parsed.expect("Failed to parse");
+        assert_eq!(parsed[0].to_slip39(), Some(shares[1].clone()));
+    }
+}
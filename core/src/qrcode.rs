@@ -0,0 +1,691 @@
+use std::path::Path;
+#[cfg(feature = "camera")]
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use image::DynamicImage;
+#[cfg(any(feature = "camera", feature = "pdf"))]
+use image::ImageBuffer;
+#[cfg(feature = "camera")]
+use nokhwa::{
+    pixel_format::RgbFormat,
+    utils::{CameraIndex, RequestedFormat, RequestedFormatType},
+    Camera,
+};
+
+use crate::{
+    backup::{BackupShard, SHARD_MAGIC},
+    errors::{BackupError, FragmentError, PayloadError, PayloadKind, QrCodeError},
+    slip39::{looks_like_mnemonic, normalize_mnemonic},
+};
+
+// RFC 1924 alphabet used by the base85 crate
+const BASE85_ALPHABET: &str =
+    "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz!#$%&()*+-;<=>?@^_`{|}~";
+
+// Version 40 with medium error correction holds 2331 characters, which is 1864 bytes in Base85
+pub const QRCODE_MAX_BYTES: usize = 1864;
+
+// Shards too large for a single QR code are split into fragments. The tag takes the place of the
+// shard version, so older releases report them as coming from a newer version.
+const FRAGMENT_TAG: u8 = 0xFF;
+// Magic, tag, part number, part count and the CRC32 of the whole shard
+const FRAGMENT_HEADER_LEN: usize = 8;
+const FRAGMENT_DATA_LEN: usize = QRCODE_MAX_BYTES - FRAGMENT_HEADER_LEN;
+
+/// Longest encoded shard that can still be printed, as the part count is stored in one byte.
+pub const MAX_SHARE_LEN: usize = u8::MAX as usize * FRAGMENT_DATA_LEN;
+
+/// Splits an encoded shard into the payloads of the QR codes it's printed as.
+pub fn split_share(share: &[u8]) -> anyhow::Result<Vec<Vec<u8>>> {
+    if share.len() <= QRCODE_MAX_BYTES {
+        return Ok(vec![share.to_vec()]);
+    }
+
+    let checksum = crc32fast::hash(share);
+    let chunks = share.chunks(FRAGMENT_DATA_LEN);
+    let total = match u8::try_from(chunks.len()) {
+        Ok(total) => total,
+        Err(_) => anyhow::bail!("The backup shard is too large to print as QR codes"),
+    };
+    Ok(chunks
+        .enumerate()
+        .map(|(index, chunk)| {
+            let mut fragment = vec![SHARD_MAGIC, FRAGMENT_TAG, index as u8 + 1, total];
+            fragment.extend_from_slice(&checksum.to_be_bytes());
+            fragment.extend_from_slice(chunk);
+            fragment
+        })
+        .collect())
+}
+
+pub fn qrcode_count(share_len: usize) -> usize {
+    if share_len <= QRCODE_MAX_BYTES {
+        1
+    } else {
+        share_len.div_ceil(FRAGMENT_DATA_LEN)
+    }
+}
+
+/// Returns the part number and part count of a fragment of a split shard.
+pub fn fragment_part(data: &[u8]) -> Option<(u8, u8)> {
+    match data {
+        [SHARD_MAGIC, FRAGMENT_TAG, part, total, ..] if data.len() > FRAGMENT_HEADER_LEN => {
+            Some((*part, *total))
+        }
+        _ => None,
+    }
+}
+
+/// Joins the fragments of split shards back together. Anything that isn't a fragment is passed
+/// through unchanged, so this can be applied to all codes found in a file.
+pub fn reassemble_fragments(codes: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>, FragmentError> {
+    let mut shares = Vec::new();
+    let mut groups: Vec<(u32, Vec<Vec<u8>>)> = Vec::new();
+    for code in codes {
+        if fragment_part(&code).is_none() {
+            shares.push(code);
+            continue;
+        }
+        let checksum = u32::from_be_bytes([code[4], code[5], code[6], code[7]]);
+        match groups.iter_mut().find(|(c, _)| *c == checksum) {
+            Some((_, fragments)) => fragments.push(code),
+            None => groups.push((checksum, vec![code])),
+        }
+    }
+
+    for (checksum, mut fragments) in groups {
+        let total = fragments[0][3];
+        if fragments.iter().any(|fragment| fragment[3] != total) {
+            return Err(FragmentError::Inconsistent);
+        }
+        fragments.sort_by_key(|fragment| fragment[2]);
+        for part in 1..=total {
+            match fragments
+                .iter()
+                .filter(|fragment| fragment[2] == part)
+                .count()
+            {
+                0 => return Err(FragmentError::MissingPart { part, total }),
+                1 => {}
+                _ => return Err(FragmentError::DuplicatePart { part, total }),
+            }
+        }
+        if fragments.len() != total as usize {
+            return Err(FragmentError::Inconsistent);
+        }
+
+        let share = fragments
+            .iter()
+            .flat_map(|fragment| fragment[FRAGMENT_HEADER_LEN..].iter().copied())
+            .collect::<Vec<u8>>();
+        if crc32fast::hash(&share) != checksum {
+            return Err(FragmentError::Inconsistent);
+        }
+        shares.push(share);
+    }
+    Ok(shares)
+}
+
+/// Like `reassemble_fragments`, for the codes found in a single file. Fragments missing a part
+/// are passed through unchanged, as the part may be in another file.
+pub fn reassemble_file_fragments(codes: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>, FragmentError> {
+    match reassemble_fragments(codes.clone()) {
+        Err(FragmentError::MissingPart { .. }) => Ok(codes),
+        result => result,
+    }
+}
+
+pub fn qrcode_encode(data: &[u8], size: usize) -> anyhow::Result<Vec<u8>> {
+    let data_ascii = share_payload(data);
+    let qrcode_data =
+        qrcode_generator::to_image(&data_ascii, qrcode_generator::QrCodeEcc::Medium, size)?;
+
+    Ok(qrcode_data)
+}
+
+/// Reads camera frames until a QR code is found, or until `stop` receives a message or its
+/// sender is dropped.
+#[cfg(feature = "camera")]
+pub fn qrcode_scan(stop: Receiver<()>) -> anyhow::Result<Vec<u8>> {
+    let mut camera = Camera::new(
+        CameraIndex::Index(0),
+        RequestedFormat::new::<RgbFormat>(RequestedFormatType::AbsoluteHighestResolution),
+    )?;
+    camera.open_stream()?;
+
+    loop {
+        if !matches!(stop.try_recv(), Err(TryRecvError::Empty)) {
+            return Err(QrCodeError::ScanCancelled.into());
+        }
+
+        let frame = camera.frame()?;
+        let frame_img = frame.decode_image::<RgbFormat>()?;
+
+        let raw_img = frame_img.as_raw();
+
+        let image = DynamicImage::ImageRgb8(
+            ImageBuffer::from_raw(frame_img.width(), frame_img.height(), raw_img.to_owned())
+                .expect("Freshly exported image must be valid"),
+        );
+
+        if let Some(barcode_data) = qrcode_find_all(&image).into_iter().next() {
+            return Ok(barcode_data);
+        }
+    }
+}
+
+pub fn qrcode_read_image(path: &Path) -> anyhow::Result<Vec<Vec<u8>>> {
+    let image = image::open(path)?;
+
+    let barcodes = qrcode_find_all_text(&image);
+    let codes = barcodes
+        .iter()
+        .filter_map(|barcode| qrcode_decode(barcode))
+        .collect::<Vec<Vec<u8>>>();
+    match barcodes.first() {
+        // Tell the user what they scanned instead, if it can be recognized
+        Some(barcode) if codes.is_empty() => {
+            Err(QrCodeError::Unrecognized(classify_payload(barcode)).into())
+        }
+        None => Err(QrCodeError::NoCodeFound.into()),
+        _ => Ok(reassemble_file_fragments(codes)?),
+    }
+}
+
+pub fn qrcode_read_file(path: &Path) -> anyhow::Result<Vec<Vec<u8>>> {
+    match path.extension().and_then(|e| e.to_str()) {
+        #[cfg(feature = "pdf")]
+        Some(extension) if extension.eq_ignore_ascii_case("pdf") => qrcode_read_pdf(path),
+        _ => qrcode_read_image(path),
+    }
+}
+
+#[cfg(feature = "pdf")]
+pub fn qrcode_read_pdf(path: &Path) -> anyhow::Result<Vec<Vec<u8>>> {
+    qrcode_read_pdf_document(lopdf::Document::load(path)?)
+}
+
+#[cfg(feature = "pdf")]
+pub fn qrcode_read_pdf_data(data: &[u8]) -> anyhow::Result<Vec<Vec<u8>>> {
+    qrcode_read_pdf_document(lopdf::Document::load_mem(data)?)
+}
+
+#[cfg(feature = "pdf")]
+fn qrcode_read_pdf_document(document: lopdf::Document) -> anyhow::Result<Vec<Vec<u8>>> {
+    use lopdf::Object;
+
+    let mut codes = Vec::new();
+
+    for page_id in document.page_iter() {
+        let (page_resources, resource_ids) = document.get_page_resources(page_id);
+        let resources = page_resources.into_iter().chain(
+            resource_ids
+                .iter()
+                .filter_map(|id| document.get_dictionary(*id).ok()),
+        );
+
+        for resource in resources {
+            let xobjects = match resource
+                .get(b"XObject")
+                .and_then(|o| document.dereference(o))
+                .and_then(|(_, o)| o.as_dict())
+            {
+                Ok(xobjects) => xobjects,
+                Err(_) => continue,
+            };
+
+            for (_, xobject) in xobjects.iter() {
+                let image = document
+                    .dereference(xobject)
+                    .and_then(|(_, o)| o.as_stream())
+                    .ok()
+                    .filter(|s| {
+                        s.dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Image")
+                    })
+                    .and_then(pdf_image_decode);
+                if let Some(image) = image {
+                    codes.extend(qrcode_find_all(&image));
+                }
+            }
+        }
+    }
+
+    if codes.is_empty() {
+        return Err(QrCodeError::NoCodeFound.into());
+    }
+
+    Ok(reassemble_file_fragments(codes)?)
+}
+
+#[cfg(feature = "pdf")]
+fn pdf_image_decode(stream: &lopdf::Stream) -> Option<DynamicImage> {
+    use lopdf::Object;
+
+    let width = stream.dict.get(b"Width").and_then(Object::as_i64).ok()? as u32;
+    let height = stream.dict.get(b"Height").and_then(Object::as_i64).ok()? as u32;
+    let filters = stream.filters().unwrap_or_default();
+
+    let pixels = match filters.iter().map(String::as_str).collect::<Vec<&str>>()[..] {
+        [] => stream.content.clone(),
+        ["DCTDecode"] => return image::load_from_memory(&stream.content).ok(),
+        ["FlateDecode"] => {
+            // lopdf refuses to decompress image streams, so present it as a plain stream
+            let mut plain = stream.clone();
+            plain.dict.remove(b"Subtype");
+            plain.decompressed_content().ok()?
+        }
+        _ => return None,
+    };
+
+    if stream
+        .dict
+        .get(b"BitsPerComponent")
+        .and_then(Object::as_i64)
+        .ok()
+        != Some(8)
+    {
+        return None;
+    }
+
+    match stream
+        .dict
+        .get(b"ColorSpace")
+        .and_then(Object::as_name)
+        .ok()
+    {
+        Some(b"DeviceGray") => {
+            ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageLuma8)
+        }
+        Some(b"DeviceRGB") => {
+            ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageRgb8)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(any(feature = "camera", feature = "pdf"))]
+fn qrcode_find_all(image: &DynamicImage) -> Vec<Vec<u8>> {
+    qrcode_find_all_text(image)
+        .into_iter()
+        .filter_map(|barcode_str| qrcode_decode(&barcode_str))
+        .collect()
+}
+
+fn qrcode_find_all_text(image: &DynamicImage) -> Vec<String> {
+    let decoder = bardecoder::default_decoder();
+    decoder
+        .decode(image)
+        .into_iter()
+        .filter_map(|barcode| barcode.ok())
+        .collect()
+}
+
+pub fn qrcode_decode(data: &str) -> Option<Vec<u8>> {
+    if looks_like_mnemonic(data) {
+        return Some(normalize_mnemonic(data).into_bytes());
+    }
+    if !is_valid_base85(data) {
+        return None;
+    }
+    base85::decode(data)
+}
+
+/// The text a shard or fragment is encoded as, the inverse of `parse_share_payload`. SLIP-0039
+/// shares are encoded as their mnemonic already, which other tools expect to scan.
+pub fn share_payload(data: &[u8]) -> String {
+    match std::str::from_utf8(data) {
+        Ok(mnemonic) if looks_like_mnemonic(mnemonic) => mnemonic.to_owned(),
+        _ => base85::encode(data),
+    }
+}
+
+/// Parses shard text pasted by the user. Whitespace and line breaks are ignored, since the
+/// Base85 alphabet doesn't contain any. The alphabet is case sensitive, so case is preserved.
+/// The words of SLIP-0039 shares are only lowercased and separated by single spaces.
+pub fn parse_share_payload(input: &str) -> anyhow::Result<Vec<u8>> {
+    if looks_like_mnemonic(input) {
+        return Ok(normalize_mnemonic(input).into_bytes());
+    }
+    let mut payload = String::with_capacity(input.len());
+    for (index, character) in input.chars().enumerate() {
+        if character.is_whitespace() {
+            continue;
+        }
+        if !is_base85_char(character) {
+            return Err(PayloadError::InvalidCharacter {
+                character,
+                position: index + 1,
+            }
+            .into());
+        }
+        payload.push(character);
+    }
+
+    // Every 4 bytes become 5 characters, a trailing group always has at least 2
+    match payload.len() {
+        0 => return Err(PayloadError::Empty.into()),
+        length if length % 5 == 1 => return Err(PayloadError::InvalidLength(length).into()),
+        _ => {}
+    }
+
+    // A full group above u32::MAX can't have been produced by the encoder
+    let overflows = payload.as_bytes().chunks_exact(5).any(|group| {
+        let value = group.iter().fold(0u64, |value, &c| {
+            value * 85 + BASE85_ALPHABET.find(c as char).unwrap_or(0) as u64
+        });
+        value > u32::MAX as u64
+    });
+    if overflows {
+        return Err(PayloadError::InvalidEncoding.into());
+    }
+
+    base85::decode(&payload).ok_or_else(|| PayloadError::InvalidEncoding.into())
+}
+
+/// Works out what a scanned or pasted payload is, to explain why it can't be used as a share.
+pub fn classify_payload(input: &str) -> PayloadKind {
+    let decoded = parse_share_payload(input);
+    let mut legacy = false;
+    if let Ok(data) = &decoded {
+        if fragment_part(data).is_some() {
+            return PayloadKind::Share;
+        }
+        match BackupShard::from_bytes(data.clone()) {
+            // Almost any long text passes as a bare share of the first release, so that is
+            // only assumed if it looks like nothing else
+            Ok(shard) if shard.is_legacy() => legacy = true,
+            Ok(_) => return PayloadKind::Share,
+            Err(error) => match error.downcast_ref() {
+                Some(BackupError::DamagedShard(_)) => return PayloadKind::Share,
+                Some(BackupError::NewerVersion(_)) => return PayloadKind::NewerShare,
+                _ => {}
+            },
+        }
+    }
+
+    let input = input.trim();
+    let lowercase = input.to_lowercase();
+    let words = input.split_whitespace().collect::<Vec<&str>>();
+    let is_word = |word: &&str| word.chars().all(|c| c.is_ascii_lowercase());
+
+    if lowercase.starts_with("http://") || lowercase.starts_with("https://") {
+        PayloadKind::Url
+    } else if is_wallet_address(input) {
+        PayloadKind::WalletAddress
+    } else if looks_like_mnemonic(input) {
+        PayloadKind::Slip39Share
+    } else if matches!(words.len(), 12 | 15 | 18 | 21 | 24) && words.iter().all(is_word) {
+        PayloadKind::Bip39Phrase
+    } else if legacy {
+        PayloadKind::Share
+    } else if decoded.is_ok() {
+        PayloadKind::NotAShare
+    } else {
+        PayloadKind::Malformed
+    }
+}
+
+fn is_wallet_address(input: &str) -> bool {
+    const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    const URI_SCHEMES: [&str; 4] = ["bitcoin:", "ethereum:", "litecoin:", "monero:"];
+
+    let lowercase = input.to_lowercase();
+    if URI_SCHEMES
+        .iter()
+        .any(|scheme| lowercase.starts_with(scheme))
+    {
+        return true;
+    }
+
+    let ethereum = input.len() == 42
+        && input.starts_with("0x")
+        && input[2..].chars().all(|c| c.is_ascii_hexdigit());
+    let bech32 = (lowercase.starts_with("bc1") || lowercase.starts_with("tb1"))
+        && (14..=74).contains(&input.len())
+        && lowercase.chars().all(|c| c.is_ascii_alphanumeric());
+    let legacy = (input.starts_with('1') || input.starts_with('3'))
+        && (26..=35).contains(&input.len())
+        && input.chars().all(|c| BASE58_ALPHABET.contains(c));
+    ethereum || bech32 || legacy
+}
+
+fn is_valid_base85(data: &str) -> bool {
+    data.chars().all(is_base85_char)
+}
+
+fn is_base85_char(c: char) -> bool {
+    BASE85_ALPHABET.contains(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::GrayImage;
+
+    use super::{
+        classify_payload, parse_share_payload, qrcode_count, qrcode_encode, qrcode_read_image,
+        reassemble_fragments, split_share, QRCODE_MAX_BYTES,
+    };
+    use crate::backup::BackupShard;
+    use crate::errors::{FragmentError, PayloadError, PayloadKind};
+
+    fn large_shard() -> BackupShard {
+        let mut data = (0..4000).map(|i| (i * 7 + 3) as u8).collect::<Vec<u8>>();
+        data[0] = 1;
+        BackupShard {
+            number: 1,
+            threshold: 1,
+            backup_id: 42,
+            data,
+            expires_at: None,
+            metadata: None,
+            slip39: None,
+        }
+    }
+
+    #[test]
+    fn split_share_round_trip() {
+        let small = vec![0u8; QRCODE_MAX_BYTES];
+        assert_eq!(split_share(&small).unwrap(), vec![small.clone()]);
+        assert_eq!(qrcode_count(small.len()), 1);
+
+        let share = large_shard().to_bytes();
+        let mut parts = split_share(&share).unwrap();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(qrcode_count(share.len()), 3);
+        assert!(parts.iter().all(|part| part.len() <= QRCODE_MAX_BYTES));
+
+        parts.reverse();
+        parts.insert(1, small.clone());
+        assert_eq!(reassemble_fragments(parts).unwrap(), vec![small, share]);
+    }
+
+    #[test]
+    fn reassembly_names_missing_and_duplicate_parts() {
+        let parts = split_share(&large_shard().to_bytes()).unwrap();
+
+        let missing = vec![parts[0].clone(), parts[2].clone()];
+        assert_eq!(
+            reassemble_fragments(missing),
+            Err(FragmentError::MissingPart { part: 2, total: 3 })
+        );
+
+        let mut duplicate = parts.clone();
+        duplicate.push(parts[2].clone());
+        assert_eq!(
+            reassemble_fragments(duplicate),
+            Err(FragmentError::DuplicatePart { part: 3, total: 3 })
+        );
+
+        let mut damaged = parts;
+        let last = damaged[1].len() - 1;
+        damaged[1][last] ^= 1;
+        assert_eq!(
+            reassemble_fragments(damaged),
+            Err(FragmentError::Inconsistent)
+        );
+    }
+
+    #[test]
+    fn read_image_round_trip() {
+        let data = vec![1u8, 2, 3, 4, 5, 6, 7, 8];
+        let pixels = qrcode_encode(&data, 512).expect("Failed to encode");
+        let image = GrayImage::from_raw(512, 512, pixels).expect("Invalid image");
+
+        let path = std::env::temp_dir().join("hyperbacked_read_image_round_trip.png");
+        image.save(&path).expect("Failed to save image");
+
+        let codes = qrcode_read_image(&path).expect("Failed to read image");
+        std::fs::remove_file(&path).ok();
+        assert_eq!(codes, vec![data]);
+    }
+
+    #[cfg(feature = "pdf")]
+    #[test]
+    fn read_pdf_round_trip() {
+        let shard = BackupShard {
+            number: 1,
+            threshold: 1,
+            backup_id: 42,
+            data: vec![1u8, 2, 3, 4, 5, 6, 7, 8],
+            expires_at: None,
+            metadata: None,
+            slip39: None,
+        };
+        let document = crate::printer::print_pdf(&shard, "Test", 1, &Default::default())
+            .expect("Failed to print");
+
+        let path = std::env::temp_dir().join("hyperbacked_read_pdf_round_trip.pdf");
+        document
+            .render_to_file(&path)
+            .expect("Failed to render PDF");
+
+        let codes = super::qrcode_read_pdf(&path).expect("Failed to read PDF");
+        std::fs::remove_file(&path).ok();
+        assert_eq!(codes, vec![shard.to_bytes()]);
+    }
+
+    #[cfg(feature = "pdf")]
+    #[test]
+    fn read_split_pdf_round_trip() {
+        let shard = large_shard();
+        let document = crate::printer::print_pdf(&shard, "Test", 1, &Default::default())
+            .expect("Failed to print");
+        let mut pdf = Vec::new();
+        document.render(&mut pdf).expect("Failed to render PDF");
+
+        let codes = super::qrcode_read_pdf_data(&pdf).expect("Failed to read PDF");
+        assert_eq!(codes, vec![shard.to_bytes()]);
+    }
+
+    #[test]
+    fn parse_clean_payload() {
+        let data = vec![0u8, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let payload = base85::encode(&data);
+        assert_eq!(parse_share_payload(&payload).unwrap(), data);
+    }
+
+    #[test]
+    fn parse_payload_with_whitespace() {
+        let data = vec![0u8, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let payload = base85::encode(&data);
+        let (first, second) = payload.split_at(6);
+        let messy = format!("  {}\r\n {}\t\n", first, second);
+        assert_eq!(parse_share_payload(&messy).unwrap(), data);
+    }
+
+    #[test]
+    fn parse_payload_reports_bad_character() {
+        let payload = format!("{}\n{}", base85::encode(&[1u8, 2, 3, 4]), "ab\"cd");
+        let error = parse_share_payload(&payload).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<PayloadError>(),
+            Some(PayloadError::InvalidCharacter {
+                character: '"',
+                position: 9
+            })
+        ));
+    }
+
+    #[test]
+    fn parse_payload_rejects_truncated_input() {
+        let payload = base85::encode(&[1u8, 2, 3, 4, 5, 6, 7, 8]);
+        let error = parse_share_payload(&format!("{}A", payload)).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<PayloadError>(),
+            Some(PayloadError::InvalidLength(11))
+        ));
+    }
+
+    #[test]
+    fn parse_payload_rejects_empty_and_overflowing_input() {
+        let error = parse_share_payload(" \n\t ").unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<PayloadError>(),
+            Some(PayloadError::Empty)
+        ));
+
+        let error = parse_share_payload("~~~~~").unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<PayloadError>(),
+            Some(PayloadError::InvalidEncoding)
+        ));
+    }
+
+    #[test]
+    fn classify_payloads() {
+        let shard = BackupShard {
+            number: 3,
+            threshold: 2,
+            backup_id: 42,
+            data: vec![3u8, 1, 4, 1, 5, 9],
+            expires_at: None,
+            metadata: None,
+            slip39: None,
+        };
+        let share = base85::encode(&shard.to_bytes());
+        assert_eq!(classify_payload(&share), PayloadKind::Share);
+
+        let mut newer = shard.to_bytes();
+        // The highest version byte is taken by split shards
+        newer[1] = u8::MAX - 1;
+        assert_eq!(
+            classify_payload(&base85::encode(&newer)),
+            PayloadKind::NewerShare
+        );
+
+        assert_eq!(
+            classify_payload(&base85::encode(&[3u8, 1, 4, 1, 5, 9])),
+            PayloadKind::NotAShare
+        );
+        // Shards of the first release had no header, only the share
+        let mut legacy = vec![2u8];
+        legacy.extend(std::iter::repeat_n(7u8, 90));
+        assert_eq!(
+            classify_payload(&base85::encode(&legacy)),
+            PayloadKind::Share
+        );
+        assert_eq!(
+            classify_payload("https://example.com/backup"),
+            PayloadKind::Url
+        );
+        for address in [
+            "bitcoin:bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq?amount=0.1",
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+            "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2",
+            "0x52908400098527886E0F7030069857D2E4169EE7",
+        ] {
+            assert_eq!(classify_payload(address), PayloadKind::WalletAddress);
+        }
+
+        let bip39 = ["abandon"; 11].join(" ") + " about";
+        assert_eq!(classify_payload(&bip39), PayloadKind::Bip39Phrase);
+        let slip39 = ["academic"; 20].join(" ");
+        assert_eq!(classify_payload(&slip39), PayloadKind::Slip39Share);
+        // The first share of the first published test vector
+        let slip39 = "duckling enlarge academic academic agency result length solution fridge \
+                      kidney coal piece deal husband erode duke ajar critical decision keyboard";
+        assert_eq!(classify_payload(slip39), PayloadKind::Share);
+
+        assert_eq!(classify_payload("not a share \""), PayloadKind::Malformed);
+    }
+}
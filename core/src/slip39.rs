@@ -0,0 +1,822 @@
+//! SLIP-0039 shares of a master secret, the way hardware wallets and other tools following the
+//! standard back up their seeds. Shares are only dealt in a single group, but shares of any
+//! number of groups can be combined.
+
+use std::collections::BTreeMap;
+
+use hmac::{Hmac, Mac};
+use lazy_static::lazy_static;
+use rand_core::CryptoRngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
+
+use crate::{
+    backup::SharingScheme,
+    errors::{BackupError, Slip39Error},
+};
+
+const WORD_LIST: &str = include_str!("../res/wordlist_slip39.txt");
+
+lazy_static! {
+    static ref WORDS: Vec<&'static str> = WORD_LIST.lines().collect();
+}
+
+// Every word stands for 10 bits
+const RADIX_BITS: usize = 10;
+// Identifier, extendable flag and iteration exponent, then the group and member parameters
+const HEADER_WORDS: usize = 4;
+const CHECKSUM_WORDS: usize = 3;
+/// Words of the shortest share, that of a 16 byte master secret.
+pub const MIN_WORDS: usize =
+    HEADER_WORDS + (MIN_SECRET_LEN * 8).div_ceil(RADIX_BITS) + CHECKSUM_WORDS;
+/// Shortest master secret the standard allows. Longer ones grow in steps of two bytes.
+pub const MIN_SECRET_LEN: usize = 16;
+/// Most shares in a group, and most groups.
+pub const MAX_SHARES: u8 = 16;
+/// The default of the reference implementation, which hardware wallets use as well: 20000
+/// rounds of PBKDF2 in all. The passphrase has to be strong, as that is all that protects it.
+pub const ITERATION_EXPONENT: u8 = 1;
+
+const BASE_ITERATION_COUNT: u32 = 10000;
+const ROUND_COUNT: u8 = 4;
+const CUSTOMIZATION: &[u8] = b"shamir";
+const CUSTOMIZATION_EXTENDABLE: &[u8] = b"shamir_extendable";
+// x coordinates of the shared secret and of its digest, below which the shares are dealt
+const SECRET_INDEX: u8 = 255;
+const DIGEST_INDEX: u8 = 254;
+const DIGEST_LEN: usize = 4;
+
+/// How the master secret of SLIP-0039 shares is encrypted, kept with every shard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Slip39Format {
+    /// The encryption runs 10000 << iteration_exponent rounds of PBKDF2
+    pub iteration_exponent: u8,
+    /// Set by newer tools, whose encryption doesn't depend on the identifier
+    #[serde(default)]
+    pub extendable: bool,
+}
+
+impl Default for Slip39Format {
+    fn default() -> Self {
+        Slip39Format {
+            iteration_exponent: ITERATION_EXPONENT,
+            extendable: false,
+        }
+    }
+}
+
+/// A single SLIP-0039 share, written as its mnemonic.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Slip39Share {
+    /// Random, the same for all shares of a master secret
+    pub identifier: u16,
+    pub format: Slip39Format,
+    /// Counted from 0, like the member index
+    pub group_index: u8,
+    pub group_threshold: u8,
+    pub group_count: u8,
+    pub member_index: u8,
+    pub member_threshold: u8,
+    pub value: Vec<u8>,
+}
+
+impl Slip39Share {
+    pub fn words(&self) -> Vec<String> {
+        let header = (self.identifier as u64 & 0x7FFF) << 25
+            | (self.format.extendable as u64) << 24
+            | (self.format.iteration_exponent as u64 & 0xF) << 20
+            | (self.group_index as u64 & 0xF) << 16
+            | (self.group_threshold as u64 - 1) << 12
+            | (self.group_count as u64 - 1) << 8
+            | (self.member_index as u64 & 0xF) << 4
+            | (self.member_threshold as u64 - 1);
+        let mut indices = (0..HEADER_WORDS)
+            .rev()
+            .map(|word| (header >> (RADIX_BITS * word)) as u32 & 0x3FF)
+            .collect::<Vec<u32>>();
+
+        // The value is padded with zero bits at the front to whole words
+        let mut bits = 0u32;
+        let mut bit_count = (RADIX_BITS - self.value.len() * 8 % RADIX_BITS) % RADIX_BITS;
+        for &byte in &self.value {
+            bits = (bits << 8) | byte as u32;
+            bit_count += 8;
+            while bit_count >= RADIX_BITS {
+                bit_count -= RADIX_BITS;
+                indices.push((bits >> bit_count) & 0x3FF);
+            }
+            bits &= (1 << bit_count) - 1;
+        }
+
+        indices.extend(create_checksum(customization(self.format), &indices));
+        indices
+            .into_iter()
+            .map(|index| WORDS[index as usize].to_owned())
+            .collect()
+    }
+
+    pub fn to_mnemonic(&self) -> String {
+        self.words().join(" ")
+    }
+
+    /// Reads a share from its words, matched case-insensitively. Like the words of BIP-39, the
+    /// first four letters are enough.
+    pub fn from_mnemonic(mnemonic: &str) -> Result<Self, Slip39Error> {
+        let words = mnemonic.split_whitespace().collect::<Vec<&str>>();
+        if words.len() < MIN_WORDS {
+            return Err(Slip39Error::TooShort(words.len()));
+        }
+        let indices = words
+            .iter()
+            .enumerate()
+            .map(|(index, word)| {
+                word_index(word)
+                    .map(|value| value as u32)
+                    .ok_or_else(|| Slip39Error::UnknownWord {
+                        word: word.to_string(),
+                        position: index + 1,
+                    })
+            })
+            .collect::<Result<Vec<u32>, Slip39Error>>()?;
+
+        let header = indices[..HEADER_WORDS]
+            .iter()
+            .fold(0u64, |header, &index| (header << RADIX_BITS) | index as u64);
+        let format = Slip39Format {
+            iteration_exponent: (header >> 20) as u8 & 0xF,
+            extendable: header >> 24 & 1 == 1,
+        };
+        if polymod(customization(format), &indices) != 1 {
+            return Err(Slip39Error::ChecksumMismatch);
+        }
+
+        let share = Slip39Share {
+            identifier: (header >> 25) as u16,
+            format,
+            group_index: (header >> 16) as u8 & 0xF,
+            group_threshold: (header >> 12) as u8 % 16 + 1,
+            group_count: (header >> 8) as u8 % 16 + 1,
+            member_index: (header >> 4) as u8 & 0xF,
+            member_threshold: header as u8 % 16 + 1,
+            value: unpack_value(&indices[HEADER_WORDS..indices.len() - CHECKSUM_WORDS])?,
+        };
+        if share.group_threshold > share.group_count {
+            return Err(Slip39Error::InvalidGroups);
+        }
+        Ok(share)
+    }
+}
+
+/// Whether pasted or scanned text is meant as a SLIP-0039 share. A single word may be
+/// mistyped, so reading it can still point out which.
+pub fn looks_like_mnemonic(text: &str) -> bool {
+    let words = text.split_whitespace().collect::<Vec<&str>>();
+    words.len() >= MIN_WORDS
+        && words
+            .iter()
+            .all(|word| word.chars().all(|c| c.is_ascii_alphabetic()))
+        && words
+            .iter()
+            .filter(|word| word_index(word).is_none())
+            .count()
+            <= 1
+}
+
+/// The words of a share in lower case, separated by single spaces.
+pub fn normalize_mnemonic(text: &str) -> String {
+    text.split_whitespace()
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Reads a master secret written in hex, which may be split up by whitespace.
+pub fn parse_master_secret(text: &str) -> Result<Vec<u8>, Slip39Error> {
+    let digits = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+    if !digits.len().is_multiple_of(2) || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Slip39Error::NotHex);
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&digits[index..index + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|_| Slip39Error::NotHex)
+}
+
+pub fn format_master_secret(master_secret: &[u8]) -> String {
+    master_secret
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Deals `count` shares of a single group, any `threshold` of which restore the master secret
+/// together with the passphrase.
+pub fn split_master_secret(
+    master_secret: &[u8],
+    passphrase: &str,
+    threshold: u8,
+    count: u8,
+    rng: &mut dyn CryptoRngCore,
+) -> Result<Vec<Slip39Share>, Slip39Error> {
+    check_secret_len(master_secret.len())?;
+    check_passphrase(passphrase)?;
+    if threshold == 1 && count > 1 {
+        return Err(Slip39Error::InvalidShareCount { threshold, count });
+    }
+
+    let identifier = rng.next_u32() as u16 & 0x7FFF;
+    let format = Slip39Format::default();
+    let mut encrypted = encrypt(master_secret, passphrase, format, identifier);
+    // With a single group, its share is the encrypted master secret itself
+    let shares = split_secret(&encrypted, threshold, count, rng);
+    encrypted.zeroize();
+    Ok(shares?
+        .into_iter()
+        .map(|(member_index, value)| Slip39Share {
+            identifier,
+            format,
+            group_index: 0,
+            group_threshold: 1,
+            group_count: 1,
+            member_index,
+            member_threshold: threshold,
+            value,
+        })
+        .collect())
+}
+
+/// Restores the master secret from enough shares of enough groups. A wrong passphrase can't be
+/// told apart from the right one, it leads to a different master secret.
+pub fn combine_shares(shares: &[Slip39Share], passphrase: &str) -> Result<Vec<u8>, Slip39Error> {
+    check_passphrase(passphrase)?;
+    let first = shares.first().ok_or(Slip39Error::NotEnoughShares {
+        provided: 0,
+        required: 1,
+    })?;
+    if shares.iter().any(|share| {
+        share.identifier != first.identifier
+            || share.format != first.format
+            || share.group_threshold != first.group_threshold
+            || share.group_count != first.group_count
+            || share.value.len() != first.value.len()
+    }) {
+        return Err(Slip39Error::MismatchedShares);
+    }
+
+    let mut groups = BTreeMap::<u8, Vec<&Slip39Share>>::new();
+    for share in shares {
+        groups.entry(share.group_index).or_default().push(share);
+    }
+    let mut group_shares = Vec::new();
+    let mut incomplete = None;
+    for (group_index, members) in groups {
+        let member_threshold = members[0].member_threshold;
+        if members
+            .iter()
+            .any(|share| share.member_threshold != member_threshold)
+        {
+            return Err(Slip39Error::MismatchedShares);
+        }
+        let points = members
+            .iter()
+            .map(|share| (share.member_index, &share.value[..]))
+            .collect::<Vec<(u8, &[u8])>>();
+        match recover_secret(member_threshold, &points) {
+            Ok(secret) => group_shares.push((group_index, secret)),
+            Err(Slip39Error::NotEnoughShares { provided, required }) => {
+                incomplete.get_or_insert(Slip39Error::NotEnoughShares { provided, required });
+            }
+            Err(error) => return Err(error),
+        }
+    }
+    if group_shares.len() < first.group_threshold as usize {
+        return Err(incomplete.unwrap_or(Slip39Error::NotEnoughGroups {
+            provided: group_shares.len(),
+            required: first.group_threshold as usize,
+        }));
+    }
+
+    let points = group_shares
+        .iter()
+        .map(|(index, secret)| (*index, &secret[..]))
+        .collect::<Vec<(u8, &[u8])>>();
+    let mut encrypted = recover_secret(first.group_threshold, &points)?;
+    for (_, secret) in &mut group_shares {
+        secret.zeroize();
+    }
+    let master_secret = decrypt(&encrypted, passphrase, first.format, first.identifier);
+    encrypted.zeroize();
+    Ok(master_secret)
+}
+
+pub fn combine_mnemonics(mnemonics: &[&str], passphrase: &str) -> Result<Vec<u8>, Slip39Error> {
+    let shares = mnemonics
+        .iter()
+        .map(|mnemonic| Slip39Share::from_mnemonic(mnemonic))
+        .collect::<Result<Vec<Slip39Share>, Slip39Error>>()?;
+    combine_shares(&shares, passphrase)
+}
+
+pub(crate) fn check_secret_len(len: usize) -> Result<(), Slip39Error> {
+    if len < MIN_SECRET_LEN || !len.is_multiple_of(2) {
+        return Err(Slip39Error::InvalidSecretLength(len));
+    }
+    Ok(())
+}
+
+fn check_passphrase(passphrase: &str) -> Result<(), Slip39Error> {
+    if !passphrase.bytes().all(|byte| (32..=126).contains(&byte)) {
+        return Err(Slip39Error::InvalidPassphrase);
+    }
+    Ok(())
+}
+
+/// Encrypts the master secret with the passphrase in a four round Feistel network, keeping its
+/// length.
+pub(crate) fn encrypt(
+    master_secret: &[u8],
+    passphrase: &str,
+    format: Slip39Format,
+    identifier: u16,
+) -> Vec<u8> {
+    feistel(
+        master_secret,
+        passphrase,
+        format,
+        identifier,
+        0..ROUND_COUNT,
+    )
+}
+
+pub(crate) fn decrypt(
+    encrypted: &[u8],
+    passphrase: &str,
+    format: Slip39Format,
+    identifier: u16,
+) -> Vec<u8> {
+    feistel(
+        encrypted,
+        passphrase,
+        format,
+        identifier,
+        (0..ROUND_COUNT).rev(),
+    )
+}
+
+fn feistel(
+    data: &[u8],
+    passphrase: &str,
+    format: Slip39Format,
+    identifier: u16,
+    rounds: impl Iterator<Item = u8>,
+) -> Vec<u8> {
+    let (left, right) = data.split_at(data.len() / 2);
+    let (mut left, mut right) = (left.to_vec(), right.to_vec());
+    // Extendable backups leave out the identifier, so shares can be added under a new one
+    let salt = match format.extendable {
+        true => Vec::new(),
+        false => [CUSTOMIZATION, &identifier.to_be_bytes()].concat(),
+    };
+    let iterations = (BASE_ITERATION_COUNT << format.iteration_exponent) / ROUND_COUNT as u32;
+    for round in rounds {
+        let mut password = [&[round], passphrase.as_bytes()].concat();
+        let mut key = vec![0u8; right.len()];
+        pbkdf2::pbkdf2::<Hmac<Sha256>>(
+            &password,
+            &[&salt[..], &right[..]].concat(),
+            iterations,
+            &mut key,
+        );
+        for (byte, key_byte) in left.iter_mut().zip(&key) {
+            *byte ^= key_byte;
+        }
+        std::mem::swap(&mut left, &mut right);
+        password.zeroize();
+        key.zeroize();
+    }
+    right.extend_from_slice(&left);
+    left.zeroize();
+    right
+}
+
+/// Shamir's secret sharing of SLIP-0039, over the AES field like `Gf256Shamir`. A digest of the
+/// secret is dealt along with it, so combining shares that don't belong together fails instead
+/// of returning garbage. The first byte of a share is its index, counted from 0.
+pub struct Slip39Shamir;
+
+impl SharingScheme for Slip39Shamir {
+    fn split(
+        &self,
+        secret: &[u8],
+        threshold: u8,
+        count: u8,
+        rng: &mut dyn CryptoRngCore,
+    ) -> Result<Vec<Vec<u8>>, BackupError> {
+        Ok(split_secret(secret, threshold, count, rng)?
+            .into_iter()
+            .map(|(index, value)| [&[index], &value[..]].concat())
+            .collect())
+    }
+
+    fn combine(&self, shares: &[&[u8]], threshold: u8) -> Result<Vec<u8>, BackupError> {
+        let points = share_points(shares)?;
+        Ok(recover_secret(threshold, &points)?)
+    }
+
+    fn extend(&self, shares: &[&[u8]], threshold: u8, x: u8) -> Result<Vec<u8>, BackupError> {
+        let points = share_points(shares)?;
+        if points.iter().any(|&(index, _)| index == x) {
+            return Err(BackupError::InvalidShard(format!(
+                "Shard {} exists already",
+                x
+            )));
+        }
+        // Only deal from shares that are known to belong together
+        recover_secret(threshold, &points)?.zeroize();
+        Ok([&[x], &interpolate(&points, x)[..]].concat())
+    }
+}
+
+fn share_points<'a>(shares: &[&'a [u8]]) -> Result<Vec<(u8, &'a [u8])>, BackupError> {
+    shares
+        .iter()
+        .map(|share| match share.split_first() {
+            Some((&index, value)) if !value.is_empty() => Ok((index, value)),
+            _ => Err(BackupError::InvalidShard(String::from("Empty share"))),
+        })
+        .collect()
+}
+
+fn split_secret(
+    secret: &[u8],
+    threshold: u8,
+    count: u8,
+    rng: &mut dyn CryptoRngCore,
+) -> Result<Vec<(u8, Vec<u8>)>, Slip39Error> {
+    if threshold == 0 || threshold > count || count > MAX_SHARES {
+        return Err(Slip39Error::InvalidShareCount { threshold, count });
+    }
+    if secret.len() < MIN_SECRET_LEN {
+        return Err(Slip39Error::InvalidSecretLength(secret.len()));
+    }
+    if threshold == 1 {
+        return Ok((0..count).map(|index| (index, secret.to_vec())).collect());
+    }
+
+    // The polynomial runs through random shares, the secret and a digest of it
+    let random_count = threshold - 2;
+    let mut shares = (0..random_count)
+        .map(|index| {
+            let mut value = vec![0u8; secret.len()];
+            rng.fill_bytes(&mut value);
+            (index, value)
+        })
+        .collect::<Vec<(u8, Vec<u8>)>>();
+    let mut digest_share = vec![0u8; secret.len()];
+    rng.fill_bytes(&mut digest_share[DIGEST_LEN..]);
+    let digest = create_digest(&digest_share[DIGEST_LEN..], secret);
+    digest_share[..DIGEST_LEN].copy_from_slice(&digest);
+
+    let mut base = shares
+        .iter()
+        .map(|(index, value)| (*index, &value[..]))
+        .collect::<Vec<(u8, &[u8])>>();
+    base.push((DIGEST_INDEX, &digest_share));
+    base.push((SECRET_INDEX, secret));
+    let dealt = (random_count..count)
+        .map(|index| (index, interpolate(&base, index)))
+        .collect::<Vec<(u8, Vec<u8>)>>();
+    digest_share.zeroize();
+    shares.extend(dealt);
+    Ok(shares)
+}
+
+fn recover_secret(threshold: u8, points: &[(u8, &[u8])]) -> Result<Vec<u8>, Slip39Error> {
+    // The same share may be given twice, but not two shares of the same index
+    let mut distinct = Vec::<(u8, &[u8])>::with_capacity(points.len());
+    for &(index, value) in points {
+        match distinct.iter().find(|(known, _)| *known == index) {
+            Some((_, known)) if *known != value => return Err(Slip39Error::DuplicateShare(index)),
+            Some(_) => {}
+            None => distinct.push((index, value)),
+        }
+    }
+    if distinct.len() < threshold as usize || distinct.is_empty() {
+        return Err(Slip39Error::NotEnoughShares {
+            provided: distinct.len(),
+            required: threshold.max(1) as usize,
+        });
+    }
+    if distinct
+        .iter()
+        .any(|(_, value)| value.len() != distinct[0].1.len())
+    {
+        return Err(Slip39Error::MismatchedShares);
+    }
+    if threshold == 1 {
+        return Ok(distinct[0].1.to_vec());
+    }
+
+    let secret = interpolate(&distinct, SECRET_INDEX);
+    let mut digest_share = interpolate(&distinct, DIGEST_INDEX);
+    let digest = create_digest(&digest_share[DIGEST_LEN..], &secret);
+    let valid = bool::from(digest.ct_eq(&digest_share[..DIGEST_LEN]));
+    digest_share.zeroize();
+    if !valid {
+        return Err(Slip39Error::InvalidDigest);
+    }
+    Ok(secret)
+}
+
+fn create_digest(random: &[u8], secret: &[u8]) -> [u8; DIGEST_LEN] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(random).expect("HMAC takes keys of any length");
+    mac.update(secret);
+    let mut digest = [0u8; DIGEST_LEN];
+    digest.copy_from_slice(&mac.finalize().into_bytes()[..DIGEST_LEN]);
+    digest
+}
+
+/// Lagrange interpolation of the points at `x`, byte by byte. Addition and subtraction are
+/// both XOR in GF(2^8).
+fn interpolate(points: &[(u8, &[u8])], x: u8) -> Vec<u8> {
+    if let Some((_, value)) = points.iter().find(|(index, _)| *index == x) {
+        return value.to_vec();
+    }
+    let mut result = vec![0u8; points[0].1.len()];
+    for (i, &(xi, yi)) in points.iter().enumerate() {
+        let basis = points
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .fold(1, |basis, (_, &(xj, _))| {
+                field_mul(basis, field_mul(x ^ xj, field_inv(xi ^ xj)))
+            });
+        for (byte, &y) in result.iter_mut().zip(yi) {
+            *byte ^= field_mul(y, basis);
+        }
+    }
+    result
+}
+
+/// Multiplication modulo the AES polynomial x^8 + x^4 + x^3 + x + 1, without branching on the
+/// secret bytes.
+fn field_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        a = (a << 1) ^ (0x1b & 0u8.wrapping_sub(a >> 7));
+        b >>= 1;
+    }
+    product
+}
+
+fn field_inv(a: u8) -> u8 {
+    // a^254 = a^-1, as the multiplicative group has 255 elements
+    (0..254).fold(1, |inverse, _| field_mul(inverse, a))
+}
+
+fn customization(format: Slip39Format) -> &'static [u8] {
+    match format.extendable {
+        true => CUSTOMIZATION_EXTENDABLE,
+        false => CUSTOMIZATION,
+    }
+}
+
+/// Remainder of the RS1024 code over GF(1024), which is 1 for intact data and its checksum.
+fn polymod(customization: &[u8], values: &[u32]) -> u32 {
+    const GENERATOR: [u32; 10] = [
+        0xE0E040, 0x1C1C080, 0x3838100, 0x7070200, 0xE0E0009, 0x1C0C2412, 0x38086C24, 0x3090FC48,
+        0x21B1F890, 0x3F3F120,
+    ];
+    customization
+        .iter()
+        .map(|&byte| byte as u32)
+        .chain(values.iter().copied())
+        .fold(1, |checksum, value| {
+            let top = checksum >> 20;
+            GENERATOR
+                .iter()
+                .enumerate()
+                .filter(|(bit, _)| top >> bit & 1 == 1)
+                .fold(
+                    (checksum & 0xFFFFF) << 10 ^ value,
+                    |checksum, (_, generator)| checksum ^ generator,
+                )
+        })
+}
+
+fn create_checksum(customization: &[u8], values: &[u32]) -> [u32; CHECKSUM_WORDS] {
+    let padded = [values, &[0; CHECKSUM_WORDS]].concat();
+    let remainder = polymod(customization, &padded) ^ 1;
+    [2, 1, 0].map(|word| remainder >> (RADIX_BITS * word) & 0x3FF)
+}
+
+/// The share value from the words between header and checksum, after the zero bits that pad
+/// it to whole words.
+fn unpack_value(indices: &[u32]) -> Result<Vec<u8>, Slip39Error> {
+    let padding = indices.len() * RADIX_BITS % 16;
+    if padding > 8 {
+        return Err(Slip39Error::InvalidPadding);
+    }
+    let mut value = Vec::with_capacity(indices.len() * RADIX_BITS / 8);
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+    for (position, &index) in indices.iter().enumerate() {
+        bits = (bits << RADIX_BITS) | index;
+        bit_count += RADIX_BITS;
+        if position == 0 {
+            bit_count -= padding;
+            if bits >> bit_count != 0 {
+                return Err(Slip39Error::InvalidPadding);
+            }
+        }
+        while bit_count >= 8 {
+            bit_count -= 8;
+            value.push((bits >> bit_count) as u8);
+        }
+        bits &= (1 << bit_count) - 1;
+    }
+    Ok(value)
+}
+
+fn word_index(word: &str) -> Option<usize> {
+    let word = word.to_ascii_lowercase();
+    // The first four letters of every word are unique
+    WORDS.binary_search(&word.as_str()).ok().or_else(|| {
+        WORDS
+            .iter()
+            .position(|candidate| word.len() >= 4 && candidate.starts_with(&word))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::thread_rng;
+    use serde_json::Value;
+    use sha2::{Digest, Sha256};
+
+    use super::{
+        combine_mnemonics, combine_shares, format_master_secret, looks_like_mnemonic,
+        parse_master_secret, split_master_secret, Slip39Shamir, Slip39Share, MIN_WORDS, WORDS,
+    };
+    use crate::{backup::SharingScheme, errors::Slip39Error};
+
+    /// The test vectors published with the reference implementation, as description,
+    /// mnemonics and the master secret in hex, empty if the mnemonics are invalid.
+    const VECTORS: &str = include_str!("../tests/fixtures/slip39_vectors.json");
+
+    #[test]
+    fn word_list_is_complete() {
+        assert_eq!(WORDS.len(), 1024);
+        assert!(WORDS.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(WORDS
+            .windows(2)
+            .all(|pair| pair[0].get(..4) != pair[1].get(..4)));
+        // SHA-256 of wordlist.txt as published with SLIP-0039
+        let digest = Sha256::digest(WORDS.join("\n") + "\n");
+        assert_eq!(
+            format!("{:x}", digest),
+            "bcc4555340332d169718aed8bf31dd9d5248cb7da6e5d355140ef4f1e601eec3"
+        );
+    }
+
+    #[test]
+    fn shares_match_published_vectors() {
+        let vectors = serde_json::from_str::<Vec<Value>>(VECTORS).unwrap();
+        assert_eq!(vectors.len(), 40);
+        for vector in vectors {
+            let description = vector[0].as_str().unwrap();
+            let mnemonics = vector[1]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|mnemonic| mnemonic.as_str().unwrap())
+                .collect::<Vec<&str>>();
+            let result = combine_mnemonics(&mnemonics, "TREZOR");
+            match vector[2].as_str().unwrap() {
+                "" => assert!(result.is_err(), "{} was accepted", description),
+                expected => {
+                    let restored = result.unwrap_or_else(|e| panic!("{}: {}", description, e));
+                    assert_eq!(format_master_secret(&restored), expected, "{}", description);
+                    // Every share reads back into the same words
+                    for mnemonic in mnemonics {
+                        let share = Slip39Share::from_mnemonic(mnemonic).unwrap();
+                        assert_eq!(share.to_mnemonic(), mnemonic);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn master_secret_round_trip() {
+        let master_secret = parse_master_secret("bb54aac4b89dc868ba37d9cc21b2cece").unwrap();
+        let shares =
+            split_master_secret(&master_secret, "TREZOR", 3, 5, &mut thread_rng()).unwrap();
+        assert_eq!(shares.len(), 5);
+        let mnemonics = shares
+            .iter()
+            .map(Slip39Share::to_mnemonic)
+            .collect::<Vec<String>>();
+        assert!(mnemonics
+            .iter()
+            .all(|mnemonic| looks_like_mnemonic(mnemonic)));
+        assert!(mnemonics
+            .iter()
+            .all(|mnemonic| mnemonic.split(' ').count() == MIN_WORDS));
+
+        let subset = [&mnemonics[4][..], &mnemonics[0][..], &mnemonics[2][..]];
+        assert_eq!(combine_mnemonics(&subset, "TREZOR").unwrap(), master_secret);
+        // Any passphrase decrypts, to a different master secret
+        assert_ne!(combine_mnemonics(&subset, "trezor").unwrap(), master_secret);
+        assert_eq!(
+            combine_mnemonics(&subset[..2], "TREZOR"),
+            Err(Slip39Error::NotEnoughShares {
+                provided: 2,
+                required: 3
+            })
+        );
+
+        // A single share of a 32 byte secret, which needs 33 words
+        let master_secret = (0..32).collect::<Vec<u8>>();
+        let shares = split_master_secret(&master_secret, "", 1, 1, &mut thread_rng()).unwrap();
+        assert_eq!(shares[0].words().len(), 33);
+        assert_eq!(combine_shares(&shares, "").unwrap(), master_secret);
+    }
+
+    #[test]
+    fn invalid_splits_are_rejected() {
+        let mut rng = thread_rng();
+        assert_eq!(
+            split_master_secret(&[0; 15], "", 2, 3, &mut rng),
+            Err(Slip39Error::InvalidSecretLength(15))
+        );
+        assert_eq!(
+            split_master_secret(&[0; 17], "", 2, 3, &mut rng),
+            Err(Slip39Error::InvalidSecretLength(17))
+        );
+        assert_eq!(
+            split_master_secret(&[0; 16], "pässword", 2, 3, &mut rng),
+            Err(Slip39Error::InvalidPassphrase)
+        );
+        for (threshold, count) in [(1, 2), (3, 2), (2, 17), (0, 1)] {
+            assert_eq!(
+                split_master_secret(&[0; 16], "", threshold, count, &mut rng),
+                Err(Slip39Error::InvalidShareCount { threshold, count })
+            );
+        }
+        assert!(parse_master_secret("abc").is_err());
+        assert!(parse_master_secret("xy").is_err());
+        assert_eq!(parse_master_secret("0A ff").unwrap(), [0x0a, 0xff]);
+    }
+
+    #[test]
+    fn mistyped_words_are_reported() {
+        let shares = split_master_secret(&[7; 16], "", 1, 1, &mut thread_rng()).unwrap();
+        let mut words = shares[0].words();
+        // Four letters are enough, in any case
+        words[5] = words[5][..4].to_uppercase();
+        assert_eq!(
+            Slip39Share::from_mnemonic(&words.join(" ")).unwrap(),
+            shares[0]
+        );
+
+        words[7] = String::from("bitcoin");
+        assert!(looks_like_mnemonic(&words.join(" ")));
+        assert_eq!(
+            Slip39Share::from_mnemonic(&words.join(" ")),
+            Err(Slip39Error::UnknownWord {
+                word: String::from("bitcoin"),
+                position: 8
+            })
+        );
+        assert_eq!(
+            Slip39Share::from_mnemonic(&words[..19].join(" ")),
+            Err(Slip39Error::TooShort(19))
+        );
+        assert!(!looks_like_mnemonic(&words[..19].join(" ")));
+        assert!(!looks_like_mnemonic(&["abandon"; 24].join(" ")));
+    }
+
+    #[test]
+    fn scheme_deals_and_extends_shares() {
+        let scheme: &dyn SharingScheme = &Slip39Shamir;
+        let secret = b"sixteen byte key, at least";
+        let shares = scheme.split(secret, 3, 5, &mut thread_rng()).unwrap();
+        assert_eq!(shares[0][0], 0);
+        let subset = [&shares[3][..], &shares[1][..], &shares[4][..]];
+        assert_eq!(scheme.combine(&subset, 3).unwrap(), secret);
+        assert_eq!(scheme.extend(&subset, 3, 2).unwrap(), shares[2]);
+
+        // Shares of another secret break the digest rather than giving a wrong secret
+        let other = scheme.split(secret, 3, 5, &mut thread_rng()).unwrap();
+        let mixed = [&shares[0][..], &shares[1][..], &other[2][..]];
+        assert!(scheme.combine(&mixed, 3).is_err());
+    }
+}
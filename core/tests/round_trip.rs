@@ -0,0 +1,93 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use hyperbacked_core::{
+    backup::{create_backup, restore_backup, BackupConfig},
+    crypto::{Secret, SecretEncoding},
+    errors::RestoreError,
+    passphrase::gen_passphrase,
+};
+
+const SECRET: &str = "This is my secret";
+
+/// Creates a backup and saves each shard to its own file, returning the paths in shard order.
+fn save_backup(dir: &Path, passphrase: &str, config: BackupConfig) -> Vec<PathBuf> {
+    let secrets = vec![Secret {
+        value: SECRET.into(),
+        password: passphrase.into(),
+        encoding: SecretEncoding::Text,
+        note: Default::default(),
+    }];
+    let shards = create_backup(secrets, config).expect("Failed to create backup");
+    shards
+        .iter()
+        .map(|shard| {
+            let path = dir.join(format!("shard-{}-of-{}.bin", shard.number, shards.len()));
+            fs::write(&path, shard.to_bytes()).expect("Failed to save shard");
+            path
+        })
+        .collect()
+}
+
+fn load_shares(paths: &[PathBuf]) -> Vec<Vec<u8>> {
+    paths
+        .iter()
+        .map(|path| fs::read(path).expect("Failed to load shard"))
+        .collect()
+}
+
+#[test]
+fn standard_backup() {
+    let dir = tempfile::tempdir().unwrap();
+    let passphrase = gen_passphrase(6, Some(' '));
+    let config = BackupConfig::builder().build().unwrap();
+    let paths = save_backup(dir.path(), &passphrase, config);
+    assert_eq!(paths.len(), 1);
+
+    let restored =
+        restore_backup(load_shares(&paths), &passphrase, None).expect("Failed to restore");
+    assert_eq!(&*restored.secret.value, SECRET);
+}
+
+#[test]
+fn distributed_backup_with_threshold_shares() {
+    let dir = tempfile::tempdir().unwrap();
+    let passphrase = gen_passphrase(6, Some(' '));
+    let config = BackupConfig::builder()
+        .with_threshold(3)
+        .with_total(5)
+        .build()
+        .unwrap();
+    let paths = save_backup(dir.path(), &passphrase, config);
+    assert_eq!(paths.len(), 5);
+
+    let shares = load_shares(&paths);
+    let chosen = vec![shares[4].clone(), shares[0].clone(), shares[2].clone()];
+    let restored = restore_backup(chosen, &passphrase, None).expect("Failed to restore");
+    assert_eq!(&*restored.secret.value, SECRET);
+    assert_eq!(restored.shards_used, vec![1, 3, 5]);
+}
+
+#[test]
+fn too_few_shares() {
+    let dir = tempfile::tempdir().unwrap();
+    let passphrase = gen_passphrase(6, Some(' '));
+    let config = BackupConfig::builder()
+        .with_threshold(3)
+        .with_total(5)
+        .build()
+        .unwrap();
+    let paths = save_backup(dir.path(), &passphrase, config);
+
+    let error = restore_backup(load_shares(&paths[..2]), &passphrase, None)
+        .expect_err("Restored from fewer shares than the threshold");
+    assert!(matches!(
+        error,
+        RestoreError::NotEnoughShares {
+            provided: 2,
+            required: 3
+        }
+    ));
+}
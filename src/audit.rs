@@ -0,0 +1,100 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+/// Audit log entries. These must never contain secrets or passphrases.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEvent {
+    BackupCreated {
+        timestamp: u64,
+        backup_id: String,
+        label: String,
+        total_shards: usize,
+        threshold: u8,
+    },
+    BackupRestored {
+        timestamp: u64,
+        backup_id: String,
+        shards_used: Vec<usize>,
+    },
+}
+
+impl AuditEvent {
+    pub fn backup_created(backup_id: u64, label: &str, total_shards: usize, threshold: u8) -> Self {
+        AuditEvent::BackupCreated {
+            timestamp: unix_timestamp(),
+            backup_id: format!("{:016x}", backup_id),
+            label: label.to_owned(),
+            total_shards,
+            threshold,
+        }
+    }
+
+    pub fn backup_restored(backup_id: u64, shards_used: Vec<usize>) -> Self {
+        AuditEvent::BackupRestored {
+            timestamp: unix_timestamp(),
+            backup_id: format!("{:016x}", backup_id),
+            shards_used,
+        }
+    }
+}
+
+pub fn append_audit_log(event: &AuditEvent, path: &Path) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(event)?)?;
+    Ok(())
+}
+
+pub fn default_audit_log_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("hyperbacked").join("audit.log"))
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{append_audit_log, AuditEvent};
+
+    #[test]
+    fn appends_json_lines() {
+        let path = std::env::temp_dir().join("hyperbacked_appends_json_lines.log");
+        fs::remove_file(&path).ok();
+
+        append_audit_log(&AuditEvent::backup_created(1, "My backup", 3, 2), &path)
+            .expect("Failed to write first event");
+        append_audit_log(&AuditEvent::backup_restored(1, vec![1, 3]), &path)
+            .expect("Failed to write second event");
+
+        let contents = fs::read_to_string(&path).expect("Failed to read log");
+        fs::remove_file(&path).ok();
+
+        let lines = contents.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 2);
+
+        let first: serde_json::Value = serde_json::from_str(lines[0]).expect("Invalid JSON");
+        assert_eq!(first["event"], "backup_created");
+        assert_eq!(first["backup_id"], "0000000000000001");
+        assert_eq!(first["threshold"], 2);
+
+        let second: serde_json::Value = serde_json::from_str(lines[1]).expect("Invalid JSON");
+        assert_eq!(second["event"], "backup_restored");
+        assert_eq!(second["shards_used"], serde_json::json!([1, 3]));
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
+
+use hyperbacked_core::{
+    backup::{shard_total, BackupShard},
+    crypto::{decrypt_blob, encrypt_blob},
+    printer::{print_pdf, PdfTheme},
+};
+
+use crate::errors::BundleError;
+
+const BUNDLE_VERSION: u8 = 1;
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// Stored unencrypted, so the bundle can be checked without the bundle password.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub version: u8,
+    pub label: String,
+    pub backup_id: String,
+    pub threshold: u8,
+    pub total_shards: usize,
+    pub files: Vec<String>,
+}
+
+pub fn save_bundle(
+    path: &Path,
+    shards: &[BackupShard],
+    backup_name: &str,
+    password: &str,
+    theme: &PdfTheme,
+) -> anyhow::Result<()> {
+    let total = shard_total(shards);
+    let files = shards
+        .iter()
+        .map(|shard| format!("shard-{}-of-{}.pdf.enc", shard.number, total))
+        .collect::<Vec<String>>();
+    let manifest = BundleManifest {
+        version: BUNDLE_VERSION,
+        label: backup_name.to_owned(),
+        backup_id: format!("{:016x}", shards.first().map_or(0, |s| s.backup_id)),
+        threshold: shards.first().map_or(0, |s| s.threshold),
+        total_shards: total,
+        files: files.clone(),
+    };
+
+    let mut zip = ZipWriter::new(File::create(path)?);
+    zip.start_file(MANIFEST_NAME, FileOptions::default())?;
+    zip.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+
+    for (shard, name) in shards.iter().zip(files) {
+        let mut pdf = Vec::new();
+        print_pdf(shard, backup_name, total, theme)?.render(&mut pdf)?;
+
+        // Ciphertext doesn't compress, so don't bother trying
+        let options = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        zip.start_file(name, options)?;
+        zip.write_all(&encrypt_blob(&pdf, password)?)?;
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+pub fn read_manifest(path: &Path) -> anyhow::Result<BundleManifest> {
+    let mut zip = ZipArchive::new(File::open(path)?)?;
+    let manifest: BundleManifest = serde_json::from_reader(zip.by_name(MANIFEST_NAME)?)?;
+    if manifest.version != BUNDLE_VERSION {
+        return Err(BundleError::UnsupportedVersion(manifest.version).into());
+    }
+    Ok(manifest)
+}
+
+/// Returns the decrypted shard PDFs in the order listed by the manifest.
+pub fn open_bundle(path: &Path, password: &str) -> anyhow::Result<Vec<Vec<u8>>> {
+    let manifest = read_manifest(path)?;
+    let mut zip = ZipArchive::new(File::open(path)?)?;
+
+    let mut pdfs = Vec::new();
+    for name in &manifest.files {
+        let mut encrypted = Vec::new();
+        zip.by_name(name)?.read_to_end(&mut encrypted)?;
+        pdfs.push(decrypt_blob(&encrypted, password)?);
+    }
+    Ok(pdfs)
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "pdf")]
+    use hyperbacked_core::backup::{restore_shards, BackupShard};
+    use hyperbacked_core::{
+        backup::{create_backup, BackupConfig},
+        crypto::{Secret, SecretEncoding},
+        passphrase::gen_passphrase,
+        printer::PdfTheme,
+    };
+
+    use super::{open_bundle, read_manifest, save_bundle};
+    #[cfg(feature = "pdf")]
+    use crate::export::import_bundle;
+
+    #[test]
+    fn bundle_round_trip() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig::builder()
+            .with_threshold(2)
+            .with_total(3)
+            .build()
+            .unwrap();
+        let shards = create_backup(secrets, config).expect("Failed to create backup");
+
+        let path = std::env::temp_dir().join("hyperbacked_bundle_round_trip.zip");
+        save_bundle(
+            &path,
+            &shards,
+            "My backup",
+            "bundle password",
+            &PdfTheme::default(),
+        )
+        .expect("Failed to save");
+
+        let manifest = read_manifest(&path).expect("Failed to read manifest");
+        assert_eq!(manifest.label, "My backup");
+        assert_eq!(manifest.threshold, 2);
+        assert_eq!(manifest.total_shards, 3);
+        assert_eq!(manifest.files.len(), 3);
+
+        let pdfs = open_bundle(&path, "bundle password").expect("Failed to open bundle");
+        let wrong = open_bundle(&path, "wrong password");
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(pdfs.len(), 3);
+        assert!(pdfs.iter().all(|pdf| pdf.starts_with(b"%PDF")));
+        assert!(wrong.is_err());
+    }
+
+    #[cfg(feature = "pdf")]
+    #[test]
+    fn restore_from_bundle() {
+        let passphrase = gen_passphrase(6, Some(' '));
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: passphrase.as_str().into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig::builder()
+            .with_threshold(2)
+            .with_total(3)
+            .build()
+            .unwrap();
+        let shards = create_backup(secrets, config).expect("Failed to create backup");
+
+        let path = std::env::temp_dir().join("hyperbacked_restore_from_bundle.zip");
+        save_bundle(
+            &path,
+            &shards,
+            "My backup",
+            "bundle password",
+            &PdfTheme::default(),
+        )
+        .expect("Failed to save");
+        let codes = import_bundle(&path, "bundle password");
+        std::fs::remove_file(&path).ok();
+
+        let shards = codes
+            .expect("Failed to import bundle")
+            .into_iter()
+            .map(|data| BackupShard::from_bytes(data).expect("Invalid shard"))
+            .collect::<Vec<BackupShard>>();
+        let restored = restore_shards(&shards, &passphrase).expect("Failed to restore backup");
+        assert_eq!(&*restored.value, "This is my secret");
+    }
+}
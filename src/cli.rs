@@ -0,0 +1,716 @@
+use std::{
+    env, fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    slice,
+};
+
+use chrono::{NaiveDate, Utc};
+use clap::{Args, Parser, Subcommand};
+use zeroize::Zeroize;
+
+use hyperbacked_core::{
+    backup::{
+        create_backup, max_secret_len, max_share_len, restore_backup, BackupConfig,
+        BackupConfigBuilder, SssBackend,
+    },
+    crypto::{
+        has_control_chars, key_file_digest, plaintext_len, should_compress, stored_len,
+        CipherSuite, KdfAlgorithm, KdfParams, KeyFileDigest, Secret, SecretEncoding, SecurityLevel,
+        MAX_SECRET_LEN,
+    },
+    passphrase::{passphrase_score, MIN_SCORE},
+    printer::{parse_hex_color, print_pdf, PdfTheme},
+    qrcode::{qrcode_count, reassemble_fragments},
+    slip39::parse_master_secret,
+};
+
+use crate::export::import_shard_file;
+
+#[cfg(feature = "pdf")]
+use crate::export::import_bundle;
+#[cfg(feature = "pdf")]
+use hyperbacked_core::printer::parse_shares_from_pdf;
+#[cfg(feature = "hardware-key")]
+use hyperbacked_core::{
+    backup::BackupShard, errors::ConfigError, hardware_key::register_hardware_key,
+};
+
+pub const VERSION: &str = concat!(
+    env!("CARGO_PKG_VERSION"),
+    " (built ",
+    env!("BUILD_DATE"),
+    ")"
+);
+
+#[derive(Parser)]
+#[command(author, version = VERSION, about)]
+pub struct Cli {
+    /// Launch the graphical interface, ignoring any subcommand
+    #[arg(long)]
+    pub gui: bool,
+
+    /// Launch the graphical interface in the system tray, notifying when tracked backups are
+    /// about to expire
+    #[cfg(feature = "tray")]
+    #[arg(long)]
+    pub tray: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Create a new backup and write one PDF per shard
+    Create {
+        /// The secret that will be backed up, read from stdin if omitted
+        #[arg(long)]
+        secret: Option<String>,
+        /// Accept secrets that aren't printable text, storing them Base64 encoded
+        #[arg(long)]
+        allow_binary: bool,
+        /// Note encrypted along with the secret, printed separately when it is restored
+        #[arg(long, default_value = "")]
+        note: String,
+        #[command(flatten)]
+        passphrase: PassphraseArgs,
+        /// File that is required next to the passphrase to restore the backup
+        #[arg(long, value_name = "PATH")]
+        key_file: Option<PathBuf>,
+        /// Register a FIDO2 security key that is required next to the passphrase to restore the
+        /// backup. Losing the key makes the backup unrecoverable
+        #[cfg(feature = "hardware-key")]
+        #[arg(long)]
+        require_hardware_key: bool,
+        /// Total number of shards to generate
+        #[arg(long, default_value_t = 1)]
+        shares: u8,
+        /// Number of shards required to restore the backup
+        #[arg(long, default_value_t = 1)]
+        threshold: u8,
+        /// Label printed on each shard and stored in its QR code
+        #[arg(long, default_value = "")]
+        label: String,
+        /// Only print the label, so it can't be read from the QR codes
+        #[arg(long)]
+        private_label: bool,
+        /// Work factor of the key derivation: fast, balanced or paranoid
+        #[arg(long, default_value_t = SecurityLevel::Balanced)]
+        security: SecurityLevel,
+        /// Exact number of key derivation rounds, overriding --security
+        #[arg(long, value_name = "ROUNDS")]
+        kdf_rounds: Option<u32>,
+        /// Key derivation to use instead of the --security presets: pbkdf2 or argon2id
+        #[arg(long, value_name = "ALGORITHM")]
+        kdf: Option<KdfAlgorithm>,
+        /// Memory Argon2id fills for every passphrase, in MiB
+        #[arg(long, value_name = "MIB", default_value_t = 64)]
+        argon2_memory: u16,
+        /// Passes Argon2id makes over its memory
+        #[arg(long, value_name = "PASSES", default_value_t = 3)]
+        argon2_iterations: u8,
+        /// Lanes Argon2id fills in parallel
+        #[arg(long, value_name = "LANES", default_value_t = 4)]
+        argon2_parallelism: u8,
+        /// Cipher that encrypts the secret: AES-256-GCM or XChaCha20-Poly1305
+        #[arg(long, default_value_t = CipherSuite::Aes256Gcm)]
+        cipher: CipherSuite,
+        /// How the encrypted secret is split into shares: sharks, or gf256 for the AES field
+        /// also used by SLIP-0039 and Vault. Both take secrets of the same length
+        #[arg(long, default_value_t = SssBackend::Sharks)]
+        sharing: SssBackend,
+        /// Standards-compatible shares (SLIP-0039) of a master secret in hex, which hardware
+        /// wallets can restore. They have their own encryption, so --security, --kdf, --cipher
+        /// and --sharing don't apply, and the label is only printed
+        #[arg(long)]
+        slip39: bool,
+        /// Reminder not to restore the backup before this date (UTC), shown when it is restored
+        /// earlier. It doesn't stop the restore
+        #[arg(long, value_name = "YYYY-MM-DD")]
+        not_before: Option<NaiveDate>,
+        /// Recommend verifying the backup this many years from now, printed on every shard
+        #[arg(long, value_name = "YEARS", value_parser = clap::value_parser!(u8).range(1..=50))]
+        verify_after: Option<u8>,
+        /// Directory the shard PDFs are written to
+        #[arg(long, default_value = ".")]
+        output_dir: PathBuf,
+        /// Image printed at the top of each shard
+        #[arg(long, value_name = "PATH")]
+        logo: Option<PathBuf>,
+        /// Colour of the text on each shard, as #rrggbb
+        #[arg(long, value_name = "HEX", value_parser = parse_hex_color)]
+        color: Option<[u8; 3]>,
+        /// Heading printed at the top of each shard
+        #[arg(long, default_value = "")]
+        title: String,
+        /// Validate the inputs and print what would be created, without deriving keys or
+        /// writing any files
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Restore a secret from previously exported shards
+    Restore {
+        /// Shard PDF or JSON files, images of their QR codes, or files containing their text payload
+        #[arg(required = true)]
+        shares: Vec<PathBuf>,
+        #[command(flatten)]
+        passphrase: PassphraseArgs,
+        /// The key file the backup was created with, if any
+        #[arg(long, value_name = "PATH")]
+        key_file: Option<PathBuf>,
+        /// The password of any exported shard bundles (.zip) among the shares
+        #[arg(long)]
+        bundle_password: Option<String>,
+    },
+    /// Check that shard PDFs hold intact shards, without restoring anything
+    #[cfg(feature = "pdf")]
+    VerifyPdf {
+        /// Shard PDFs, one shard each or several saved into one
+        #[arg(required = true)]
+        pdfs: Vec<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+pub struct PassphraseArgs {
+    /// The passphrase used to encrypt the secret. Prefer the other options, as this one ends up in
+    /// the shell history
+    #[arg(long)]
+    passphrase: Option<String>,
+    /// Read the passphrase from this environment variable
+    #[arg(long, value_name = "VAR_NAME")]
+    passphrase_env: Option<String>,
+    /// Read the passphrase from the first line of this file, or from stdin if it is `-`
+    #[arg(long, value_name = "PATH")]
+    passphrase_file: Option<PathBuf>,
+}
+
+impl PassphraseArgs {
+    fn uses_stdin(&self) -> bool {
+        self.passphrase_file.as_deref() == Some(Path::new("-"))
+    }
+
+    fn read(self) -> anyhow::Result<String> {
+        let passphrase = if let Some(passphrase) = self.passphrase {
+            passphrase
+        } else if let Some(name) = self.passphrase_env {
+            env::var(&name)
+                .map_err(|_| anyhow::anyhow!("Environment variable {} is not set", name))?
+        } else if let Some(path) = self.passphrase_file {
+            let contents = if path == Path::new("-") {
+                let mut contents = String::new();
+                io::stdin().read_to_string(&mut contents)?;
+                contents
+            } else {
+                warn_if_world_readable(&path)?;
+                fs::read_to_string(&path)?
+            };
+            contents.lines().next().unwrap_or_default().to_owned()
+        } else {
+            unreachable!("clap requires one of the passphrase options")
+        };
+
+        if passphrase.is_empty() {
+            anyhow::bail!("The passphrase is empty");
+        }
+        Ok(passphrase)
+    }
+}
+
+#[cfg(unix)]
+fn warn_if_world_readable(path: &Path) -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if fs::metadata(path)?.permissions().mode() & 0o004 != 0 {
+        eprintln!(
+            "Warning: {} is readable by all users, restrict it with chmod 600",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn warn_if_world_readable(_path: &Path) -> anyhow::Result<()> {
+    Ok(())
+}
+
+pub fn run(command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::Create {
+            secret,
+            allow_binary,
+            note,
+            passphrase,
+            key_file,
+            #[cfg(feature = "hardware-key")]
+            require_hardware_key,
+            shares,
+            threshold,
+            label,
+            private_label,
+            security,
+            kdf_rounds,
+            kdf,
+            argon2_memory,
+            argon2_iterations,
+            argon2_parallelism,
+            cipher,
+            sharing,
+            slip39,
+            not_before,
+            verify_after,
+            output_dir,
+            logo,
+            color,
+            title,
+            dry_run,
+        } => {
+            let qrcode_label = if private_label || slip39 {
+                ""
+            } else {
+                label.as_str()
+            };
+            if secret.is_none() && passphrase.uses_stdin() {
+                anyhow::bail!("The secret and the passphrase can't both be read from stdin");
+            }
+            let kdf = match (kdf_rounds, kdf) {
+                (Some(_), Some(KdfAlgorithm::Argon2id)) => {
+                    anyhow::bail!("--kdf-rounds only applies to PBKDF2, Argon2id takes --argon2-*")
+                }
+                (Some(rounds), _) => KdfParams::Pbkdf2 { rounds },
+                (None, Some(KdfAlgorithm::Argon2id)) => KdfParams::Argon2id {
+                    memory_mib: argon2_memory,
+                    iterations: argon2_iterations,
+                    parallelism: argon2_parallelism,
+                },
+                (None, _) => security.kdf_params().ok_or_else(|| {
+                    anyhow::anyhow!("The advanced security level needs --kdf-rounds or --kdf")
+                })?,
+            };
+            let passphrase = passphrase.read()?;
+            let key_file = key_file.as_deref().map(read_key_file).transpose()?;
+            let (value, encoding) = read_secret(secret, allow_binary)?;
+            if slip39 {
+                // Checked up front so a dry run catches it too
+                parse_master_secret(&value)?.zeroize();
+            }
+            #[cfg(feature = "hardware-key")]
+            if slip39 && require_hardware_key {
+                return Err(ConfigError::Slip39Unsupported("a hardware key").into());
+            }
+            let secret = Secret {
+                value: value.into(),
+                password: passphrase.into(),
+                encoding,
+                note: note.into(),
+            };
+            let mut builder = BackupConfig::builder()
+                .with_threshold(threshold)
+                .with_total(shares)
+                .with_label(qrcode_label)
+                .with_kdf(kdf)
+                .with_cipher(cipher)
+                .with_sharing(sharing)
+                .with_slip39(slip39);
+            if let Some(key_file) = key_file {
+                builder = builder.with_key_file(key_file);
+            }
+            if let Some(date) = not_before {
+                if date <= Utc::now().date_naive() {
+                    anyhow::bail!("The date given with --not-before has to lie in the future");
+                }
+                let midnight = date.and_hms_opt(0, 0, 0).expect("Midnight exists");
+                builder = builder.with_not_before(midnight.and_utc());
+            }
+            if let Some(years) = verify_after {
+                builder = builder.with_verify_after(years);
+            }
+            let config = validate_create(&secret, builder, qrcode_label, logo.as_deref())?;
+            if dry_run {
+                println!("Dry run, nothing was written.");
+                let secrets = slice::from_ref(&secret);
+                if should_compress(secrets) {
+                    println!(
+                        "Secret:     {} bytes ({}), compressed to {}",
+                        plaintext_len(&secret.value, encoding),
+                        encoding,
+                        stored_len(&secret, true)
+                    );
+                } else {
+                    println!(
+                        "Secret:     {} bytes ({})",
+                        plaintext_len(&secret.value, encoding),
+                        encoding
+                    );
+                }
+                println!(
+                    "Passphrase: strength {}/4",
+                    passphrase_score(&secret.password, &user_inputs(&secret, qrcode_label))
+                );
+                println!(
+                    "Shards:     {} of {} required to restore",
+                    threshold, shares
+                );
+                println!(
+                    "QR codes:   {} per shard",
+                    qrcode_count(max_share_len(
+                        slice::from_ref(&secret),
+                        qrcode_label,
+                        cipher
+                    ))
+                );
+                println!("Label:      {}", label);
+                if slip39 {
+                    println!("Format:     SLIP-0039, encrypted as the standard prescribes");
+                } else {
+                    println!("Cipher:     {}", cipher);
+                    println!("Sharing:    {}", sharing);
+                }
+                println!(
+                    "Key file:   {}",
+                    if key_file.is_some() {
+                        "required"
+                    } else {
+                        "none"
+                    }
+                );
+                #[cfg(feature = "hardware-key")]
+                if require_hardware_key {
+                    println!(
+                        "Hardware:   security key required, registered when writing the shards"
+                    );
+                }
+                if let Some(date) = not_before {
+                    println!(
+                        "Reminder:   not to be restored before {} (doesn't stop a restore)",
+                        date
+                    );
+                }
+                if let Some(years) = verify_after {
+                    println!("Verify:     recommended within {} years", years);
+                }
+                if !slip39 {
+                    println!(
+                        "KDF:        {}, about {:.1}s per key on this computer",
+                        kdf,
+                        kdf.estimate_duration().as_secs_f64()
+                    );
+                }
+                for number in 1..=shares {
+                    let file = output_dir.join(format!("shard-{}-of-{}.pdf", number, shares));
+                    println!("Would write {}", file.display());
+                }
+                return Ok(());
+            }
+            // Only once everything else checked out, as registering takes a touch of the key
+            #[cfg(feature = "hardware-key")]
+            let hardware_key = match require_hardware_key {
+                true => {
+                    eprintln!("Touch your security key twice when it blinks, to register it");
+                    Some(register_hardware_key()?)
+                }
+                false => None,
+            };
+            #[cfg(feature = "hardware-key")]
+            let config = match &hardware_key {
+                Some((challenge, secret)) => {
+                    builder.with_hardware_key(challenge, *secret).build()?
+                }
+                None => config,
+            };
+            let theme = PdfTheme {
+                logo_path: logo,
+                primary_color: color.unwrap_or_default(),
+                title,
+            };
+            create(secret, config, &label, output_dir, &theme)
+        }
+        Command::Restore {
+            shares,
+            passphrase,
+            key_file,
+            bundle_password,
+        } => {
+            let passphrase = passphrase.read()?;
+            let key_file = key_file.as_deref().map(read_key_file).transpose()?;
+            restore(
+                &shares,
+                &passphrase,
+                key_file.as_ref(),
+                bundle_password.as_deref(),
+            )
+        }
+        #[cfg(feature = "pdf")]
+        Command::VerifyPdf { pdfs } => verify_pdfs(&pdfs),
+    }
+}
+
+/// Reports every shard in the PDFs, failing if any of them is damaged or unreadable. The check
+/// catches damage, but a forged shard can pass it: only a restore proves the shards genuine.
+#[cfg(feature = "pdf")]
+fn verify_pdfs(pdfs: &[PathBuf]) -> anyhow::Result<()> {
+    let mut failed = 0;
+    for pdf in pdfs {
+        match parse_shares_from_pdf(pdf) {
+            Ok(shards) => {
+                for shard in shards {
+                    println!(
+                        "{}: shard #{} of backup {:016x} is intact, fingerprint {}",
+                        pdf.display(),
+                        shard.number,
+                        shard.backup_id,
+                        shard.display_fingerprint()
+                    );
+                }
+            }
+            Err(error) => {
+                eprintln!("{}: {}", pdf.display(), error);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{} of {} PDF(s) failed the check", failed, pdfs.len());
+    }
+    Ok(())
+}
+
+fn read_secret(
+    secret: Option<String>,
+    allow_binary: bool,
+) -> anyhow::Result<(String, SecretEncoding)> {
+    let data = match secret {
+        Some(secret) => secret.into_bytes(),
+        None => {
+            let mut data = Vec::new();
+            io::stdin().read_to_end(&mut data)?;
+            // Drop the line break added by echo and most editors
+            if data.ends_with(b"\n") {
+                data.pop();
+                if data.ends_with(b"\r") {
+                    data.pop();
+                }
+            }
+            data
+        }
+    };
+
+    match String::from_utf8(data) {
+        Ok(text) if !has_control_chars(&text) => Ok((text, SecretEncoding::Text)),
+        Ok(text) if allow_binary => Ok((base64::encode(text), SecretEncoding::Base64)),
+        Err(error) if allow_binary => {
+            Ok((base64::encode(error.into_bytes()), SecretEncoding::Base64))
+        }
+        Ok(_) => anyhow::bail!(
+            "The secret contains control characters, pass --allow-binary to back it up anyway"
+        ),
+        Err(_) => {
+            anyhow::bail!("The secret is not valid UTF-8, pass --allow-binary to back it up anyway")
+        }
+    }
+}
+
+fn read_key_file(path: &Path) -> anyhow::Result<KeyFileDigest> {
+    let data = fs::read(path)
+        .map_err(|e| anyhow::anyhow!("Can't read the key file {}: {}", path.display(), e))?;
+    if data.is_empty() {
+        anyhow::bail!("The key file {} is empty", path.display());
+    }
+    Ok(key_file_digest(&data))
+}
+
+/// Checks everything a backup needs before any key derivation, so `--dry-run` fails exactly
+/// when a real run would.
+fn validate_create(
+    secret: &Secret,
+    builder: BackupConfigBuilder,
+    label: &str,
+    logo: Option<&Path>,
+) -> anyhow::Result<BackupConfig> {
+    let config = builder.build()?;
+    if secret.value.is_empty() {
+        anyhow::bail!("The secret is empty");
+    }
+    let len = plaintext_len(&secret.value, secret.encoding);
+    if len > MAX_SECRET_LEN {
+        anyhow::bail!(
+            "The secret is too long ({} bytes, at most {} are supported)",
+            len,
+            MAX_SECRET_LEN
+        );
+    }
+    // Compressible secrets may be longer than what fits uncompressed
+    let stored_len = stored_len(secret, should_compress(slice::from_ref(secret)));
+    let max_len = max_secret_len(&config);
+    if stored_len > max_len {
+        anyhow::bail!(
+            "The secret is too long ({} bytes, at most {} fit in a backup)",
+            stored_len,
+            max_len
+        );
+    }
+    let score = passphrase_score(&secret.password, &user_inputs(secret, label));
+    if score < MIN_SCORE {
+        anyhow::bail!(
+            "The passphrase is too weak (strength {}/4, at least {} is required)",
+            score,
+            MIN_SCORE
+        );
+    }
+    if let Some(logo) = logo {
+        if !logo.is_file() {
+            anyhow::bail!("The logo {} does not exist", logo.display());
+        }
+    }
+    Ok(config)
+}
+
+/// Things an attacker may know, which make a passphrase weaker if it contains them.
+fn user_inputs<'a>(secret: &'a Secret, label: &'a str) -> Vec<&'a str> {
+    let mut inputs = vec![label];
+    if secret.encoding == SecretEncoding::Text {
+        inputs.push(&secret.value);
+    }
+    inputs
+}
+
+fn create(
+    secret: Secret,
+    config: BackupConfig,
+    label: &str,
+    output_dir: PathBuf,
+    theme: &PdfTheme,
+) -> anyhow::Result<()> {
+    let shards = create_backup(vec![secret], config)?;
+
+    fs::create_dir_all(&output_dir)?;
+    for shard in &shards {
+        let file = output_dir.join(format!("shard-{}-of-{}.pdf", shard.number, shards.len()));
+        print_pdf(shard, label, shards.len(), theme)?.render_to_file(&file)?;
+        println!(
+            "Wrote {} (fingerprint {})",
+            file.display(),
+            shard.display_fingerprint()
+        );
+    }
+
+    Ok(())
+}
+
+fn restore(
+    paths: &[PathBuf],
+    passphrase: &str,
+    key_file: Option<&KeyFileDigest>,
+    bundle_password: Option<&str>,
+) -> anyhow::Result<()> {
+    let shares = read_shares(paths, bundle_password)?;
+    #[cfg(feature = "hardware-key")]
+    if shares
+        .iter()
+        .filter_map(|share| BackupShard::from_bytes(share.clone()).ok())
+        .any(|shard| shard.hardware_key().is_some())
+    {
+        eprintln!("Touch the security key of this backup when it blinks");
+    }
+    let restored = restore_backup(shares, passphrase, key_file)?;
+    // Keep stdout to the secret alone, so it can be piped
+    if let Some(metadata) = &restored.metadata {
+        eprintln!("{}", metadata.summary(restored.threshold));
+        if let Some(verify_by) = metadata.verification_due(Utc::now().date_naive()) {
+            eprintln!(
+                "This backup was due to be verified by {}, check that every shard is still readable",
+                verify_by.format("%Y-%m")
+            );
+        }
+        if let Some(not_before) = metadata.restored_early(Utc::now()) {
+            eprintln!(
+                "This backup was marked not to be restored before {}",
+                not_before.format("%Y-%m-%d")
+            );
+        }
+    }
+    if !restored.secret.note.is_empty() {
+        eprintln!("Note: {}", &*restored.secret.note);
+    }
+    println!("{}", &*restored.secret.value);
+    Ok(())
+}
+
+/// Reads the shares of all files, joining split shards whose parts are spread over several.
+fn read_shares(paths: &[PathBuf], bundle_password: Option<&str>) -> anyhow::Result<Vec<Vec<u8>>> {
+    let mut shares = Vec::new();
+    for path in paths {
+        let codes = match path.extension().and_then(|e| e.to_str()) {
+            #[cfg(feature = "pdf")]
+            Some(extension) if extension.eq_ignore_ascii_case("zip") => {
+                let password = bundle_password.ok_or_else(|| {
+                    anyhow::anyhow!("{} is a bundle, pass --bundle-password", path.display())
+                })?;
+                import_bundle(path, password)?
+            }
+            _ => import_shard_file(path)?,
+        };
+        shares.extend(codes);
+    }
+    Ok(reassemble_fragments(shares)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use hyperbacked_core::{
+        backup::{create_backup, restore_backup, BackupConfig},
+        crypto::{KdfParams, Secret, SecretEncoding},
+        qrcode::{share_payload, split_share},
+    };
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::read_shares;
+
+    #[test]
+    fn split_shards_are_joined_across_files() {
+        // Random data doesn't compress, so the shards need several QR codes
+        let mut data = vec![0u8; 3000];
+        StdRng::seed_from_u64(7).fill(&mut data[..]);
+        let value = base64::encode(&data);
+        let secrets = vec![Secret {
+            value: value.clone().into(),
+            password: "correct horse battery staple".into(),
+            encoding: SecretEncoding::Base64,
+            note: Default::default(),
+        }];
+        let config = BackupConfig::builder()
+            .with_threshold(2)
+            .with_total(3)
+            .with_kdf(KdfParams::Pbkdf2 { rounds: 1_000 })
+            .build()
+            .unwrap();
+        let shards = create_backup(secrets, config).unwrap();
+        let first = split_share(&shards[0].to_bytes()).unwrap();
+        let second = split_share(&shards[1].to_bytes()).unwrap();
+        assert!(first.len() > 1);
+
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, parts: &[Vec<u8>]| {
+            let path = dir.path().join(name);
+            let payloads = parts.iter().map(|part| share_payload(part));
+            fs::write(&path, payloads.collect::<Vec<String>>().join("\n\n")).unwrap();
+            path
+        };
+        let paths = [
+            write("first-a.txt", &first[..1]),
+            write("first-b.txt", &first[1..]),
+            write("second.txt", &second),
+        ];
+
+        let shares = read_shares(&paths, None).unwrap();
+        assert_eq!(shares.len(), 2);
+        let restored = restore_backup(shares, "correct horse battery staple", None).unwrap();
+        assert_eq!(&*restored.secret.value, value);
+        assert!(read_shares(&paths[..1], None).is_err());
+    }
+}
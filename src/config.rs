@@ -0,0 +1,210 @@
+use std::{fs, path::PathBuf};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use hyperbacked_core::printer::PdfTheme;
+
+use crate::{audit::default_audit_log_path, gui::BackupType};
+
+/// Non-sensitive workflow preferences. Secrets and passphrases must never be stored here.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub label: String,
+    pub output_dir: Option<PathBuf>,
+    pub audit_log: Option<PathBuf>,
+    pub dark_mode: bool,
+    /// Seconds before a copied secret is cleared from the clipboard
+    pub clipboard_clear_secs: u64,
+    /// Days before a tracked backup expires that the renewal reminder shows up
+    pub reminder_days: u32,
+    /// Lowest zxcvbn score (out of 4) accepted without confirming the passphrase is weak
+    pub min_passphrase_score: u8,
+    /// Leave the label out of the shards, so only the printed text shows it
+    pub hide_label_in_qr: bool,
+    /// Number of words in generated passphrases
+    pub passphrase_words: u8,
+    // Serialized as TOML tables, so these have to come after all plain values
+    pub backup_type: BackupType,
+    pub pdf_theme: PdfTheme,
+    pub accessibility: AccessibilityConfig,
+    pub tracked_backups: Vec<TrackedBackup>,
+}
+
+pub const MIN_FONT_SCALE: f32 = 1.0;
+pub const MAX_FONT_SCALE: f32 = 2.0;
+
+/// Display preferences for users with visual impairments.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessibilityConfig {
+    /// Pure white text on pure black, regardless of dark mode
+    pub high_contrast: bool,
+    /// Multiplies every text size of the interface
+    pub font_scale: f32,
+}
+
+impl Default for AccessibilityConfig {
+    fn default() -> Self {
+        Self {
+            high_contrast: false,
+            font_scale: MIN_FONT_SCALE,
+        }
+    }
+}
+
+impl AccessibilityConfig {
+    pub fn scale(&self) -> f32 {
+        self.font_scale.clamp(MIN_FONT_SCALE, MAX_FONT_SCALE)
+    }
+
+    pub fn text_size(&self, size: u16) -> u16 {
+        (size as f32 * self.scale()).round() as u16
+    }
+}
+
+/// A previously created backup with an expiry date, remembered for renewal reminders.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TrackedBackup {
+    pub label: String,
+    pub backup_id: String,
+    pub expires_at: NaiveDate,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            label: String::new(),
+            output_dir: None,
+            audit_log: None,
+            dark_mode: true,
+            clipboard_clear_secs: 30,
+            reminder_days: 30,
+            min_passphrase_score: 3,
+            hide_label_in_qr: false,
+            passphrase_words: 6,
+            backup_type: BackupType::default(),
+            pdf_theme: PdfTheme::default(),
+            accessibility: AccessibilityConfig::default(),
+            tracked_backups: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> Self {
+        config_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn audit_log_path(&self) -> Option<PathBuf> {
+        self.audit_log.clone().or_else(default_audit_log_path)
+    }
+
+    pub fn due_reminders(&self, today: NaiveDate) -> Vec<TrackedBackup> {
+        self.tracked_backups
+            .iter()
+            .filter(|backup| (backup.expires_at - today).num_days() <= self.reminder_days as i64)
+            .cloned()
+            .collect()
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = config_path().ok_or_else(|| anyhow::anyhow!("No config directory found"))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+fn config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("hyperbacked").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use chrono::NaiveDate;
+
+    use super::{AccessibilityConfig, Config, TrackedBackup};
+    use hyperbacked_core::printer::PdfTheme;
+
+    use crate::gui::BackupType;
+
+    #[test]
+    fn toml_round_trip() {
+        let config = Config {
+            label: String::from("My backup"),
+            output_dir: Some(PathBuf::from("/tmp/backups")),
+            audit_log: None,
+            dark_mode: false,
+            clipboard_clear_secs: 60,
+            reminder_days: 14,
+            min_passphrase_score: 4,
+            hide_label_in_qr: true,
+            passphrase_words: 8,
+            backup_type: BackupType::Custom { min: 5, max: 9 },
+            pdf_theme: PdfTheme {
+                logo_path: Some(PathBuf::from("/tmp/logo.png")),
+                primary_color: [0, 90, 200],
+                title: String::from("ACME Corp"),
+            },
+            accessibility: AccessibilityConfig {
+                high_contrast: true,
+                font_scale: 1.5,
+            },
+            tracked_backups: vec![TrackedBackup {
+                label: String::from("My backup"),
+                backup_id: String::from("0123456789abcdef"),
+                expires_at: NaiveDate::from_ymd_opt(2030, 1, 31).unwrap(),
+            }],
+        };
+
+        let serialized = toml::to_string(&config).expect("Failed to serialize");
+        let deserialized: Config = toml::from_str(&serialized).expect("Failed to deserialize");
+        assert_eq!(deserialized, config);
+    }
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let config: Config = toml::from_str("label = \"Old\"").expect("Failed to deserialize");
+        assert_eq!(config.label, "Old");
+        assert_eq!(config.backup_type, BackupType::Standard);
+        assert_eq!(config.output_dir, None);
+        assert!(config.dark_mode);
+        assert_eq!(config.passphrase_words, 6);
+        assert_eq!(config.clipboard_clear_secs, 30);
+        assert_eq!(config.pdf_theme, PdfTheme::default());
+    }
+
+    #[test]
+    fn due_reminders_within_threshold() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let tracked = |label: &str, days: i64| TrackedBackup {
+            label: label.to_owned(),
+            backup_id: String::new(),
+            expires_at: today + chrono::Duration::days(days),
+        };
+        let config = Config {
+            tracked_backups: vec![
+                tracked("expired", -3),
+                tracked("soon", 30),
+                tracked("later", 31),
+            ],
+            ..Config::default()
+        };
+
+        let labels = config
+            .due_reminders(today)
+            .into_iter()
+            .map(|backup| backup.label)
+            .collect::<Vec<String>>();
+        assert_eq!(labels, vec!["expired", "soon"]);
+    }
+}
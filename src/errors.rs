@@ -1,33 +1,18 @@
 use std::{error, fmt};
 
 #[derive(Debug)]
-pub enum BackupError {
-    SharksError(String),
+pub enum BundleError {
+    UnsupportedVersion(u8),
 }
 
-impl fmt::Display for BackupError {
+impl fmt::Display for BundleError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self {
-            BackupError::SharksError(message) => write!(f, "SharksError: {}", message),
-        }
-    }
-}
-
-impl error::Error for BackupError {}
-
-#[derive(Debug)]
-pub enum CryptoError {
-    InvalidNumberOfHeaders(usize),
-}
-
-impl fmt::Display for CryptoError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match &self {
-            CryptoError::InvalidNumberOfHeaders(num) => {
-                write!(f, "Invalid number of headers in ciphertext: {}", num)
+            BundleError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported bundle version: {}", version)
             }
         }
     }
 }
 
-impl error::Error for CryptoError {}
+impl error::Error for BundleError {}
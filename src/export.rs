@@ -0,0 +1,209 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use hyperbacked_core::{
+    backup::BackupShard,
+    crypto::{DecryptedSecret, SecretEncoding},
+    qrcode::{parse_share_payload, qrcode_read_file, reassemble_file_fragments},
+};
+#[cfg(feature = "pdf")]
+use hyperbacked_core::{errors::BackupError, qrcode::qrcode_read_pdf_data};
+
+#[cfg(feature = "pdf")]
+use crate::bundle::{open_bundle, read_manifest};
+
+#[derive(Serialize, Deserialize)]
+struct ShardFile {
+    label: String,
+    total_shards: usize,
+    #[serde(flatten)]
+    shard: BackupShard,
+}
+
+pub fn export_json(
+    shard: &BackupShard,
+    backup_name: &str,
+    total_shards: usize,
+) -> anyhow::Result<String> {
+    let file = ShardFile {
+        label: backup_name.to_owned(),
+        total_shards,
+        shard: shard.clone(),
+    };
+    Ok(serde_json::to_string_pretty(&file)?)
+}
+
+pub fn import_json(json: &str) -> anyhow::Result<BackupShard> {
+    let file: ShardFile = serde_json::from_str(json)?;
+    Ok(file.shard)
+}
+
+pub fn import_shard_file(path: &Path) -> anyhow::Result<Vec<Vec<u8>>> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+
+    match extension.as_deref() {
+        Some("png") | Some("jpg") | Some("jpeg") | Some("pdf") => qrcode_read_file(path),
+        Some("json") => Ok(vec![import_json(&fs::read_to_string(path)?)?.to_bytes()]),
+        _ => {
+            // The parts of a split shard are separated by blank lines
+            let text = fs::read_to_string(path)?.replace("\r\n", "\n");
+            let codes = text
+                .split("\n\n")
+                .filter(|payload| !payload.trim().is_empty())
+                .map(|payload| {
+                    parse_share_payload(payload).map_err(|error| {
+                        anyhow::anyhow!("{} is not a valid backup shard: {}", path.display(), error)
+                    })
+                })
+                .collect::<anyhow::Result<Vec<Vec<u8>>>>()?;
+            Ok(reassemble_file_fragments(codes)?)
+        }
+    }
+}
+
+/// Lists the images and PDFs directly inside a folder, for importing a batch of shard photos.
+pub fn list_shard_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+        if path.is_file() && matches!(extension.as_deref(), Some("png" | "jpg" | "jpeg" | "pdf")) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Decrypts the shard PDFs in a bundle and reads their QR codes.
+#[cfg(feature = "pdf")]
+pub fn import_bundle(path: &Path, password: &str) -> anyhow::Result<Vec<Vec<u8>>> {
+    // The manifest is readable without the password, so an incomplete bundle fails early
+    let manifest = read_manifest(path)?;
+    if manifest.files.len() < manifest.threshold as usize {
+        return Err(BackupError::NotEnoughShards {
+            provided: manifest.files.len(),
+            required: manifest.threshold as usize,
+        }
+        .into());
+    }
+
+    let mut codes = Vec::new();
+    for pdf in open_bundle(path, password)? {
+        codes.extend(qrcode_read_pdf_data(&pdf)?);
+    }
+    Ok(codes)
+}
+
+pub fn export_secret(
+    secret: &DecryptedSecret,
+    path: &Path,
+    trailing_newline: bool,
+) -> anyhow::Result<()> {
+    let mut data = secret.to_bytes()?;
+    if trailing_newline && secret.encoding == SecretEncoding::Text {
+        data.push(b'\n');
+    }
+    write_atomic(path, &data)
+}
+
+// Writes next to the destination first so a failed write never leaves a truncated file behind
+fn write_atomic(path: &Path, data: &[u8]) -> anyhow::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("{} is not a file path", path.display()))?;
+    let mut temp_name = file_name.to_owned();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = fs::write(&temp_path, data).and_then(|_| fs::rename(&temp_path, path));
+    if result.is_err() {
+        fs::remove_file(&temp_path).ok();
+    }
+    Ok(result?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{export_json, export_secret, import_json, list_shard_files};
+    use hyperbacked_core::{
+        backup::BackupShard,
+        crypto::{DecryptedSecret, SecretEncoding, SecretString},
+    };
+
+    #[test]
+    fn json_round_trip() {
+        let shard = BackupShard {
+            number: 2,
+            threshold: 2,
+            backup_id: 0x1234_5678_9abc_def0,
+            data: vec![2u8, 7, 1, 8, 2, 8],
+            expires_at: None,
+            metadata: None,
+            slip39: None,
+        };
+
+        let json = export_json(&shard, "My backup", 3).expect("Failed to export");
+        let imported = import_json(&json).expect("Failed to import");
+
+        assert_eq!(imported.number, shard.number);
+        assert_eq!(imported.backup_id, shard.backup_id);
+        assert_eq!(imported.data, shard.data);
+    }
+
+    #[test]
+    fn export_secret_writes_raw_bytes() {
+        let dir = std::env::temp_dir();
+        let text_path = dir.join("hyperbacked_export_secret.txt");
+        let binary_path = dir.join("hyperbacked_export_secret.bin");
+
+        let text = DecryptedSecret {
+            value: SecretString::from("correct horse"),
+            encoding: SecretEncoding::Text,
+            note: SecretString::default(),
+        };
+        export_secret(&text, &text_path, true).expect("Failed to save text secret");
+        assert_eq!(fs::read(&text_path).unwrap(), b"correct horse\n");
+
+        let binary = DecryptedSecret {
+            value: SecretString::from(base64::encode([0u8, 159, 146, 150, 255])),
+            encoding: SecretEncoding::Base64,
+            note: SecretString::default(),
+        };
+        export_secret(&binary, &binary_path, true).expect("Failed to save binary secret");
+        assert_eq!(fs::read(&binary_path).unwrap(), [0u8, 159, 146, 150, 255]);
+
+        fs::remove_file(text_path).ok();
+        fs::remove_file(binary_path).ok();
+    }
+
+    #[test]
+    fn list_shard_files_filters_extensions() {
+        let dir = std::env::temp_dir().join("hyperbacked_list_shard_files");
+        fs::create_dir_all(dir.join("nested.png")).unwrap();
+        for name in ["b.JPG", "a.png", "c.pdf", "notes.txt", "shard.json"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+
+        let files = list_shard_files(&dir).expect("Failed to list folder");
+        let names = files
+            .iter()
+            .map(|file| file.file_name().unwrap().to_str().unwrap())
+            .collect::<Vec<&str>>();
+        assert_eq!(names, vec!["a.png", "b.JPG", "c.pdf"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,136 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use rand::{thread_rng, RngCore};
+
+const OVERWRITE_CHUNK_LEN: usize = 64 * 1024;
+
+/// Overwrites the file with random bytes, then zeros, and deletes it. If the file can't be
+/// overwritten in place, `shred` on Linux and `srm` on macOS get a try before giving up.
+pub fn secure_delete(path: &Path) -> anyhow::Result<()> {
+    // Overwriting a link would hit whatever it points to, not the link itself
+    if !fs::symlink_metadata(path)?.is_file() {
+        anyhow::bail!("{} is not a regular file", path.display());
+    }
+    if is_solid_state(path) == Some(true) {
+        eprintln!(
+            "{} is on a solid state drive, which may keep copies of the overwritten data",
+            path.display()
+        );
+    }
+
+    if let Err(error) = overwrite(path) {
+        return delete_with_tool(path).map_err(|_| error);
+    }
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+fn overwrite(path: &Path) -> anyhow::Result<()> {
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    let len = file.metadata()?.len();
+    let mut rng = thread_rng();
+    write_pass(&mut file, len, |chunk| rng.fill_bytes(chunk))?;
+    write_pass(&mut file, len, |chunk| chunk.fill(0))?;
+    Ok(())
+}
+
+fn write_pass(file: &mut File, len: u64, mut fill: impl FnMut(&mut [u8])) -> anyhow::Result<()> {
+    file.seek(SeekFrom::Start(0))?;
+    let mut chunk = vec![0u8; OVERWRITE_CHUNK_LEN];
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk_len = remaining.min(OVERWRITE_CHUNK_LEN as u64) as usize;
+        fill(&mut chunk[..chunk_len]);
+        file.write_all(&chunk[..chunk_len])?;
+        remaining -= chunk_len as u64;
+    }
+    // Each pass has to reach the disk, not just the page cache
+    file.sync_all()?;
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn delete_with_tool(path: &Path) -> anyhow::Result<()> {
+    run_tool(
+        std::process::Command::new("shred")
+            .args(["-z", "-u"])
+            .arg(path),
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn delete_with_tool(path: &Path) -> anyhow::Result<()> {
+    run_tool(std::process::Command::new("srm").arg(path))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn delete_with_tool(path: &Path) -> anyhow::Result<()> {
+    anyhow::bail!("No tool to securely delete {}", path.display())
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn run_tool(command: &mut std::process::Command) -> anyhow::Result<()> {
+    let status = command.status()?;
+    if !status.success() {
+        anyhow::bail!("{:?} failed with {}", command.get_program(), status);
+    }
+    Ok(())
+}
+
+/// Whether the file lies on a drive that doesn't spin, as far as the kernel knows.
+#[cfg(target_os = "linux")]
+fn is_solid_state(path: &Path) -> Option<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let dev = fs::metadata(path).ok()?.dev();
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+    let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+    let device = fs::canonicalize(format!("/sys/dev/block/{}:{}", major, minor)).ok()?;
+    // Partitions keep the queue settings of their disk in the parent directory
+    [device.join("queue"), device.parent()?.join("queue")]
+        .iter()
+        .find_map(|queue| fs::read_to_string(queue.join("rotational")).ok())
+        .map(|rotational| rotational.trim() == "0")
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_solid_state(_path: &Path) -> Option<bool> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{overwrite, secure_delete};
+
+    #[test]
+    fn overwrite_keeps_length_and_ends_with_zeros() {
+        let path = std::env::temp_dir().join("hyperbacked-overwrite-test.pdf");
+        let data = (0..200_000)
+            .map(|i| (i % 251) as u8 + 1)
+            .collect::<Vec<u8>>();
+        fs::write(&path, &data).unwrap();
+
+        overwrite(&path).expect("Failed to overwrite");
+        let overwritten = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(overwritten.len(), data.len());
+        assert!(overwritten.iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn secure_delete_removes_file() {
+        let path = std::env::temp_dir().join("hyperbacked-secure-delete-test.pdf");
+        fs::write(&path, b"shard").unwrap();
+        secure_delete(&path).expect("Failed to delete");
+        assert!(!path.exists());
+
+        assert!(secure_delete(&path).is_err());
+        assert!(secure_delete(&std::env::temp_dir()).is_err());
+    }
+}
@@ -1,37 +1,230 @@
-use std::fmt::Display;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
 
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use futures_timer::Delay;
 use iced::{
     alignment::Horizontal,
-    executor,
+    clipboard, event, executor,
+    futures::{channel::mpsc as async_mpsc, StreamExt},
+    keyboard, subscription,
     theme::{self, Palette},
     widget::{
-        button, column, container, horizontal_space, pick_list, row, scrollable, text, text_input,
-        vertical_space,
+        self, button, checkbox, column, container, horizontal_space, image, pick_list,
+        progress_bar, row, scrollable, text, text_input, vertical_space,
     },
-    Alignment, Application, Color, Command, Element, Length, Theme,
+    window, Alignment, Application, Color, Command, Element, Event, Length, Subscription, Theme,
 };
 
 use rfd::FileDialog;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use hyperbacked_core::{
+    backup::{
+        create_backup, create_backup_deterministic, create_backup_stream, decrypt_backup_stream,
+        diagnose_shards, extend_backup_from, is_stream_key, max_secret_len, restore_backup,
+        shard_total, share_len, unlock_password, verify_backup, BackupConfig, BackupShard,
+        Diagnosis, EncodedShare, RecoverySeed, RestoredContext, RestoredSecret, SssBackend,
+        MAX_DIAGNOSE_SHARDS,
+    },
+    crypto::{
+        fingerprint, gen_secret, has_control_chars, key_file_digest, plaintext_len,
+        should_compress, stored_len, CipherSuite, DecryptedSecret, KdfAlgorithm, KdfParams,
+        KeyFileDigest, Secret, SecretEncoding, SecretFormat, SecretString, SecurityLevel,
+        MAX_KDF_ROUNDS, MAX_SECRETS, MAX_SECRET_LEN, MIN_KDF_ROUNDS,
+    },
+    errors::{BackupError, ConfigError, FragmentError, PayloadKind, RestoreError},
+    mnemonic::{decode_words, looks_like_words, WORDS_PER_BLOCK},
+    passphrase::{gen_passphrase, passphrase_strength, PassphraseStrength, MAX_WORDS, MIN_WORDS},
+    printer::{format_hex_color, parse_hex_color, print_merged_pdf, print_pdf, PdfTheme},
+    qrcode::{
+        classify_payload, fragment_part, parse_share_payload, qrcode_count, qrcode_encode,
+        qrcode_read_file, qrcode_scan, reassemble_fragments, split_share,
+    },
+    slip39::looks_like_mnemonic,
+};
 
 use crate::{
-    backup::{create_backup, recover_backup, BackupConfig, BackupShard},
-    crypto::Secret,
-    passphrase::gen_passphrase,
-    printer::print_pdf,
-    qrcode::qrcode_scan,
+    audit::{append_audit_log, default_audit_log_path, AuditEvent},
+    bundle::save_bundle,
+    config::{AccessibilityConfig, Config, TrackedBackup, MAX_FONT_SCALE, MIN_FONT_SCALE},
+    export::{export_json, export_secret, import_shard_file, list_shard_files},
+    fileops::secure_delete,
+    history::{BackupHistory, BackupRecord},
+    storage::{default_history_path, load_history, save_history, HistoryEntry},
 };
 
+#[cfg(any(unix, windows))]
+use hyperbacked_core::printer::print_to_system_printer;
+#[cfg(feature = "tray")]
+use std::collections::HashMap;
+#[cfg(feature = "tray")]
+use tray_icon::TrayIcon;
+
+#[cfg(feature = "tray")]
+use crate::tray::{create_tray_icon, notify_expiring, tray_events, TrayEvent};
+
+const MAX_SHARDS: u8 = 16;
+const MAX_DECRYPT_HINT_FAILURES: usize = 3;
+const QRCODE_PREVIEW_SIZE: usize = 160;
+
+// Text sizes before the font scale of the accessibility settings is applied
+const HERO_TEXT_SIZE: u16 = 50;
+const TITLE_TEXT_SIZE: u16 = 30;
+const HEADING_TEXT_SIZE: u16 = 24;
+/// The default of iced, used for all text without a size of its own
+pub const BODY_TEXT_SIZE: u16 = 20;
+const SMALL_TEXT_SIZE: u16 = 16;
+const CAPTION_TEXT_SIZE: u16 = 14;
+const FONT_SCALE_STEP: f32 = 0.25;
+#[cfg(feature = "tray")]
+const REMINDER_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How the app was started.
+#[derive(Debug, Default)]
+pub struct Flags {
+    /// Starts hidden in the system tray, which closing the window returns to
+    #[cfg(feature = "tray")]
+    pub tray: bool,
+}
+
 pub struct HyperbackedApp {
     page: AppPage,
-    secret: String,
-    passphrase: String,
+    // Where closing the settings returns to
+    page_before_settings: Option<AppPage>,
+    secret: SecretString,
+    secret_encoding: SecretEncoding,
+    secret_has_control_chars: bool,
+    secret_format: SecretFormat,
+    secret_strength: usize,
+    secret_generated: bool,
+    // Set while the secret comes from a file, whose content is never shown
+    secret_file: Option<SecretFile>,
+    secret_file_error: Option<String>,
+    // Encrypted with the secret, but shown apart from it after restoring
+    secret_note: SecretString,
+    stored_sizes: StoredSizes,
+    // A file too large for the shards, which is encrypted to a separate file instead
+    stream_file: Option<StreamFile>,
+    // Set while the stream file is encrypted, along with how many bytes are done
+    streaming: Option<StreamJob>,
+    decrypting_stream: Option<DecryptJob>,
+    stream_progress: u64,
+    stream_output: Option<PathBuf>,
+    passphrase: SecretString,
+    passphrase_confirm: SecretString,
+    // Confirmed for the current passphrase only, so it is cleared whenever that changes
+    passphrase_written_down: bool,
+    passphrase_words: u8,
+    passphrase_separator: PassphraseSeparator,
+    passphrase_strength: Option<PassphraseStrength>,
+    min_passphrase_score: u8,
+    // Like passphrase_written_down, this only applies to the current passphrase
+    allow_weak_passphrase: bool,
+    key_file: Option<KeyFile>,
+    key_file_error: Option<String>,
     label: String,
+    hide_label_in_qr: bool,
+    expiry: String,
+    not_before: String,
+    verify_after: String,
+    additional_secrets: Vec<SecretEntry>,
     backup_type: BackupType,
+    security_level: SecurityLevel,
+    cipher: CipherSuite,
+    sharing: SssBackend,
+    slip39: bool,
+    // Only used by the advanced security level
+    kdf_algorithm: KdfAlgorithm,
+    kdf_rounds_input: String,
+    argon2_memory_input: String,
+    argon2_iterations_input: String,
+    argon2_parallelism_input: String,
+    // How long deriving a single key takes with the chosen work factor
+    kdf_estimate: Option<Duration>,
+    output_dir: Option<PathBuf>,
+    audit_log: Option<PathBuf>,
+    // Derive the shards from a recovery seed, which is shown once with them
+    reproducible: bool,
+    recovery_seed: Option<RecoverySeed>,
+    // Set while the create page reprints a lost shard instead of creating a backup
+    reprint: Option<ReprintState>,
     generated_backup: Option<Vec<BackupShard>>,
+    spinner_frame: usize,
+    pdf_theme: PdfTheme,
+    // Kept separately so a half-typed colour doesn't reset the theme
+    pdf_color_input: String,
+    failure_reason: Option<BackupError>,
+    verify_passphrase: SecretString,
+    bundle_password: String,
+    verify_result: Option<Result<(), String>>,
+    verifying: bool,
+    shard_qrcodes: Vec<Option<image::Handle>>,
+    saved_shares: HashSet<usize>,
+    // Shards sent to the printer that it hasn't accepted yet
+    #[cfg(any(unix, windows))]
+    printing: HashSet<usize>,
+    // Shard whose BIP-39 words are shown below it
+    shown_words: Option<usize>,
+    print_error: Option<String>,
+    unsaved_warning: bool,
     should_exit: bool,
-    is_scanning: bool,
-    scanned_codes: Vec<Vec<u8>>,
-    decoded_secret: String,
+    restore: RestoreState,
+    confirm_exit: bool,
+    decoded_secret: Option<DecryptedSecret>,
+    decoded_shards: Vec<usize>,
+    decode_notice: Option<String>,
+    file_hovered: bool,
+    decoded_backup_id: Option<u64>,
+    decoded_summary: Option<String>,
+    decoded_verify_by: Option<NaiveDate>,
+    // The not-before date of the restored backup, if it was restored before it
+    decoded_not_before: Option<DateTime<Utc>>,
+    history_path: Option<PathBuf>,
+    history: Vec<HistoryEntry>,
+    backup_history: BackupHistory,
+    history_label: String,
+    // The history entry recorded for the current restore, if the user opted in
+    recorded_entry: Option<u64>,
+    // The backup recorded in the backup history, if the user opted in
+    recorded_backup: Option<String>,
+    backup_history_error: Option<String>,
+    secret_visible: bool,
+    clear_clipboard: bool,
+    clipboard_timer: u64,
+    clipboard_clear_secs: u64,
+    // Seconds left until the clipboard is cleared, and what was copied into it
+    clipboard_remaining: Option<u64>,
+    clipboard_copied: Option<SecretString>,
+    dark_mode: bool,
+    accessibility: AccessibilityConfig,
+    reminder_days: u32,
+    tracked_backups: Vec<TrackedBackup>,
+    reminders: Vec<TrackedBackup>,
+    trailing_newline: bool,
+    save_status: Option<Result<String, String>>,
+    /// Share files the last restore read, offered for secure deletion
+    loaded_files: Vec<LoadedFile>,
+    // The shards of the last restore and their password, to deal more shards from
+    extend: Option<ExtendState>,
+    show_shortcuts: bool,
+    #[cfg(feature = "tray")]
+    tray: bool,
+    // Only set once the event loop runs, which the tray has to be created from
+    #[cfg(feature = "tray")]
+    tray_icon: Option<TrayIcon>,
+    // The day each tracked backup was last notified about, so it's only once a day
+    #[cfg(feature = "tray")]
+    notified: HashMap<String, NaiveDate>,
 }
 
 #[derive(Debug, Clone)]
@@ -39,48 +232,455 @@ pub enum AppPage {
     Welcome,
     CreateBackup,
     RestoreBackup,
+    FolderImport,
     BackupGenerating,
+    Diagnosing,
     BackupResults,
+    VerifyBackup,
     DecodeSuccess,
-    DecodeFailure,
+    ExtendBackup,
+    History,
+    PdfSettings,
+    Settings,
+    About,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     SwitchPage(AppPage),
+    OpenSettings,
+    CloseSettings,
+    ChooseOutputDir,
+    ResetOutputDir,
+    ChooseAuditLog,
+    ResetAuditLog,
     SecretChanged(String),
+    SecretNoteChanged(String),
+    LoadSecretFile,
+    ClearSecretFile,
     PassphraseChanged(String),
+    PassphraseConfirmChanged(String),
+    PassphraseWrittenDownChanged(bool),
+    AllowWeakPassphraseChanged(bool),
+    ChooseKeyFile,
+    RemoveKeyFile,
+    PassphraseWordsChanged(u8),
+    PassphraseSeparatorChanged(PassphraseSeparator),
+    GeneratePassphrase,
+    SecretFormatChanged(SecretFormat),
+    SecretStrengthChanged(SecretStrength),
     GenerateSecret,
     CreateBackup,
+    ReproducibleChanged(bool),
+    StartReprint,
+    ReprintSeedChanged(String),
+    ReprintNumberChanged(String),
     LabelChanged(String),
+    HideLabelChanged(bool),
+    ExpiryChanged(String),
+    NotBeforeChanged(String),
+    ToggleHighContrast,
+    FontScaleChanged(f32),
+    VerifyAfterChanged(String),
+    DismissReminder,
+    RenewBackup(String),
+    AddSecret,
+    RemoveSecret(usize),
+    AdditionalSecretChanged(usize, String),
+    AdditionalPassphraseChanged(usize, String),
+    AdditionalLabelChanged(usize, String),
     BackupTypeChanged(BackupType),
-    BackupCompleted(Option<Vec<BackupShard>>),
+    SecurityLevelChanged(SecurityLevel),
+    CipherChanged(CipherSuite),
+    SharingChanged(SssBackend),
+    Slip39Changed(bool),
+    KdfRoundsChanged(String),
+    KdfAlgorithmChanged(KdfAlgorithm),
+    Argon2MemoryChanged(String),
+    Argon2IterationsChanged(String),
+    Argon2ParallelismChanged(String),
+    CustomShardsChanged {
+        min: u8,
+        max: u8,
+    },
+    BackupCompleted(Result<Vec<BackupShard>, BackupError>),
+    StreamProgress(u64),
+    DecryptProgress(u64),
+    Tick,
+    PdfTitleChanged(String),
+    PdfColorChanged(String),
+    ChooseLogo,
+    RemoveLogo,
     SaveBackup(usize),
+    SaveBackupJson(usize),
+    ToggleShareWords(usize),
+    #[cfg(any(unix, windows))]
+    PrintShare(usize),
+    #[cfg(any(unix, windows))]
+    SharePrinted(usize, Result<(), String>),
+    LeaveResults {
+        exit: bool,
+    },
+    BundlePasswordChanged(String),
+    SaveBundle,
+    MergeShares,
+    VerifyPassphraseChanged(String),
+    VerifyBackup,
+    BackupVerified(Result<bool, String>),
     End,
+    ConfirmExit,
+    CancelExit,
+    RestorePassphraseChanged(String),
+    ChooseRestoreKeyFile,
+    RemoveRestoreKeyFile,
+    ClearRestoreSession,
     ScanCode,
-    ScanComplete(Option<Vec<u8>>),
+    StopScan,
+    ScanComplete(Result<Vec<u8>, String>),
+    ImportFile,
+    FileHovered,
+    FilesHoveredLeft,
+    FileDropped(PathBuf),
+    DroppedFileImported(usize, Result<Vec<Vec<u8>>, String>),
+    ImportFolder,
+    FolderFileScanned(usize, Result<Vec<Vec<u8>>, String>),
+    RestoreBackupGroup(u64),
+    CancelFolderImport,
+    FileImported(Result<(Vec<PathBuf>, Vec<Vec<u8>>), String>),
+    SecureDeleteToggled(usize, bool),
+    SecureDeleteSelected,
+    SecureDelete(PathBuf),
+    SecureDeleted(PathBuf, Result<(), String>),
+    ExtendCountChanged(u8),
+    ExtendFirstChanged(usize),
+    ExtendBackup,
+    BackupExtended(Result<Vec<BackupShard>, String>),
+    ShareTextChanged(String),
+    AddShare,
+    FocusNext,
+    FocusPrevious,
+    Shortcut(Shortcut),
+    ToggleShortcuts,
+    RemoveShard(usize),
     DecodeSecrets,
+    RetryDecrypt,
+    DecryptFinished(Result<RestoredSecret, RestoreError>),
+    DiagnoseShards,
+    DiagnosisComplete(Option<Diagnosis>),
+    ToggleSecretVisibility,
+    RecordRestoreChanged(bool),
+    RecordBackupChanged(bool),
+    HistoryLabelChanged(String),
+    DeleteHistoryEntry(u64),
+    ReverifyBackup(String),
+    DeleteBackupRecord(String),
+    CopyToClipboard(String),
+    /// Copies the restored secret, which stays out of the message
+    CopySecret,
+    ClearClipboardChanged(bool),
+    ClipboardTick(u64),
+    ClipboardRead(Option<String>),
+    ToggleTheme,
+    SaveSecret,
+    DecryptStreamFile,
+    StreamFileDecrypted(Result<String, String>),
+    TrailingNewlineChanged(bool),
+    #[cfg(feature = "tray")]
+    TrayEvent(TrayEvent),
+    #[cfg(feature = "tray")]
+    CheckReminders,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Keys that act on the current page. They are only sent while no text input has focus.
+#[derive(Debug, Clone, Copy)]
+pub enum Shortcut {
+    Next,
+    Back,
+    GeneratePassphrase,
+}
+
+#[derive(Debug, Clone)]
+struct SecretFile {
+    name: String,
+    len: usize,
+    fingerprint: String,
+}
+
+/// How the secrets are stored, worked out whenever they change, as compressing them on every
+/// render is too slow.
+#[derive(Debug, Clone, Default)]
+struct StoredSizes {
+    compressed: bool,
+    lens: Vec<usize>,
+}
+
+impl StoredSizes {
+    fn of(secrets: &[Secret]) -> Self {
+        let compressed = should_compress(secrets);
+        StoredSizes {
+            compressed,
+            lens: secrets
+                .iter()
+                .map(|secret| stored_len(secret, compressed))
+                .collect(),
+        }
+    }
+
+    fn longest(&self) -> usize {
+        self.lens.iter().copied().max().unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct StreamFile {
+    path: PathBuf,
+    name: String,
+    len: u64,
+}
+
+/// Everything the background thread needs to encrypt a stream file and create its shards.
+#[derive(Debug, Clone)]
+struct StreamJob {
+    input: PathBuf,
+    output: PathBuf,
+    backup_type: BackupType,
+    label: String,
+    kdf: KdfParams,
+    cipher: CipherSuite,
+    sharing: SssBackend,
+    key_file: Option<KeyFileDigest>,
+    not_before: Option<DateTime<Utc>>,
+    verify_after: Option<u8>,
+    passphrase: SecretString,
+}
+
+/// A stream file being decrypted on a thread of its own.
+#[derive(Debug, Clone)]
+struct DecryptJob {
+    input: StreamFile,
+    output: PathBuf,
+    secret: DecryptedSecret,
+    backup_id: u64,
+}
+
+enum StreamEvent<T> {
+    Progress(u64),
+    Done(T),
+}
+
+type StreamWork<T> = Box<dyn FnOnce(&mut dyn FnMut(u64)) -> T + Send>;
+
+#[derive(Debug, Default, Clone)]
+struct ReprintState {
+    seed: String,
+    number: String,
+}
+
+impl ReprintState {
+    fn seed(&self) -> Option<RecoverySeed> {
+        self.seed.parse().ok()
+    }
+
+    fn number(&self) -> Option<usize> {
+        self.number.trim().parse().ok().filter(|number| *number > 0)
+    }
+
+    fn number_valid(&self, total_shards: u8) -> bool {
+        self.number()
+            .is_some_and(|number| number <= total_shards as usize)
+    }
+}
+
+struct ExtendState {
+    shards: Vec<BackupShard>,
+    password: SecretString,
+    // Number of the first added shard, which skips shards added before that weren't loaded
+    first: usize,
+    additional: u8,
+    extending: bool,
+    error: Option<String>,
+    // Set once the results page shows the added shards
+    dealt: bool,
+}
+
+impl ExtendState {
+    fn new(shards: Vec<BackupShard>, password: SecretString) -> Self {
+        let mut extend = ExtendState {
+            shards,
+            password,
+            first: 0,
+            additional: 1,
+            extending: false,
+            error: None,
+            dealt: false,
+        };
+        extend.first = extend.next_number();
+        extend
+    }
+
+    /// Lowest number an added shard can have, after the original total and every shard known.
+    fn next_number(&self) -> usize {
+        let total = self
+            .shards
+            .first()
+            .and_then(|shard| shard.metadata.as_ref())
+            .map_or(0, |metadata| metadata.total_shards as usize);
+        shard_total(&self.shards).max(total) + 1
+    }
+
+    fn max_additional(&self) -> u8 {
+        (u8::MAX as usize + 1)
+            .saturating_sub(self.first)
+            .min(MAX_SHARDS as usize) as u8
+    }
+}
+
+/// Only the digest of a key file is kept, never its content.
+#[derive(Debug, Clone)]
+struct KeyFile {
+    name: String,
+    digest: KeyFileDigest,
+}
+
+#[derive(Debug, Default, Clone)]
+struct SecretEntry {
+    secret: SecretString,
+    passphrase: SecretString,
+    label: String,
+    passphrase_score: Option<u8>,
+}
+
+impl SecretEntry {
+    fn update_passphrase_score(&mut self) {
+        let user_inputs = [self.label.as_str(), &self.secret];
+        self.passphrase_score =
+            passphrase_strength(&self.passphrase, &user_inputs).map(|strength| strength.score);
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode")]
 pub enum BackupType {
+    #[default]
     Standard,
-    Distributed { min: u8, max: u8 },
+    Distributed {
+        min: u8,
+        max: u8,
+    },
+    Custom {
+        min: u8,
+        max: u8,
+    },
 }
 
 impl Default for HyperbackedApp {
     fn default() -> Self {
         Self {
             page: AppPage::Welcome,
+            page_before_settings: None,
             secret: Default::default(),
+            secret_encoding: SecretEncoding::Text,
+            secret_has_control_chars: false,
+            secret_format: SecretFormat::default(),
+            secret_strength: 32,
+            secret_generated: false,
+            secret_file: None,
+            secret_file_error: None,
+            secret_note: Default::default(),
+            stored_sizes: StoredSizes::default(),
+            stream_file: None,
+            streaming: None,
+            decrypting_stream: None,
+            stream_progress: 0,
+            stream_output: None,
             passphrase: Default::default(),
+            passphrase_confirm: Default::default(),
+            passphrase_written_down: false,
+            passphrase_words: 6,
+            passphrase_separator: PassphraseSeparator::Space,
+            passphrase_strength: None,
+            min_passphrase_score: 3,
+            allow_weak_passphrase: false,
+            key_file: None,
+            key_file_error: None,
             label: Default::default(),
+            hide_label_in_qr: false,
+            expiry: String::new(),
+            not_before: String::new(),
+            verify_after: String::new(),
+            additional_secrets: Vec::new(),
             backup_type: BackupType::Standard,
+            security_level: SecurityLevel::default(),
+            cipher: CipherSuite::default(),
+            sharing: SssBackend::default(),
+            slip39: false,
+            kdf_algorithm: KdfAlgorithm::default(),
+            kdf_rounds_input: String::new(),
+            argon2_memory_input: String::from("64"),
+            argon2_iterations_input: String::from("3"),
+            argon2_parallelism_input: String::from("4"),
+            kdf_estimate: None,
+            output_dir: None,
+            audit_log: None,
+            reproducible: false,
+            recovery_seed: None,
+            reprint: None,
             generated_backup: None,
+            spinner_frame: 0,
+            pdf_theme: PdfTheme::default(),
+            pdf_color_input: String::new(),
+            failure_reason: None,
+            verify_passphrase: Default::default(),
+            bundle_password: String::new(),
+            verify_result: None,
+            verifying: false,
+            shard_qrcodes: Vec::new(),
+            saved_shares: HashSet::new(),
+            #[cfg(any(unix, windows))]
+            printing: HashSet::new(),
+            shown_words: None,
+            print_error: None,
+            unsaved_warning: false,
             should_exit: false,
-            is_scanning: false,
-            scanned_codes: Vec::new(),
-            decoded_secret: Default::default(),
+            restore: RestoreState::default(),
+            confirm_exit: false,
+            decoded_secret: None,
+            decoded_shards: Vec::new(),
+            decode_notice: None,
+            file_hovered: false,
+            decoded_backup_id: None,
+            decoded_summary: None,
+            decoded_verify_by: None,
+            decoded_not_before: None,
+            history_path: None,
+            history: Vec::new(),
+            backup_history: BackupHistory::default(),
+            history_label: String::new(),
+            recorded_entry: None,
+            recorded_backup: None,
+            backup_history_error: None,
+            secret_visible: false,
+            clear_clipboard: true,
+            clipboard_timer: 0,
+            clipboard_clear_secs: 30,
+            clipboard_remaining: None,
+            clipboard_copied: None,
+            dark_mode: true,
+            accessibility: AccessibilityConfig::default(),
+            reminder_days: 30,
+            tracked_backups: Vec::new(),
+            reminders: Vec::new(),
+            trailing_newline: false,
+            save_status: None,
+            loaded_files: Vec::new(),
+            extend: None,
+            show_shortcuts: false,
+            #[cfg(feature = "tray")]
+            tray: false,
+            #[cfg(feature = "tray")]
+            tray_icon: None,
+            #[cfg(feature = "tray")]
+            notified: HashMap::new(),
         }
     }
 }
@@ -89,20 +689,75 @@ impl Application for HyperbackedApp {
     type Executor = executor::Default;
     type Message = Message;
     type Theme = iced::Theme;
-    type Flags = ();
+    type Flags = Flags;
 
-    fn new(_flags: Self::Flags) -> (Self, Command<Message>) {
-        (HyperbackedApp::default(), Command::none())
+    fn new(flags: Self::Flags) -> (Self, Command<Message>) {
+        let config = Config::load();
+        let history_path = default_history_path();
+        let app = HyperbackedApp {
+            history: history_path
+                .as_deref()
+                .map(load_history)
+                .unwrap_or_default(),
+            history_path,
+            backup_history: BackupHistory::load(BackupHistory::default_path()),
+            reminders: config.due_reminders(Local::now().date_naive()),
+            audit_log: config.audit_log_path(),
+            dark_mode: config.dark_mode,
+            accessibility: config.accessibility,
+            clipboard_clear_secs: config.clipboard_clear_secs,
+            backup_type: config.backup_type,
+            label: config.label,
+            hide_label_in_qr: config.hide_label_in_qr,
+            passphrase_words: config.passphrase_words.clamp(MIN_WORDS, MAX_WORDS),
+            output_dir: config.output_dir,
+            reminder_days: config.reminder_days,
+            min_passphrase_score: config.min_passphrase_score,
+            tracked_backups: config.tracked_backups,
+            pdf_color_input: format_hex_color(config.pdf_theme.primary_color),
+            pdf_theme: config.pdf_theme,
+            kdf_estimate: SecurityLevel::default()
+                .kdf_params()
+                .map(|kdf| kdf.estimate_duration()),
+            #[cfg(feature = "tray")]
+            tray: flags.tray,
+            ..Default::default()
+        };
+        #[cfg(feature = "tray")]
+        if app.tray {
+            return (app, Command::perform(async {}, |_| Message::CheckReminders));
+        }
+        #[cfg(not(feature = "tray"))]
+        let _ = flags;
+        (app, Command::none())
     }
 
     fn theme(&self) -> Theme {
-        Theme::custom(Palette {
-            background: Color::from_rgb(0.09, 0.09, 0.15),
-            text: Color::from_rgb(0.8, 0.8, 0.8),
-            primary: Color::from_rgb(0.5, 0.5, 0.75),
-            success: Color::from_rgb(0.33, 0.7, 0.5),
-            danger: Color::from_rgb(0.90, 0.30, 0.45),
-        })
+        if self.accessibility.high_contrast {
+            Theme::custom(Palette {
+                background: Color::BLACK,
+                text: Color::WHITE,
+                primary: Color::from_rgb(1.0, 0.85, 0.0),
+                success: Color::from_rgb(0.3, 1.0, 0.5),
+                danger: Color::from_rgb(1.0, 0.4, 0.4),
+            })
+        } else if self.dark_mode {
+            Theme::custom(Palette {
+                background: Color::from_rgb(0.09, 0.09, 0.15),
+                text: Color::from_rgb(0.8, 0.8, 0.8),
+                primary: Color::from_rgb(0.5, 0.5, 0.75),
+                success: Color::from_rgb(0.33, 0.7, 0.5),
+                danger: Color::from_rgb(0.90, 0.30, 0.45),
+            })
+        } else {
+            Theme::custom(Palette {
+                background: Color::from_rgb(0.96, 0.96, 0.98),
+                text: Color::from_rgb(0.15, 0.15, 0.2),
+                primary: Color::from_rgb(0.35, 0.35, 0.65),
+                success: Color::from_rgb(0.2, 0.55, 0.35),
+                danger: Color::from_rgb(0.80, 0.20, 0.35),
+            })
+        }
     }
 
     fn title(&self) -> String {
@@ -112,50 +767,450 @@ impl Application for HyperbackedApp {
     fn update(&mut self, message: Message) -> Command<Message> {
         match message {
             Message::SwitchPage(page) => {
+                if matches!(page, AppPage::Welcome) {
+                    self.wipe_create_flow();
+                    self.page_before_settings = None;
+                }
                 self.page = page;
             }
+            Message::OpenSettings => {
+                // The PDF settings opened from the settings go back to them, keeping the page
+                // the settings were opened from
+                if !matches!(self.page, AppPage::Settings) && !self.in_settings() {
+                    self.page_before_settings = Some(self.page.clone());
+                }
+                self.page = AppPage::Settings;
+            }
+            Message::CloseSettings => {
+                self.page = self.page_before_settings.take().unwrap_or(AppPage::Welcome);
+            }
+            Message::ChooseOutputDir => {
+                let mut dialog = FileDialog::new();
+                if let Some(output_dir) = &self.output_dir {
+                    dialog = dialog.set_directory(output_dir);
+                }
+                if let Some(dir) = dialog.pick_folder() {
+                    self.output_dir = Some(dir);
+                    self.save_config();
+                }
+            }
+            Message::ResetOutputDir => {
+                self.output_dir = None;
+                self.save_config();
+            }
+            Message::ChooseAuditLog => {
+                let file = FileDialog::new()
+                    .add_filter("Log Files", &["log"])
+                    .save_file();
+                if let Some(file) = file {
+                    self.audit_log = Some(file);
+                    self.save_config();
+                }
+            }
+            Message::ResetAuditLog => {
+                self.audit_log = default_audit_log_path();
+                self.save_config();
+            }
             Message::SecretChanged(secret) => {
-                self.secret = secret;
+                if secret.is_empty() {
+                    self.secret_encoding = SecretEncoding::Text;
+                }
+                self.secret_has_control_chars =
+                    self.secret_encoding == SecretEncoding::Text && has_control_chars(&secret);
+                self.secret = secret.into();
+                self.secret_generated = false;
+                self.update_passphrase_strength();
+                self.update_stored_sizes();
             }
-            Message::PassphraseChanged(passphrase) => {
-                self.passphrase = passphrase;
+            Message::SecretFormatChanged(format) => {
+                self.secret_format = format;
+                let strengths = format.strengths();
+                if !strengths.contains(&self.secret_strength) {
+                    self.secret_strength = strengths
+                        .iter()
+                        .rev()
+                        .find(|&&strength| strength < self.secret_strength)
+                        .unwrap_or(&strengths[0])
+                        .to_owned();
+                }
+            }
+            Message::SecretStrengthChanged(strength) => {
+                self.secret_strength = strength.bytes;
             }
             Message::GenerateSecret => {
-                self.passphrase = gen_passphrase(6);
+                if let Ok(secret) = gen_secret(self.secret_format, self.secret_strength) {
+                    self.secret = secret.into();
+                    self.secret_encoding = SecretEncoding::Text;
+                    self.secret_has_control_chars = false;
+                    self.secret_generated = true;
+                    self.update_passphrase_strength();
+                    self.update_stored_sizes();
+                }
+            }
+            Message::SecretNoteChanged(note) => {
+                self.secret_note = note.into();
+                self.update_stored_sizes();
+            }
+            Message::LoadSecretFile => {
+                if let Some(file) = FileDialog::new().pick_file() {
+                    match std::fs::metadata(&file) {
+                        // Binary secrets are prefixed with a marker byte
+                        Ok(metadata) if metadata.len() >= MAX_SECRET_LEN as u64 => {
+                            self.secret = SecretString::default();
+                            self.secret_file = None;
+                            self.stream_file = Some(StreamFile {
+                                name: file_name(&file),
+                                len: metadata.len(),
+                                path: file,
+                            });
+                            self.secret_encoding = SecretEncoding::Base64;
+                            self.secret_has_control_chars = false;
+                            self.secret_file_error = None;
+                            self.update_passphrase_strength();
+                            self.update_stored_sizes();
+                            return Command::none();
+                        }
+                        _ => {}
+                    }
+                    match read_secret_file(&file) {
+                        Ok(mut data) => {
+                            self.stream_file = None;
+                            self.secret = base64::encode(&data).into();
+                            self.secret_file = Some(SecretFile {
+                                name: file_name(&file),
+                                len: data.len(),
+                                fingerprint: fingerprint(&data),
+                            });
+                            data.zeroize();
+                            self.secret_encoding = SecretEncoding::Base64;
+                            self.secret_has_control_chars = false;
+                            self.secret_file_error = None;
+                            self.update_passphrase_strength();
+                            self.update_stored_sizes();
+                        }
+                        Err(error) => {
+                            self.secret_file_error =
+                                Some(format!("Failed to load {}: {}", file_name(&file), error));
+                        }
+                    }
+                }
+            }
+            Message::ClearSecretFile => {
+                self.secret = SecretString::default();
+                self.secret_file = None;
+                self.stream_file = None;
+                self.secret_encoding = SecretEncoding::Text;
+                self.update_passphrase_strength();
+                self.update_stored_sizes();
+            }
+            Message::PassphraseChanged(passphrase) => {
+                self.passphrase = passphrase.into();
+                self.passphrase_written_down = false;
+                self.allow_weak_passphrase = false;
+                self.update_passphrase_strength();
+            }
+            Message::PassphraseConfirmChanged(passphrase_confirm) => {
+                self.passphrase_confirm = passphrase_confirm.into();
+            }
+            Message::PassphraseWrittenDownChanged(written_down) => {
+                self.passphrase_written_down = written_down;
+            }
+            Message::AllowWeakPassphraseChanged(allow) => {
+                self.allow_weak_passphrase = allow;
+            }
+            Message::ChooseKeyFile => {
+                if let Some(file) = FileDialog::new().pick_file() {
+                    match read_key_file(&file) {
+                        Ok(key_file) => {
+                            self.key_file = Some(key_file);
+                            self.key_file_error = None;
+                        }
+                        Err(error) => self.key_file_error = Some(error),
+                    }
+                }
+            }
+            Message::RemoveKeyFile => {
+                self.key_file = None;
+                self.key_file_error = None;
+            }
+            Message::PassphraseWordsChanged(words) => {
+                self.passphrase_words = words;
+                self.save_config();
+            }
+            Message::PassphraseSeparatorChanged(separator) => {
+                self.passphrase_separator = separator;
+            }
+            Message::GeneratePassphrase => {
+                self.passphrase =
+                    gen_passphrase(self.passphrase_words, self.passphrase_separator.as_char())
+                        .into();
+                // There is nothing to mistype, but it still has to be written down
+                self.passphrase_confirm = self.passphrase.clone();
+                self.passphrase_written_down = false;
+                self.allow_weak_passphrase = false;
+                self.update_passphrase_strength();
             }
             Message::CreateBackup => {
+                if !self.backup_type.is_valid()
+                    || self.expiry_date().is_err()
+                    || self.not_before().is_err()
+                    || self.verify_after().is_err()
+                    || self.passphrase != self.passphrase_confirm
+                    || !self.passphrase_written_down
+                    || !self.passphrase_accepted()
+                    || !self.passphrases_distinct()
+                {
+                    return Command::none();
+                }
+                let kdf = match self.kdf_params() {
+                    Some(kdf) => kdf,
+                    None => return Command::none(),
+                };
+                if let Some(input) = self.stream_file.as_ref().map(|file| file.path.clone()) {
+                    let output = match self.save_file_dialog("Encrypted files", "enc") {
+                        Some(output) => output,
+                        None => return Command::none(),
+                    };
+                    self.streaming = Some(StreamJob {
+                        input,
+                        output: output.clone(),
+                        backup_type: self.backup_type,
+                        label: self.qrcode_label().to_owned(),
+                        kdf,
+                        cipher: self.cipher,
+                        sharing: self.sharing,
+                        key_file: self.key_file_digest(),
+                        not_before: self.not_before().ok().flatten(),
+                        verify_after: self.verify_after().ok().flatten(),
+                        passphrase: self.passphrase.clone(),
+                    });
+                    self.stream_progress = 0;
+                    self.stream_output = Some(output);
+                    self.recovery_seed = None;
+                    self.page = AppPage::BackupGenerating;
+                    self.spinner_frame = 0;
+                    return spinner_tick();
+                }
+                let seed = match &self.reprint {
+                    Some(reprint) => match reprint.seed() {
+                        Some(seed) => Some(seed),
+                        None => return Command::none(),
+                    },
+                    None => self.reproducible.then(RecoverySeed::generate),
+                };
+                self.recovery_seed = seed.filter(|_| self.reprint.is_none());
                 self.page = AppPage::BackupGenerating;
+                self.spinner_frame = 0;
 
                 let backup_type = self.backup_type.clone();
-                let secret = self.secret.clone();
-                let passphrase = self.passphrase.clone();
+                let label = self.qrcode_label().to_owned();
+                let secrets = self.secrets();
+                let cipher = self.cipher;
+                let sharing = self.sharing;
+                let slip39 = self.slip39();
+                let key_file = self.key_file_digest();
+                let not_before = self.not_before().ok().flatten();
+                let verify_after = self.verify_after().ok().flatten();
 
-                return Command::perform(
+                let generate = Command::perform(
                     async move {
-                        let secrets = &[Secret {
-                            value: secret.as_str(),
-                            password: passphrase.as_str(),
-                        }];
-
-                        return create_backup(secrets.to_vec(), backup_type.to_config()).ok();
+                        let config = backup_type.to_config(
+                            &label,
+                            kdf,
+                            cipher,
+                            sharing,
+                            slip39,
+                            key_file,
+                            not_before,
+                            verify_after,
+                        )?;
+                        match seed {
+                            Some(seed) => create_backup_deterministic(secrets, config, &seed),
+                            None => create_backup(secrets, config),
+                        }
                     },
                     Message::BackupCompleted,
                 );
+                return Command::batch([generate, spinner_tick()]);
+            }
+            Message::ReproducibleChanged(reproducible) => {
+                self.reproducible = reproducible;
+            }
+            Message::StartReprint => {
+                self.reprint = Some(ReprintState::default());
+                self.page = AppPage::CreateBackup;
+            }
+            Message::ReprintSeedChanged(seed) => {
+                if let Some(reprint) = &mut self.reprint {
+                    reprint.seed = seed;
+                }
+            }
+            Message::ReprintNumberChanged(number) => {
+                if let Some(reprint) = &mut self.reprint {
+                    reprint.number = number;
+                }
+            }
+            Message::Tick => {
+                // PBKDF2 can't report its progress, so the spinner only shows that work is going on
+                if matches!(self.page, AppPage::BackupGenerating) {
+                    self.spinner_frame += 1;
+                    return spinner_tick();
+                }
             }
             Message::LabelChanged(label) => {
                 self.label = label;
+                self.update_passphrase_strength();
+            }
+            Message::HideLabelChanged(hide) => {
+                self.hide_label_in_qr = hide;
+                self.save_config();
+            }
+            Message::NotBeforeChanged(not_before) => {
+                self.not_before = not_before;
+            }
+            Message::VerifyAfterChanged(verify_after) => {
+                self.verify_after = verify_after;
+            }
+            Message::ExpiryChanged(expiry) => {
+                self.expiry = expiry;
+            }
+            Message::DismissReminder => {
+                self.reminders.clear();
+            }
+            Message::RenewBackup(label) => {
+                self.reminders.clear();
+                self.label = label;
+                self.page = AppPage::CreateBackup;
+            }
+            Message::AddSecret => {
+                if self.additional_secrets.len() + 1 < MAX_SECRETS && self.stream_file.is_none() {
+                    self.additional_secrets.push(SecretEntry::default());
+                    self.update_stored_sizes();
+                }
+            }
+            Message::RemoveSecret(index) => {
+                self.additional_secrets.remove(index);
+                self.update_stored_sizes();
+            }
+            Message::AdditionalSecretChanged(index, secret) => {
+                self.additional_secrets[index].secret = secret.into();
+                self.additional_secrets[index].update_passphrase_score();
+                self.update_stored_sizes();
+            }
+            Message::AdditionalPassphraseChanged(index, passphrase) => {
+                self.additional_secrets[index].passphrase = passphrase.into();
+                self.additional_secrets[index].update_passphrase_score();
+            }
+            Message::AdditionalLabelChanged(index, label) => {
+                self.additional_secrets[index].label = label;
+                self.additional_secrets[index].update_passphrase_score();
+            }
+            Message::SecurityLevelChanged(level) => {
+                self.security_level = level;
+                self.update_kdf_estimate();
+            }
+            Message::CipherChanged(cipher) => {
+                self.cipher = cipher;
+            }
+            Message::SharingChanged(sharing) => {
+                self.sharing = sharing;
+            }
+            Message::Slip39Changed(slip39) => {
+                self.slip39 = slip39;
+            }
+            Message::KdfRoundsChanged(rounds) => {
+                self.kdf_rounds_input = rounds;
+                self.update_kdf_estimate();
+            }
+            Message::KdfAlgorithmChanged(algorithm) => {
+                self.kdf_algorithm = algorithm;
+                self.update_kdf_estimate();
+            }
+            Message::Argon2MemoryChanged(memory) => {
+                self.argon2_memory_input = memory;
+                self.update_kdf_estimate();
+            }
+            Message::Argon2IterationsChanged(iterations) => {
+                self.argon2_iterations_input = iterations;
+                self.update_kdf_estimate();
+            }
+            Message::Argon2ParallelismChanged(parallelism) => {
+                self.argon2_parallelism_input = parallelism;
+                self.update_kdf_estimate();
             }
             Message::BackupTypeChanged(backup_type) => {
-                self.backup_type = backup_type;
+                // Keep the previously configured values when re-selecting the custom mode
+                if !matches!(
+                    (self.backup_type, backup_type),
+                    (BackupType::Custom { .. }, BackupType::Custom { .. })
+                ) {
+                    self.backup_type = backup_type;
+                }
+                self.save_config();
+            }
+            Message::CustomShardsChanged { min, max } => {
+                self.backup_type = BackupType::Custom { min, max };
+                self.save_config();
+            }
+            Message::StreamProgress(done) => {
+                self.stream_progress = done;
             }
             Message::BackupCompleted(result) => {
+                // Ends the subscription that reported the progress
+                self.streaming = None;
+                self.failure_reason = result.as_ref().err().cloned();
+                let mut result = result.ok();
+                let expires_at = self.expiry_date().ok().flatten();
+                for shard in result.iter_mut().flatten() {
+                    shard.expires_at = expires_at;
+                }
+                if let (Some(first), Some(expires_at)) = (
+                    result.as_ref().and_then(|shards| shards.first()),
+                    expires_at,
+                ) {
+                    // A new backup under the same label replaces the one it renews
+                    if !self.label.is_empty() {
+                        self.tracked_backups
+                            .retain(|backup| backup.label != self.label);
+                    }
+                    self.tracked_backups.push(TrackedBackup {
+                        label: self.label.clone(),
+                        backup_id: format!("{:016x}", first.backup_id),
+                        expires_at,
+                    });
+                    self.save_config();
+                }
+                // A reprinted shard belongs to a backup that was logged when it was created
+                if let Some(first) = result
+                    .as_ref()
+                    .and_then(|shards| shards.first())
+                    .filter(|_| self.reprint.is_none())
+                {
+                    let shards = result.as_ref().map_or(0, |shards| shards.len());
+                    self.audit(AuditEvent::backup_created(
+                        first.backup_id,
+                        &self.label,
+                        shards,
+                        first.threshold,
+                    ));
+                }
+                self.recorded_backup = None;
+                self.backup_history_error = None;
+                self.shard_qrcodes = result
+                    .iter()
+                    .flatten()
+                    .map(|shard| shard_qrcode(shard).ok())
+                    .collect();
                 self.generated_backup = result;
+                self.saved_shares.clear();
+                self.shown_words = None;
+                self.print_error = None;
+                self.unsaved_warning = false;
+                self.verify_result = None;
                 self.page = AppPage::BackupResults;
             }
             Message::SaveBackup(num) => {
-                let file = FileDialog::new()
-                    .add_filter("PDF Files", &["pdf"])
-                    .save_file();
+                let file = self.save_file_dialog("PDF Files", "pdf");
                 if let Some(file) = file {
                     let backup = self.generated_backup.as_ref().unwrap();
 
@@ -164,184 +1219,3784 @@ impl Application for HyperbackedApp {
                         .find(|backup| backup.number == num)
                         .expect("Backup shard for saving must be defined at this point.");
 
-                    let pdf_data = print_pdf(share, &self.label, backup.len()).unwrap();
+                    let pdf_data =
+                        print_pdf(share, &self.label, shard_total(backup), &self.pdf_theme)
+                            .unwrap();
                     pdf_data.render_to_file(file).unwrap();
+                    self.saved_shares.insert(num);
+                }
+            }
+            Message::SaveBackupJson(num) => {
+                let file = self.save_file_dialog("JSON Files", "json");
+                if let Some(file) = file {
+                    let backup = self.generated_backup.as_ref().unwrap();
+
+                    let share = backup
+                        .iter()
+                        .find(|backup| backup.number == num)
+                        .expect("Backup shard for saving must be defined at this point.");
+
+                    let json_data = export_json(share, &self.label, shard_total(backup)).unwrap();
+                    std::fs::write(file, json_data).unwrap();
+                    self.saved_shares.insert(num);
+                }
+            }
+            Message::ToggleShareWords(num) => {
+                self.shown_words = (self.shown_words != Some(num)).then_some(num);
+            }
+            #[cfg(any(unix, windows))]
+            Message::PrintShare(num) => {
+                let backup = self.generated_backup.as_ref().unwrap();
+
+                let share = backup
+                    .iter()
+                    .find(|backup| backup.number == num)
+                    .expect("Backup shard for printing must be defined at this point.")
+                    .clone();
+                let total = shard_total(backup);
+                let label = self.label.clone();
+                let pdf_theme = self.pdf_theme.clone();
+                self.printing.insert(num);
+                // Waits for the printer to accept the job, which mustn't block the interface
+                return Command::perform(
+                    async move {
+                        print_pdf(&share, &label, total, &pdf_theme)
+                            .and_then(print_to_system_printer)
+                            .map_err(|error| error.to_string())
+                    },
+                    move |result| Message::SharePrinted(num, result),
+                );
+            }
+            #[cfg(any(unix, windows))]
+            Message::SharePrinted(num, result) => {
+                self.printing.remove(&num);
+                match result {
+                    Ok(()) => {
+                        self.print_error = None;
+                        self.saved_shares.insert(num);
+                    }
+                    Err(error) => self.print_error = Some(format!("Shard #{}: {}", num, error)),
+                }
+            }
+            Message::LeaveResults { exit } => {
+                // Warn once, leaving on the second attempt
+                if self.unsaved_shares() > 0 && !self.unsaved_warning {
+                    self.unsaved_warning = true;
+                    return Command::none();
+                }
+                self.unsaved_warning = false;
+                return self.update(if exit {
+                    Message::End
+                } else {
+                    Message::SwitchPage(AppPage::Welcome)
+                });
+            }
+            Message::BundlePasswordChanged(password) => {
+                self.bundle_password = password;
+            }
+            Message::SaveBundle => {
+                let file = self.save_file_dialog("ZIP Archives", "zip");
+                if let (Some(file), Some(backup)) = (file, &self.generated_backup) {
+                    if let Err(error) = save_bundle(
+                        &file,
+                        backup,
+                        &self.label,
+                        &self.bundle_password,
+                        &self.pdf_theme,
+                    ) {
+                        self.print_error = Some(format!("Failed to save bundle: {}", error));
+                    } else {
+                        self.print_error = None;
+                        self.saved_shares
+                            .extend(backup.iter().map(|shard| shard.number));
+                    }
+                }
+            }
+            Message::MergeShares => {
+                let file = self.save_file_dialog("PDF Files", "pdf");
+                if let (Some(file), Some(backup)) = (file, &self.generated_backup) {
+                    let shards = backup
+                        .iter()
+                        .filter(|shard| self.is_shown(shard))
+                        .cloned()
+                        .collect::<Vec<BackupShard>>();
+                    let result = print_merged_pdf(
+                        &shards,
+                        &self.label,
+                        shard_total(backup),
+                        &self.pdf_theme,
+                    )
+                    .and_then(|doc| Ok(doc.render_to_file(file)?));
+                    match result {
+                        Ok(()) => {
+                            self.print_error = None;
+                            self.saved_shares
+                                .extend(shards.iter().map(|shard| shard.number));
+                        }
+                        Err(error) => self.print_error = Some(error.to_string()),
+                    }
+                }
+            }
+            Message::VerifyPassphraseChanged(passphrase) => {
+                self.verify_passphrase = passphrase.into();
+            }
+            Message::VerifyBackup => {
+                let passphrase = std::mem::take(&mut self.verify_passphrase);
+                let shards = self.generated_backup.clone().unwrap_or_default();
+                let secret = self.secret.clone();
+                let key_file = self.key_file_digest();
+                self.verifying = true;
+                self.verify_result = None;
+                return Command::perform(
+                    async move {
+                        let password =
+                            unlock_password(&shards, &passphrase, key_file.as_ref(), None)
+                                .map_err(|e| e.to_string())?;
+                        verify_backup(&shards, &password, &secret).map_err(|e| e.to_string())
+                    },
+                    Message::BackupVerified,
+                );
+            }
+            Message::BackupVerified(result) => {
+                self.verifying = false;
+                let retry = result.is_err();
+                self.verify_result = Some(match result {
+                    Ok(true) => Ok(()),
+                    Ok(false) => Err(String::from(
+                        "Verification failed - the restored secret does not match the original.",
+                    )),
+                    Err(error) => Err(format!(
+                        "Verification failed ({}) - check your passphrase and try again.",
+                        error
+                    )),
+                });
+                // Only a wrong passphrase can be retried, so the original is no longer needed
+                if !retry {
+                    self.secret.zeroize();
                 }
             }
             Message::End => {
+                #[cfg(feature = "tray")]
+                if self.tray_icon.is_some() && !self.restore.in_progress() {
+                    return self.hide_to_tray();
+                }
+                self.request_exit();
+            }
+            Message::ConfirmExit => {
+                self.restore.clear();
+                self.save_config();
+                self.wipe_create_flow();
                 self.should_exit = true;
             }
+            Message::CancelExit => {
+                self.confirm_exit = false;
+            }
+            Message::RestorePassphraseChanged(passphrase) => {
+                self.restore.passphrase = passphrase.into();
+            }
+            Message::ChooseRestoreKeyFile => {
+                if let Some(file) = FileDialog::new().pick_file() {
+                    match read_key_file(&file) {
+                        Ok(key_file) => {
+                            self.restore.key_file = Some(key_file);
+                            self.restore.error = None;
+                        }
+                        Err(error) => self.restore.error = Some(error),
+                    }
+                }
+            }
+            Message::RemoveRestoreKeyFile => {
+                self.restore.key_file = None;
+            }
+            Message::ClearRestoreSession => {
+                self.restore.clear();
+            }
             Message::ScanCode => {
-                self.is_scanning = true;
-                return Command::perform(async { qrcode_scan().ok() }, Message::ScanComplete);
+                // Dropping the sender stops the camera, e.g. when the session is cleared
+                let (stop, receiver) = mpsc::channel();
+                self.restore.scan_stop = Some(stop);
+                self.restore.is_scanning = true;
+                self.restore.error = None;
+                return Command::perform(
+                    async move { qrcode_scan(receiver).map_err(|e| e.to_string()) },
+                    Message::ScanComplete,
+                );
+            }
+            Message::StopScan => {
+                self.restore.scan_stop = None;
+                self.restore.is_scanning = false;
             }
             Message::ScanComplete(data) => {
-                self.is_scanning = false;
-                if let Some(data) = data {
-                    self.scanned_codes.push(data);
-                } else {
-                    eprintln!("Failed to scan code!");
+                if self.restore.scan_stop.take().is_none() {
+                    return Command::none();
+                }
+                self.restore.is_scanning = false;
+                match data {
+                    Ok(data) => self.add_scanned_shard(data),
+                    Err(error) => {
+                        self.restore.error = Some(format!(
+                            "Could not access the camera ({}). You can import the PDF or a photo of your backup shards instead.",
+                            error
+                        ));
+                    }
+                }
+            }
+            Message::ImportFile => {
+                let files = FileDialog::new()
+                    .add_filter("Backup files", &["pdf", "json", "png", "jpg", "jpeg"])
+                    .pick_files();
+                if let Some(files) = files {
+                    self.restore.is_scanning = true;
+                    self.restore.error = None;
+                    return Command::perform(
+                        async move {
+                            let mut codes = Vec::new();
+                            for file in &files {
+                                let found = import_shard_file(file)
+                                    .map_err(|e| format!("{}: {}", file.display(), e))?;
+                                codes.extend(found);
+                            }
+                            Ok((files, codes))
+                        },
+                        Message::FileImported,
+                    );
                 }
             }
-            Message::DecodeSecrets => {
-                let backup_secret = recover_backup(&self.scanned_codes, &self.passphrase);
-                match backup_secret {
-                    Ok(decoded) => {
-                        self.decoded_secret = decoded;
-                        self.page = AppPage::DecodeSuccess;
+            Message::FileHovered => {
+                self.file_hovered = matches!(self.page, AppPage::RestoreBackup);
+            }
+            Message::FilesHoveredLeft => {
+                self.file_hovered = false;
+            }
+            Message::FileDropped(file) => {
+                self.file_hovered = false;
+                // Drops anywhere else could interrupt a backup or hide a decoded secret
+                if !matches!(self.page, AppPage::RestoreBackup) {
+                    return Command::none();
+                }
+                self.restore.dropped_files.push((file, None));
+                self.restore.error = None;
+                let pending = self
+                    .restore
+                    .dropped_files
+                    .iter()
+                    .filter(|(_, r)| r.is_none());
+                if pending.count() == 1 {
+                    return self.import_next_dropped_file();
+                }
+            }
+            Message::DroppedFileImported(index, codes) => {
+                // The session may have been cleared while the file was being read
+                match self.restore.dropped_files.get(index) {
+                    Some((_, None)) => {}
+                    _ => return Command::none(),
+                }
+                let before = self.restore.scanned_shards.len();
+                let result = codes.and_then(|codes| {
+                    self.restore.error = None;
+                    codes
+                        .into_iter()
+                        .for_each(|data| self.add_scanned_shard(data));
+                    match self.restore.scanned_shards.len() - before {
+                        0 => Err(self
+                            .restore
+                            .error
+                            .take()
+                            .unwrap_or_else(|| String::from("No backup shards found."))),
+                        added => Ok(added),
                     }
-                    Err(_) => {
-                        self.page = AppPage::DecodeFailure;
+                });
+                if result.is_ok() {
+                    let file = self.restore.dropped_files[index].0.clone();
+                    self.restore.loaded_files.push(file);
+                }
+                self.restore.dropped_files[index].1 = Some(result);
+                return self.import_next_dropped_file();
+            }
+            Message::ImportFolder => {
+                if let Some(dir) = FileDialog::new().pick_folder() {
+                    match list_shard_files(&dir) {
+                        Ok(files) if files.is_empty() => {
+                            self.restore.error = Some(format!(
+                                "{} doesn't contain any images or PDFs.",
+                                dir.display()
+                            ));
+                        }
+                        Ok(files) => {
+                            self.restore.error = None;
+                            self.restore.folder_import = Some(FolderImport {
+                                files,
+                                ..FolderImport::default()
+                            });
+                            self.page = AppPage::FolderImport;
+                            return self.scan_next_folder_file();
+                        }
+                        Err(error) => {
+                            self.restore.error =
+                                Some(format!("Could not read {}: {}", dir.display(), error));
+                        }
                     }
                 }
             }
-        }
-        Command::none()
-    }
-
-    fn should_exit(&self) -> bool {
-        self.should_exit
-    }
-
-    fn view(&self) -> Element<Message> {
-        let page = match &self.page {
-            AppPage::Welcome => self.welcome_page(),
-            AppPage::CreateBackup => self.create_backup_page(),
-            AppPage::BackupGenerating => self.generating_page(),
-            AppPage::BackupResults => self.backup_results_page(),
-            AppPage::RestoreBackup => self.recover_backup_page(),
-            AppPage::DecodeSuccess => self.decode_success_page(),
-            AppPage::DecodeFailure => self.decode_failure_page(),
-        };
-
-        container(page)
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .padding(40)
-            .center_x()
-            .center_y()
-            .into()
-    }
-}
-
+            Message::FolderFileScanned(index, codes) => {
+                // Results of a cancelled import may still arrive
+                if let Some(import) = &mut self.restore.folder_import {
+                    if import.scanned == index {
+                        let file = import.files[index].clone();
+                        import.add(&file, codes);
+                        import.scanned += 1;
+                        return self.scan_next_folder_file();
+                    }
+                }
+            }
+            Message::RestoreBackupGroup(backup_id) => {
+                if let Some(mut import) = self.restore.folder_import.take() {
+                    for shard in import.groups.remove(&backup_id).unwrap_or_default() {
+                        self.add_scanned_shard(shard.to_bytes());
+                    }
+                    import.clear();
+                }
+                self.page = AppPage::RestoreBackup;
+            }
+            Message::CancelFolderImport => {
+                if let Some(mut import) = self.restore.folder_import.take() {
+                    import.clear();
+                }
+                self.page = AppPage::RestoreBackup;
+            }
+            Message::FileImported(codes) => {
+                self.restore.is_scanning = false;
+                match codes {
+                    Ok((files, codes)) => {
+                        codes
+                            .into_iter()
+                            .for_each(|data| self.add_scanned_shard(data));
+                        self.restore.loaded_files.extend(files);
+                    }
+                    Err(error) => self.restore.error = Some(error),
+                }
+            }
+            Message::SecureDeleteToggled(index, selected) => {
+                if let Some(file) = self.loaded_files.get_mut(index) {
+                    file.selected = selected;
+                }
+            }
+            Message::SecureDeleteSelected => {
+                let selected = self
+                    .loaded_files
+                    .iter()
+                    .filter(|file| file.selected && file.status.is_none())
+                    .map(|file| file.path.clone())
+                    .collect::<Vec<PathBuf>>();
+                return Command::batch(
+                    selected
+                        .into_iter()
+                        .map(|path| self.update(Message::SecureDelete(path))),
+                );
+            }
+            Message::SecureDelete(path) => {
+                return Command::perform(
+                    async move {
+                        let result = secure_delete(&path).map_err(|error| error.to_string());
+                        (path, result)
+                    },
+                    |(path, result)| Message::SecureDeleted(path, result),
+                );
+            }
+            Message::SecureDeleted(path, result) => {
+                if let Some(file) = self.loaded_files.iter_mut().find(|file| file.path == path) {
+                    file.selected = false;
+                    file.status = Some(result);
+                }
+            }
+            Message::ExtendCountChanged(additional) => {
+                if let Some(extend) = &mut self.extend {
+                    extend.additional = additional.clamp(1, extend.max_additional().max(1));
+                }
+            }
+            Message::ExtendFirstChanged(first) => {
+                if let Some(extend) = &mut self.extend {
+                    extend.first = first.clamp(extend.next_number(), u8::MAX as usize);
+                    extend.additional = extend.additional.min(extend.max_additional().max(1));
+                }
+            }
+            Message::ExtendBackup => {
+                let extend = match self.extend.as_mut() {
+                    Some(extend) if !extend.extending => extend,
+                    _ => return Command::none(),
+                };
+                let existing = RestoredContext::Shards {
+                    shards: extend.shards.clone(),
+                    password: extend.password.clone(),
+                };
+                let first = extend.first;
+                let additional = extend.additional;
+                extend.extending = true;
+                extend.error = None;
+                return Command::perform(
+                    async move {
+                        extend_backup_from(existing, Some(first), additional)
+                            .map_err(|error| error.to_string())
+                    },
+                    Message::BackupExtended,
+                );
+            }
+            Message::BackupExtended(result) => {
+                // The session was cleared while the shards were dealt
+                let extend = match self.extend.as_mut() {
+                    Some(extend) => extend,
+                    None => return Command::none(),
+                };
+                extend.extending = false;
+                let shards = match result {
+                    Ok(shards) => shards,
+                    Err(error) => {
+                        extend.error = Some(error);
+                        return Command::none();
+                    }
+                };
+                extend.dealt = true;
+                if let Some(metadata) = shards.first().and_then(|shard| shard.metadata.as_ref()) {
+                    self.label = metadata.label.clone();
+                }
+                self.shard_qrcodes = shards
+                    .iter()
+                    .map(|shard| shard_qrcode(shard).ok())
+                    .collect();
+                self.generated_backup = Some(shards);
+                self.reprint = None;
+                self.recovery_seed = None;
+                self.stream_output = None;
+                self.saved_shares.clear();
+                self.shown_words = None;
+                self.print_error = None;
+                self.unsaved_warning = false;
+                self.verify_result = None;
+                self.page = AppPage::BackupResults;
+            }
+            Message::ShareTextChanged(text) => {
+                self.restore.share_text = text;
+                self.restore.share_text_error = None;
+            }
+            Message::AddShare => {
+                let text = std::mem::take(&mut self.restore.share_text);
+                // SLIP-0039 shares are parsed like any other payload
+                let words = looks_like_words(&text) && !looks_like_mnemonic(&text);
+                let parsed = if words {
+                    let words = text
+                        .split_whitespace()
+                        .map(str::to_owned)
+                        .collect::<Vec<String>>();
+                    decode_words(&words).map_err(anyhow::Error::from)
+                } else {
+                    parse_share_payload(&text)
+                };
+                let checked = parsed
+                    .map_err(|error| match classify_payload(&text) {
+                        _ if words => format!("{}.", error),
+                        PayloadKind::Share | PayloadKind::Malformed => format!("{}.", error),
+                        kind => format!("{}.", kind),
+                    })
+                    .and_then(|data| self.check_typed_share(&data).map(|()| data));
+                match checked {
+                    Ok(data) => {
+                        self.restore.error = None;
+                        self.add_scanned_shard(data);
+                    }
+                    Err(error) => {
+                        self.restore.share_text_error = Some(error);
+                        // Keep the text, so a typo can be fixed instead of typing it again
+                        self.restore.share_text = text;
+                    }
+                }
+            }
+            Message::FocusNext => return widget::focus_next(),
+            Message::FocusPrevious => return widget::focus_previous(),
+            Message::Shortcut(shortcut) => {
+                let action = match shortcut {
+                    Shortcut::Back if self.show_shortcuts => Some(Message::ToggleShortcuts),
+                    Shortcut::Back => self.back_action(),
+                    _ if self.show_shortcuts || self.confirm_exit || !self.reminders.is_empty() => {
+                        None
+                    }
+                    Shortcut::Next => self.next_action(),
+                    Shortcut::GeneratePassphrase => matches!(self.page, AppPage::CreateBackup)
+                        .then_some(Message::GeneratePassphrase),
+                };
+                if let Some(message) = action {
+                    return self.update(message);
+                }
+            }
+            Message::ToggleShortcuts => {
+                self.show_shortcuts = !self.show_shortcuts;
+            }
+            Message::RemoveShard(index) => {
+                self.restore.scanned_shards.remove(index).data.zeroize();
+                self.restore.encoded_shares = None;
+                self.restore.error = None;
+            }
+            Message::DecodeSecrets => match self.collect_shards() {
+                Ok(shards) => {
+                    self.restore.encoded_shares =
+                        Some(shards.iter().map(BackupShard::to_bytes).collect());
+                    self.restore.decrypt_failures = 0;
+                    return self.update(Message::RetryDecrypt);
+                }
+                Err(error) => {
+                    self.restore.error = Some(error);
+                }
+            },
+            Message::RetryDecrypt => {
+                let shares = match &self.restore.encoded_shares {
+                    Some(shares) if !self.restore.decrypting => shares.clone(),
+                    _ => return Command::none(),
+                };
+                let passphrase = self.restore.passphrase.clone();
+                let key_file = self
+                    .restore
+                    .key_file
+                    .as_ref()
+                    .map(|key_file| key_file.digest);
+                self.restore.decrypting = true;
+                self.restore.error = None;
+                // The shards decide the work factor, which may take a while to derive
+                return Command::perform(
+                    async move { restore_backup(shares, &passphrase, key_file.as_ref()) },
+                    Message::DecryptFinished,
+                );
+            }
+            // The session was cleared while decrypting
+            Message::DecryptFinished(_) if !self.restore.decrypting => {}
+            Message::DecryptFinished(result) => {
+                self.restore.decrypting = false;
+                match result {
+                    Ok(restored) => {
+                        self.decode_notice = None;
+                        self.finish_restore(restored.secret, restored.shards_used);
+                    }
+                    Err(RestoreError::WrongPassphrase) => {
+                        self.restore.decrypt_failures += 1;
+                        let hint = if self.restore.decrypt_failures >= MAX_DECRYPT_HINT_FAILURES {
+                            " The passphrase might belong to a different backup."
+                        } else {
+                            ""
+                        };
+                        self.restore.error = Some(format!(
+                            "Decryption failed - check your passphrase (attempt {}).{}",
+                            self.restore.decrypt_failures, hint
+                        ));
+                    }
+                    Err(RestoreError::ShareVerificationError { index, number }) => {
+                        // The shares were encoded in the order they were scanned
+                        if index < self.restore.scanned_shards.len() {
+                            self.restore.scanned_shards.remove(index).data.zeroize();
+                        }
+                        self.restore.encoded_shares = None;
+                        self.restore.error = Some(damaged_shard_message(number));
+                    }
+                    Err(error) => {
+                        self.restore.encoded_shares = None;
+                        self.restore.error = Some(format!("{}.", error));
+                    }
+                }
+            }
+            Message::DiagnoseShards => {
+                let shards = self.collected_shards();
+                let key_file = self
+                    .restore
+                    .key_file
+                    .as_ref()
+                    .map(|key_file| &key_file.digest);
+                let passphrase =
+                    match unlock_password(&shards, &self.restore.passphrase, key_file, None) {
+                        Ok(passphrase) => passphrase,
+                        Err(error) => {
+                            self.restore.error = Some(format!("{}.", error));
+                            return Command::none();
+                        }
+                    };
+                self.page = AppPage::Diagnosing;
+                return Command::perform(
+                    async move { diagnose_shards(&shards, &passphrase) },
+                    Message::DiagnosisComplete,
+                );
+            }
+            Message::DiagnosisComplete(Some(diagnosis)) => {
+                self.decode_notice = match diagnosis.damaged.len() {
+                    0 => None,
+                    _ => Some(format!(
+                        "Backup shard(s) {} appear corrupted; the backup was recovered without them.",
+                        shard_list(&diagnosis.damaged)
+                    )),
+                };
+                self.finish_restore(diagnosis.secret, diagnosis.used);
+            }
+            Message::DiagnosisComplete(None) => {
+                self.restore.error = Some(String::from(
+                    "No valid combination of backup shards was found.",
+                ));
+                self.page = AppPage::RestoreBackup;
+            }
+            Message::RecordRestoreChanged(true) => {
+                let id = rand::random::<u64>();
+                self.history.push(HistoryEntry {
+                    id,
+                    label: self.history_label.clone(),
+                    backup_id: format!("{:016x}", self.decoded_backup_id.unwrap_or_default()),
+                    restored_on: Local::now().date_naive(),
+                    shards_used: self.decoded_shards.clone(),
+                });
+                self.recorded_entry = Some(id);
+                self.save_history();
+            }
+            Message::RecordRestoreChanged(false) => {
+                if let Some(id) = self.recorded_entry.take() {
+                    self.history.retain(|entry| entry.id != id);
+                    self.save_history();
+                }
+            }
+            Message::RecordBackupChanged(true) => {
+                let record = self.generated_backup.as_deref().and_then(|shards| {
+                    BackupRecord::new(shards, &self.label, Local::now().date_naive())
+                });
+                if let Some(record) = record {
+                    self.recorded_backup = Some(record.backup_id.clone());
+                    self.backup_history_error = self
+                        .backup_history
+                        .add(record)
+                        .err()
+                        .map(|error| format!("Failed to save backup history: {}", error));
+                }
+            }
+            Message::RecordBackupChanged(false) => {
+                if let Some(backup_id) = self.recorded_backup.take() {
+                    self.backup_history_error = self
+                        .backup_history
+                        .delete(&backup_id)
+                        .err()
+                        .map(|error| format!("Failed to save backup history: {}", error));
+                }
+            }
+            Message::HistoryLabelChanged(label) => {
+                self.history_label = label;
+                if let Some(id) = self.recorded_entry {
+                    if let Some(entry) = self.history.iter_mut().find(|entry| entry.id == id) {
+                        entry.label = self.history_label.clone();
+                    }
+                    self.save_history();
+                }
+            }
+            Message::ReverifyBackup(backup_id) => {
+                self.restore.clear();
+                self.restore.expected = self.backup_history.find(&backup_id).cloned();
+                self.page = AppPage::RestoreBackup;
+            }
+            Message::DeleteBackupRecord(backup_id) => {
+                if self.recorded_backup.as_ref() == Some(&backup_id) {
+                    self.recorded_backup = None;
+                }
+                self.backup_history_error = self
+                    .backup_history
+                    .delete(&backup_id)
+                    .err()
+                    .map(|error| format!("Failed to save backup history: {}", error));
+            }
+            Message::DeleteHistoryEntry(id) => {
+                self.history.retain(|entry| entry.id != id);
+                if self.recorded_entry == Some(id) {
+                    self.recorded_entry = None;
+                }
+                self.save_history();
+            }
+            Message::ToggleSecretVisibility => {
+                self.secret_visible = !self.secret_visible;
+            }
+            Message::CopyToClipboard(contents) => {
+                return self.copy_to_clipboard(contents);
+            }
+            Message::CopySecret => {
+                if let Some(decoded) = &self.decoded_secret {
+                    return self.copy_to_clipboard(decoded.value.to_string());
+                }
+            }
+            Message::ClearClipboardChanged(clear_clipboard) => {
+                self.clear_clipboard = clear_clipboard;
+            }
+            Message::ClipboardTick(timer) => {
+                // A newer copy restarts the countdown
+                if timer != self.clipboard_timer {
+                    return Command::none();
+                }
+                match self.clipboard_remaining {
+                    Some(remaining) if remaining > 1 => {
+                        self.clipboard_remaining = Some(remaining - 1);
+                        return clipboard_tick(timer);
+                    }
+                    _ => {
+                        self.clipboard_remaining = None;
+                        return clipboard::read(Message::ClipboardRead);
+                    }
+                }
+            }
+            Message::ClipboardRead(contents) => {
+                // Leave the clipboard alone if the user has copied something else since
+                let unchanged =
+                    contents.is_some() && contents.as_deref() == self.clipboard_copied.as_deref();
+                if let Some(mut contents) = contents {
+                    contents.zeroize();
+                }
+                self.clipboard_copied = None;
+                if unchanged {
+                    return clipboard::write(String::new());
+                }
+            }
+            Message::PdfTitleChanged(title) => {
+                self.pdf_theme.title = title;
+                self.save_config();
+            }
+            Message::PdfColorChanged(color) => {
+                if let Ok(parsed) = parse_hex_color(&color) {
+                    self.pdf_theme.primary_color = parsed;
+                    self.save_config();
+                }
+                self.pdf_color_input = color;
+            }
+            Message::ChooseLogo => {
+                let file = FileDialog::new()
+                    .add_filter("Images", &["png", "jpg", "jpeg"])
+                    .pick_file();
+                if let Some(file) = file {
+                    self.pdf_theme.logo_path = Some(file);
+                    self.save_config();
+                }
+            }
+            Message::RemoveLogo => {
+                self.pdf_theme.logo_path = None;
+                self.save_config();
+            }
+            Message::ToggleTheme => {
+                self.dark_mode = !self.dark_mode;
+                self.save_config();
+            }
+            Message::ToggleHighContrast => {
+                self.accessibility.high_contrast = !self.accessibility.high_contrast;
+                self.save_config();
+            }
+            Message::FontScaleChanged(font_scale) => {
+                self.accessibility.font_scale = font_scale.clamp(MIN_FONT_SCALE, MAX_FONT_SCALE);
+                self.save_config();
+            }
+            Message::SaveSecret => {
+                let file = FileDialog::new().save_file();
+                if let (Some(file), Some(secret)) = (file, &self.decoded_secret) {
+                    self.save_status = Some(
+                        export_secret(secret, &file, self.trailing_newline)
+                            .map(|_| format!("Saved to {}.", file.display()))
+                            .map_err(|error| format!("Failed to save: {}", error)),
+                    );
+                }
+            }
+            Message::DecryptStreamFile => {
+                let input = FileDialog::new()
+                    .add_filter("Encrypted files", &["enc"])
+                    .pick_file();
+                let output = input.as_ref().and_then(|_| FileDialog::new().save_file());
+                if let (Some(input), Some(output), Some(secret), Some(backup_id)) = (
+                    input,
+                    output,
+                    self.decoded_secret.clone(),
+                    self.decoded_backup_id,
+                ) {
+                    let len = match std::fs::metadata(&input) {
+                        Ok(metadata) => metadata.len(),
+                        Err(error) => {
+                            self.save_status = Some(Err(format!("Failed to decrypt: {}", error)));
+                            return Command::none();
+                        }
+                    };
+                    self.save_status = None;
+                    self.stream_progress = 0;
+                    self.decrypting_stream = Some(DecryptJob {
+                        input: StreamFile {
+                            name: file_name(&input),
+                            len,
+                            path: input,
+                        },
+                        output,
+                        secret,
+                        backup_id,
+                    });
+                }
+            }
+            Message::DecryptProgress(done) => {
+                self.stream_progress = done;
+            }
+            Message::StreamFileDecrypted(result) => {
+                // Ends the subscription that reported the progress
+                self.decrypting_stream = None;
+                self.save_status = Some(result);
+            }
+            Message::TrailingNewlineChanged(trailing_newline) => {
+                self.trailing_newline = trailing_newline;
+            }
+            #[cfg(feature = "tray")]
+            Message::TrayEvent(TrayEvent::Open) => {
+                return window::set_mode(window::Mode::Windowed);
+            }
+            #[cfg(feature = "tray")]
+            Message::TrayEvent(TrayEvent::Quit) => {
+                self.request_exit();
+                // A restore in progress asks for confirmation in the window first
+                if self.confirm_exit {
+                    return window::set_mode(window::Mode::Windowed);
+                }
+            }
+            #[cfg(feature = "tray")]
+            Message::CheckReminders => {
+                if self.tray_icon.is_none() {
+                    match create_tray_icon() {
+                        Ok(icon) => self.tray_icon = Some(icon),
+                        // Without an icon to bring it back from, the window can't stay hidden
+                        Err(error) => {
+                            eprintln!("Failed to add the tray icon: {}", error);
+                            return window::set_mode(window::Mode::Windowed);
+                        }
+                    }
+                }
+                self.notify_reminders();
+                return reminder_tick();
+            }
+        }
+        Command::none()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        let events = subscription::events_with(|event, status| match (event, status) {
+            (
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code: keyboard::KeyCode::Tab,
+                    modifiers,
+                }),
+                event::Status::Ignored,
+            ) if modifiers.shift() => Some(Message::FocusPrevious),
+            (
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code: keyboard::KeyCode::Tab,
+                    ..
+                }),
+                event::Status::Ignored,
+            ) => Some(Message::FocusNext),
+            // A focused text input captures every key but Tab, so typing never triggers these
+            (
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code: keyboard::KeyCode::Enter | keyboard::KeyCode::NumpadEnter,
+                    ..
+                }),
+                event::Status::Ignored,
+            ) => Some(Message::Shortcut(Shortcut::Next)),
+            (
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code: keyboard::KeyCode::Escape,
+                    ..
+                }),
+                event::Status::Ignored,
+            ) => Some(Message::Shortcut(Shortcut::Back)),
+            (
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code: keyboard::KeyCode::G,
+                    modifiers,
+                }),
+                event::Status::Ignored,
+            ) if modifiers.command() => Some(Message::Shortcut(Shortcut::GeneratePassphrase)),
+            (Event::Window(window::Event::CloseRequested), _) => Some(Message::End),
+            (Event::Window(window::Event::FileHovered(_)), _) => Some(Message::FileHovered),
+            (Event::Window(window::Event::FilesHoveredLeft), _) => Some(Message::FilesHoveredLeft),
+            (Event::Window(window::Event::FileDropped(file)), _) => {
+                Some(Message::FileDropped(file))
+            }
+            _ => None,
+        });
+        let mut subscriptions = vec![events];
+        if let Some(job) = &self.streaming {
+            subscriptions.push(stream_backup(job.clone()));
+        }
+        if let Some(job) = &self.decrypting_stream {
+            subscriptions.push(decrypt_stream(job.clone()));
+        }
+        #[cfg(feature = "tray")]
+        if self.tray {
+            subscriptions.push(tray_events());
+        }
+        Subscription::batch(subscriptions)
+    }
+
+    fn should_exit(&self) -> bool {
+        self.should_exit
+    }
+
+    fn view(&self) -> Element<Message> {
+        let page = match &self.page {
+            _ if self.show_shortcuts => self.shortcuts_page(),
+            _ if self.confirm_exit => self.confirm_exit_page(),
+            _ if !self.reminders.is_empty() => self.reminder_page(),
+            AppPage::Welcome => self.welcome_page(),
+            AppPage::CreateBackup => self.create_backup_page(),
+            AppPage::BackupGenerating => self.generating_page(),
+            AppPage::Diagnosing => self.diagnosing_page(),
+            AppPage::BackupResults => self.backup_results_page(),
+            AppPage::VerifyBackup => self.verify_backup_page(),
+            AppPage::RestoreBackup => self.recover_backup_page(),
+            AppPage::FolderImport => self.folder_import_page(),
+            AppPage::DecodeSuccess => self.decode_success_page(),
+            AppPage::ExtendBackup => self.extend_backup_page(),
+            AppPage::History => self.history_page(),
+            AppPage::PdfSettings => self.pdf_settings_page(),
+            AppPage::Settings => self.settings_page(),
+            AppPage::About => self.about_page(),
+        };
+
+        let mut settings_button = button(
+            image(gear_icon(self.theme().palette().text))
+                .width(Length::Units(GEAR_ICON_SIZE as u16))
+                .height(Length::Units(GEAR_ICON_SIZE as u16)),
+        )
+        .style(theme::Button::Secondary);
+        // Leaving a page that is still working would lose its result
+        if !matches!(
+            self.page,
+            AppPage::Settings | AppPage::BackupGenerating | AppPage::Diagnosing
+        ) {
+            settings_button = settings_button.on_press(Message::OpenSettings);
+        }
+        let header = row![
+            horizontal_space(Length::Fill),
+            settings_button,
+            horizontal_space(Length::Units(10)),
+            button(text("?"))
+                .on_press(Message::ToggleShortcuts)
+                .style(theme::Button::Secondary),
+            horizontal_space(Length::Units(10)),
+            button(text(if self.dark_mode {
+                "Light mode"
+            } else {
+                "Dark mode"
+            }))
+            .on_press(Message::ToggleTheme)
+            .style(theme::Button::Secondary)
+        ]
+        .padding([10, 10, 0, 10]);
+
+        column![
+            header,
+            container(page)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .padding([0, 40, 40, 40])
+                .center_x()
+                .center_y()
+        ]
+        .into()
+    }
+}
+
 impl HyperbackedApp {
+    fn update_stored_sizes(&mut self) {
+        self.stored_sizes = StoredSizes::of(&self.secrets());
+    }
+
+    fn update_passphrase_strength(&mut self) {
+        // A passphrase that repeats the label or secret is much easier to guess
+        let mut user_inputs = vec![self.label.as_str()];
+        if self.secret_encoding == SecretEncoding::Text {
+            user_inputs.push(&self.secret);
+        }
+        self.passphrase_strength = passphrase_strength(&self.passphrase, &user_inputs);
+    }
+
     fn get_passphrase_crack_time(&self) -> String {
-        let estimate = zxcvbn::zxcvbn(&self.passphrase, &[]);
-        match estimate {
-            Ok(estimate) => {
-                let crack_time = estimate.crack_times().offline_slow_hashing_1e4_per_second();
-                format!("{}", crack_time)
+        match &self.passphrase_strength {
+            Some(strength) => strength.crack_time.clone(),
+            None => String::new(),
+        }
+    }
+
+    fn get_passphrase_score(&self) -> Option<u8> {
+        self.passphrase_strength
+            .as_ref()
+            .map(|strength| strength.score)
+    }
+
+    fn is_passphrase_weak(&self) -> bool {
+        self.get_passphrase_score()
+            .is_some_and(|score| score < self.min_passphrase_score)
+    }
+
+    fn passphrase_accepted(&self) -> bool {
+        self.get_passphrase_score().is_some()
+            && (!self.is_passphrase_weak() || self.allow_weak_passphrase)
+    }
+
+    // Each passphrase has to unlock exactly one of the secrets in the backup
+    fn passphrases_distinct(&self) -> bool {
+        self.additional_secrets
+            .iter()
+            .enumerate()
+            .all(|(index, entry)| {
+                entry.passphrase != self.passphrase
+                    && self.additional_secrets[..index]
+                        .iter()
+                        .all(|other| other.passphrase != entry.passphrase)
+            })
+    }
+
+    fn secret_labels(&self) -> Vec<String> {
+        let primary = if self.label.is_empty() {
+            String::from("Secret #1")
+        } else {
+            self.label.clone()
+        };
+        std::iter::once(primary)
+            .chain(
+                self.additional_secrets
+                    .iter()
+                    .enumerate()
+                    .map(|(index, entry)| {
+                        if entry.label.is_empty() {
+                            format!("Secret #{}", index + 2)
+                        } else {
+                            entry.label.clone()
+                        }
+                    }),
+            )
+            .collect()
+    }
+
+    fn finish_restore(&mut self, decoded: DecryptedSecret, numbers: Vec<usize>) {
+        let first = self.collected_shards().into_iter().next();
+        self.decoded_backup_id = first.as_ref().map(|shard| shard.backup_id);
+        if let Some(backup_id) = self.decoded_backup_id {
+            self.audit(AuditEvent::backup_restored(backup_id, numbers.clone()));
+        }
+        self.recorded_entry = None;
+        self.history_label.clear();
+        self.decoded_summary = None;
+        self.decoded_verify_by = None;
+        self.decoded_not_before = None;
+        if let Some(shard) = first {
+            if let Some(metadata) = &shard.metadata {
+                self.decoded_summary = Some(metadata.summary(shard.threshold));
+                self.decoded_verify_by = metadata.verification_due(Utc::now().date_naive());
+                self.decoded_not_before = metadata.restored_early(Utc::now());
+                self.history_label = metadata.label.clone();
+            }
+        }
+        self.decoded_secret = Some(decoded);
+        self.decoded_shards = numbers;
+        self.secret_visible = false;
+        self.save_status = None;
+        let mut loaded_files = std::mem::take(&mut self.restore.loaded_files);
+        loaded_files.sort();
+        loaded_files.dedup();
+        self.loaded_files = loaded_files
+            .into_iter()
+            .map(|path| LoadedFile {
+                path,
+                selected: false,
+                status: None,
+            })
+            .collect();
+        let used = self
+            .collected_shards()
+            .into_iter()
+            .filter(|shard| self.decoded_shards.contains(&shard.number))
+            .collect::<Vec<BackupShard>>();
+        let key_file = self
+            .restore
+            .key_file
+            .as_ref()
+            .map(|key_file| &key_file.digest);
+        // The first release didn't record the threshold, which new shards have to be dealt for
+        // Backups that need a hardware key can't be extended here, as that would ask it again
+        self.extend = unlock_password(&used, &self.restore.passphrase, key_file, None)
+            .ok()
+            .filter(|_| !used.iter().any(BackupShard::is_legacy))
+            .map(|password| ExtendState::new(used, password));
+        // The restore is done, so the collected shards are no longer needed
+        self.restore.clear();
+        self.page = AppPage::DecodeSuccess;
+    }
+
+    fn can_create_backup(&self) -> bool {
+        let additional_complete = self
+            .additional_secrets
+            .iter()
+            .all(|entry| !entry.secret.trim().is_empty() && !entry.passphrase.trim().is_empty());
+        !self.passphrase.trim().is_empty()
+            && self.passphrase == self.passphrase_confirm
+            && self.passphrase_written_down
+            && self.passphrase_accepted()
+            && (!self.secret.trim().is_empty() || self.stream_file.is_some())
+            && additional_complete
+            && self.passphrases_distinct()
+            && self.backup_type.is_valid()
+            && self.expiry_date().is_ok()
+            && self.not_before().is_ok()
+            && self.verify_after().is_ok()
+            && self.kdf_params().is_some()
+            && self.secrets_fit()
+            // Only a single secret can be streamed, and its key is random
+            && (self.stream_file.is_none()
+                || (self.additional_secrets.is_empty() && self.reprint.is_none()))
+            && match &self.reprint {
+                Some(reprint) => {
+                    reprint.seed().is_some()
+                        && reprint.number_valid(self.backup_type.total_shards())
+                }
+                None => true,
+            }
+    }
+
+    /// Longest secret the backup can hold with the current settings.
+    fn max_secret_len(&self) -> usize {
+        let kdf = self.kdf_params().unwrap_or_default();
+        self.backup_type
+            .to_config(
+                self.qrcode_label(),
+                kdf,
+                self.cipher,
+                self.sharing,
+                self.slip39(),
+                self.key_file_digest(),
+                self.not_before().ok().flatten(),
+                self.verify_after().ok().flatten(),
+            )
+            .map_or(MAX_SECRET_LEN, |config| max_secret_len(&config))
+    }
+
+    fn text_size(&self, size: u16) -> u16 {
+        self.accessibility.text_size(size)
+    }
+
+    fn key_file_digest(&self) -> Option<KeyFileDigest> {
+        self.key_file.as_ref().map(|key_file| key_file.digest)
+    }
+
+    fn secrets_fit(&self) -> bool {
+        let max_len = self.max_secret_len();
+        self.stored_sizes.longest() <= max_len
+            && self
+                .secrets()
+                .iter()
+                .all(|secret| plaintext_len(&secret.value, secret.encoding) <= MAX_SECRET_LEN)
+    }
+
+    fn can_extend(&self) -> bool {
+        self.extend
+            .as_ref()
+            .is_some_and(|extend| !extend.extending && extend.max_additional() > 0)
+    }
+
+    /// Whether the results page shows shards added to a restored backup.
+    fn is_extended(&self) -> bool {
+        self.extend.as_ref().is_some_and(|extend| extend.dealt)
+    }
+
+    fn can_verify_backup(&self) -> bool {
+        !self.verify_passphrase.is_empty() && !self.secret.is_empty() && !self.verifying
+    }
+
+    /// What the decrypt button on the restore page does, if it is enabled.
+    fn decrypt_action(&self) -> Option<Message> {
+        let collected_shards = self.collected_shards().len();
+        if self.restore.decrypting {
+            // Disabled until the running attempt finishes
+            None
+        } else if self.restore.encoded_shares.is_some() {
+            Some(Message::RetryDecrypt)
+        } else if collected_shards > 0
+            && collected_shards >= self.required_shards().unwrap_or(0)
+            && self.mismatched_shard().is_none()
+        {
+            Some(Message::DecodeSecrets)
+        } else {
+            None
+        }
+    }
+
+    /// The primary button of the current page, triggered by Enter.
+    fn next_action(&self) -> Option<Message> {
+        match self.page {
+            AppPage::CreateBackup => self.can_create_backup().then_some(Message::CreateBackup),
+            AppPage::RestoreBackup => self.decrypt_action(),
+            AppPage::BackupResults if self.is_extended() => None,
+            AppPage::BackupResults => Some(Message::SwitchPage(AppPage::VerifyBackup)),
+            AppPage::ExtendBackup => self.can_extend().then_some(Message::ExtendBackup),
+            AppPage::VerifyBackup => self.can_verify_backup().then_some(Message::VerifyBackup),
+            _ => None,
+        }
+    }
+
+    /// The back or cancel button of the current page, triggered by Escape.
+    fn back_action(&self) -> Option<Message> {
+        if self.confirm_exit {
+            return Some(Message::CancelExit);
+        }
+        if !self.reminders.is_empty() {
+            return Some(Message::DismissReminder);
+        }
+        match self.page {
+            AppPage::CreateBackup
+            | AppPage::RestoreBackup
+            | AppPage::DecodeSuccess
+            | AppPage::History
+            | AppPage::About => Some(Message::SwitchPage(AppPage::Welcome)),
+            AppPage::PdfSettings => Some(self.pdf_settings_back()),
+            AppPage::Settings => Some(Message::CloseSettings),
+            AppPage::FolderImport => Some(Message::CancelFolderImport),
+            AppPage::BackupResults => Some(Message::LeaveResults { exit: false }),
+            AppPage::VerifyBackup => Some(Message::SwitchPage(AppPage::BackupResults)),
+            AppPage::ExtendBackup => Some(Message::SwitchPage(AppPage::DecodeSuccess)),
+            AppPage::Welcome | AppPage::BackupGenerating | AppPage::Diagnosing => None,
+        }
+    }
+
+    /// The work factor of the chosen security level, or `None` if the advanced rounds are invalid.
+    fn kdf_params(&self) -> Option<KdfParams> {
+        self.security_level.kdf_params().or_else(|| {
+            let kdf = match self.kdf_algorithm {
+                KdfAlgorithm::Pbkdf2 => KdfParams::Pbkdf2 {
+                    rounds: self.kdf_rounds_input.trim().parse().ok()?,
+                },
+                KdfAlgorithm::Argon2id => KdfParams::Argon2id {
+                    memory_mib: self.argon2_memory_input.trim().parse().ok()?,
+                    iterations: self.argon2_iterations_input.trim().parse().ok()?,
+                    parallelism: self.argon2_parallelism_input.trim().parse().ok()?,
+                },
+            };
+            kdf.validate().is_ok().then_some(kdf)
+        })
+    }
+
+    fn update_kdf_estimate(&mut self) {
+        self.kdf_estimate = self.kdf_params().map(|kdf| kdf.estimate_duration());
+    }
+
+    /// The optional expiry date typed on the create page, which has to lie in the future.
+    fn expiry_date(&self) -> Result<Option<NaiveDate>, ()> {
+        let expiry = self.expiry.trim();
+        if expiry.is_empty() {
+            return Ok(None);
+        }
+        match NaiveDate::parse_from_str(expiry, "%Y-%m-%d") {
+            Ok(date) if date > Local::now().date_naive() => Ok(Some(date)),
+            _ => Err(()),
+        }
+    }
+
+    /// The optional date typed on the create page, before which restoring the backup shows a
+    /// reminder, starting at midnight UTC.
+    fn not_before(&self) -> Result<Option<DateTime<Utc>>, ()> {
+        let not_before = self.not_before.trim();
+        if not_before.is_empty() {
+            return Ok(None);
+        }
+        match NaiveDate::parse_from_str(not_before, "%Y-%m-%d") {
+            Ok(date) if date > Utc::now().date_naive() => {
+                Ok(date.and_hms_opt(0, 0, 0).map(|time| time.and_utc()))
+            }
+            _ => Err(()),
+        }
+    }
+
+    fn verify_after(&self) -> Result<Option<u8>, ()> {
+        let verify_after = self.verify_after.trim();
+        if verify_after.is_empty() {
+            return Ok(None);
+        }
+        match verify_after.parse() {
+            Ok(years @ 1..=50) => Ok(Some(years)),
+            _ => Err(()),
+        }
+    }
+
+    fn unsaved_shares(&self) -> usize {
+        self.generated_backup.as_ref().map_or(0, |backup| {
+            backup
+                .iter()
+                .filter(|shard| self.is_shown(shard) && !self.saved_shares.contains(&shard.number))
+                .count()
+        })
+    }
+
+    /// Whether the results page lists the shard, which is only the reprinted one when reprinting.
+    /// The others are kept, as verifying needs enough of them.
+    fn is_shown(&self, shard: &BackupShard) -> bool {
+        match self.reprint.as_ref().and_then(ReprintState::number) {
+            Some(number) => shard.number == number,
+            None => true,
+        }
+    }
+
+    /// The label stored in the shards, which stays empty if the user keeps it off the QR codes.
+    fn qrcode_label(&self) -> &str {
+        // SLIP-0039 shares have no room for it
+        if self.hide_label_in_qr || self.slip39() {
+            ""
+        } else {
+            &self.label
+        }
+    }
+
+    /// Streamed files are backed up by a random key, which isn't a master secret.
+    fn slip39(&self) -> bool {
+        self.slip39 && self.stream_file.is_none()
+    }
+
+    fn save_config(&self) {
+        let config = Config {
+            label: self.label.clone(),
+            hide_label_in_qr: self.hide_label_in_qr,
+            passphrase_words: self.passphrase_words,
+            output_dir: self.output_dir.clone(),
+            audit_log: self.audit_log.clone(),
+            dark_mode: self.dark_mode,
+            accessibility: self.accessibility,
+            clipboard_clear_secs: self.clipboard_clear_secs,
+            reminder_days: self.reminder_days,
+            min_passphrase_score: self.min_passphrase_score,
+            backup_type: self.backup_type,
+            pdf_theme: self.pdf_theme.clone(),
+            tracked_backups: self.tracked_backups.clone(),
+        };
+        if let Err(error) = config.save() {
+            eprintln!("Failed to save config: {}", error);
+        }
+    }
+
+    fn save_history(&self) {
+        if let Some(path) = &self.history_path {
+            if let Err(error) = save_history(path, &self.history) {
+                eprintln!("Failed to save restore history: {}", error);
+            }
+        }
+    }
+
+    fn audit(&self, event: AuditEvent) {
+        if let Some(path) = &self.audit_log {
+            if let Err(error) = append_audit_log(&event, path) {
+                eprintln!("Failed to write audit log: {}", error);
+            }
+        }
+    }
+
+    fn save_file_dialog(&mut self, name: &str, extension: &str) -> Option<PathBuf> {
+        let mut dialog = FileDialog::new().add_filter(name, &[extension]);
+        if let Some(output_dir) = &self.output_dir {
+            dialog = dialog.set_directory(output_dir);
+        }
+
+        let file = dialog.save_file();
+        if let Some(parent) = file.as_ref().and_then(|f| f.parent()) {
+            self.output_dir = Some(parent.to_path_buf());
+        }
+        file
+    }
+
+    fn add_scanned_shard(&mut self, data: Vec<u8>) {
+        if let Some((part, total)) = fragment_part(&data) {
+            return self.add_scanned_fragment(data, part, total);
+        }
+        match self.check_scanned_shard(data) {
+            Ok(shard) => {
+                self.restore.scanned_shards.push(shard);
+                self.restore.encoded_shares = None;
+            }
+            Err(error) => self.restore.error = Some(error),
+        }
+    }
+
+    fn check_scanned_shard(&self, data: Vec<u8>) -> Result<BackupShard, String> {
+        match BackupShard::from_bytes(data) {
+            Ok(shard)
+                if self
+                    .restore
+                    .scanned_shards
+                    .iter()
+                    .any(|s| s.number == shard.number) =>
+            {
+                Err(format!(
+                    "Backup shard #{} has already been scanned.",
+                    shard.number
+                ))
+            }
+            Ok(shard)
+                if self
+                    .restore
+                    .scanned_shards
+                    .iter()
+                    .any(|s| s.backup_id != shard.backup_id) =>
+            {
+                Err(format!(
+                    "Backup shard #{} belongs to a different backup than the shards collected so far.",
+                    shard.number
+                ))
+            }
+            Ok(shard) => Ok(shard),
+            Err(error) => Err(match error.downcast_ref::<BackupError>() {
+                Some(BackupError::DamagedShard(number)) => damaged_shard_message(*number),
+                Some(BackupError::NewerVersion(_)) => format!("{}.", error),
+                _ => format!("{}.", PayloadKind::NotAShare),
+            }),
+        }
+    }
+
+    /// Checks a typed share before it's added, so a rejected one can stay in the text field to be
+    /// corrected.
+    fn check_typed_share(&self, data: &[u8]) -> Result<(), String> {
+        let (part, total) = match fragment_part(data) {
+            Some(fragment) => fragment,
+            None => return self.check_scanned_shard(data.to_vec()).map(drop),
+        };
+        if self
+            .restore
+            .fragments
+            .iter()
+            .any(|fragment| fragment == data)
+        {
+            return Err(format!(
+                "Part {} of {} has already been scanned.",
+                part, total
+            ));
+        }
+        let mut fragments = self.restore.fragments.clone();
+        fragments.push(data.to_vec());
+        match reassemble_fragments(fragments) {
+            Ok(shares) => shares
+                .into_iter()
+                .try_for_each(|share| self.check_scanned_shard(share).map(drop)),
+            Err(FragmentError::MissingPart { .. }) => Ok(()),
+            Err(error) => Err(format!("{}.", error)),
+        }
+    }
+
+    fn add_scanned_fragment(&mut self, data: Vec<u8>, part: u8, total: u8) {
+        if self.restore.fragments.contains(&data) {
+            self.restore.error = Some(format!(
+                "Part {} of {} has already been scanned.",
+                part, total
+            ));
+            return;
+        }
+        self.restore.fragments.push(data);
+        let result = reassemble_fragments(self.restore.fragments.clone());
+        if !matches!(result, Err(FragmentError::MissingPart { .. })) {
+            self.restore.fragments.iter_mut().for_each(Zeroize::zeroize);
+            self.restore.fragments.clear();
+        }
+        match result {
+            Ok(shares) => {
+                self.restore.error = None;
+                shares
+                    .into_iter()
+                    .for_each(|share| self.add_scanned_shard(share));
+            }
+            Err(FragmentError::MissingPart { part: missing, .. }) => {
+                self.restore.error = Some(format!(
+                    "Scanned part {} of {} of a backup shard, scan part {} next.",
+                    part, total, missing
+                ));
+            }
+            Err(error) => self.restore.error = Some(format!("{}.", error)),
+        }
+    }
+
+    fn secure_delete_view(&self) -> Element<Message> {
+        if self.loaded_files.is_empty() {
+            return column![].into();
+        }
+        let grey = theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5));
+        let mut files = column![text("Securely delete loaded share files")].spacing(5);
+        for (index, file) in self.loaded_files.iter().enumerate() {
+            let name = file.path.display().to_string();
+            files = files.push(match &file.status {
+                None => Element::from(checkbox(name, file.selected, move |selected| {
+                    Message::SecureDeleteToggled(index, selected)
+                })),
+                Some(Ok(())) => text(format!("Deleted {}", name)).style(grey).into(),
+                Some(Err(error)) => text(format!("Could not delete {}: {}", name, error))
+                    .style(self.theme().palette().danger)
+                    .into(),
+            });
+        }
+        let mut delete = button(text("Delete selected"))
+            .padding(10)
+            .style(theme::Button::Destructive);
+        if self
+            .loaded_files
+            .iter()
+            .any(|file| file.selected && file.status.is_none())
+        {
+            delete = delete.on_press(Message::SecureDeleteSelected);
+        }
+        files
+            .push(
+                row![
+                    delete,
+                    horizontal_space(Length::Units(10)),
+                    text("Overwriting can't reach every copy on SSDs and flash drives.")
+                        .size(self.text_size(SMALL_TEXT_SIZE))
+                        .style(grey),
+                ]
+                .align_items(Alignment::Center),
+            )
+            .into()
+    }
+
+    fn import_next_dropped_file(&self) -> Command<Message> {
+        let next = self
+            .restore
+            .dropped_files
+            .iter()
+            .position(|(_, result)| result.is_none());
+        match next {
+            Some(index) => {
+                let file = self.restore.dropped_files[index].0.clone();
+                Command::perform(
+                    async move { import_shard_file(&file).map_err(|e| e.to_string()) },
+                    move |codes| Message::DroppedFileImported(index, codes),
+                )
+            }
+            None => Command::none(),
+        }
+    }
+
+    fn scan_next_folder_file(&self) -> Command<Message> {
+        match &self.restore.folder_import {
+            Some(import) if !import.is_done() => {
+                let index = import.scanned;
+                let file = import.files[index].clone();
+                Command::perform(
+                    async move { qrcode_read_file(&file).map_err(|e| e.to_string()) },
+                    move |codes| Message::FolderFileScanned(index, codes),
+                )
+            }
+            _ => Command::none(),
+        }
+    }
+
+    /// Writes to the clipboard, and starts the countdown to clear it again if that is enabled.
+    fn copy_to_clipboard(&mut self, contents: String) -> Command<Message> {
+        self.clipboard_timer += 1;
+        if !self.clear_clipboard {
+            return clipboard::write(contents);
+        }
+
+        self.clipboard_copied = Some(SecretString::from(contents.clone()));
+        self.clipboard_remaining = Some(self.clipboard_clear_secs);
+        Command::batch([
+            clipboard::write(contents),
+            clipboard_tick(self.clipboard_timer),
+        ])
+    }
+
+    /// Exits, unless a restore is in progress, which asks for confirmation first.
+    fn request_exit(&mut self) {
+        if self.restore.in_progress() {
+            self.confirm_exit = true;
+        } else {
+            self.save_config();
+            self.wipe_create_flow();
+            self.should_exit = true;
+        }
+    }
+
+    /// Hides the window instead of exiting, wiping everything it showed as the app keeps running.
+    #[cfg(feature = "tray")]
+    fn hide_to_tray(&mut self) -> Command<Message> {
+        self.save_config();
+        self.wipe_create_flow();
+        self.decoded_secret = None;
+        self.decoded_shards.clear();
+        self.extend = None;
+        self.page_before_settings = None;
+        self.page = AppPage::Welcome;
+        window::set_mode(window::Mode::Hidden)
+    }
+
+    /// Shows a notification for each tracked backup that is due, once a day.
+    #[cfg(feature = "tray")]
+    fn notify_reminders(&mut self) {
+        let today = Local::now().date_naive();
+        let config = Config {
+            reminder_days: self.reminder_days,
+            tracked_backups: self.tracked_backups.clone(),
+            ..Default::default()
+        };
+        for backup in config.due_reminders(today) {
+            if self.notified.get(&backup.backup_id) == Some(&today) {
+                continue;
+            }
+            match notify_expiring(&backup, today) {
+                Ok(()) => {
+                    self.notified.insert(backup.backup_id, today);
+                }
+                Err(error) => eprintln!("Failed to show a notification: {}", error),
+            }
+        }
+    }
+
+    /// Clears everything typed on the create page, along with the passphrase used to verify.
+    fn wipe_create_flow(&mut self) {
+        self.secret.zeroize();
+        self.secret_generated = false;
+        self.secret_note.zeroize();
+        self.secret_file = None;
+        self.secret_file_error = None;
+        self.passphrase.zeroize();
+        self.passphrase_confirm.zeroize();
+        self.passphrase_written_down = false;
+        self.allow_weak_passphrase = false;
+        self.key_file = None;
+        self.key_file_error = None;
+        self.recovery_seed = None;
+        self.reprint = None;
+        self.stream_file = None;
+        self.stream_output = None;
+        self.verify_passphrase.zeroize();
+        self.passphrase_strength = None;
+        // The entries zeroize themselves when dropped
+        self.additional_secrets.clear();
+        self.extend = None;
+        self.update_stored_sizes();
+    }
+
+    /// The secret and its decoys, copied into strings that are zeroized once the backup is done.
+    fn secrets(&self) -> Vec<Secret> {
+        let mut secrets = vec![Secret {
+            value: self.secret.clone(),
+            password: self.passphrase.clone(),
+            encoding: self.secret_encoding,
+            note: self.secret_note.clone(),
+        }];
+        secrets.extend(self.additional_secrets.iter().map(|entry| Secret {
+            value: entry.secret.clone(),
+            password: entry.passphrase.clone(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }));
+        secrets
+    }
+
+    fn qrcodes_per_shard(&self) -> usize {
+        qrcode_count(share_len(
+            self.stored_sizes.longest(),
+            self.qrcode_label(),
+            self.cipher,
+        ))
+    }
+
+    fn collected_shards(&self) -> Vec<BackupShard> {
+        self.restore.scanned_shards.clone()
+    }
+
+    fn required_shards(&self) -> Option<usize> {
+        self.collected_shards()
+            .first()
+            .map(|shard| shard.threshold as usize)
+    }
+
+    fn mismatched_shard(&self) -> Option<usize> {
+        let shards = self.collected_shards();
+        let backup_id = shards.first()?.backup_id;
+        shards
+            .iter()
+            .find(|shard| shard.backup_id != backup_id)
+            .map(|shard| shard.number)
+    }
+
+    fn collected_summary(&self) -> String {
+        let mut numbers = self
+            .collected_shards()
+            .iter()
+            .map(|shard| shard.number)
+            .collect::<Vec<usize>>();
+        numbers.sort_unstable();
+
+        let list = numbers
+            .iter()
+            .map(|number| format!("#{}", number))
+            .collect::<Vec<String>>()
+            .join(", ");
+        match (numbers.len(), self.required_shards()) {
+            (0, _) | (_, None) => String::from("No backup shards collected yet"),
+            // Shards of the first release don't say how many are needed
+            (count, Some(0)) => format!("{} backup shard(s) collected: {}", count, list),
+            (count, Some(required)) if count > required => format!(
+                "{} of {} required backup shards collected: {} (only {} are needed)",
+                count, required, list, required
+            ),
+            (count, Some(required)) => format!(
+                "{} of {} required backup shards collected: {}",
+                count, required, list
+            ),
+        }
+    }
+
+    fn collect_shards(&self) -> Result<Vec<BackupShard>, String> {
+        let shards = self.restore.scanned_shards.clone();
+        if let Some(number) = self.mismatched_shard() {
+            return Err(format!(
+                "Backup shard #{} belongs to a different backup and can't be combined.",
+                number
+            ));
+        }
+        Ok(shards)
+    }
+
+    fn decode_success_page(&self) -> Element<Message> {
+        let (secret, encoding) = match &self.decoded_secret {
+            Some(decoded) => (&*decoded.value, decoded.encoding),
+            None => ("", SecretEncoding::Text),
+        };
+        let shard_list = shard_list(&self.decoded_shards);
+        let decode_notice = match &self.decode_notice {
+            Some(notice) => text(notice).style(self.theme().palette().danger),
+            None => text(""),
+        };
+        let verify_notice = match self.decoded_verify_by {
+            Some(verify_by) => text(format!(
+                "This backup was due to be verified by {}. Check that every shard is still readable, or create a new backup.",
+                verify_by.format("%Y-%m")
+            ))
+            .style(self.theme().palette().primary),
+            None => text(""),
+        };
+        let not_before_notice = match self.decoded_not_before {
+            Some(not_before) => text(format!(
+                "This backup was marked not to be restored before {}. Make sure that restoring it now is intended.",
+                not_before.format("%Y-%m-%d")
+            ))
+            .style(self.theme().palette().primary),
+            None => text(""),
+        };
+        let shown_secret = if self.secret_visible {
+            secret.to_owned()
+        } else {
+            "•".repeat(secret.chars().count().min(64))
+        };
+        let stream_key = self.decoded_secret.as_ref().is_some_and(is_stream_key);
+        let secret_view = match self.decoded_secret.as_ref().map(DecryptedSecret::to_bytes) {
+            _ if stream_key => text("This backup holds the key of a file that was encrypted separately. Choose that file to decrypt it.").into(),
+            Some(Ok(data)) if encoding == SecretEncoding::Base64 => self.binary_secret_view(&data),
+            _ => self.text_secret_view(shown_secret),
+        };
+        let note_view: Element<Message> = match &self.decoded_secret {
+            Some(decoded) if !decoded.note.is_empty() => column![
+                text("Note stored with the secret"),
+                vertical_space(Length::Units(5)),
+                row![
+                    container(text(&*decoded.note))
+                        .width(Length::Fill)
+                        .padding(10)
+                        .style(theme::Container::Box),
+                    horizontal_space(Length::Units(10)),
+                    button(text("Copy note"))
+                        .padding(10)
+                        .on_press(Message::CopyToClipboard(decoded.note.to_string())),
+                ]
+                .align_items(Alignment::Center),
+                vertical_space(Length::Units(10)),
+            ]
+            .into(),
+            _ => column![].into(),
+        };
+        let save_status: Element<Message> = match (&self.decrypting_stream, &self.save_status) {
+            (Some(job), _) => column![
+                progress_bar(0.0..=job.input.len as f32, self.stream_progress as f32)
+                    .width(Length::Units(400)),
+                text(format!(
+                    "Decrypting {}: {} of {} MB",
+                    job.input.name,
+                    self.stream_progress / 1_000_000,
+                    job.input.len / 1_000_000
+                )),
+            ]
+            .spacing(5)
+            .into(),
+            (None, Some(Ok(status))) => text(status).style(self.theme().palette().success).into(),
+            (None, Some(Err(error))) => text(error).style(self.theme().palette().danger).into(),
+            (None, None) => text("").into(),
+        };
+        let mut save_options = row![button(text(match encoding {
+            SecretEncoding::Text => "Save to file",
+            SecretEncoding::Base64 => "Save as file",
+        }))
+        .padding(10)
+        .on_press(Message::SaveSecret)]
+        .align_items(Alignment::Center);
+        if stream_key {
+            let mut decrypt_button = button(text("Decrypt file...")).padding(10);
+            if self.decrypting_stream.is_none() {
+                decrypt_button = decrypt_button.on_press(Message::DecryptStreamFile);
+            }
+            save_options = row![decrypt_button];
+        } else if encoding == SecretEncoding::Text {
+            save_options = save_options
+                .push(horizontal_space(Length::Units(10)))
+                .push(checkbox(
+                    "Add trailing newline",
+                    self.trailing_newline,
+                    Message::TrailingNewlineChanged,
+                ));
+        }
+        let mut add_shards_button = button("Add shards...")
+            .padding([10, 40])
+            .style(theme::Button::Secondary);
+        if self.extend.is_some() {
+            add_shards_button =
+                add_shards_button.on_press(Message::SwitchPage(AppPage::ExtendBackup));
+        }
+        column![
+            text("Your decrypted secret").size(self.text_size(TITLE_TEXT_SIZE)),
+            vertical_space(Length::Units(20)),
+            text(format!("Restored from backup shard(s) {}.", shard_list))
+                .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+            text(self.decoded_summary.as_deref().unwrap_or_default())
+                .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+            decode_notice,
+            verify_notice,
+            not_before_notice,
+            vertical_space(Length::Units(10)),
+            note_view,
+            secret_view,
+            vertical_space(Length::Units(10)),
+            save_options,
+            save_status,
+            self.secure_delete_view(),
+            vertical_space(Length::Units(10)),
+            row![
+                checkbox(
+                    "Record restore in history",
+                    self.recorded_entry.is_some(),
+                    Message::RecordRestoreChanged
+                ),
+                horizontal_space(Length::Units(10)),
+                text_input(
+                    "Label for the history (optional)...",
+                    &self.history_label,
+                    Message::HistoryLabelChanged
+                )
+                .padding(10),
+            ]
+            .align_items(Alignment::Center),
+            text(
+                "Only the label, backup ID, date and shard numbers are recorded, never the secret."
+            )
+            .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+            vertical_space(Length::Units(20)),
+            row![
+                button("Home")
+                    .padding([10, 40])
+                    .on_press(Message::SwitchPage(AppPage::Welcome))
+                    .style(theme::Button::Secondary),
+                horizontal_space(Length::Fill),
+                add_shards_button,
+                horizontal_space(Length::Units(10)),
+                button("Exit")
+                    .padding([10, 40])
+                    .on_press(Message::End)
+                    .style(theme::Button::Primary),
+            ],
+        ]
+        .align_items(Alignment::Center)
+        .into()
+    }
+
+    fn extend_backup_page(&self) -> Element<Message> {
+        let extend = match &self.extend {
+            Some(extend) => extend,
+            None => return column![].into(),
+        };
+        let first = extend.first;
+        let last = first + extend.additional as usize - 1;
+        let numbers = if extend.additional == 1 {
+            format!("The new shard will be #{}.", first)
+        } else {
+            format!("The new shards will be #{} to #{}.", first, last)
+        };
+        let threshold = extend.shards.first().map_or(0, |shard| shard.threshold);
+        let status = match &extend.error {
+            _ if extend.extending => text("Creating shards..."),
+            Some(error) => text(error).style(self.theme().palette().danger),
+            None => text(""),
+        };
+        let mut create_button = button("Create shards")
+            .padding([10, 40])
+            .style(theme::Button::Primary);
+        if self.can_extend() {
+            create_button = create_button.on_press(Message::ExtendBackup);
+        }
+        column![
+            text("Add shards to your backup").size(self.text_size(TITLE_TEXT_SIZE)),
+            vertical_space(Length::Units(20)),
+            text(format!("New shards are dealt from the ones you just restored with. Together with the existing shards, any {} of them recover the backup, so nothing has to be collected back or printed again.", threshold)),
+            vertical_space(Length::Units(20)),
+            row![
+                stepper_button(
+                    "-",
+                    extend.additional > 1,
+                    Message::ExtendCountChanged(extend.additional.saturating_sub(1))
+                ),
+                text(format!("{} shard(s)", extend.additional))
+                    .width(Length::Units(100))
+                    .horizontal_alignment(Horizontal::Center),
+                stepper_button(
+                    "+",
+                    extend.additional < extend.max_additional(),
+                    Message::ExtendCountChanged(extend.additional.saturating_add(1))
+                ),
+            ]
+            .align_items(Alignment::Center),
+            vertical_space(Length::Units(10)),
+            row![
+                text("Start at"),
+                horizontal_space(Length::Units(10)),
+                stepper_button(
+                    "-",
+                    first > extend.next_number(),
+                    Message::ExtendFirstChanged(first - 1)
+                ),
+                text(format!("#{}", first))
+                    .width(Length::Units(100))
+                    .horizontal_alignment(Horizontal::Center),
+                stepper_button(
+                    "+",
+                    first < u8::MAX as usize,
+                    Message::ExtendFirstChanged(first + 1)
+                ),
+            ]
+            .align_items(Alignment::Center),
+            text(numbers),
+            text("Only the shards loaded for this restore are known here. If shards were added to this backup before, start after the last of them, as shards with the same number can't be combined.")
+                .style(self.theme().palette().danger),
+            text("Anyone holding a threshold of the old shards can still restore the backup. To revoke shards, create a new backup instead.")
+                .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+            vertical_space(Length::Units(10)),
+            status,
+            vertical_space(Length::Fill),
+            row![
+                button("Back")
+                    .padding([10, 40])
+                    .on_press(Message::SwitchPage(AppPage::DecodeSuccess))
+                    .style(theme::Button::Secondary),
+                horizontal_space(Length::Fill),
+                create_button,
+            ],
+        ]
+        .align_items(Alignment::Center)
+        .into()
+    }
+
+    // Lossy UTF-8 would corrupt a binary secret, so it is summarized instead of shown
+    fn binary_secret_view(&self, data: &[u8]) -> Element<Message> {
+        column![
+            text(format!(
+                "This is a binary secret of {} bytes. Save it as a file to restore the original.",
+                data.len()
+            )),
+            vertical_space(Length::Units(10)),
+            text(format!("SHA-256: {}", fingerprint(data)))
+                .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+            vertical_space(Length::Units(10)),
+            container(text(hex_preview(data)))
+                .width(Length::Fill)
+                .padding(10)
+                .style(theme::Container::Box),
+            vertical_space(Length::Fill),
+        ]
+        .height(Length::Fill)
+        .into()
+    }
+
+    fn text_secret_view(&self, shown_secret: String) -> Element<Message> {
+        column![
+            scrollable(
+                container(text(shown_secret))
+                    .width(Length::Fill)
+                    .padding(10)
+                    .style(theme::Container::Box)
+            )
+            .height(Length::Fill),
+            vertical_space(Length::Units(10)),
+            row![
+                button(text(if self.secret_visible {
+                    "Hide"
+                } else {
+                    "Reveal"
+                }))
+                .padding(10)
+                .on_press(Message::ToggleSecretVisibility),
+                horizontal_space(Length::Units(10)),
+                button(text("Copy to clipboard"))
+                    .padding(10)
+                    .on_press(Message::CopySecret),
+                horizontal_space(Length::Units(10)),
+                checkbox(
+                    format!("Clear clipboard after {} s", self.clipboard_clear_secs),
+                    self.clear_clipboard,
+                    Message::ClearClipboardChanged
+                ),
+                horizontal_space(Length::Units(10)),
+                match self.clipboard_remaining {
+                    Some(remaining) => text(format!("clears in {}s", remaining))
+                        .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+                    None => text(""),
+                },
+            ]
+            .align_items(Alignment::Center),
+        ]
+        .height(Length::Fill)
+        .into()
+    }
+
+    fn recover_backup_page(&self) -> Element<Message> {
+        let idle = row![
+            text("Add all required backup shards"),
+            horizontal_space(Length::Fill),
+            button(text("Scan QR"))
+                .padding(10)
+                .on_press(Message::ScanCode),
+            horizontal_space(Length::Units(10)),
+            button(text("Import file"))
+                .padding(10)
+                .on_press(Message::ImportFile),
+            horizontal_space(Length::Units(10)),
+            button(text("Import folder"))
+                .padding(10)
+                .on_press(Message::ImportFolder)
+        ]
+        .align_items(Alignment::Center);
+        let mut scanning = row![text(
+            "Scanning for QR Codes. When using the camera, position the code in front of it."
+        )]
+        .align_items(Alignment::Center);
+        if self.restore.scan_stop.is_some() {
+            scanning = scanning.push(horizontal_space(Length::Fill)).push(
+                button(text("Stop"))
+                    .padding(10)
+                    .on_press(Message::StopScan)
+                    .style(theme::Button::Secondary),
+            );
+        }
+        let required_shards = self.required_shards().unwrap_or(0);
+        let collected_shards = self.collected_shards().len();
+        let scanned_list = self
+            .restore
+            .scanned_shards
+            .iter()
+            .enumerate()
+            .map(|(index, shard)| {
+                let hint = match &self.restore.expected {
+                    Some(record) if !record.contains(shard) => " - not in the recorded backup",
+                    _ if required_shards > 0 && index >= required_shards => " - not needed",
+                    Some(_) => " - matches the record",
+                    None => "",
+                };
+                container(
+                    row![
+                        text(format!(
+                            "Backup shard #{} ({} bytes){}",
+                            shard.number,
+                            shard.data.len(),
+                            hint
+                        )),
+                        horizontal_space(Length::Fill),
+                        button(text("Remove"))
+                            .on_press(Message::RemoveShard(index))
+                            .style(theme::Button::Secondary)
+                    ]
+                    .align_items(Alignment::Center),
+                )
+                .width(Length::Fill)
+                .padding(10)
+                .style(theme::Container::Box)
+                .into()
+            });
+        let mut add_share_button = button(text("Add share")).padding(10);
+        if !self.restore.share_text.trim().is_empty() {
+            add_share_button = add_share_button.on_press(Message::AddShare);
+        }
+        let share_entry = column![
+            row![
+                text_input(
+                    "Type or paste the text printed below a QR code, or the shard's words, and press Enter...",
+                    &self.restore.share_text,
+                    Message::ShareTextChanged
+                )
+                .on_submit(Message::AddShare)
+                .style(theme::TextInput::Custom(Box::new(ValidatedInput(
+                    self.restore.share_text_error.as_ref().map(|_| false)
+                ))))
+                .padding(10),
+                horizontal_space(Length::Units(10)),
+                add_share_button
+            ]
+            .align_items(Alignment::Center),
+            text(self.restore.share_text_error.as_deref().unwrap_or_default())
+                .style(self.theme().palette().danger)
+        ]
+        .spacing(5);
+        let code_list = column(scanned_list.collect::<Vec<Element<Message>>>()).spacing(10);
+        let drop_hint: Element<Message> = if self.file_hovered {
+            container(text("Drop the images to import them as backup shards"))
+                .width(Length::Fill)
+                .padding(20)
+                .center_x()
+                .style(theme::Container::Box)
+                .into()
+        } else {
+            vertical_space(Length::Shrink).into()
+        };
+        let dropped_list = column(
+            self.restore
+                .dropped_files
+                .iter()
+                .map(|(file, result)| {
+                    let status = match result {
+                        None => text("Importing..."),
+                        Some(Ok(1)) => text("1 shard added").style(self.theme().palette().success),
+                        Some(Ok(added)) => text(format!("{} shards added", added))
+                            .style(self.theme().palette().success),
+                        Some(Err(error)) => text(error).style(self.theme().palette().danger),
+                    };
+                    row![text(format!("{}: ", file_name(file))), status].into()
+                })
+                .collect::<Vec<Element<Message>>>(),
+        )
+        .spacing(5)
+        .padding([0, 10]);
+        let mismatch_error = self.mismatched_shard().map(|number| {
+            format!(
+                "Backup shard #{} belongs to a different backup and can't be combined.",
+                number
+            )
+        });
+        let error_text = match self.restore.error.as_ref().or(mismatch_error.as_ref()) {
+            Some(error) => text(error).style(self.theme().palette().danger),
+            None => text(""),
+        };
+        let mut clear_button = button(text("Clear session"))
+            .padding([10, 20])
+            .style(theme::Button::Secondary);
+        if self.restore.in_progress() {
+            clear_button = clear_button.on_press(Message::ClearRestoreSession);
+        }
+        let mut decrypt_button = button(text(if self.restore.decrypting {
+            "Decrypting..."
+        } else if self.restore.encoded_shares.is_some() {
+            "Retry"
+        } else {
+            "Decrypt"
+        }))
+        .padding([10, 40])
+        .style(theme::Button::Positive);
+        let mut diagnose_button = button(text("Diagnose"))
+            .padding([10, 20])
+            .style(theme::Button::Secondary);
+        if self.restore.decrypt_failures > 0
+            && !self.restore.decrypting
+            && required_shards > 0
+            && collected_shards > required_shards
+            && collected_shards <= MAX_DIAGNOSE_SHARDS
+        {
+            diagnose_button = diagnose_button.on_press(Message::DiagnoseShards);
+        }
+        if let Some(action) = self.decrypt_action() {
+            decrypt_button = decrypt_button.on_press(action);
+        }
+        let key_file_prompt: Element<Message> = if self
+            .collected_shards()
+            .iter()
+            .any(BackupShard::requires_key_file)
+        {
+            column![
+                vertical_space(Length::Units(10)),
+                row![
+                    text("Key file "),
+                    text("*").style(self.theme().palette().danger),
+                ],
+                vertical_space(Length::Units(10)),
+                key_file_picker(
+                    self.restore.key_file.as_ref(),
+                    "This backup was created with a key file, choose the same file",
+                    Message::ChooseRestoreKeyFile,
+                    Message::RemoveRestoreKeyFile
+                ),
+            ]
+            .into()
+        } else {
+            column![].into()
+        };
+        let reverify_hint = match &self.restore.expected {
+            Some(record) => text(format!(
+                "Re-verifying {}. Scanned shards are checked against the fingerprints recorded when it was created, decrypting them proves the backup still works.",
+                record.name()
+            )),
+            None => text(""),
+        };
+        column![
+            text("Recover a backup").size(self.text_size(TITLE_TEXT_SIZE)),
+            reverify_hint,
+            vertical_space(Length::Units(20)),
+            if self.restore.is_scanning {
+                scanning
+            } else {
+                idle
+            },
+            vertical_space(Length::Units(10)),
+            text(self.collected_summary())
+                .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+            vertical_space(Length::Units(10)),
+            progress_bar(
+                0.0..=required_shards.max(1) as f32,
+                collected_shards.min(required_shards) as f32
+            )
+            .height(Length::Units(8)),
+            vertical_space(Length::Units(10)),
+            share_entry,
+            vertical_space(Length::Units(10)),
+            drop_hint,
+            dropped_list,
+            scrollable(container(code_list).padding(10)),
+            vertical_space(Length::Fill),
+            column![
+                row![
+                    text("Passphrase "),
+                    text("*").style(self.theme().palette().danger),
+                ],
+                vertical_space(Length::Units(10)),
+                text_input(
+                    "Enter the passphrase...",
+                    &self.restore.passphrase,
+                    Message::RestorePassphraseChanged
+                )
+                .padding(10)
+                .width(Length::Fill),
+            ],
+            key_file_prompt,
+            vertical_space(Length::Units(10)),
+            error_text,
+            vertical_space(Length::Units(10)),
+            row![
+                button(text("Back"))
+                    .padding([10, 40])
+                    .on_press(Message::SwitchPage(AppPage::Welcome))
+                    .style(theme::Button::Secondary),
+                horizontal_space(Length::Fill),
+                clear_button,
+                horizontal_space(Length::Units(10)),
+                diagnose_button,
+                horizontal_space(Length::Units(10)),
+                decrypt_button
+            ]
+        ]
+        .align_items(Alignment::Center)
+        .into()
+    }
+
+    fn backup_results_page(&self) -> Element<Message> {
+        let task_list = match &self.generated_backup {
+            Some(shards) if shards.len() > 0 => column(
+                shards
+                    .iter()
+                    .zip(&self.shard_qrcodes)
+                    .filter(|(shard, _)| self.is_shown(shard))
+                    .map(|(shard, qrcode)| {
+                        let saved = self.saved_shares.contains(&shard.number);
+                        let preview: Element<Message> = match qrcode {
+                            Some(qrcode) => image(qrcode.clone())
+                                .width(Length::Units(QRCODE_PREVIEW_SIZE as u16))
+                                .height(Length::Units(QRCODE_PREVIEW_SIZE as u16))
+                                .into(),
+                            None => text("QR code unavailable").into(),
+                        };
+                        let actions = row![
+                            preview,
+                            horizontal_space(Length::Units(10)),
+                            column![
+                                text(match qrcode_count(shard.to_bytes().len()) {
+                                    1 => format!("Shard #{}", shard.number),
+                                    count =>
+                                        format!("Shard #{} ({} QR codes)", shard.number, count),
+                                }),
+                                text(format!("Fingerprint {}", shard.display_fingerprint()))
+                                    .size(self.text_size(SMALL_TEXT_SIZE))
+                                    .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+                            ],
+                            horizontal_space(Length::Units(10)),
+                            if saved {
+                                text("Saved").style(self.theme().palette().success)
+                            } else {
+                                text("")
+                            },
+                            horizontal_space(Length::Fill),
+                            button(text(if saved { "Re-save PDF" } else { "Save PDF" }))
+                                .on_press(Message::SaveBackup(shard.number)),
+                            horizontal_space(Length::Units(10)),
+                            button(text("Save JSON"))
+                                .on_press(Message::SaveBackupJson(shard.number)),
+                            horizontal_space(Length::Units(10)),
+                            button(text(if self.shown_words == Some(shard.number) {
+                                "Hide words"
+                            } else {
+                                "Words"
+                            }))
+                            .on_press(Message::ToggleShareWords(shard.number))
+                            .style(theme::Button::Secondary)
+                        ]
+                        .padding(10)
+                        .align_items(Alignment::Center);
+                        #[cfg(any(unix, windows))]
+                        let actions = actions.push(horizontal_space(Length::Units(10))).push(
+                            if self.printing.contains(&shard.number) {
+                                button(text("Printing..."))
+                            } else {
+                                button(text("Print")).on_press(Message::PrintShare(shard.number))
+                            },
+                        );
+                        let mut entry = column![actions];
+                        if self.shown_words == Some(shard.number) {
+                            entry = entry.push(share_words_view(shard, self.accessibility));
+                        }
+                        container(entry).style(theme::Container::Box).into()
+                    })
+                    .collect::<Vec<Element<Message>>>(),
+            )
+            .spacing(10),
+            _ => column![container(text(match &self.failure_reason {
+                Some(error) => format!(
+                    "Backup failed to generate: {}\n{}",
+                    error,
+                    failure_hint(error)
+                ),
+                None => String::from("Backup failed to generate: unknown error"),
+            }))
+            .width(Length::Fill)
+            .padding(10)
+            .style(theme::Container::Custom(Box::new(Banner(false))))],
+        };
+        let mut bundle_button = button(text("Export bundle")).padding(10);
+        if !self.bundle_password.is_empty() && self.bundle_password != *self.passphrase {
+            bundle_button = bundle_button.on_press(Message::SaveBundle);
+        }
+
+        let print_error = match &self.print_error {
+            Some(error) => text(error).style(self.theme().palette().danger),
+            None => text(""),
+        };
+
+        // Reprinted and added shards belong to a backup that was created before
+        let record_backup: Element<Message> = if self.reprint.is_none() && !self.is_extended() {
+            column![
+                checkbox(
+                    "Record backup in history (label, date and fingerprints only)",
+                    self.recorded_backup.is_some(),
+                    Message::RecordBackupChanged
+                ),
+                text(self.backup_history_error.as_deref().unwrap_or_default())
+                    .style(self.theme().palette().danger),
+            ]
+            .into()
+        } else {
+            vertical_space(Length::Units(0)).into()
+        };
+
+        let unsaved_banner = if self.unsaved_warning {
+            text(format!(
+                "{} shard(s) haven't been saved yet. Press again to leave anyway.",
+                self.unsaved_shares()
+            ))
+            .style(self.theme().palette().danger)
+        } else {
+            text("")
+        };
+
+        let intro = match self.reprint.as_ref().and_then(ReprintState::number) {
+            _ if self.is_extended() => String::from("The shards below were added to your backup. Any of them can stand in for one of the original shards, so export each of them as a PDF or JSON file and distribute them to trusted people."),
+            _ if self.stream_output.is_some() => format!("The shards below hold the key of {}, which has to be kept as well. Export each of them as a PDF or JSON file and distribute them to trusted people.", self.stream_output.as_deref().map(file_name).unwrap_or_default()),
+            Some(number) => format!("Shard #{} was created again from the recovery seed. If the backup details match the original, it is identical to the lost shard and works together with the ones you still have.", number),
+            None => String::from("You can export each of the shards below as a PDF or JSON file and distribute them to trusted people. Only the number of shards configured on the last page is required to recover the backup."),
+        };
+        let recovery_seed: Element<Message> = match &self.recovery_seed {
+            Some(seed) => container(
+                row![
+                    column![
+                        text(format!("Recovery seed: {}", seed)),
+                        text("Write it down and keep it apart from the shards and passphrase. With it and the details from the last page, a lost shard can be printed again.")
+                            .size(self.text_size(SMALL_TEXT_SIZE)),
+                    ]
+                    .width(Length::Fill),
+                    horizontal_space(Length::Units(10)),
+                    button(text("Copy"))
+                        .padding(10)
+                        .on_press(Message::CopyToClipboard(seed.to_string()))
+                        .style(theme::Button::Secondary),
+                ]
+                .align_items(Alignment::Center),
+            )
+            .width(Length::Fill)
+            .padding(10)
+            .style(theme::Container::Box)
+            .into(),
+            None => vertical_space(Length::Units(0)).into(),
+        };
+
+        // All secrets are encrypted into the same ciphertext, so they share one set of shards
+        let labels = self.secret_labels();
+        let secrets_summary = if labels.len() > 1 {
+            text(format!(
+                "These shards protect {} secrets, each unlocked by its own passphrase: {}.",
+                labels.len(),
+                labels.join(", ")
+            ))
+        } else {
+            text("")
+        };
+        // Added shards have no secret of this session to compare against
+        let verify_button: Element<Message> = if self.is_extended() {
+            horizontal_space(Length::Units(0)).into()
+        } else {
+            button("Verify shares")
+                .padding([10, 40])
+                .on_press(Message::SwitchPage(AppPage::VerifyBackup))
+                .style(theme::Button::Secondary)
+                .into()
+        };
+        column![
+            text("Your backup shards").size(self.text_size(TITLE_TEXT_SIZE)),
+            vertical_space(Length::Units(20)),
+            text(intro),
+            secrets_summary,
+            recovery_seed,
+            vertical_space(Length::Units(20)),
+            scrollable(container(task_list).padding(20)),
+            vertical_space(Length::Fill),
+            self.verify_banner(),
+            record_backup,
+            print_error,
+            unsaved_banner,
+            row![
+                text_input(
+                    "Bundle password (different from the passphrase)...",
+                    &self.bundle_password,
+                    Message::BundlePasswordChanged
+                )
+                .padding(10),
+                horizontal_space(Length::Units(10)),
+                bundle_button,
+                horizontal_space(Length::Units(10)),
+                button(text("Save all in one PDF"))
+                    .padding(10)
+                    .on_press(Message::MergeShares),
+            ]
+            .align_items(Alignment::Center),
+            vertical_space(Length::Units(10)),
+            row![
+                button("Home")
+                    .padding([10, 40])
+                    .on_press(Message::LeaveResults { exit: false })
+                    .style(theme::Button::Secondary),
+                horizontal_space(Length::Fill),
+                verify_button,
+                horizontal_space(Length::Units(10)),
+                button("Exit")
+                    .padding([10, 40])
+                    .on_press(Message::LeaveResults { exit: true })
+                    .style(theme::Button::Primary),
+            ],
+        ]
+        .align_items(Alignment::Center)
+        .into()
+    }
+
+    fn verify_backup_page(&self) -> Element<Message> {
+        let mut verify_button = button(if self.verifying {
+            "Verifying..."
+        } else {
+            "Verify"
+        })
+        .padding([10, 40])
+        .style(theme::Button::Positive);
+        if self.can_verify_backup() {
+            verify_button = verify_button.on_press(Message::VerifyBackup);
+        }
+
+        column![
+            text("Verify your backup").size(self.text_size(TITLE_TEXT_SIZE)),
+            vertical_space(Length::Units(20)),
+            text("Re-enter the passphrase to restore the backup from its shards and compare it against your original secret. The original secret is removed from memory once the check has run."),
+            vertical_space(Length::Units(20)),
+            text_input(
+                "Type the passphrase of this backup...",
+                &self.verify_passphrase,
+                Message::VerifyPassphraseChanged
+            )
+            .padding(10),
+            vertical_space(Length::Units(10)),
+            self.verify_banner(),
+            vertical_space(Length::Fill),
+            row![
+                button("Back")
+                    .padding([10, 40])
+                    .on_press(Message::SwitchPage(AppPage::BackupResults))
+                    .style(theme::Button::Secondary),
+                horizontal_space(Length::Fill),
+                verify_button,
+            ],
+        ]
+        .align_items(Alignment::Center)
+        .into()
+    }
+
+    fn verify_banner(&self) -> Element<Message> {
+        let (message, success) = match &self.verify_result {
+            Some(Ok(())) => (
+                "Backup verified - the shards restore your original secret.",
+                true,
+            ),
+            Some(Err(error)) => (error.as_str(), false),
+            None => return vertical_space(Length::Shrink).into(),
+        };
+        container(text(message))
+            .width(Length::Fill)
+            .padding(10)
+            .style(theme::Container::Custom(Box::new(Banner(success))))
+            .into()
+    }
+
+    fn reminder_page(&self) -> Element<Message> {
+        let today = Local::now().date_naive();
+        let reminders = column(
+            self.reminders
+                .iter()
+                .map(|backup| {
+                    let name = if backup.label.is_empty() {
+                        format!("Backup {}", backup.backup_id)
+                    } else {
+                        backup.label.clone()
+                    };
+                    let due = if backup.expires_at < today {
+                        text(format!("Expired on {}", backup.expires_at))
+                            .style(self.theme().palette().danger)
+                    } else {
+                        text(format!("Expires on {}", backup.expires_at))
+                    };
+                    container(
+                        row![
+                            column![text(name), due],
+                            horizontal_space(Length::Fill),
+                            button(text("Renew"))
+                                .padding(10)
+                                .on_press(Message::RenewBackup(backup.label.clone())),
+                        ]
+                        .padding(10)
+                        .align_items(Alignment::Center),
+                    )
+                    .style(theme::Container::Box)
+                    .into()
+                })
+                .collect::<Vec<Element<Message>>>(),
+        )
+        .spacing(10);
+
+        column![
+            text("Time to renew your backups").size(self.text_size(TITLE_TEXT_SIZE)),
+            vertical_space(Length::Units(20)),
+            text("The following backups are about to expire. Create a new backup of the secret and destroy the old shards."),
+            vertical_space(Length::Units(20)),
+            scrollable(reminders).height(Length::Fill),
+            vertical_space(Length::Units(10)),
+            row![
+                horizontal_space(Length::Fill),
+                button("Remind me later")
+                    .padding([10, 40])
+                    .on_press(Message::DismissReminder)
+                    .style(theme::Button::Secondary),
+            ],
+        ]
+        .align_items(Alignment::Center)
+        .into()
+    }
+
+    fn shortcuts_page(&self) -> Element<Message> {
+        let shortcuts = [
+            ("Enter", "Continue, e.g. encrypt or decrypt the backup"),
+            ("Escape", "Go back to the previous page"),
+            ("Ctrl+G", "Generate a passphrase while creating a backup"),
+            ("Tab / Shift+Tab", "Move to the next or previous field"),
+        ]
+        .into_iter()
+        .fold(column![].spacing(10), |list, (keys, action)| {
+            list.push(row![text(keys).width(Length::Units(160)), text(action)])
+        });
+
+        column![
+            text("Keyboard shortcuts").size(self.text_size(TITLE_TEXT_SIZE)),
+            vertical_space(Length::Units(20)),
+            shortcuts,
+            vertical_space(Length::Units(20)),
+            text("Shortcuts are ignored while a text field has focus. Press Escape to leave the field first.")
+                .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+            vertical_space(Length::Units(40)),
+            row![
+                horizontal_space(Length::Fill),
+                button("Close")
+                    .padding([10, 40])
+                    .on_press(Message::ToggleShortcuts)
+                    .style(theme::Button::Secondary),
+            ],
+        ]
+        .into()
+    }
+
+    fn confirm_exit_page(&self) -> Element<Message> {
+        column![
+            text("Quit while restoring?").size(self.text_size(TITLE_TEXT_SIZE)),
+            vertical_space(Length::Units(20)),
+            text("The backup shards and passphrase you entered will be discarded."),
+            vertical_space(Length::Units(40)),
+            row![
+                button("Cancel")
+                    .padding([10, 40])
+                    .on_press(Message::CancelExit)
+                    .style(theme::Button::Secondary),
+                horizontal_space(Length::Fill),
+                button("Quit")
+                    .padding([10, 40])
+                    .on_press(Message::ConfirmExit)
+                    .style(theme::Button::Destructive),
+            ],
+        ]
+        .align_items(Alignment::Center)
+        .into()
+    }
+
+    fn folder_import_page(&self) -> Element<Message> {
+        let import = match &self.restore.folder_import {
+            Some(import) => import,
+            None => return column![].into(),
+        };
+        let status = if import.is_done() {
+            format!(
+                "Scanned {} file(s). Pick the backup you want to restore.",
+                import.files.len()
+            )
+        } else {
+            format!(
+                "Scanning file {} of {}: {}",
+                import.scanned + 1,
+                import.files.len(),
+                file_name(&import.files[import.scanned])
+            )
+        };
+
+        let groups = column(
+            import
+                .groups
+                .iter()
+                .map(|(backup_id, shards)| {
+                    let numbers = shards
+                        .iter()
+                        .map(|shard| shard.number)
+                        .collect::<Vec<usize>>();
+                    let mut restore_button = button(text("Restore this backup")).padding(10);
+                    if import.is_done() {
+                        restore_button =
+                            restore_button.on_press(Message::RestoreBackupGroup(*backup_id));
+                    }
+                    container(
+                        row![
+                            column![
+                                text(format!("Backup {:016x}", backup_id)),
+                                text(format!(
+                                    "{} of {} required backup shards: {}",
+                                    shards.len(),
+                                    shards[0].threshold,
+                                    shard_list(&numbers)
+                                ))
+                                .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+                            ],
+                            horizontal_space(Length::Fill),
+                            restore_button,
+                        ]
+                        .padding(10)
+                        .align_items(Alignment::Center),
+                    )
+                    .style(theme::Container::Box)
+                    .into()
+                })
+                .collect::<Vec<Element<Message>>>(),
+        )
+        .spacing(10);
+        let failures = column(
+            import
+                .failures
+                .iter()
+                .map(|(file, reason)| {
+                    text(format!("{}: {}", file_name(file), reason))
+                        .style(self.theme().palette().danger)
+                        .into()
+                })
+                .collect::<Vec<Element<Message>>>(),
+        )
+        .spacing(5);
+
+        column![
+            text("Import folder").size(self.text_size(TITLE_TEXT_SIZE)),
+            vertical_space(Length::Units(20)),
+            text(status),
+            vertical_space(Length::Units(10)),
+            progress_bar(0.0..=import.files.len() as f32, import.scanned as f32)
+                .height(Length::Units(8)),
+            vertical_space(Length::Units(10)),
+            scrollable(
+                column![
+                    groups,
+                    vertical_space(Length::Units(10)),
+                    if import.failures.is_empty() {
+                        text("")
+                    } else {
+                        text("Files that couldn't be read")
+                    },
+                    failures,
+                ]
+                .padding(10)
+            )
+            .height(Length::Fill),
+            vertical_space(Length::Units(10)),
+            row![
+                button("Back")
+                    .padding([10, 40])
+                    .on_press(Message::CancelFolderImport)
+                    .style(theme::Button::Secondary),
+                horizontal_space(Length::Fill),
+            ],
+        ]
+        .into()
+    }
+
+    fn diagnosing_page(&self) -> Element<Message> {
+        column![
+            text("Diagnosing...").size(self.text_size(HERO_TEXT_SIZE)),
+            vertical_space(Length::Units(40)),
+            text("Trying combinations of your backup shards to find a damaged one.")
+        ]
+        .align_items(Alignment::Center)
+        .into()
+    }
+
+    fn generating_page(&self) -> Element<Message> {
+        if let (Some(_), Some(stream_file)) = (&self.streaming, &self.stream_file) {
+            if self.stream_progress > 0 {
+                return column![
+                    text("Generating your backup...").size(self.text_size(HERO_TEXT_SIZE)),
+                    vertical_space(Length::Units(40)),
+                    progress_bar(0.0..=stream_file.len as f32, self.stream_progress as f32)
+                        .width(Length::Units(400)),
+                    vertical_space(Length::Units(20)),
+                    text(format!(
+                        "Encrypting {}: {} of {} MB",
+                        stream_file.name,
+                        self.stream_progress / 1_000_000,
+                        stream_file.len / 1_000_000
+                    )),
+                ]
+                .align_items(Alignment::Center)
+                .into();
             }
-            Err(_) => String::new(),
         }
+        column![
+            text("Generating your backup...").size(self.text_size(HERO_TEXT_SIZE)),
+            vertical_space(Length::Units(40)),
+            spinner(self.spinner_frame),
+            vertical_space(Length::Units(20)),
+            text(match self.kdf_estimate {
+                Some(estimate) => format!(
+                    "Deriving keys, this should take {}",
+                    format_duration(estimate * (self.additional_secrets.len() as u32 + 1))
+                ),
+                None => String::from("Deriving keys..."),
+            })
+        ]
+        .align_items(Alignment::Center)
+        .into()
     }
 
-    fn decode_failure_page(&self) -> Element<Message> {
+    fn create_backup_page(&self) -> Element<Message> {
+        let mut next_button = button(if self.reprint.is_some() {
+            "Reprint"
+        } else {
+            "Encrypt"
+        })
+        .padding([10, 40])
+        .style(theme::Button::Positive);
+        let passphrases_match = self.passphrase == self.passphrase_confirm;
+        if self.can_create_backup() {
+            next_button = next_button.on_press(Message::CreateBackup)
+        }
+        let expiry_valid = match self.expiry_date() {
+            Ok(None) => None,
+            Ok(Some(_)) => Some(true),
+            Err(_) => Some(false),
+        };
+        let not_before_valid = match self.not_before() {
+            Ok(None) => None,
+            Ok(Some(_)) => Some(true),
+            Err(_) => Some(false),
+        };
+        let verify_after_valid = match self.verify_after() {
+            Ok(None) => None,
+            Ok(Some(_)) => Some(true),
+            Err(_) => Some(false),
+        };
+        let plaintext_len = plaintext_len(&self.secret, self.secret_encoding);
+        let secrets = self.stored_sizes.lens.len();
+        let compressed = self.stored_sizes.compressed;
+        // Every slot is as long as the longest secret, so that one decides whether they all fit
+        let secret_len = self.stored_sizes.longest();
+        let max_secret_len = self.max_secret_len();
+
+        let secret_counter = match &self.stream_file {
+            Some(_) => text("encrypted separately")
+                .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+            None => text(format!(
+                "{} / {} bytes ({}), {}",
+                secret_len,
+                max_secret_len,
+                if secrets > 1 {
+                    format!("longest of {} secrets", secrets)
+                } else if compressed {
+                    format!(
+                        "{}, compressed from {}",
+                        self.secret_encoding, plaintext_len
+                    )
+                } else {
+                    self.secret_encoding.to_string()
+                },
+                match self.qrcodes_per_shard() {
+                    1 => String::from("fits in one QR code"),
+                    count => format!("will use {} QR codes per shard", count),
+                }
+            ))
+            .style(if secret_len > max_secret_len {
+                theme::Text::Color(self.theme().palette().danger)
+            } else {
+                theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))
+            }),
+        };
+        // The key of a streamed file is random, there is no secret to annotate
+        let secret_note: Element<Message> = match &self.stream_file {
+            Some(_) => column![].into(),
+            None => text_input(
+                "Optionally type an encrypted note, only shown after restoring...",
+                &self.secret_note,
+                Message::SecretNoteChanged,
+            )
+            .padding(10)
+            .into(),
+        };
+        let generate_secret: Element<Message> = match (&self.stream_file, &self.secret_file) {
+            (None, None) => {
+                let strengths = self
+                    .secret_format
+                    .strengths()
+                    .iter()
+                    .map(|&bytes| SecretStrength {
+                        format: self.secret_format,
+                        bytes,
+                    })
+                    .collect::<Vec<SecretStrength>>();
+                let selected = SecretStrength {
+                    format: self.secret_format,
+                    bytes: self.secret_strength,
+                };
+                let mut generate = column![row![
+                    text("No secret yet?"),
+                    horizontal_space(Length::Fill),
+                    pick_list(
+                        &SecretFormat::ALL[..],
+                        Some(self.secret_format),
+                        Message::SecretFormatChanged
+                    )
+                    .padding(10),
+                    horizontal_space(Length::Units(10)),
+                    pick_list(strengths, Some(selected), Message::SecretStrengthChanged)
+                        .padding(10),
+                    horizontal_space(Length::Units(10)),
+                    button(text("Generate secret"))
+                        .padding(10)
+                        .on_press(Message::GenerateSecret),
+                ]
+                .align_items(Alignment::Center)]
+                .spacing(5);
+                if self.secret_generated {
+                    generate = generate
+                        .push(
+                            text(format!(
+                                "SHA-256 {}",
+                                fingerprint(self.secret.as_bytes())
+                            ))
+                            .size(self.text_size(CAPTION_TEXT_SIZE))
+                            .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+                        )
+                        .push(
+                            text("This secret was just generated and exists nowhere but in the backup you are creating. Use it only once the shards are stored safely: if they are lost, so is the secret.")
+                                .size(self.text_size(SMALL_TEXT_SIZE))
+                                .style(self.theme().palette().danger),
+                        );
+                }
+                generate.into()
+            }
+            _ => column![].into(),
+        };
+        let secret_input = match (&self.stream_file, &self.secret_file) {
+            (Some(stream_file), _) => row![
+                column![
+                    text(format!("{} ({} bytes)", stream_file.name, stream_file.len)),
+                    text("Too large for the shards, so it is encrypted to a separate file and the shards hold its key")
+                        .size(self.text_size(CAPTION_TEXT_SIZE))
+                        .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+                ]
+                .width(Length::Fill),
+                horizontal_space(Length::Units(10)),
+                button(text("Clear"))
+                    .padding(10)
+                    .on_press(Message::ClearSecretFile)
+                    .style(theme::Button::Secondary),
+            ],
+            (None, Some(file)) => row![
+                column![
+                    text(format!("{} ({} bytes)", file.name, file.len)),
+                    text(format!("SHA-256 {}", file.fingerprint))
+                        .size(self.text_size(CAPTION_TEXT_SIZE))
+                        .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+                ]
+                .width(Length::Fill),
+                horizontal_space(Length::Units(10)),
+                button(text("Clear"))
+                    .padding(10)
+                    .on_press(Message::ClearSecretFile)
+                    .style(theme::Button::Secondary),
+            ],
+            (None, None) => row![text_input(
+                "Type the secret that will be backed up",
+                &self.secret,
+                Message::SecretChanged
+            )
+            .padding(10)],
+        }
+        .push(horizontal_space(Length::Units(10)))
+        .push(
+            button(text("Load from file..."))
+                .padding(10)
+                .on_press(Message::LoadSecretFile),
+        )
+        .align_items(Alignment::Center);
+
+        let (weak_passphrase, weak_passphrase_feedback): (Element<Message>, Element<Message>) =
+            match &self.passphrase_strength {
+                Some(strength) if self.is_passphrase_weak() => (
+                    checkbox(
+                        "Use weak passphrase anyway",
+                        self.allow_weak_passphrase,
+                        Message::AllowWeakPassphraseChanged,
+                    )
+                    .into(),
+                    text(if strength.feedback.is_empty() {
+                        String::from("This passphrase is easy to guess.")
+                    } else {
+                        strength.feedback.join(" ")
+                    })
+                    .size(self.text_size(SMALL_TEXT_SIZE))
+                    .style(self.theme().palette().danger)
+                    .into(),
+                ),
+                _ => (column![].into(), column![].into()),
+            };
+
+        let confirm_status = match (self.passphrase_confirm.is_empty(), passphrases_match) {
+            (true, _) => text(""),
+            (false, true) => text("Passphrases match").style(self.theme().palette().success),
+            (false, false) => text("Passphrases don't match").style(self.theme().palette().danger),
+        };
+
+        let additional_secrets = column(
+            self.additional_secrets
+                .iter()
+                .enumerate()
+                .map(|(index, entry)| {
+                    container(
+                        column![
+                            row![
+                                text(format!("Secret #{}", index + 2)),
+                                horizontal_space(Length::Fill),
+                                button(text("Remove"))
+                                    .on_press(Message::RemoveSecret(index))
+                                    .style(theme::Button::Secondary)
+                            ]
+                            .align_items(Alignment::Center),
+                            text_input("Optionally type a label...", &entry.label, move |l| {
+                                Message::AdditionalLabelChanged(index, l)
+                            })
+                            .padding(10),
+                            text_input(
+                                "Type the secret that will be backed up",
+                                &entry.secret,
+                                move |s| Message::AdditionalSecretChanged(index, s)
+                            )
+                            .padding(10),
+                            text_input(
+                                "Type a different passphrase for this secret...",
+                                &entry.passphrase,
+                                move |p| Message::AdditionalPassphraseChanged(index, p)
+                            )
+                            .padding(10),
+                            progress_bar(
+                                0.0..=5.0,
+                                entry
+                                    .passphrase_score
+                                    .map_or(0.0, |score| score as f32 + 1.0)
+                            )
+                            .height(Length::Units(4))
+                            .style(theme::ProgressBar::Custom(Box::new(StrengthBar(
+                                entry.passphrase_score.unwrap_or(0)
+                            )))),
+                        ]
+                        .spacing(10)
+                        .padding(10),
+                    )
+                    .style(theme::Container::Box)
+                    .into()
+                })
+                .collect::<Vec<Element<Message>>>(),
+        );
+        // A decoy opens with its own passphrase and reveals only itself, for when a passphrase
+        // has to be given up under pressure
+        let mut add_decoy_button = button("Add decoy secret")
+            .padding(10)
+            .style(theme::Button::Secondary);
+        if self.additional_secrets.len() + 1 < MAX_SECRETS && self.stream_file.is_none() {
+            add_decoy_button = add_decoy_button.on_press(Message::AddSecret);
+        }
+        let not_before_note = if matches!(self.not_before(), Ok(Some(_))) {
+            text(
+                "Only a reminder, shown when the backup is restored earlier. It doesn't stop \
+                 anyone from restoring it.",
+            )
+            .size(self.text_size(SMALL_TEXT_SIZE))
+        } else {
+            text("")
+        };
+
+        let duplicate_error = if self.passphrases_distinct() {
+            text("")
+        } else {
+            text("Every secret needs its own passphrase.").style(self.theme().palette().danger)
+        };
+
+        let custom_shards: Element<Message> = match self.backup_type {
+            BackupType::Custom { min, max } => column![
+                row![
+                    text("Required shards"),
+                    horizontal_space(Length::Units(10)),
+                    stepper_button(
+                        "-",
+                        min > 2,
+                        Message::CustomShardsChanged {
+                            min: min.saturating_sub(1),
+                            max
+                        }
+                    ),
+                    text(min)
+                        .width(Length::Units(30))
+                        .horizontal_alignment(Horizontal::Center),
+                    stepper_button(
+                        "+",
+                        min < max,
+                        Message::CustomShardsChanged { min: min + 1, max }
+                    ),
+                    horizontal_space(Length::Fill),
+                    text("Total shards"),
+                    horizontal_space(Length::Units(10)),
+                    stepper_button(
+                        "-",
+                        max > min,
+                        Message::CustomShardsChanged { min, max: max - 1 }
+                    ),
+                    text(max)
+                        .width(Length::Units(30))
+                        .horizontal_alignment(Horizontal::Center),
+                    stepper_button(
+                        "+",
+                        max < MAX_SHARDS,
+                        Message::CustomShardsChanged { min, max: max + 1 }
+                    ),
+                ]
+                .align_items(Alignment::Center),
+                text(self.backup_type.validation_error().unwrap_or_default())
+                    .style(self.theme().palette().danger),
+            ]
+            .into(),
+            _ => vertical_space(Length::Units(0)).into(),
+        };
+
+        let mut security_level = row![
+            text("Security level"),
+            horizontal_space(Length::Units(10)),
+            pick_list(
+                &SecurityLevel::ALL[..],
+                Some(self.security_level),
+                Message::SecurityLevelChanged
+            )
+            .padding(10),
+        ]
+        .align_items(Alignment::Center);
+        if self.security_level == SecurityLevel::Advanced {
+            security_level = security_level
+                .push(horizontal_space(Length::Units(10)))
+                .push(
+                    pick_list(
+                        &KdfAlgorithm::ALL[..],
+                        Some(self.kdf_algorithm),
+                        Message::KdfAlgorithmChanged,
+                    )
+                    .padding(10),
+                );
+            let inputs = match self.kdf_algorithm {
+                KdfAlgorithm::Pbkdf2 => vec![(
+                    text_input("Rounds", &self.kdf_rounds_input, Message::KdfRoundsChanged),
+                    &self.kdf_rounds_input,
+                    150,
+                )],
+                KdfAlgorithm::Argon2id => vec![
+                    (
+                        text_input(
+                            "MiB",
+                            &self.argon2_memory_input,
+                            Message::Argon2MemoryChanged,
+                        ),
+                        &self.argon2_memory_input,
+                        70,
+                    ),
+                    (
+                        text_input(
+                            "Passes",
+                            &self.argon2_iterations_input,
+                            Message::Argon2IterationsChanged,
+                        ),
+                        &self.argon2_iterations_input,
+                        70,
+                    ),
+                    (
+                        text_input(
+                            "Lanes",
+                            &self.argon2_parallelism_input,
+                            Message::Argon2ParallelismChanged,
+                        ),
+                        &self.argon2_parallelism_input,
+                        70,
+                    ),
+                ],
+            };
+            let valid = self.kdf_params().is_some();
+            for (input, value, width) in inputs {
+                security_level = security_level
+                    .push(horizontal_space(Length::Units(10)))
+                    .push(input.padding(10).width(Length::Units(width)).style(
+                        theme::TextInput::Custom(Box::new(ValidatedInput(
+                            (!value.is_empty()).then_some(valid),
+                        ))),
+                    ));
+            }
+        }
+        let security_level = security_level
+            .push(horizontal_space(Length::Units(20)))
+            .push(text("Cipher"))
+            .push(horizontal_space(Length::Units(10)))
+            .push(
+                pick_list(
+                    &CipherSuite::ALL[..],
+                    Some(self.cipher),
+                    Message::CipherChanged,
+                )
+                .padding(10),
+            )
+            .push(horizontal_space(Length::Units(20)))
+            .push(text("Sharing"))
+            .push(horizontal_space(Length::Units(10)))
+            .push(
+                pick_list(
+                    &SssBackend::ALL[..],
+                    Some(self.sharing),
+                    Message::SharingChanged,
+                )
+                .padding(10),
+            );
+        let security_level = security_level.push(horizontal_space(Length::Fill)).push(
+            text(match self.kdf_estimate {
+                Some(estimate) => format!(
+                    "Each passphrase takes {} to check on this computer",
+                    format_duration(estimate)
+                ),
+                None => match self.kdf_algorithm {
+                    KdfAlgorithm::Pbkdf2 => {
+                        format!("Between {} and {} rounds", MIN_KDF_ROUNDS, MAX_KDF_ROUNDS)
+                    }
+                    KdfAlgorithm::Argon2id => ConfigError::Argon2ParamsOutOfRange.to_string(),
+                },
+            })
+            .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+        );
+
+        let reproducible: Element<Message> = match &self.reprint {
+            Some(reprint) => row![
+                text_input("Recovery seed", &reprint.seed, Message::ReprintSeedChanged)
+                    .padding(10)
+                    .style(theme::TextInput::Custom(Box::new(ValidatedInput(
+                        (!reprint.seed.is_empty()).then(|| reprint.seed().is_some())
+                    )))),
+                horizontal_space(Length::Units(10)),
+                text_input(
+                    "Shard number",
+                    &reprint.number,
+                    Message::ReprintNumberChanged
+                )
+                .padding(10)
+                .width(Length::Units(150))
+                .style(theme::TextInput::Custom(Box::new(ValidatedInput(
+                    (!reprint.number.is_empty())
+                        .then(|| reprint.number_valid(self.backup_type.total_shards()))
+                )))),
+            ]
+            .align_items(Alignment::Center)
+            .into(),
+            None if self.stream_file.is_some() => vertical_space(Length::Units(0)).into(),
+            None => row![checkbox(
+                "Make lost shards reprintable with a recovery seed",
+                self.reproducible,
+                Message::ReproducibleChanged
+            )]
+            .width(Length::Fill)
+            .into(),
+        };
+        let slip39: Element<Message> = match (&self.stream_file, self.slip39) {
+            (Some(_), _) => vertical_space(Length::Units(0)).into(),
+            (None, false) => row![checkbox(
+                "Standards-compatible shares (SLIP-0039)",
+                self.slip39,
+                Message::Slip39Changed
+            )]
+            .width(Length::Fill)
+            .into(),
+            (None, true) => column![
+                checkbox(
+                    "Standards-compatible shares (SLIP-0039)",
+                    self.slip39,
+                    Message::Slip39Changed
+                ),
+                text(
+                    "Hardware wallets can restore these shares. The secret has to be a master \
+                     secret in hex, encrypted by the standard instead of the settings above, and \
+                     a wrong passphrase restores a different secret instead of failing. The label \
+                     is only printed, and notes, key files, reminders and more secrets don't fit."
+                )
+                .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+            ]
+            .spacing(5)
+            .width(Length::Fill)
+            .into(),
+        };
+
         column![
-            text("Failed to decrypt!").size(30),
+            if self.reprint.is_some() {
+                column![
+                    text("Reprint a lost shard").size(self.text_size(TITLE_TEXT_SIZE)),
+                    text("Enter the secrets, passphrases and settings exactly as for the original backup.")
+                        .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+                ]
+                .align_items(Alignment::Center)
+            } else {
+                column![text("Create a new backup").size(self.text_size(TITLE_TEXT_SIZE))]
+            },
+            vertical_space(Length::Fill),
+            row![
+                text("Secret "),
+                text("*").style(self.theme().palette().danger),
+                horizontal_space(Length::Fill),
+                text(match &self.secret_file_error {
+                    Some(error) => format!("{} ", error),
+                    None if self.secret_has_control_chars => String::from(
+                        "Contains control characters - use Load from file for binary data "
+                    ),
+                    None => String::new(),
+                })
+                .style(self.theme().palette().danger),
+                secret_counter,
+            ],
+            secret_input,
+            generate_secret,
+            secret_note,
             vertical_space(Length::Units(20)),
-            text("Please make sure that you provided enough backup shards, and that the passphrase is correct.").horizontal_alignment(Horizontal::Center),
+            row![
+                text("Passphrase "),
+                text("*").style(self.theme().palette().danger),
+                horizontal_space(Length::Fill),
+                text(self.get_passphrase_crack_time())
+                    .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5)))
+            ],
+            row![
+                text_input(
+                    "Type a secure passphrase...",
+                    &self.passphrase,
+                    Message::PassphraseChanged
+                )
+                .padding(10),
+                horizontal_space(Length::Units(10)),
+                stepper_button(
+                    "-",
+                    self.passphrase_words > MIN_WORDS,
+                    Message::PassphraseWordsChanged(self.passphrase_words - 1)
+                ),
+                text(format!("{} words", self.passphrase_words))
+                    .width(Length::Units(70))
+                    .horizontal_alignment(Horizontal::Center),
+                stepper_button(
+                    "+",
+                    self.passphrase_words < MAX_WORDS,
+                    Message::PassphraseWordsChanged(self.passphrase_words + 1)
+                ),
+                horizontal_space(Length::Units(10)),
+                pick_list(
+                    &PassphraseSeparator::ALL[..],
+                    Some(self.passphrase_separator),
+                    Message::PassphraseSeparatorChanged
+                )
+                .padding(10),
+                horizontal_space(Length::Units(10)),
+                button(text("Generate passphrase"))
+                    .padding(10)
+                    .on_press(Message::GeneratePassphrase)
+            ]
+            .align_items(Alignment::Center),
+            progress_bar(
+                0.0..=5.0,
+                self.get_passphrase_score()
+                    .map_or(0.0, |score| score as f32 + 1.0)
+            )
+            .height(Length::Units(6))
+            .style(theme::ProgressBar::Custom(Box::new(StrengthBar(
+                self.get_passphrase_score().unwrap_or(0)
+            )))),
+            vertical_space(Length::Units(10)),
+            row![
+                text_input(
+                    "Repeat the passphrase...",
+                    &self.passphrase_confirm,
+                    Message::PassphraseConfirmChanged
+                )
+                .padding(10),
+                horizontal_space(Length::Units(10)),
+                confirm_status,
+            ]
+            .align_items(Alignment::Center),
+            vertical_space(Length::Units(5)),
+            row![
+                checkbox(
+                    "I have written the passphrase down",
+                    self.passphrase_written_down,
+                    Message::PassphraseWrittenDownChanged
+                ),
+                horizontal_space(Length::Fill),
+                weak_passphrase,
+            ],
+            weak_passphrase_feedback,
+            vertical_space(Length::Units(10)),
+            row![
+                text("Key file (optional)"),
+                horizontal_space(Length::Fill),
+                text(self.key_file_error.as_deref().unwrap_or_default())
+                    .style(self.theme().palette().danger),
+            ],
+            key_file_picker(
+                self.key_file.as_ref(),
+                "Without it, the passphrase alone can't restore the backup",
+                Message::ChooseKeyFile,
+                Message::RemoveKeyFile
+            ),
+            vertical_space(Length::Units(10)),
+            row![
+                column![
+                    row![
+                        text("Backup mode "),
+                        text("*").style(self.theme().palette().danger),
+                    ],
+                    vertical_space(Length::Units(10)),
+                    pick_list(
+                        BackupType::choices(self.backup_type),
+                        Some(self.backup_type),
+                        Message::BackupTypeChanged
+                    )
+                    .padding(10)
+                ]
+                .width(Length::Fill),
+                horizontal_space(Length::Units(10)),
+                column![
+                    text("Label"),
+                    vertical_space(Length::Units(10)),
+                    text_input(
+                        "Optionally type a label...",
+                        &self.label,
+                        Message::LabelChanged
+                    )
+                    .padding(10),
+                    vertical_space(Length::Units(5)),
+                    checkbox(
+                        "Only print the label, keep it out of the QR code",
+                        self.hide_label_in_qr,
+                        Message::HideLabelChanged
+                    )
+                ]
+                .width(Length::Fill),
+                horizontal_space(Length::Units(10)),
+                column![
+                    text("Expires on"),
+                    vertical_space(Length::Units(10)),
+                    text_input(
+                        "YYYY-MM-DD (optional)",
+                        &self.expiry,
+                        Message::ExpiryChanged
+                    )
+                    .padding(10)
+                    .style(theme::TextInput::Custom(Box::new(ValidatedInput(
+                        expiry_valid
+                    ))))
+                ]
+                .width(Length::Fill),
+                horizontal_space(Length::Units(10)),
+                column![
+                    text("Restore reminder: not before"),
+                    vertical_space(Length::Units(10)),
+                    text_input(
+                        "YYYY-MM-DD (optional)",
+                        &self.not_before,
+                        Message::NotBeforeChanged
+                    )
+                    .padding(10)
+                    .style(theme::TextInput::Custom(Box::new(ValidatedInput(
+                        not_before_valid
+                    ))))
+                ]
+                .width(Length::Fill),
+                horizontal_space(Length::Units(10)),
+                column![
+                    text("Verify again after"),
+                    vertical_space(Length::Units(10)),
+                    text_input(
+                        "Years (optional)",
+                        &self.verify_after,
+                        Message::VerifyAfterChanged
+                    )
+                    .padding(10)
+                    .style(theme::TextInput::Custom(Box::new(ValidatedInput(
+                        verify_after_valid
+                    ))))
+                ]
+                .width(Length::Fill),
+            ],
+            not_before_note,
+            custom_shards,
+            security_level,
+            slip39,
+            reproducible,
+            scrollable(additional_secrets.spacing(10)).height(Length::Fill),
+            duplicate_error,
+            row![
+                button("Back")
+                    .padding([10, 40])
+                    .on_press(Message::SwitchPage(AppPage::Welcome))
+                    .style(theme::Button::Secondary),
+                horizontal_space(Length::Fill),
+                add_decoy_button,
+                horizontal_space(Length::Units(10)),
+                next_button
+            ]
+            .width(Length::Fill)
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center)
+        .into()
+    }
+
+    fn pdf_settings_page(&self) -> Element<Message> {
+        let color_valid = parse_hex_color(&self.pdf_color_input).is_ok();
+        let logo = match &self.pdf_theme.logo_path {
+            Some(path) => row![
+                text(file_name(path)),
+                horizontal_space(Length::Fill),
+                button(text("Remove"))
+                    .padding(10)
+                    .on_press(Message::RemoveLogo)
+                    .style(theme::Button::Secondary),
+                horizontal_space(Length::Units(10)),
+                button(text("Change"))
+                    .padding(10)
+                    .on_press(Message::ChooseLogo),
+            ],
+            None => row![
+                text("No logo"),
+                horizontal_space(Length::Fill),
+                button(text("Choose logo"))
+                    .padding(10)
+                    .on_press(Message::ChooseLogo),
+            ],
+        }
+        .align_items(Alignment::Center);
+
+        column![
+            text("PDF settings").size(self.text_size(TITLE_TEXT_SIZE)),
+            text("These apply to every shard PDF you save or print.")
+                .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
             vertical_space(Length::Units(20)),
-            button("Try again").padding([10, 20]).on_press(Message::SwitchPage(AppPage::RestoreBackup)),
+            text("Title"),
+            text_input(
+                "Optionally type a heading for the top of each shard...",
+                &self.pdf_theme.title,
+                Message::PdfTitleChanged
+            )
+            .padding(10),
+            vertical_space(Length::Units(10)),
+            text("Text colour"),
+            text_input("#000000", &self.pdf_color_input, Message::PdfColorChanged)
+                .style(theme::TextInput::Custom(Box::new(ValidatedInput(Some(
+                    color_valid
+                )))))
+                .padding(10),
+            vertical_space(Length::Units(10)),
+            text("Logo"),
+            logo,
+            vertical_space(Length::Fill),
+            row![button(if self.in_settings() { "Back" } else { "Home" })
+                .padding([10, 40])
+                .on_press(self.pdf_settings_back())
+                .style(theme::Button::Secondary)],
         ]
-        .align_items(Alignment::Center)
+        .spacing(5)
         .into()
     }
 
-    fn decode_success_page(&self) -> Element<Message> {
+    /// Whether the PDF settings were opened from the settings rather than the welcome page.
+    fn in_settings(&self) -> bool {
+        matches!(self.page, AppPage::PdfSettings) && self.page_before_settings.is_some()
+    }
+
+    fn pdf_settings_back(&self) -> Message {
+        if self.in_settings() {
+            Message::OpenSettings
+        } else {
+            Message::SwitchPage(AppPage::Welcome)
+        }
+    }
+
+    fn settings_page(&self) -> Element<Message> {
+        let path_row = |path: Option<&Path>, unset: &str, choose: Message, reset: Message| {
+            let mut reset_button = button(text("Use default"))
+                .padding(10)
+                .style(theme::Button::Secondary);
+            if path.is_some() {
+                reset_button = reset_button.on_press(reset);
+            }
+            row![
+                text(path.map_or_else(|| unset.to_owned(), |path| path.display().to_string()))
+                    .width(Length::Fill),
+                horizontal_space(Length::Units(10)),
+                reset_button,
+                horizontal_space(Length::Units(10)),
+                button(text("Change")).padding(10).on_press(choose),
+            ]
+            .align_items(Alignment::Center)
+        };
+
         column![
-            text("Your decrypted secret").size(30),
+            text("Settings").size(self.text_size(TITLE_TEXT_SIZE)),
+            text("Changes are saved right away.")
+                .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
             vertical_space(Length::Units(20)),
-            scrollable(
-                container(text(&self.decoded_secret))
-                    .padding(10)
-                    .style(theme::Container::Box)
-            )
-            .height(Length::Fill),
+            row![
+                column![
+                    text("Default backup mode"),
+                    pick_list(
+                        BackupType::choices(self.backup_type),
+                        Some(self.backup_type),
+                        Message::BackupTypeChanged
+                    )
+                    .padding(10),
+                ]
+                .spacing(5)
+                .width(Length::Fill),
+                horizontal_space(Length::Units(10)),
+                column![
+                    text("Words per generated passphrase"),
+                    row![
+                        stepper_button(
+                            "-",
+                            self.passphrase_words > MIN_WORDS,
+                            Message::PassphraseWordsChanged(self.passphrase_words - 1)
+                        ),
+                        text(format!("{} words", self.passphrase_words))
+                            .width(Length::Units(70))
+                            .horizontal_alignment(Horizontal::Center),
+                        stepper_button(
+                            "+",
+                            self.passphrase_words < MAX_WORDS,
+                            Message::PassphraseWordsChanged(self.passphrase_words + 1)
+                        ),
+                    ]
+                    .align_items(Alignment::Center),
+                ]
+                .spacing(5)
+                .width(Length::Fill),
+            ],
+            vertical_space(Length::Units(10)),
+            text("Output folder"),
+            path_row(
+                self.output_dir.as_deref(),
+                "The folder of the last saved shard",
+                Message::ChooseOutputDir,
+                Message::ResetOutputDir
+            ),
+            vertical_space(Length::Units(10)),
+            text("Audit log"),
+            path_row(
+                self.audit_log
+                    .as_deref()
+                    .filter(|&path| Some(path) != default_audit_log_path().as_deref()),
+                "Default location",
+                Message::ChooseAuditLog,
+                Message::ResetAuditLog
+            ),
+            vertical_space(Length::Units(10)),
+            row![
+                checkbox("Dark mode", self.dark_mode, |_| Message::ToggleTheme).width(Length::Fill),
+                checkbox("High contrast", self.accessibility.high_contrast, |_| {
+                    Message::ToggleHighContrast
+                })
+                .width(Length::Fill),
+            ],
+            vertical_space(Length::Units(10)),
+            text("Text size"),
+            row![
+                stepper_button(
+                    "-",
+                    self.accessibility.font_scale > MIN_FONT_SCALE,
+                    Message::FontScaleChanged(self.accessibility.font_scale - FONT_SCALE_STEP)
+                ),
+                text(format!("{:.0}%", self.accessibility.scale() * 100.0))
+                    .width(Length::Units(70))
+                    .horizontal_alignment(Horizontal::Center),
+                stepper_button(
+                    "+",
+                    self.accessibility.font_scale < MAX_FONT_SCALE,
+                    Message::FontScaleChanged(self.accessibility.font_scale + FONT_SCALE_STEP)
+                ),
+                horizontal_space(Length::Units(10)),
+                text("Text without a size of its own and the window follow after a restart.")
+                    .size(self.text_size(SMALL_TEXT_SIZE))
+                    .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+            ]
+            .align_items(Alignment::Center),
+            vertical_space(Length::Fill),
+            row![
+                button("Back")
+                    .padding([10, 40])
+                    .on_press(Message::CloseSettings)
+                    .style(theme::Button::Secondary),
+                horizontal_space(Length::Fill),
+                button("PDF settings")
+                    .padding([10, 20])
+                    .on_press(Message::SwitchPage(AppPage::PdfSettings))
+                    .style(theme::Button::Secondary),
+            ],
         ]
-        .align_items(Alignment::Center)
+        .spacing(5)
         .into()
     }
 
-    fn recover_backup_page(&self) -> Element<Message> {
-        let idle = row![
-            text("Please scan the QR codes from all required backup shards"),
-            horizontal_space(Length::Fill),
-            button(text("Scan code"))
-                .padding(10)
-                .on_press(Message::ScanCode)
-        ]
-        .align_items(Alignment::Center);
-        let scanning = row![text(
-            "Scanning for QR Codes. Please position the code in front of your camera."
-        )];
-        let code_list = column(
-            self.scanned_codes
-                .iter()
-                .enumerate()
-                .map(|(num, code)| {
-                    container(text(format!(
-                        "Backup shard #{} ({} bytes)",
-                        num + 1,
-                        code.len()
-                    )))
-                    .width(Length::Fill)
-                    .padding(10)
-                    .style(theme::Container::Box)
+    fn about_page(&self) -> Element<Message> {
+        let grey = theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5));
+        let dependencies = column(
+            crypto_dependencies()
+                .map(|(name, version)| {
+                    row![
+                        text(name).width(Length::Units(100)),
+                        text(version).width(Length::Units(100)),
+                        text(format!("https://rustsec.org/packages/{}.html", name)).style(grey),
+                    ]
                     .into()
                 })
                 .collect::<Vec<Element<Message>>>(),
         )
-        .spacing(10);
+        .spacing(5);
+
         column![
-            text("Recover a backup").size(30),
-            vertical_space(Length::Units(20)),
-            if self.is_scanning { scanning } else { idle },
+            text("About Hyperbacked").size(self.text_size(TITLE_TEXT_SIZE)),
+            text(format!("Version {}", env!("CARGO_PKG_VERSION"))),
+            text(format!("Built on {}", env!("BUILD_DATE"))),
             vertical_space(Length::Units(20)),
-            scrollable(container(code_list).padding(10)),
+            text("Cryptographic dependencies"),
+            text("Check their security advisories before trusting a build with your secrets.")
+                .style(grey),
+            vertical_space(Length::Units(5)),
+            dependencies,
             vertical_space(Length::Fill),
-            column![
-                row![
-                    text("Passphrase "),
-                    text("*").style(self.theme().palette().danger),
-                ],
-                vertical_space(Length::Units(10)),
-                text_input(
-                    "Enter the passphrase...",
-                    &self.passphrase,
-                    Message::PassphraseChanged
-                )
-                .padding(10)
-                .width(Length::Fill),
-            ],
-            vertical_space(Length::Units(20)),
-            row![
-                button(text("Back"))
-                    .padding([10, 40])
-                    .on_press(Message::SwitchPage(AppPage::Welcome))
-                    .style(theme::Button::Secondary),
-                horizontal_space(Length::Fill),
-                button(text("Decrypt"))
-                    .padding([10, 40])
-                    .on_press(Message::DecodeSecrets)
-                    .style(theme::Button::Positive)
-            ]
+            row![button("Home")
+                .padding([10, 40])
+                .on_press(Message::SwitchPage(AppPage::Welcome))
+                .style(theme::Button::Secondary)],
         ]
-        .align_items(Alignment::Center)
+        .spacing(5)
         .into()
     }
 
-    fn backup_results_page(&self) -> Element<Message> {
-        let task_list = match &self.generated_backup {
-            Some(shards) if shards.len() > 0 => column(
-                shards
+    fn history_page(&self) -> Element<Message> {
+        let entries: Element<Message> = if self.history.is_empty() {
+            text("No restores have been recorded yet.").into()
+        } else {
+            column(
+                self.history
                     .iter()
-                    .map(|shard| {
+                    .rev()
+                    .map(|entry| {
+                        let name = if entry.label.is_empty() {
+                            format!("Backup {}", entry.backup_id)
+                        } else {
+                            format!("{} ({})", entry.label, entry.backup_id)
+                        };
                         container(
                             row![
-                                text(format!("Shard #{}", shard.number)),
+                                column![
+                                    text(name),
+                                    text(format!(
+                                        "Restored on {} from backup shard(s) {}",
+                                        entry.restored_on,
+                                        shard_list(&entry.shards_used)
+                                    ))
+                                    .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+                                ],
                                 horizontal_space(Length::Fill),
-                                button(text("Save")).on_press(Message::SaveBackup(shard.number))
+                                button(text("Delete"))
+                                    .padding(10)
+                                    .on_press(Message::DeleteHistoryEntry(entry.id))
+                                    .style(theme::Button::Destructive),
                             ]
                             .padding(10)
                             .align_items(Alignment::Center),
@@ -351,178 +5006,841 @@ impl HyperbackedApp {
                     })
                     .collect::<Vec<Element<Message>>>(),
             )
-            .spacing(10),
-            _ => column![text("Backup failed to generate.")].into(),
+            .spacing(10)
+            .into()
         };
+
         column![
-            text("Your backup shards").size(30),
+            text("Restore history").size(self.text_size(TITLE_TEXT_SIZE)),
             vertical_space(Length::Units(20)),
-            text("You can export each of the shards below as a PDF and distribute them to trusted people. Only the number of shards configured on the last page is required to recover the backup."),
+            scrollable(entries).height(Length::Fill),
+            vertical_space(Length::Units(10)),
+            row![button("Home")
+                .padding([10, 40])
+                .on_press(Message::SwitchPage(AppPage::Welcome))
+                .style(theme::Button::Secondary)],
+        ]
+        .align_items(Alignment::Center)
+        .into()
+    }
+
+    fn welcome_page(&self) -> Element<Message> {
+        let create_btn = button(text("Create backup").horizontal_alignment(Horizontal::Center))
+            .on_press(Message::SwitchPage(AppPage::CreateBackup))
+            .padding([10, 20])
+            .width(Length::Units(350));
+
+        let restore_btn = button(text("Restore backup").horizontal_alignment(Horizontal::Center))
+            .on_press(Message::SwitchPage(AppPage::RestoreBackup))
+            .padding([10, 20])
+            .width(Length::Units(350));
+
+        let history_btn = button(text("Restore history").horizontal_alignment(Horizontal::Center))
+            .on_press(Message::SwitchPage(AppPage::History))
+            .padding([10, 20])
+            .width(Length::Units(350))
+            .style(theme::Button::Secondary);
+
+        let settings_btn = button(text("PDF settings").horizontal_alignment(Horizontal::Center))
+            .on_press(Message::SwitchPage(AppPage::PdfSettings))
+            .padding([10, 20])
+            .width(Length::Units(350))
+            .style(theme::Button::Secondary);
+
+        let reprint_btn =
+            button(text("Reprint a lost shard").horizontal_alignment(Horizontal::Center))
+                .on_press(Message::StartReprint)
+                .padding([10, 20])
+                .width(Length::Units(350))
+                .style(theme::Button::Secondary);
+
+        let about_btn = button(text("About").horizontal_alignment(Horizontal::Center))
+            .on_press(Message::SwitchPage(AppPage::About))
+            .padding([10, 20])
+            .width(Length::Units(350))
+            .style(theme::Button::Secondary);
+
+        column![
+            text("Welcome to Hyperbacked").size(self.text_size(HERO_TEXT_SIZE)),
+            text("Select one of the following actions to continue"),
+            vertical_space(Length::Units(48)),
+            create_btn,
+            restore_btn,
+            history_btn,
+            reprint_btn,
+            settings_btn,
+            about_btn,
+            self.recent_backups(),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center)
+        .into()
+    }
+
+    fn recent_backups(&self) -> Element<Message> {
+        let records = self.backup_history.records();
+        if records.is_empty() && self.backup_history_error.is_none() {
+            return vertical_space(Length::Shrink).into();
+        }
+
+        let entries = records
+            .iter()
+            .rev()
+            .map(|record| {
+                container(
+                    row![
+                        column![
+                            text(record.name()),
+                            text(format!(
+                                "Created on {}, {} of {} shards. Fingerprints: {}",
+                                record.created_on,
+                                record.threshold,
+                                record.fingerprints.len(),
+                                record.fingerprints.join(", ")
+                            ))
+                            .size(self.text_size(SMALL_TEXT_SIZE))
+                            .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+                        ]
+                        .width(Length::Fill),
+                        horizontal_space(Length::Units(10)),
+                        button(text("Re-verify"))
+                            .padding(10)
+                            .on_press(Message::ReverifyBackup(record.backup_id.clone()))
+                            .style(theme::Button::Secondary),
+                        horizontal_space(Length::Units(10)),
+                        button(text("Delete record"))
+                            .padding(10)
+                            .on_press(Message::DeleteBackupRecord(record.backup_id.clone()))
+                            .style(theme::Button::Destructive),
+                    ]
+                    .padding(10)
+                    .align_items(Alignment::Center),
+                )
+                .style(theme::Container::Box)
+                .into()
+            })
+            .collect::<Vec<Element<Message>>>();
+        column![
             vertical_space(Length::Units(20)),
-            scrollable(container(task_list).padding(20)),
-            vertical_space(Length::Fill),
-            row![
-                button("Home")
-                    .padding([10, 40])
-                    .on_press(Message::SwitchPage(AppPage::Welcome))
-                    .style(theme::Button::Secondary),
-                horizontal_space(Length::Fill),
-                button("Exit")
-                    .padding([10, 40])
-                    .on_press(Message::End)
-                    .style(theme::Button::Primary),
-            ],
+            text("Recent backups").size(self.text_size(HEADING_TEXT_SIZE)),
+            text("Only the label, date and fingerprints are kept, never a secret or shard.")
+                .size(self.text_size(SMALL_TEXT_SIZE)),
+            scrollable(column(entries).spacing(10)).height(Length::Units(200)),
+            text(self.backup_history_error.as_deref().unwrap_or_default())
+                .style(self.theme().palette().danger),
         ]
+        .spacing(10)
+        .width(Length::Units(700))
         .align_items(Alignment::Center)
         .into()
     }
+}
+
+#[derive(Default)]
+struct RestoreState {
+    is_scanning: bool,
+    scan_stop: Option<mpsc::Sender<()>>,
+    scanned_shards: Vec<BackupShard>,
+    /// Payload being typed in by hand, for shards that can't be scanned
+    share_text: String,
+    share_text_error: Option<String>,
+    passphrase: SecretString,
+    key_file: Option<KeyFile>,
+    error: Option<String>,
+    encoded_shares: Option<Vec<EncodedShare>>,
+    decrypting: bool,
+    decrypt_failures: usize,
+    folder_import: Option<FolderImport>,
+    /// Record of the backup being re-verified, which scanned shards are checked against
+    expected: Option<BackupRecord>,
+    /// Parts of a split shard, scanned one QR code at a time
+    fragments: Vec<Vec<u8>>,
+    /// Files dropped onto the window, with their import result once they have been read
+    dropped_files: Vec<(PathBuf, Option<Result<usize, String>>)>,
+    /// Files shards were read from, in the order they were loaded
+    loaded_files: Vec<PathBuf>,
+}
+
+impl RestoreState {
+    fn in_progress(&self) -> bool {
+        !self.scanned_shards.is_empty()
+            || !self.share_text.is_empty()
+            || !self.passphrase.is_empty()
+    }
+
+    fn clear(&mut self) {
+        for shard in &mut self.scanned_shards {
+            shard.data.zeroize();
+        }
+        self.share_text.zeroize();
+        for share in self.encoded_shares.iter_mut().flatten() {
+            share.zeroize();
+        }
+        for fragment in &mut self.fragments {
+            fragment.zeroize();
+        }
+        self.passphrase.zeroize();
+        if let Some(import) = &mut self.folder_import {
+            import.clear();
+        }
+        *self = Self::default();
+    }
+}
+
+/// A share file read during the last restore, and the outcome of deleting it.
+struct LoadedFile {
+    path: PathBuf,
+    selected: bool,
+    status: Option<Result<(), String>>,
+}
+
+/// Shards decoded from a folder of photos, grouped by the backup they belong to.
+#[derive(Default)]
+struct FolderImport {
+    files: Vec<PathBuf>,
+    scanned: usize,
+    groups: BTreeMap<u64, Vec<BackupShard>>,
+    failures: Vec<(PathBuf, String)>,
+}
+
+impl FolderImport {
+    fn is_done(&self) -> bool {
+        self.scanned == self.files.len()
+    }
+
+    fn add(&mut self, file: &Path, codes: Result<Vec<Vec<u8>>, String>) {
+        let codes = match codes {
+            Ok(codes) => codes,
+            Err(error) => {
+                self.failures.push((file.to_path_buf(), error));
+                return;
+            }
+        };
+        for data in codes {
+            match BackupShard::from_bytes(data) {
+                Ok(shard) => {
+                    let group = self.groups.entry(shard.backup_id).or_default();
+                    // The same shard is often photographed more than once
+                    if group.iter().all(|s| s.number != shard.number) {
+                        group.push(shard);
+                    }
+                }
+                Err(error) => self.failures.push((file.to_path_buf(), error.to_string())),
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        for shard in self.groups.values_mut().flatten() {
+            shard.data.zeroize();
+        }
+        self.groups.clear();
+    }
+}
+
+struct ValidatedInput(Option<bool>);
+
+impl ValidatedInput {
+    fn with_border(
+        &self,
+        theme: &Theme,
+        appearance: text_input::Appearance,
+    ) -> text_input::Appearance {
+        let border_color = match self.0 {
+            Some(true) => theme.palette().success,
+            Some(false) => theme.palette().danger,
+            None => return appearance,
+        };
+        text_input::Appearance {
+            border_color,
+            border_width: 2.0,
+            ..appearance
+        }
+    }
+}
+
+impl text_input::StyleSheet for ValidatedInput {
+    type Style = Theme;
+
+    fn active(&self, style: &Self::Style) -> text_input::Appearance {
+        self.with_border(style, style.active(&theme::TextInput::Default))
+    }
+
+    fn focused(&self, style: &Self::Style) -> text_input::Appearance {
+        self.with_border(style, style.focused(&theme::TextInput::Default))
+    }
+
+    fn hovered(&self, style: &Self::Style) -> text_input::Appearance {
+        self.with_border(style, style.hovered(&theme::TextInput::Default))
+    }
+
+    fn placeholder_color(&self, style: &Self::Style) -> Color {
+        style.placeholder_color(&theme::TextInput::Default)
+    }
+
+    fn value_color(&self, style: &Self::Style) -> Color {
+        style.value_color(&theme::TextInput::Default)
+    }
+
+    fn selection_color(&self, style: &Self::Style) -> Color {
+        style.selection_color(&theme::TextInput::Default)
+    }
+}
+
+struct Banner(bool);
+
+impl container::StyleSheet for Banner {
+    type Style = Theme;
+
+    fn appearance(&self, style: &Self::Style) -> container::Appearance {
+        let palette = style.palette();
+        container::Appearance {
+            text_color: Some(Color::WHITE),
+            background: Some(
+                if self.0 {
+                    palette.success
+                } else {
+                    palette.danger
+                }
+                .into(),
+            ),
+            border_radius: 4.0,
+            ..Default::default()
+        }
+    }
+}
+
+struct SpinnerDot(bool);
+
+impl container::StyleSheet for SpinnerDot {
+    type Style = Theme;
+
+    fn appearance(&self, style: &Self::Style) -> container::Appearance {
+        let palette = style.palette();
+        container::Appearance {
+            background: Some(
+                if self.0 {
+                    palette.primary
+                } else {
+                    Color::from_rgb(0.5, 0.5, 0.5)
+                }
+                .into(),
+            ),
+            border_radius: 5.0,
+            ..Default::default()
+        }
+    }
+}
+
+struct StrengthBar(u8);
+
+impl progress_bar::StyleSheet for StrengthBar {
+    type Style = Theme;
+
+    fn appearance(&self, style: &Self::Style) -> progress_bar::Appearance {
+        // Blend from danger at score 0 to success at score 4
+        let palette = style.palette();
+        let t = self.0.min(4) as f32 / 4.0;
+        let mix = |from: f32, to: f32| from + (to - from) * t;
+        let bar = Color::from_rgb(
+            mix(palette.danger.r, palette.success.r),
+            mix(palette.danger.g, palette.success.g),
+            mix(palette.danger.b, palette.success.b),
+        );
+
+        progress_bar::Appearance {
+            background: style.extended_palette().background.strong.color.into(),
+            bar: bar.into(),
+            border_radius: 2.0,
+        }
+    }
+}
+
+/// What to change before trying again, shown below the reason a backup failed.
+fn failure_hint(error: &BackupError) -> &'static str {
+    match error {
+        BackupError::SecretTooLarge { .. } => {
+            "Shorten the secret or the label, or back the secret up as a file instead."
+        }
+        BackupError::InvalidConfig { .. } => {
+            "Go back and check the number of shards and the key derivation settings."
+        }
+        BackupError::CryptoFailure(_) => "Go back and check the secrets and their passphrases.",
+        BackupError::StreamFailure(_) => {
+            "Check that the file is readable and that there is room for its encrypted copy."
+        }
+        _ => "This is a bug in Hyperbacked, please report it.",
+    }
+}
+
+/// Hex dump of the data, abbreviated to the first and last 16 bytes.
+fn hex_preview(data: &[u8]) -> String {
+    let hex = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<Vec<String>>()
+            .join(" ")
+    };
+    if data.len() <= 32 {
+        hex(data)
+    } else {
+        format!("{} … {}", hex(&data[..16]), hex(&data[data.len() - 16..]))
+    }
+}
+
+/// Encrypts the stream file on a thread of its own, reporting its progress.
+fn stream_backup(job: StreamJob) -> Subscription<Message> {
+    let id = ("encrypt", job.output.clone());
+    let work: StreamWork<_> = Box::new(move |progress| {
+        stream_backup_file(&job, progress).map_err(|error| match error.downcast::<BackupError>() {
+            Ok(error) => error,
+            Err(error) => BackupError::StreamFailure(error.to_string()),
+        })
+    });
+    stream_progress(id, work, Message::StreamProgress, Message::BackupCompleted)
+}
+
+/// Decrypts the stream file on a thread of its own, reporting its progress.
+fn decrypt_stream(job: DecryptJob) -> Subscription<Message> {
+    let id = ("decrypt", job.output.clone());
+    let work: StreamWork<_> = Box::new(move |progress| {
+        decrypt_stream_file(
+            &job.secret,
+            job.backup_id,
+            &job.input.path,
+            &job.output,
+            progress,
+        )
+        .map(|len| format!("Decrypted {} bytes to {}.", len, job.output.display()))
+        .map_err(|error| format!("Failed to decrypt: {}", error))
+    });
+    stream_progress(
+        id,
+        work,
+        Message::DecryptProgress,
+        Message::StreamFileDecrypted,
+    )
+}
+
+/// Runs the work on a thread of its own, passing the progress it reports on over a channel.
+fn stream_progress<T: Send + 'static>(
+    id: (&'static str, PathBuf),
+    work: StreamWork<T>,
+    progress: fn(u64) -> Message,
+    done: fn(T) -> Message,
+) -> Subscription<Message> {
+    enum State<T> {
+        Starting(StreamWork<T>),
+        Running(async_mpsc::UnboundedReceiver<StreamEvent<T>>),
+        Finished,
+    }
+
+    subscription::unfold(id, State::Starting(work), move |state| async move {
+        match state {
+            State::Starting(work) => {
+                let (sender, receiver) = async_mpsc::unbounded();
+                thread::spawn(move || {
+                    // Every chunk reports progress, which would redraw far too often
+                    let mut reported = Instant::now();
+                    let result = work(&mut |done| {
+                        if reported.elapsed() > Duration::from_millis(100) {
+                            reported = Instant::now();
+                            let _ = sender.unbounded_send(StreamEvent::Progress(done));
+                        }
+                    });
+                    let _ = sender.unbounded_send(StreamEvent::Done(result));
+                });
+                (None, State::Running(receiver))
+            }
+            State::Running(mut receiver) => match receiver.next().await {
+                Some(StreamEvent::Progress(done)) => {
+                    (Some(progress(done)), State::Running(receiver))
+                }
+                Some(StreamEvent::Done(result)) => (Some(done(result)), State::Finished),
+                None => (None, State::Finished),
+            },
+            State::Finished => iced::futures::future::pending().await,
+        }
+    })
+}
+
+fn stream_backup_file(
+    job: &StreamJob,
+    progress: impl FnMut(u64),
+) -> anyhow::Result<Vec<BackupShard>> {
+    let config = job.backup_type.to_config(
+        &job.label,
+        job.kdf,
+        job.cipher,
+        job.sharing,
+        false,
+        job.key_file,
+        job.not_before,
+        job.verify_after,
+    )?;
+    let reader = BufReader::new(File::open(&job.input)?);
+    let writer = BufWriter::new(File::create(&job.output)?);
+    let result = create_backup_stream(reader, writer, config, &job.passphrase, progress);
+    if result.is_err() {
+        let _ = std::fs::remove_file(&job.output);
+    }
+    result
+}
+
+fn decrypt_stream_file(
+    secret: &DecryptedSecret,
+    backup_id: u64,
+    input: &Path,
+    output: &Path,
+    progress: impl FnMut(u64),
+) -> anyhow::Result<u64> {
+    let reader = BufReader::new(File::open(input)?);
+    let writer = BufWriter::new(File::create(output)?);
+    let result = decrypt_backup_stream(secret, backup_id, reader, writer, progress);
+    // Whatever was written before the damage was found can't be trusted
+    if result.is_err() {
+        let _ = std::fs::remove_file(output);
+    }
+    result
+}
 
-    fn generating_page(&self) -> Element<Message> {
-        column![
-            text("Generating your backup...").size(50),
-            vertical_space(Length::Units(40)),
-            text("This should only take a few seconds :)")
-        ]
-        .align_items(Alignment::Center)
-        .into()
+fn spinner_tick() -> Command<Message> {
+    Command::perform(Delay::new(Duration::from_millis(120)), |_| Message::Tick)
+}
+
+fn spinner(frame: usize) -> Element<'static, Message> {
+    const DOTS: usize = 8;
+    row((0..DOTS)
+        .map(|dot| {
+            container(horizontal_space(Length::Units(10)))
+                .height(Length::Units(10))
+                .style(theme::Container::Custom(Box::new(SpinnerDot(
+                    dot == frame % DOTS,
+                ))))
+                .into()
+        })
+        .collect())
+    .spacing(8)
+    .into()
+}
+
+fn clipboard_tick(timer: u64) -> Command<Message> {
+    Command::perform(
+        async move {
+            Delay::new(Duration::from_secs(1)).await;
+            timer
+        },
+        Message::ClipboardTick,
+    )
+}
+
+#[cfg(feature = "tray")]
+fn reminder_tick() -> Command<Message> {
+    Command::perform(Delay::new(REMINDER_CHECK_INTERVAL), |_| {
+        Message::CheckReminders
+    })
+}
+
+/// Reads a secret file, refusing files that are too large to be backed up before reading them.
+fn read_secret_file(path: &Path) -> anyhow::Result<Vec<u8>> {
+    // Binary secrets are prefixed with a marker byte
+    let max_len = MAX_SECRET_LEN - 1;
+    let len = std::fs::metadata(path)?.len();
+    if len > max_len as u64 {
+        anyhow::bail!(
+            "the file is {} bytes, at most {} fit in a backup",
+            len,
+            max_len
+        );
     }
+    Ok(std::fs::read(path)?)
+}
 
-    fn create_backup_page(&self) -> Element<Message> {
-        let mut next_button = button("Encrypt")
-            .padding([10, 40])
-            .style(theme::Button::Positive);
-        if !self.passphrase.trim().is_empty() && !self.secret.trim().is_empty() {
-            next_button = next_button.on_press(Message::CreateBackup)
-        }
+fn read_key_file(path: &Path) -> Result<KeyFile, String> {
+    let mut data = std::fs::read(path)
+        .map_err(|error| format!("Failed to load {}: {}", file_name(path), error))?;
+    if data.is_empty() {
+        return Err(format!("{} is empty", file_name(path)));
+    }
+    let digest = key_file_digest(&data);
+    data.zeroize();
+    Ok(KeyFile {
+        name: file_name(path),
+        digest,
+    })
+}
 
-        column![
-            text("Create a new backup").size(30),
-            vertical_space(Length::Fill),
-            row![
-                text("Secret "),
-                text("*").style(self.theme().palette().danger),
-                horizontal_space(Length::Fill),
-                text(format!("{} bytes used", self.secret.len()))
-                    .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5)))
-            ],
-            text_input(
-                "Type the secret that will be backed up",
-                &self.secret,
-                Message::SecretChanged
-            )
-            .padding(10),
-            vertical_space(Length::Units(20)),
-            row![
-                text("Passphrase "),
-                text("*").style(self.theme().palette().danger),
-                horizontal_space(Length::Fill),
-                text(self.get_passphrase_crack_time())
-                    .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5)))
-            ],
-            row![
-                text_input(
-                    "Type a secure passphrase...",
-                    &self.passphrase,
-                    Message::PassphraseChanged
-                )
-                .padding(10),
-                horizontal_space(Length::Units(10)),
-                button(text("Generate"))
-                    .padding(10)
-                    .on_press(Message::GenerateSecret)
-            ],
-            vertical_space(Length::Units(20)),
-            row![
-                column![
-                    row![
-                        text("Backup mode "),
-                        text("*").style(self.theme().palette().danger),
-                    ],
-                    vertical_space(Length::Units(10)),
-                    pick_list(
-                        &BackupType::ALL[..],
-                        Some(self.backup_type),
-                        Message::BackupTypeChanged
-                    )
-                    .padding(10)
-                ]
-                .width(Length::Fill),
-                horizontal_space(Length::Units(10)),
-                column![
-                    text("Label"),
-                    vertical_space(Length::Units(10)),
-                    text_input(
-                        "Optionally type a label...",
-                        &self.label,
-                        Message::LabelChanged
-                    )
-                    .padding(10)
-                ]
-                .width(Length::Fill),
-            ],
-            vertical_space(Length::Fill),
-            row![
-                button("Back")
-                    .padding([10, 40])
-                    .on_press(Message::SwitchPage(AppPage::Welcome))
-                    .style(theme::Button::Secondary),
-                horizontal_space(Length::Fill),
-                next_button
-            ]
-            .width(Length::Fill)
-        ]
-        .spacing(10)
-        .align_items(Alignment::Center)
-        .into()
+/// The chosen key file with a button to remove it, or a hint and a button to choose one.
+fn key_file_picker<'a>(
+    key_file: Option<&KeyFile>,
+    hint: &str,
+    choose: Message,
+    remove: Message,
+) -> Element<'a, Message> {
+    match key_file {
+        Some(key_file) => row![
+            text(&key_file.name).width(Length::Fill),
+            button(text("Remove"))
+                .padding(10)
+                .on_press(remove)
+                .style(theme::Button::Secondary),
+        ],
+        None => row![
+            text(hint)
+                .width(Length::Fill)
+                .style(theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+            button(text("Choose file...")).padding(10).on_press(choose),
+        ],
     }
+    .align_items(Alignment::Center)
+    .into()
+}
 
-    fn welcome_page(&self) -> Element<Message> {
-        let create_btn = button(text("Create backup").horizontal_alignment(Horizontal::Center))
-            .on_press(Message::SwitchPage(AppPage::CreateBackup))
-            .padding([10, 20])
-            .width(Length::Units(350));
+/// Names and locked versions of the crates the encryption relies on, recorded by the build script.
+fn crypto_dependencies() -> impl Iterator<Item = (&'static str, &'static str)> {
+    env!("CRYPTO_DEPENDENCIES")
+        .split(',')
+        .filter_map(|dependency| dependency.split_once('='))
+}
 
-        let restore_btn = button(text("Restore backup").horizontal_alignment(Horizontal::Center))
-            .on_press(Message::SwitchPage(AppPage::RestoreBackup))
-            .padding([10, 20])
-            .width(Length::Units(350));
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
 
-        column![
-            text("Welcome to Hyperbacked").size(50),
-            text("Select one of the following actions to continue"),
-            vertical_space(Length::Units(48)),
-            create_btn,
-            restore_btn,
-        ]
-        .spacing(10)
-        .align_items(Alignment::Center)
-        .into()
+fn shard_list(numbers: &[usize]) -> String {
+    numbers
+        .iter()
+        .map(|number| format!("#{}", number))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn format_duration(duration: Duration) -> String {
+    match duration.as_secs() {
+        0 => String::from("less than a second"),
+        1 => String::from("about a second"),
+        secs @ 2..=119 => format!("about {} seconds", secs),
+        secs => format!("about {} minutes", secs / 60),
+    }
+}
+
+fn damaged_shard_message(number: usize) -> String {
+    format!("Backup shard #{} appears damaged - rescan it.", number)
+}
+
+// Same payload as the printed PDF, so either can be scanned during restore
+/// Preview of the shard's first QR code, split shards show the rest on the printout.
+fn shard_qrcode(shard: &BackupShard) -> anyhow::Result<image::Handle> {
+    let parts = split_share(&shard.to_bytes())?;
+    let luma = qrcode_encode(&parts[0], QRCODE_PREVIEW_SIZE)?;
+    let bgra = luma
+        .into_iter()
+        .flat_map(|value| [value, value, value, 255])
+        .collect::<Vec<u8>>();
+    Ok(image::Handle::from_pixels(
+        QRCODE_PREVIEW_SIZE as u32,
+        QRCODE_PREVIEW_SIZE as u32,
+        bgra,
+    ))
+}
+
+const GEAR_ICON_SIZE: u32 = 16;
+
+/// A gear drawn into a small image, as the bundled font has no icons.
+fn gear_icon(color: Color) -> image::Handle {
+    // Rendered at twice the size so the scaled down icon is smooth
+    const TEETH: f32 = 8.0;
+    let size = GEAR_ICON_SIZE * 2;
+    let center = size as f32 / 2.0;
+    let [r, g, b, _] = color.into_rgba8();
+    let mut bgra = Vec::with_capacity((size * size * 4) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let dx = x as f32 + 0.5 - center;
+            let dy = y as f32 + 0.5 - center;
+            let radius = (dx * dx + dy * dy).sqrt() / center;
+            let tooth = (dy.atan2(dx) * TEETH).cos() > 0.0;
+            let outer = if tooth { 0.95 } else { 0.72 };
+            let inside = radius <= outer && radius >= 0.3;
+            bgra.extend_from_slice(&[b, g, r, if inside { 255 } else { 0 }]);
+        }
+    }
+    image::Handle::from_pixels(size, size, bgra)
+}
+
+/// The shard as numbered BIP-39 words, six to a line and a gap after each checksummed group.
+/// Entropy of a generated secret, shown in words for mnemonics and in bytes otherwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SecretStrength {
+    format: SecretFormat,
+    bytes: usize,
+}
+
+impl Display for SecretStrength {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.format {
+            SecretFormat::Mnemonic => write!(f, "{} words", self.bytes * 3 / 4),
+            _ => write!(f, "{} bytes", self.bytes),
+        }
+    }
+}
+
+fn share_words_view(
+    shard: &BackupShard,
+    accessibility: AccessibilityConfig,
+) -> Element<'static, Message> {
+    let words = shard.to_words();
+    let lines = words
+        .chunks(WORDS_PER_BLOCK)
+        .enumerate()
+        .map(|(block, block_words)| {
+            block_words
+                .chunks(6)
+                .enumerate()
+                .map(|(line, line_words)| {
+                    line_words
+                        .iter()
+                        .enumerate()
+                        .map(|(index, word)| {
+                            let number = block * WORDS_PER_BLOCK + line * 6 + index + 1;
+                            format!("{}. {}", number, word)
+                        })
+                        .collect::<Vec<String>>()
+                        .join("   ")
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        })
+        .collect::<Vec<String>>()
+        .join("\n\n");
+    let description = match shard.slip39 {
+        Some(_) => format!(
+            "{} SLIP-0039 words, which hardware wallets accept. They hold the same share as the QR code.",
+            words.len()
+        ),
+        None => format!(
+            "{} BIP-39 words, in groups of {} with their own checksum. They hold the same shard as the QR codes.",
+            words.len(),
+            WORDS_PER_BLOCK
+        ),
+    };
+    column![
+        text(description).size(accessibility.text_size(SMALL_TEXT_SIZE)),
+        text(lines).size(accessibility.text_size(SMALL_TEXT_SIZE)),
+        button(text("Copy words"))
+            .on_press(Message::CopyToClipboard(words.join(" ")))
+            .style(theme::Button::Secondary),
+    ]
+    .spacing(10)
+    .padding(10)
+    .into()
+}
+
+fn stepper_button(label: &str, enabled: bool, message: Message) -> Element<Message> {
+    let stepper = button(text(label).horizontal_alignment(Horizontal::Center))
+        .width(Length::Units(30))
+        .style(theme::Button::Secondary);
+    if enabled {
+        stepper.on_press(message).into()
+    } else {
+        stepper.into()
     }
 }
 
 impl BackupType {
-    const ALL: [BackupType; 4] = [
+    const ALL: [BackupType; 5] = [
         BackupType::Standard,
         BackupType::Distributed { min: 2, max: 3 },
         BackupType::Distributed { min: 3, max: 5 },
         BackupType::Distributed { min: 4, max: 7 },
+        BackupType::Custom { min: 2, max: 3 },
     ];
 
-    fn to_config(&self) -> BackupConfig {
-        let min_shards = match *self {
+    /// The presets, with the custom entry showing the shard counts chosen for it.
+    fn choices(selected: BackupType) -> Vec<BackupType> {
+        BackupType::ALL
+            .iter()
+            .map(|&choice| match (choice, selected) {
+                (BackupType::Custom { .. }, BackupType::Custom { .. }) => selected,
+                _ => choice,
+            })
+            .collect()
+    }
+
+    fn is_valid(&self) -> bool {
+        self.validation_error().is_none()
+    }
+
+    fn validation_error(&self) -> Option<String> {
+        match *self {
+            BackupType::Standard => None,
+            // A single required shard would just be copies of the whole backup
+            BackupType::Custom { min, .. } if min < 2 => {
+                Some(String::from("At least 2 shards must be required."))
+            }
+            BackupType::Distributed { min, .. } if min < 1 => {
+                Some(String::from("At least 1 shard must be required."))
+            }
+            BackupType::Distributed { min, max } | BackupType::Custom { min, max } if min > max => {
+                Some(format!(
+                    "Can't require {} shards when only {} are created.",
+                    min, max
+                ))
+            }
+            BackupType::Distributed { max, .. } | BackupType::Custom { max, .. }
+                if max > MAX_SHARDS =>
+            {
+                Some(format!("At most {} shards can be created.", MAX_SHARDS))
+            }
+            _ => None,
+        }
+    }
+
+    fn total_shards(&self) -> u8 {
+        match *self {
             BackupType::Standard => 1,
-            BackupType::Distributed { min, .. } => min,
-        };
+            BackupType::Distributed { max, .. } | BackupType::Custom { max, .. } => max,
+        }
+    }
 
-        let total_shards = match *self {
+    #[allow(clippy::too_many_arguments)]
+    fn to_config(
+        &self,
+        label: &str,
+        kdf: KdfParams,
+        cipher: CipherSuite,
+        sharing: SssBackend,
+        slip39: bool,
+        key_file: Option<KeyFileDigest>,
+        not_before: Option<DateTime<Utc>>,
+        verify_after: Option<u8>,
+    ) -> Result<BackupConfig, ConfigError> {
+        let min_shards = match *self {
             BackupType::Standard => 1,
-            BackupType::Distributed { max, .. } => max,
+            BackupType::Distributed { min, .. } | BackupType::Custom { min, .. } => min,
         };
 
-        return BackupConfig {
-            total_shards,
-            min_shards,
-        };
+        let mut builder = BackupConfig::builder()
+            .with_threshold(min_shards)
+            .with_total(self.total_shards())
+            .with_label(label)
+            .with_kdf(kdf)
+            .with_cipher(cipher)
+            .with_sharing(sharing)
+            .with_slip39(slip39);
+        if let Some(key_file) = key_file {
+            builder = builder.with_key_file(key_file);
+        }
+        if let Some(not_before) = not_before {
+            builder = builder.with_not_before(not_before);
+        }
+        if let Some(years) = verify_after {
+            builder = builder.with_verify_after(years);
+        }
+        builder.build()
     }
 }
 
@@ -533,6 +5851,461 @@ impl Display for BackupType {
             BackupType::Distributed { min, max } => {
                 write!(f, "Distributed ({} of {} shards required)", min, max)
             }
+            BackupType::Custom { min, max } => {
+                write!(f, "Custom ({} of {} shards required)", min, max)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassphraseSeparator {
+    Space,
+    Hyphen,
+    Dot,
+    None,
+}
+
+impl PassphraseSeparator {
+    const ALL: [PassphraseSeparator; 4] = [
+        PassphraseSeparator::Space,
+        PassphraseSeparator::Hyphen,
+        PassphraseSeparator::Dot,
+        PassphraseSeparator::None,
+    ];
+
+    fn as_char(&self) -> Option<char> {
+        match self {
+            PassphraseSeparator::Space => Some(' '),
+            PassphraseSeparator::Hyphen => Some('-'),
+            PassphraseSeparator::Dot => Some('.'),
+            PassphraseSeparator::None => None,
+        }
+    }
+}
+
+impl Display for PassphraseSeparator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PassphraseSeparator::Space => write!(f, "Spaces"),
+            PassphraseSeparator::Hyphen => write!(f, "Hyphens"),
+            PassphraseSeparator::Dot => write!(f, "Dots"),
+            PassphraseSeparator::None => write!(f, "No separator"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyperbacked_core::{
+        backup::{create_backup, extend_backup, restore_shards, BackupConfig, RecoverySeed},
+        crypto::{DecryptedSecret, KdfParams, Secret, SecretEncoding, SecretFormat},
+        mnemonic::encode_words,
+        passphrase::PassphraseStrength,
+    };
+    use iced::{Application, Color};
+
+    use std::path::PathBuf;
+
+    use super::{
+        crypto_dependencies, AppPage, BackupType, HyperbackedApp, LoadedFile, Message,
+        RestoredContext, SecretStrength, StreamFile, TITLE_TEXT_SIZE,
+    };
+    use crate::config::AccessibilityConfig;
+
+    fn app_with_secret() -> HyperbackedApp {
+        let mut app = HyperbackedApp::default();
+        let _ = app.update(Message::SecretChanged(String::from("This is my secret")));
+        app
+    }
+
+    #[test]
+    fn crypto_dependencies_are_locked() {
+        // Cargo.lock isn't tracked, so the versions themselves depend on the checkout
+        let dependencies = crypto_dependencies().collect::<Vec<(&str, &str)>>();
+        assert!(dependencies.iter().any(|(name, _)| *name == "sharks"));
+        assert!(dependencies
+            .iter()
+            .all(|(_, version)| *version != "unknown"));
+    }
+
+    #[test]
+    fn custom_choice_keeps_shard_counts() {
+        let custom = BackupType::Custom { min: 5, max: 9 };
+        let choices = BackupType::choices(custom);
+        assert_eq!(choices.len(), BackupType::ALL.len());
+        assert!(choices.contains(&custom));
+        assert!(choices.contains(&BackupType::Distributed { min: 3, max: 5 }));
+
+        assert_eq!(BackupType::choices(BackupType::Standard), BackupType::ALL);
+    }
+
+    #[test]
+    fn settings_return_to_previous_page() {
+        let mut app = app_with_secret();
+        let _ = app.update(Message::SwitchPage(AppPage::CreateBackup));
+        let _ = app.update(Message::OpenSettings);
+        assert!(matches!(app.page, AppPage::Settings));
+
+        let _ = app.update(Message::CloseSettings);
+        assert!(matches!(app.page, AppPage::CreateBackup));
+        // Visiting the settings must not wipe what was typed
+        assert_eq!(&*app.secret, "This is my secret");
+
+        // The PDF settings are a subpage, going back leads through the settings
+        let _ = app.update(Message::OpenSettings);
+        let _ = app.update(Message::SwitchPage(AppPage::PdfSettings));
+        let _ = app.update(app.back_action().unwrap());
+        assert!(matches!(app.page, AppPage::Settings));
+        let _ = app.update(Message::SwitchPage(AppPage::PdfSettings));
+        let _ = app.update(Message::OpenSettings);
+        let _ = app.update(Message::CloseSettings);
+        assert!(matches!(app.page, AppPage::CreateBackup));
+        assert_eq!(&*app.secret, "This is my secret");
+    }
+
+    #[test]
+    fn copy_secret_reads_restored_secret() {
+        let mut app = HyperbackedApp::default();
+        let _ = app.update(Message::CopySecret);
+        assert!(app.clipboard_copied.is_none());
+
+        app.decoded_secret = Some(DecryptedSecret {
+            value: "This is my secret".into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        });
+        let _ = app.update(Message::CopySecret);
+        assert_eq!(app.clipboard_copied.as_deref(), Some("This is my secret"));
+        assert_eq!(app.clipboard_remaining, Some(app.clipboard_clear_secs));
+    }
+
+    #[test]
+    fn accessibility_scales_text_and_overrides_theme() {
+        let mut app = HyperbackedApp::default();
+        assert_eq!(app.text_size(TITLE_TEXT_SIZE), 30);
+
+        app.accessibility = AccessibilityConfig {
+            high_contrast: true,
+            font_scale: 1.5,
+        };
+        assert_eq!(app.text_size(TITLE_TEXT_SIZE), 45);
+        for dark_mode in [true, false] {
+            app.dark_mode = dark_mode;
+            let palette = app.theme().palette();
+            assert_eq!(palette.background, Color::BLACK);
+            assert_eq!(palette.text, Color::WHITE);
+        }
+
+        // Hand-edited configs can't make the text unreadably small or large
+        app.accessibility.font_scale = 0.1;
+        assert_eq!(app.text_size(TITLE_TEXT_SIZE), 30);
+        app.accessibility.font_scale = 10.0;
+        assert_eq!(app.text_size(TITLE_TEXT_SIZE), 60);
+    }
+
+    #[test]
+    fn reprint_needs_seed_and_shard_number() {
+        let mut app = app_with_secret();
+        let _ = app.update(Message::GeneratePassphrase);
+        let _ = app.update(Message::PassphraseWrittenDownChanged(true));
+        app.backup_type = BackupType::Distributed { min: 2, max: 3 };
+        let _ = app.update(Message::StartReprint);
+        assert!(matches!(app.page, AppPage::CreateBackup));
+        assert!(!app.can_create_backup());
+
+        let seed = RecoverySeed::generate().to_string();
+        let _ = app.update(Message::ReprintSeedChanged(seed));
+        let _ = app.update(Message::ReprintNumberChanged(String::from("4")));
+        assert!(!app.can_create_backup());
+        let _ = app.update(Message::ReprintNumberChanged(String::from("3")));
+        assert!(app.can_create_backup());
+
+        let _ = app.update(Message::SwitchPage(AppPage::Welcome));
+        assert!(app.reprint.is_none());
+    }
+
+    #[test]
+    fn streamed_file_replaces_secret() {
+        let mut app = HyperbackedApp {
+            stream_file: Some(StreamFile {
+                path: PathBuf::from("disk.img"),
+                name: String::from("disk.img"),
+                len: 1 << 30,
+            }),
+            ..Default::default()
+        };
+        let _ = app.update(Message::GeneratePassphrase);
+        let _ = app.update(Message::PassphraseWrittenDownChanged(true));
+        assert!(app.can_create_backup());
+
+        // Decoys need secrets of their own, which a streamed backup doesn't have
+        let _ = app.update(Message::AddSecret);
+        assert!(app.additional_secrets.is_empty());
+        let _ = app.update(Message::StartReprint);
+        assert!(!app.can_create_backup());
+
+        let _ = app.update(Message::SwitchPage(AppPage::Welcome));
+        assert!(app.stream_file.is_none());
+    }
+
+    #[test]
+    fn generated_passphrase_fills_confirmation() {
+        let mut app = app_with_secret();
+        let _ = app.update(Message::GeneratePassphrase);
+        assert_eq!(app.passphrase, app.passphrase_confirm);
+        assert!(!app.can_create_backup());
+
+        let _ = app.update(Message::PassphraseWrittenDownChanged(true));
+        assert!(app.can_create_backup());
+    }
+
+    #[test]
+    fn mismatched_confirmation_blocks_creation() {
+        let mut app = app_with_secret();
+        let _ = app.update(Message::PassphraseChanged(String::from(
+            "correct horse battery",
+        )));
+        let _ = app.update(Message::PassphraseConfirmChanged(String::from(
+            "correct horse batery",
+        )));
+        let _ = app.update(Message::PassphraseWrittenDownChanged(true));
+        assert!(!app.can_create_backup());
+
+        let _ = app.update(Message::PassphraseConfirmChanged(String::from(
+            "correct horse battery",
+        )));
+        assert!(app.can_create_backup());
+    }
+
+    #[test]
+    fn generated_secret_is_separate_from_passphrase() {
+        let mut app = HyperbackedApp::default();
+        let _ = app.update(Message::SecretFormatChanged(SecretFormat::Hex));
+        let _ = app.update(Message::SecretStrengthChanged(SecretStrength {
+            format: SecretFormat::Hex,
+            bytes: 64,
+        }));
+        let _ = app.update(Message::GenerateSecret);
+        assert_eq!(app.secret.len(), 128);
+        assert!(app.secret_generated);
+        assert!(app.passphrase.is_empty());
+
+        // Mnemonics don't come in 64 bytes, so the strength drops to the next one offered
+        let _ = app.update(Message::SecretFormatChanged(SecretFormat::Mnemonic));
+        assert_eq!(app.secret_strength, 32);
+        let _ = app.update(Message::GenerateSecret);
+        assert_eq!(app.secret.split(' ').count(), 24);
+
+        let _ = app.update(Message::SecretChanged(String::from("typed by hand")));
+        assert!(!app.secret_generated);
+        assert_eq!(app.stored_sizes.lens, [13]);
+        let _ = app.update(Message::AddSecret);
+        let _ = app.update(Message::AdditionalSecretChanged(0, String::from("decoy")));
+        assert_eq!(app.stored_sizes.lens, [13, 5]);
+    }
+
+    #[test]
+    fn loaded_files_are_offered_for_deletion() {
+        let mut app = HyperbackedApp::default();
+        let file = PathBuf::from("/tmp/shard-1-of-3.pdf");
+        let _ = app.update(Message::FileImported(Ok((vec![file.clone()], Vec::new()))));
+        assert_eq!(app.restore.loaded_files, vec![file.clone()]);
+        let _ = app.update(Message::FileImported(Err(String::from("Unreadable"))));
+        assert_eq!(app.restore.loaded_files.len(), 1);
+
+        app.loaded_files = vec![LoadedFile {
+            path: file.clone(),
+            selected: false,
+            status: None,
+        }];
+        let _ = app.update(Message::SecureDeleteToggled(0, true));
+        assert!(app.loaded_files[0].selected);
+        let _ = app.update(Message::SecureDeleted(file, Ok(())));
+        assert!(!app.loaded_files[0].selected);
+        assert_eq!(app.loaded_files[0].status, Some(Ok(())));
+    }
+
+    #[test]
+    fn restored_backup_can_be_extended() {
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: "correct horse battery staple".into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig::builder()
+            .with_threshold(2)
+            .with_total(3)
+            .with_label("Family photos")
+            .with_kdf(KdfParams::Pbkdf2 { rounds: 1_000 })
+            .build()
+            .unwrap();
+        let shards = create_backup(secrets, config).unwrap();
+
+        let mut app = HyperbackedApp::default();
+        app.restore.scanned_shards = vec![shards[0].clone(), shards[2].clone()];
+        app.restore.passphrase = "correct horse battery staple".into();
+        let decrypted = restore_shards(&app.restore.scanned_shards, &app.restore.passphrase);
+        app.finish_restore(decrypted.unwrap(), vec![1, 3]);
+        let extend = app.extend.as_ref().expect("Restored shards are kept");
+        assert_eq!(extend.next_number(), 4);
+        assert_eq!(extend.first, 4);
+
+        // Numbers of shards that are known can't be picked
+        let _ = app.update(Message::ExtendFirstChanged(2));
+        assert_eq!(app.extend.as_ref().unwrap().first, 4);
+        let _ = app.update(Message::ExtendFirstChanged(255));
+        let _ = app.update(Message::ExtendCountChanged(2));
+        assert_eq!(app.extend.as_ref().unwrap().additional, 1);
+        let _ = app.update(Message::ExtendFirstChanged(4));
+
+        let _ = app.update(Message::SwitchPage(AppPage::ExtendBackup));
+        let _ = app.update(Message::ExtendCountChanged(2));
+        let _ = app.update(Message::ExtendBackup);
+        assert!(!app.can_extend());
+        let existing = app.extend.as_ref().map(|extend| RestoredContext::Shards {
+            shards: extend.shards.clone(),
+            password: extend.password.clone(),
+        });
+        let added = extend_backup(existing.unwrap(), 2).map_err(|error| error.to_string());
+        let _ = app.update(Message::BackupExtended(added));
+        assert!(matches!(app.page, AppPage::BackupResults));
+        assert!(app.is_extended());
+        assert!(app.next_action().is_none());
+        assert_eq!(app.label, "Family photos");
+        let numbers = app
+            .generated_backup
+            .iter()
+            .flatten()
+            .map(|shard| shard.number)
+            .collect::<Vec<usize>>();
+        assert_eq!(numbers, [4, 5]);
+
+        let _ = app.update(Message::LeaveResults { exit: false });
+        let _ = app.update(Message::LeaveResults { exit: false });
+        assert!(app.extend.is_none());
+    }
+
+    #[test]
+    fn backups_are_recorded_on_request() {
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: "correct horse battery staple".into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig::builder()
+            .with_kdf(KdfParams::Pbkdf2 { rounds: 1_000 })
+            .build()
+            .unwrap();
+        let shards = create_backup(secrets, config).unwrap();
+
+        let mut app = HyperbackedApp::default();
+        let _ = app.update(Message::BackupCompleted(Ok(shards)));
+        assert!(app.backup_history.records().is_empty());
+        let _ = app.update(Message::RecordBackupChanged(true));
+        assert_eq!(app.backup_history.records().len(), 1);
+        let _ = app.update(Message::RecordBackupChanged(false));
+        assert!(app.backup_history.records().is_empty());
+    }
+
+    #[test]
+    fn rejected_typed_share_is_kept() {
+        let secrets = vec![Secret {
+            value: "This is my secret".into(),
+            password: "correct horse battery staple".into(),
+            encoding: SecretEncoding::Text,
+            note: Default::default(),
+        }];
+        let config = BackupConfig::builder()
+            .with_kdf(KdfParams::Pbkdf2 { rounds: 1_000 })
+            .build()
+            .unwrap();
+        let shards = create_backup(secrets, config).unwrap();
+        let typed = |data: &[u8]| encode_words(data).join(" ");
+
+        let mut app = HyperbackedApp::default();
+        let mut damaged = shards[0].to_bytes();
+        *damaged.last_mut().unwrap() ^= 1;
+        let _ = app.update(Message::ShareTextChanged(typed(&damaged)));
+        let _ = app.update(Message::AddShare);
+        assert_eq!(app.restore.share_text, typed(&damaged));
+        assert!(app.restore.share_text_error.is_some());
+        assert!(app.restore.error.is_none());
+        assert!(app.restore.scanned_shards.is_empty());
+
+        let _ = app.update(Message::ShareTextChanged(typed(&shards[0].to_bytes())));
+        let _ = app.update(Message::AddShare);
+        assert!(app.restore.share_text.is_empty());
+        assert_eq!(app.restore.scanned_shards.len(), 1);
+
+        let _ = app.update(Message::ShareTextChanged(typed(&shards[0].to_bytes())));
+        let _ = app.update(Message::AddShare);
+        assert_eq!(app.restore.share_text, typed(&shards[0].to_bytes()));
+        assert!(app.restore.share_text_error.is_some());
+        assert_eq!(app.restore.scanned_shards.len(), 1);
+    }
+
+    #[test]
+    fn changed_passphrase_needs_writing_down_again() {
+        let mut app = app_with_secret();
+        let _ = app.update(Message::GeneratePassphrase);
+        let _ = app.update(Message::PassphraseWrittenDownChanged(true));
+        let _ = app.update(Message::GeneratePassphrase);
+        assert!(!app.passphrase_written_down);
+        assert!(!app.can_create_backup());
+    }
+
+    #[test]
+    fn weak_passphrase_needs_override() {
+        for score in 0..=4 {
+            let mut app = app_with_secret();
+            let _ = app.update(Message::GeneratePassphrase);
+            let _ = app.update(Message::PassphraseWrittenDownChanged(true));
+            app.passphrase_strength = Some(PassphraseStrength {
+                score,
+                crack_time: String::new(),
+                feedback: Vec::new(),
+            });
+            assert_eq!(app.can_create_backup(), score >= 3, "score {}", score);
+
+            let _ = app.update(Message::AllowWeakPassphraseChanged(true));
+            assert!(app.can_create_backup(), "score {}", score);
         }
     }
+
+    #[test]
+    fn passphrase_repeating_the_label_is_weak() {
+        let mut app = app_with_secret();
+        let _ = app.update(Message::PassphraseChanged(String::from(
+            "Grandmas Jewelry Vault",
+        )));
+        let unrelated_score = app.get_passphrase_score();
+        let _ = app.update(Message::LabelChanged(String::from(
+            "Grandmas Jewelry Vault",
+        )));
+        assert!(app.get_passphrase_score() < unrelated_score);
+    }
+
+    #[test]
+    fn additional_secrets_have_their_own_strength() {
+        let mut app = app_with_secret();
+        let _ = app.update(Message::AddSecret);
+        assert_eq!(app.additional_secrets[0].passphrase_score, None);
+
+        let _ = app.update(Message::AdditionalPassphraseChanged(
+            0,
+            String::from("Grandmas Jewelry Vault"),
+        ));
+        let unrelated_score = app.additional_secrets[0].passphrase_score;
+        assert!(unrelated_score.is_some());
+        assert_eq!(app.get_passphrase_score(), None);
+
+        let _ = app.update(Message::AdditionalLabelChanged(
+            0,
+            String::from("Grandmas Jewelry Vault"),
+        ));
+        assert!(app.additional_secrets[0].passphrase_score < unrelated_score);
+    }
 }
@@ -0,0 +1,214 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::NaiveDate;
+use hyperbacked_core::backup::BackupShard;
+use rusqlite::{params, Connection};
+
+/// A backup that was created, to remind the user what they have backed up. Only what is
+/// printed on the shards anyway is kept: never a secret, passphrase or share.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackupRecord {
+    pub backup_id: String,
+    pub label: String,
+    pub created_on: NaiveDate,
+    pub threshold: u8,
+    /// Fingerprint of every shard, the first one belonging to shard #1
+    pub fingerprints: Vec<String>,
+}
+
+impl BackupRecord {
+    pub fn new(shards: &[BackupShard], label: &str, created_on: NaiveDate) -> Option<Self> {
+        let first = shards.first()?;
+        let mut shards = shards.iter().collect::<Vec<&BackupShard>>();
+        shards.sort_by_key(|shard| shard.number);
+        Some(BackupRecord {
+            backup_id: format!("{:016x}", first.backup_id),
+            label: label.to_owned(),
+            created_on,
+            threshold: first.threshold,
+            fingerprints: shards
+                .iter()
+                .map(|shard| shard.display_fingerprint())
+                .collect(),
+        })
+    }
+
+    pub fn name(&self) -> String {
+        if self.label.is_empty() {
+            format!("Backup {}", self.backup_id)
+        } else {
+            format!("{} ({})", self.label, self.backup_id)
+        }
+    }
+
+    /// Whether the shard is one of those recorded, so a scanned shard can be checked against it.
+    pub fn contains(&self, shard: &BackupShard) -> bool {
+        format!("{:016x}", shard.backup_id) == self.backup_id
+            && self.fingerprints.get(shard.number.wrapping_sub(1))
+                == Some(&shard.display_fingerprint())
+    }
+}
+
+/// The backups created so far, newest last, kept in an SQLite database next to the restore
+/// history.
+#[derive(Default)]
+pub struct BackupHistory {
+    path: Option<PathBuf>,
+    records: Vec<BackupRecord>,
+}
+
+impl BackupHistory {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("hyperbacked").join("backups.sqlite3"))
+    }
+
+    /// Reads the records, an unreadable database is an empty history.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let records = match path.as_deref().map(read_records).transpose() {
+            Ok(records) => records.unwrap_or_default(),
+            Err(error) => {
+                eprintln!("Failed to read backup history: {}", error);
+                Vec::new()
+            }
+        };
+        BackupHistory { path, records }
+    }
+
+    pub fn records(&self) -> &[BackupRecord] {
+        &self.records
+    }
+
+    pub fn find(&self, backup_id: &str) -> Option<&BackupRecord> {
+        self.records
+            .iter()
+            .find(|record| record.backup_id == backup_id)
+    }
+
+    /// Adds the record, replacing an older one of the same backup.
+    pub fn add(&mut self, record: BackupRecord) -> anyhow::Result<()> {
+        if let Some(path) = &self.path {
+            // Replacing the row moves it to the end, like the record in memory
+            open(path)?.execute(
+                "INSERT OR REPLACE INTO backups
+                    (backup_id, label, created_on, threshold, fingerprints)
+                    VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    record.backup_id,
+                    record.label,
+                    record.created_on,
+                    record.threshold,
+                    record.fingerprints.join(" "),
+                ],
+            )?;
+        }
+        self.records
+            .retain(|existing| existing.backup_id != record.backup_id);
+        self.records.push(record);
+        Ok(())
+    }
+
+    pub fn delete(&mut self, backup_id: &str) -> anyhow::Result<()> {
+        if let Some(path) = &self.path {
+            open(path)?.execute(
+                "DELETE FROM backups WHERE backup_id = ?1",
+                params![backup_id],
+            )?;
+        }
+        self.records.retain(|record| record.backup_id != backup_id);
+        Ok(())
+    }
+}
+
+fn open(path: &Path) -> anyhow::Result<Connection> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let connection = Connection::open(path)?;
+    connection.execute_batch(
+        "CREATE TABLE IF NOT EXISTS backups (
+            backup_id TEXT PRIMARY KEY,
+            label TEXT NOT NULL,
+            created_on TEXT NOT NULL,
+            threshold INTEGER NOT NULL,
+            fingerprints TEXT NOT NULL
+        )",
+    )?;
+    Ok(connection)
+}
+
+fn read_records(path: &Path) -> anyhow::Result<Vec<BackupRecord>> {
+    // A history that was never saved to is empty, without creating the database for it
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let connection = open(path)?;
+    let mut statement = connection.prepare(
+        "SELECT backup_id, label, created_on, threshold, fingerprints
+            FROM backups ORDER BY rowid",
+    )?;
+    let records = statement
+        .query_map([], |row| {
+            Ok(BackupRecord {
+                backup_id: row.get(0)?,
+                label: row.get(1)?,
+                created_on: row.get(2)?,
+                threshold: row.get(3)?,
+                fingerprints: row
+                    .get::<_, String>(4)?
+                    .split_whitespace()
+                    .map(str::to_owned)
+                    .collect(),
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<BackupRecord>>>()?;
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use hyperbacked_core::backup::BackupShard;
+
+    use super::{BackupHistory, BackupRecord};
+
+    fn shard(number: usize, backup_id: u64) -> BackupShard {
+        BackupShard {
+            number,
+            threshold: 2,
+            backup_id,
+            data: vec![number as u8, 9, 9, 9],
+            expires_at: None,
+            metadata: None,
+            slip39: None,
+        }
+    }
+
+    #[test]
+    fn records_survive_reloading() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("backups.sqlite3");
+        let created_on = NaiveDate::from_ymd_opt(2026, 10, 1).unwrap();
+        let shards = [shard(2, 5), shard(1, 5), shard(3, 5)];
+        let record = BackupRecord::new(&shards, "Wallet", created_on).unwrap();
+        assert_eq!(record.fingerprints[0], shards[1].display_fingerprint());
+        assert!(shards.iter().all(|shard| record.contains(shard)));
+        assert!(!record.contains(&shard(1, 6)));
+        assert!(!record.contains(&shard(4, 5)));
+
+        let mut history = BackupHistory::load(Some(path.clone()));
+        history.add(record.clone()).unwrap();
+        history.add(record.clone()).unwrap();
+        let other = BackupRecord::new(&[shard(1, 6)], "", created_on).unwrap();
+        history.add(other.clone()).unwrap();
+
+        let mut history = BackupHistory::load(Some(path.clone()));
+        assert_eq!(history.records(), [record.clone(), other]);
+        history.delete(&record.backup_id).unwrap();
+        assert!(BackupHistory::load(Some(path.clone()))
+            .find(&record.backup_id)
+            .is_none());
+    }
+}
@@ -1,22 +1,55 @@
+use clap::Parser;
 use iced::{Application, Settings};
 
-use crate::gui::HyperbackedApp;
+use crate::{
+    cli::Cli,
+    config::Config,
+    gui::{Flags, HyperbackedApp, BODY_TEXT_SIZE},
+};
 
-mod backup;
-mod crypto;
+mod audit;
+mod bundle;
+mod cli;
+mod config;
 mod errors;
+mod export;
+mod fileops;
 mod gui;
-mod passphrase;
-mod printer;
-mod qrcode;
+mod history;
+mod storage;
+#[cfg(feature = "tray")]
+mod tray;
 
 fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    #[cfg(feature = "tray")]
+    let tray = cli.tray;
+    #[cfg(not(feature = "tray"))]
+    let tray = false;
+    if let (false, Some(command)) = (cli.gui || tray, cli.command) {
+        return cli::run(command);
+    }
+
+    let accessibility = Config::load().accessibility;
     HyperbackedApp::run(Settings {
         window: iced::window::Settings {
-            size: (700, 550),
+            // Grows with the text, which would otherwise no longer fit
+            size: (
+                (700.0 * accessibility.scale()) as u32,
+                (550.0 * accessibility.scale()) as u32,
+            ),
             resizable: false,
+            // In the tray, the window only shows once the icon is clicked
+            visible: !tray,
             ..Default::default()
         },
+        flags: Flags {
+            #[cfg(feature = "tray")]
+            tray,
+        },
+        // Closing the window goes through Message::End, which may ask for confirmation
+        exit_on_close_request: false,
+        default_text_size: accessibility.text_size(BODY_TEXT_SIZE),
         ..Default::default()
     })?;
     Ok(())
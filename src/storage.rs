@@ -0,0 +1,87 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::NaiveDate;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// A restore the user chose to remember. Secrets and passphrases must never be stored here.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: u64,
+    pub label: String,
+    pub backup_id: String,
+    pub restored_on: NaiveDate,
+    pub shards_used: Vec<usize>,
+}
+
+pub fn default_history_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("hyperbacked").join("history.json"))
+}
+
+/// Loads a history, such as the restore history. A missing file is an empty history, an
+/// unreadable one is moved aside so the next save doesn't overwrite what may still be
+/// recoverable by hand.
+pub fn load_history<T: DeserializeOwned>(path: &Path) -> Vec<T> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => return Vec::new(),
+    };
+
+    match serde_json::from_str(&contents) {
+        Ok(entries) => entries,
+        Err(error) => {
+            eprintln!("Failed to read {}: {}", path.display(), error);
+            fs::rename(path, path.with_extension("json.corrupt")).ok();
+            Vec::new()
+        }
+    }
+}
+
+pub fn save_history<T: Serialize>(path: &Path, entries: &[T]) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(entries)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use chrono::NaiveDate;
+
+    use super::{load_history, save_history, HistoryEntry};
+
+    #[test]
+    fn history_round_trip() {
+        let path = std::env::temp_dir().join("hyperbacked_history_round_trip.json");
+        let entries = vec![HistoryEntry {
+            id: 7,
+            label: String::from("My backup"),
+            backup_id: String::from("0123456789abcdef"),
+            restored_on: NaiveDate::from_ymd_opt(2026, 5, 4).unwrap(),
+            shards_used: vec![1, 3],
+        }];
+
+        save_history(&path, &entries).expect("Failed to save history");
+        assert_eq!(load_history::<HistoryEntry>(&path), entries);
+
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn corrupt_history_is_moved_aside() {
+        let path = std::env::temp_dir().join("hyperbacked_history_corrupt.json");
+        let moved = path.with_extension("json.corrupt");
+        fs::write(&path, "[{\"id\": ").unwrap();
+
+        assert!(load_history::<HistoryEntry>(&path).is_empty());
+        assert!(!path.exists());
+        assert!(moved.exists());
+
+        fs::remove_file(&moved).ok();
+    }
+}
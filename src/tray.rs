@@ -0,0 +1,163 @@
+//! The system tray icon of `--tray` mode, and the desktop notifications it sends as tracked
+//! backups approach their expiry date.
+
+use chrono::NaiveDate;
+use iced::{
+    futures::{channel::mpsc as async_mpsc, StreamExt},
+    subscription, Subscription,
+};
+use notify_rust::Notification;
+use tray_icon::{
+    menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem},
+    Icon, MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent,
+};
+
+use crate::{config::TrackedBackup, gui::Message};
+
+const OPEN_ITEM: &str = "open";
+const QUIT_ITEM: &str = "quit";
+const ICON_SIZE: u32 = 32;
+
+/// What was picked from the tray icon or its menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrayEvent {
+    Open,
+    Quit,
+}
+
+/// Adds the icon to the system tray, where it stays until the returned handle is dropped. Has to
+/// be called from the running event loop, which macOS and Windows deliver its clicks to.
+pub fn create_tray_icon() -> anyhow::Result<TrayIcon> {
+    let open = MenuItem::with_id(OPEN_ITEM, "Open Hyperbacked", true, None);
+    let quit = MenuItem::with_id(QUIT_ITEM, "Quit", true, None);
+    let menu = Menu::with_items(&[&open, &PredefinedMenuItem::separator(), &quit])?;
+    let icon = TrayIconBuilder::new()
+        .with_menu(Box::new(menu))
+        .with_tooltip("Hyperbacked")
+        .with_icon(Icon::from_rgba(icon_rgba(), ICON_SIZE, ICON_SIZE)?)
+        .build()?;
+    Ok(icon)
+}
+
+/// Clicks on the tray icon and its menu, which arrive on threads of the tray.
+pub fn tray_events() -> Subscription<Message> {
+    enum State {
+        Starting,
+        Listening(async_mpsc::UnboundedReceiver<TrayEvent>),
+    }
+
+    subscription::unfold("tray", State::Starting, |state| async move {
+        match state {
+            State::Starting => {
+                let (sender, receiver) = async_mpsc::unbounded();
+                let menu_sender = sender.clone();
+                MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+                    let event = match event.id() {
+                        id if id == OPEN_ITEM => TrayEvent::Open,
+                        id if id == QUIT_ITEM => TrayEvent::Quit,
+                        _ => return,
+                    };
+                    let _ = menu_sender.unbounded_send(event);
+                }));
+                TrayIconEvent::set_event_handler(Some(move |event| {
+                    if let TrayIconEvent::Click {
+                        button: MouseButton::Left,
+                        button_state: MouseButtonState::Up,
+                        ..
+                    } = event
+                    {
+                        let _ = sender.unbounded_send(TrayEvent::Open);
+                    }
+                }));
+                (None, State::Listening(receiver))
+            }
+            State::Listening(mut receiver) => match receiver.next().await {
+                Some(event) => (Some(Message::TrayEvent(event)), State::Listening(receiver)),
+                None => iced::futures::future::pending().await,
+            },
+        }
+    })
+}
+
+/// Shows a desktop notification that the backup is about to expire, or already has.
+pub fn notify_expiring(backup: &TrackedBackup, today: NaiveDate) -> anyhow::Result<()> {
+    Notification::new()
+        .appname("Hyperbacked")
+        .summary("Time to renew your backup")
+        .body(&reminder_text(backup, today))
+        .show()?;
+    Ok(())
+}
+
+fn reminder_text(backup: &TrackedBackup, today: NaiveDate) -> String {
+    let name = if backup.label.is_empty() {
+        format!("Backup {}", backup.backup_id)
+    } else {
+        backup.label.clone()
+    };
+    let due = match (backup.expires_at - today).num_days() {
+        days if days < 0 => format!("expired on {}", backup.expires_at),
+        0 => String::from("expires today"),
+        1 => String::from("expires tomorrow"),
+        days => format!("expires in {} days, on {}", days, backup.expires_at),
+    };
+    format!(
+        "{} {}. Create a new backup of the secret and destroy the old shards.",
+        name, due
+    )
+}
+
+/// A padlock in the colours of the dark theme, as there are no image files to load one from.
+fn icon_rgba() -> Vec<u8> {
+    let size = ICON_SIZE as f32;
+    let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+            let body =
+                (0.2 * size..0.8 * size).contains(&x) && (0.45 * size..0.9 * size).contains(&y);
+            let dx = x - size / 2.0;
+            let dy = y - 0.45 * size;
+            let radius = (dx * dx + dy * dy).sqrt();
+            let shackle = dy <= 0.0 && (0.16 * size..0.26 * size).contains(&radius);
+            let alpha = if body || shackle { 255 } else { 0 };
+            rgba.extend_from_slice(&[128, 128, 191, alpha]);
+        }
+    }
+    rgba
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{icon_rgba, reminder_text, ICON_SIZE};
+    use crate::config::TrackedBackup;
+
+    #[test]
+    fn reminder_counts_down() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let backup = |label: &str, days: i64| TrackedBackup {
+            label: label.to_owned(),
+            backup_id: String::from("0123456789abcdef"),
+            expires_at: today + chrono::Duration::days(days),
+        };
+        assert_eq!(
+            reminder_text(&backup("Cold wallet", 12), today),
+            "Cold wallet expires in 12 days, on 2024-03-13. Create a new backup of the secret and \
+             destroy the old shards."
+        );
+        assert!(reminder_text(&backup("", 1), today)
+            .starts_with("Backup 0123456789abcdef expires tomorrow."));
+        assert!(reminder_text(&backup("Cold wallet", 0), today).contains("expires today"));
+        assert!(reminder_text(&backup("Cold wallet", -2), today).contains("expired on 2024-02-28"));
+    }
+
+    #[test]
+    fn icon_has_its_size() {
+        let rgba = icon_rgba();
+        assert_eq!(rgba.len(), (ICON_SIZE * ICON_SIZE * 4) as usize);
+        assert!(rgba.chunks(4).any(|pixel| pixel[3] == 255));
+        assert!(rgba.chunks(4).any(|pixel| pixel[3] == 0));
+    }
+}